@@ -1,12 +1,11 @@
 use cosmwasm_schema::write_api;
-use cosmwasm_std::Empty;
-
-use cw721_custom::{ ExecuteMsg, InstantiateMsg, QueryMsg };
+use cw721_custom::{ Cw721CustomExecuteMsg, Cw721CustomQueryMsg, InstantiateMsg, MigrateMsg };
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
-        execute: ExecuteMsg<Empty, Empty>,
-        query: QueryMsg<Empty>,
+        execute: Cw721CustomExecuteMsg,
+        query: Cw721CustomQueryMsg,
+        migrate: MigrateMsg,
     }
 }
@@ -1,15 +1,20 @@
-use cosmwasm_std::testing::{ mock_dependencies, mock_env, mock_info };
+use cosmwasm_std::testing::{ mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR };
 
 use cosmwasm_std::{
     from_json,
     to_json_binary,
     Addr,
+    BankMsg,
+    Binary,
     Coin,
     CosmosMsg,
+    Decimal,
     DepsMut,
     Empty,
+    Event,
     Response,
     StdError,
+    SubMsg,
     Uint128,
     WasmMsg,
 };
@@ -22,10 +27,13 @@ use cw721::{
     Cw721ReceiveMsg,
     Expiration,
     NftInfoResponse,
+    NumTokensResponse,
     OperatorResponse,
     OperatorsResponse,
     OwnerOfResponse,
+    TokensResponse,
 };
+use cw20::{ Cw20ExecuteMsg, Cw20ReceiveMsg };
 use cw_ownable::OwnershipError;
 
 use crate::{
@@ -37,6 +45,77 @@ use crate::{
     MinterResponse,
     QueryMsg,
 };
+use crate::msg::{
+    ActivityResponse,
+    ApprovalPermit,
+    MintVoucher,
+    AuctionsResponse,
+    BridgeLockResponse,
+    BurnedTokenResponse,
+    BurnedTokensResponse,
+    CanMintResponse,
+    ConfigResponse,
+    ContractStatusResponse,
+    Cw20HookMsg,
+    Granter,
+    Holder,
+    HoldersResponse,
+    HolderBalanceResponse,
+    ListingsResponse,
+    MintLedgerResponse,
+    NumHoldersResponse,
+    OperatorCountResponse,
+    OperatorGrantsResponse,
+    OwnerTokensResponse,
+    PowerResponse,
+    PermitParams,
+    RangeOrder,
+    RedemptionsResponse,
+    SupplyResponse,
+    TokenOwner,
+    TokenRangeResponse,
+    TokensDetailedResponse,
+    TokensWithOwnerResponse,
+    TransferableResponse,
+    UnrevealedTokensResponse,
+    VestedProceeds,
+    VerifyHoldingsResponse,
+    VestedProceedsResponse,
+    WalletMints,
+    TokenApproval,
+    TokenHistoryResponse,
+    TokenStatsResponse,
+};
+use crate::state::{
+    token_id_from_key,
+    token_key,
+    ActivityAction,
+    Auction,
+    Bid,
+    BurnRecord,
+    CoSignConfig,
+    HistoryAction,
+    Listing,
+    OperatorGrant,
+    OperatorPermissions,
+    PaginationConfig,
+    PartnerConfig,
+    RedemptionStatus,
+    RewardAsset,
+    SaleRecord,
+    SaleStats,
+    Royalty,
+    SnapshotStrategy,
+    SubscriptionConfig,
+    SupplyCapBasis,
+    TokenInfo,
+    VestingConfig,
+    DEFAULT_ACTIVITY_BUFFER_SIZE,
+    DEFAULT_AUCTION_EXTENSION,
+    DEFAULT_MAX_APPROVALS_PER_TOKEN,
+    DEFAULT_MAX_TOKEN_HISTORY,
+    RAFFLE_REVEAL_PERIOD,
+};
 
 const MINTER: &str = "merlin";
 const CONTRACT_NAME: &str = "Magic Power";
@@ -55,6 +134,7 @@ fn setup_contract(deps: DepsMut<'_>) -> Cw721Contract<'static, Extension, Empty,
             amount: Uint128::new(1000000),
         }),
         max_supply: Some(4),
+        ..Default::default()
     };
     let info = mock_info(
         "creator",
@@ -81,6 +161,7 @@ fn proper_instantiation() {
             amount: Uint128::new(1000000),
         }),
         max_supply: Some(1000),
+        ..Default::default()
     };
     let info = mock_info(
         "creator",
@@ -292,7 +373,7 @@ fn burning() {
 
     let mint_msg = ExecuteMsg::Mint {
         owner: MINTER.to_string(),
-        token_uri: Some(token_uri),
+        token_uri: Some(token_uri.clone()),
         extension: None,
     };
 
@@ -314,7 +395,22 @@ fn burning() {
 
     assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
 
-    let _ = contract.execute(deps.as_mut(), mock_env(), allowed, burn_msg).unwrap();
+    let res = contract.execute(deps.as_mut(), mock_env(), allowed, burn_msg).unwrap();
+    assert_eq!(
+        res,
+        Response::new()
+            .add_attribute("action", "burn")
+            .add_attribute("sender", MINTER)
+            .add_attribute("owner", MINTER)
+            .add_attribute("token_id", "0")
+            .add_attribute("token_uri", token_uri.clone())
+            .add_event(
+                Event::new("cw721_burn")
+                    .add_attribute("owner", MINTER)
+                    .add_attribute("token_id", "0")
+                    .add_attribute("token_uri", token_uri)
+            )
+    );
 
     // ensure num tokens decreases
     let count = contract.num_tokens(deps.as_ref()).unwrap();
@@ -444,7 +540,7 @@ fn sending_nft() {
     assert_eq!(
         res,
         Response::new()
-            .add_message(expected)
+            .add_submessage(SubMsg::reply_always(expected, 1))
             .add_attribute("action", "send_nft")
             .add_attribute("sender", "venus")
             .add_attribute("recipient", "another_contract")
@@ -498,10 +594,19 @@ fn approving_revoking() {
     assert_eq!(
         res,
         Response::new()
+            .add_event(
+                Event::new("cw721_approve")
+                    .add_attribute("granter", "demeter")
+                    .add_attribute("spender", "random")
+                    .add_attribute("token_id", token_id.clone())
+                    .add_attribute("expires", "never")
+            )
             .add_attribute("action", "approve")
             .add_attribute("sender", "demeter")
+            .add_attribute("granter", "demeter")
             .add_attribute("spender", "random")
             .add_attribute("token_id", token_id.clone())
+            .add_attribute("expires", "never")
     );
 
     // test approval query
@@ -611,6 +716,8 @@ fn approving_all_revoking_all() {
     let approve_all_msg = ExecuteMsg::ApproveAll {
         operator: String::from("random"),
         expires: None,
+        max_uses: None,
+        permissions: None,
     };
     let owner = mock_info(
         "demeter",
@@ -620,9 +727,17 @@ fn approving_all_revoking_all() {
     assert_eq!(
         res,
         Response::new()
+            .add_event(
+                Event::new("cw721_approve_all")
+                    .add_attribute("granter", "demeter")
+                    .add_attribute("operator", "random")
+                    .add_attribute("expires", "never")
+            )
             .add_attribute("action", "approve_all")
             .add_attribute("sender", "demeter")
+            .add_attribute("granter", "demeter")
             .add_attribute("operator", "random")
+            .add_attribute("expires", "never")
     );
 
     // random can now transfer
@@ -655,6 +770,8 @@ fn approving_all_revoking_all() {
     let approve_all_msg = ExecuteMsg::ApproveAll {
         operator: String::from("operator"),
         expires: None,
+        max_uses: None,
+        permissions: None,
     };
     // person is now the owner of the tokens
     let owner = mock_info(
@@ -702,6 +819,8 @@ fn approving_all_revoking_all() {
     let approve_all_msg = ExecuteMsg::ApproveAll {
         operator: String::from("buddy"),
         expires: Some(buddy_expires),
+        max_uses: None,
+        permissions: None,
     };
     let owner = mock_info(
         "person",
@@ -797,7 +916,7 @@ fn test_set_withdraw_address() {
     let err = contract
         .set_withdraw_address(
             &mut deps.storage,
-            &mut deps.api,
+            &deps.api,
             &Addr::unchecked("other"),
             "foo".to_string()
         )
@@ -808,7 +927,7 @@ fn test_set_withdraw_address() {
     contract
         .set_withdraw_address(
             &mut deps.storage,
-            &mut deps.api,
+            &deps.api,
             &Addr::unchecked(MINTER),
             "foo".to_string()
         )
@@ -839,7 +958,7 @@ fn test_remove_withdraw_address() {
     contract
         .set_withdraw_address(
             &mut deps.storage,
-            &mut deps.api,
+            &deps.api,
             &Addr::unchecked(MINTER),
             "foo".to_string()
         )
@@ -851,7 +970,7 @@ fn test_remove_withdraw_address() {
     contract
         .set_withdraw_address(
             &mut deps.storage,
-            &mut deps.api,
+            &deps.api,
             &Addr::unchecked(MINTER),
             "foo".to_string()
         )
@@ -866,19 +985,22 @@ fn test_withdraw_funds() {
     let contract = setup_contract(deps.as_mut());
 
     // no withdraw address set
-    let err = contract.withdraw_funds(deps.as_mut().storage, &Coin::new(100, "uark")).unwrap_err();
+    let block = mock_env().block;
+    let err = contract
+        .withdraw_funds(deps.as_mut().storage, &block, &Coin::new(100, "uark"))
+        .unwrap_err();
     assert_eq!(err, ContractError::NoWithdrawAddress {});
 
     // set and withdraw by non-owner
     contract
         .set_withdraw_address(
             &mut deps.storage,
-            &mut deps.api,
+            &deps.api,
             &Addr::unchecked(MINTER),
             "foo".to_string()
         )
         .unwrap();
-    contract.withdraw_funds(deps.as_mut().storage, &Coin::new(100, "uark")).unwrap();
+    contract.withdraw_funds(deps.as_mut().storage, &block, &Coin::new(100, "uark")).unwrap();
 }
 
 #[test]
@@ -945,3 +1067,6725 @@ fn query_tokens_by_owner() {
         .unwrap();
     assert_eq!(&by_demeter[1..], &tokens.tokens[..]);
 }
+
+#[test]
+fn tracking_transfer_stats() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let token_id = "0".to_string();
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+    };
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+    contract.execute(deps.as_mut(), mock_env(), minter, mint_msg).unwrap();
+
+    // freshly minted token has never moved
+    let stats = contract.token_stats(deps.as_ref(), token_id.clone()).unwrap();
+    assert_eq!(stats, TokenStatsResponse {
+        transfer_count: 0,
+        last_transferred_at: None,
+    });
+
+    // transfer venus -> mars -> venus, then send to a contract
+    let mut env = mock_env();
+    for (sender, recipient) in [("venus", "mars"), ("mars", "venus")] {
+        env.block.time = env.block.time.plus_seconds(60);
+        let transfer_msg = ExecuteMsg::TransferNft {
+            recipient: String::from(recipient),
+            token_id: token_id.clone(),
+        };
+        contract.execute(deps.as_mut(), env.clone(), mock_info(sender, &[]), transfer_msg).unwrap();
+    }
+    env.block.time = env.block.time.plus_seconds(60);
+    let send_msg = ExecuteMsg::SendNft {
+        contract: String::from("another_contract"),
+        token_id: token_id.clone(),
+        msg: to_json_binary("hold this").unwrap(),
+    };
+    contract.execute(deps.as_mut(), env.clone(), mock_info("venus", &[]), send_msg).unwrap();
+
+    let stats: TokenStatsResponse = from_json(
+        contract
+            .query(deps.as_ref(), mock_env(), QueryMsg::TokenStats { token_id: token_id.clone() })
+            .unwrap()
+    ).unwrap();
+    assert_eq!(stats, TokenStatsResponse {
+        transfer_count: 3,
+        last_transferred_at: Some(env.block.time),
+    });
+
+    // approvals do not count as movement
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("random"),
+        token_id: token_id.clone(),
+        expires: None,
+    };
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("another_contract", &[]), approve_msg)
+        .unwrap();
+    let stats = contract.token_stats(deps.as_ref(), token_id).unwrap();
+    assert_eq!(stats.transfer_count, 3);
+
+    // tokens stored before the counters existed load with defaults
+    let legacy: TokenInfo<Extension> = from_json(
+        br#"{"owner":"venus","approvals":[],"token_uri":null,"extension":null}"#
+    ).unwrap();
+    assert_eq!(legacy.transfer_count, 0);
+    assert_eq!(legacy.last_transferred_at, None);
+}
+
+#[test]
+fn burning_all() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+
+    // demeter gets two tokens, ceres one
+    for owner in ["demeter", "ceres", "demeter"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg).unwrap();
+    }
+
+    // an operator cannot burn on the owner's behalf, it only burns its own (empty) wallet
+    let approve_all_msg = ExecuteMsg::ApproveAll {
+        operator: String::from("random"),
+        expires: None,
+        max_uses: None,
+        permissions: None,
+    };
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), approve_all_msg)
+        .unwrap();
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), ExecuteMsg::BurnAll {
+            start_after: None,
+            limit: None,
+        })
+        .unwrap();
+    let burned: TokensResponse = from_json(res.data.unwrap()).unwrap();
+    assert!(burned.tokens.is_empty());
+    assert_eq!(3, contract.num_tokens(deps.as_ref()).unwrap().count);
+
+    // partial batch
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), ExecuteMsg::BurnAll {
+            start_after: None,
+            limit: Some(1),
+        })
+        .unwrap();
+    let burned: TokensResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(burned.tokens, vec!["0"]);
+    assert_eq!(2, contract.num_tokens(deps.as_ref()).unwrap().count);
+
+    // the rest
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), ExecuteMsg::BurnAll {
+            start_after: None,
+            limit: None,
+        })
+        .unwrap();
+    let burned: TokensResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(burned.tokens, vec!["2"]);
+
+    // wallet is now empty
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), ExecuteMsg::BurnAll {
+            start_after: None,
+            limit: None,
+        })
+        .unwrap();
+    let burned: TokensResponse = from_json(res.data.unwrap()).unwrap();
+    assert!(burned.tokens.is_empty());
+
+    // ceres still holds her token
+    let tokens = contract.all_tokens(deps.as_ref(), None, None).unwrap();
+    assert_eq!(tokens.tokens, vec!["1"]);
+    assert_eq!(1, contract.num_tokens(deps.as_ref()).unwrap().count);
+}
+
+#[test]
+fn burning_all_skips_staked_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+    for owner in ["demeter", "demeter", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg).unwrap();
+    }
+    // the first token of the wallet can't be burned
+    let stake_msg = ExecuteMsg::Stake { token_id: "0".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), stake_msg).unwrap();
+
+    // the staked token takes up the whole batch
+    let burn_all = |start_after: Option<&str>| ExecuteMsg::BurnAll {
+        start_after: start_after.map(String::from),
+        limit: Some(1),
+    };
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_all(None))
+        .unwrap();
+    let burned: TokensResponse = from_json(res.data.unwrap()).unwrap();
+    assert!(burned.tokens.is_empty());
+    assert!(res.attributes.contains(&cosmwasm_std::attr("done", "false")));
+    assert!(res.attributes.contains(&cosmwasm_std::attr("next_start_after", "0")));
+
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_all(Some("0")))
+        .unwrap();
+    let burned: TokensResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(burned.tokens, vec!["1"]);
+    assert!(res.attributes.contains(&cosmwasm_std::attr("next_start_after", "1")));
+
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_all(Some("1")))
+        .unwrap();
+    let burned: TokensResponse = from_json(res.data.unwrap()).unwrap();
+    assert!(burned.tokens.is_empty());
+    assert!(res.attributes.contains(&cosmwasm_std::attr("done", "true")));
+    assert!(!res.attributes.iter().any(|attr| attr.key == "next_start_after"));
+
+    // only the staked token is left in the wallet
+    let tokens = contract.all_tokens(deps.as_ref(), None, None).unwrap();
+    assert_eq!(tokens.tokens, vec!["0", "2"]);
+}
+
+#[test]
+fn nesting_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+
+    // avatar "0" and hat "1" for demeter, sword "2" for ceres
+    for owner in ["demeter", "demeter", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg).unwrap();
+    }
+    let nest = |child_id: &str, parent_id: &str| ExecuteMsg::NestToken {
+        child_id: child_id.to_string(),
+        parent_id: parent_id.to_string(),
+    };
+
+    // can't nest into someone else's token
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), nest("1", "2"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), nest("1", "0")).unwrap();
+
+    let parent: Option<String> = from_json(
+        contract
+            .query(deps.as_ref(), mock_env(), QueryMsg::Parent { token_id: "1".to_string() })
+            .unwrap()
+    ).unwrap();
+    assert_eq!(parent, Some("0".to_string()));
+    let children = contract.children(deps.as_ref(), "0".to_string(), None, None).unwrap();
+    assert_eq!(children.tokens, vec!["1"]);
+
+    // cycles are rejected
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), nest("0", "1"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NestingCycle {});
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), nest("0", "0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NestingCycle {});
+
+    // nested token can't move or burn on its own, neither can a parent be burned
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "1".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), transfer_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenNested {});
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), ExecuteMsg::Burn {
+            token_id: "0".to_string(),
+        })
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenHasChildren {});
+
+    // moving the parent moves the child with it
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), transfer_msg).unwrap();
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "1".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "ceres");
+
+    // the old owner can't unnest anymore, the new one can
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), ExecuteMsg::UnnestToken {
+            child_id: "1".to_string(),
+        })
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), ExecuteMsg::UnnestToken {
+            child_id: "1".to_string(),
+        })
+        .unwrap();
+    let tokens = contract.tokens(deps.as_ref(), "ceres".to_string(), None, None).unwrap();
+    assert_eq!(tokens.tokens, vec!["0", "1", "2"]);
+    let children = contract.children(deps.as_ref(), "0".to_string(), None, None).unwrap();
+    assert!(children.tokens.is_empty());
+}
+
+#[test]
+fn burning_by_operators() {
+    for operators_can_burn in [true, false] {
+        let mut deps = mock_dependencies();
+        let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+        let msg = InstantiateMsg {
+            name: CONTRACT_NAME.to_string(),
+            symbol: SYMBOL.to_string(),
+            minter: Some(String::from(MINTER)),
+            operators_can_burn: Some(operators_can_burn),
+            ..Default::default()
+        };
+        contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let can_burn: bool = from_json(
+            contract.query(deps.as_ref(), mock_env(), QueryMsg::OperatorsCanBurn {}).unwrap()
+        ).unwrap();
+        assert_eq!(can_burn, operators_can_burn);
+
+        for _ in 0..2 {
+            let mint_msg = ExecuteMsg::Mint {
+                owner: String::from("demeter"),
+                token_uri: None,
+                extension: None,
+            };
+            contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+        }
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from("operator"),
+            expires: None,
+            max_uses: None,
+            permissions: None,
+        };
+        contract
+            .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), approve_all_msg)
+            .unwrap();
+
+        // the operator may always transfer
+        let transfer_msg = ExecuteMsg::TransferNft {
+            recipient: String::from("demeter"),
+            token_id: "1".to_string(),
+        };
+        contract
+            .execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), transfer_msg)
+            .unwrap();
+
+        // but burning depends on the flag
+        let res = contract.execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("operator", &[]),
+            ExecuteMsg::Burn { token_id: "0".to_string() }
+        );
+        if operators_can_burn {
+            res.unwrap();
+        } else {
+            assert_eq!(res.unwrap_err(), ContractError::OnlyOwnerCanBurn {});
+            // the owner still can
+            contract
+                .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), ExecuteMsg::Burn {
+                    token_id: "0".to_string(),
+                })
+                .unwrap();
+        }
+    }
+}
+
+#[test]
+fn sweeping_expired_operators() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let env = mock_env();
+
+    // demeter grants two short-lived and one permanent operator, ceres one short-lived
+    let grants = [
+        ("demeter", "buddy", Some(Expiration::AtHeight(env.block.height + 10))),
+        ("demeter", "friend", Some(Expiration::AtHeight(env.block.height + 10))),
+        ("demeter", "market", None),
+        ("ceres", "buddy", Some(Expiration::AtHeight(env.block.height + 10))),
+    ];
+    for (granter, operator, expires) in grants {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from(operator),
+            expires,
+            max_uses: None,
+            permissions: None,
+        };
+        contract
+            .execute(deps.as_mut(), env.clone(), mock_info(granter, &[]), approve_all_msg)
+            .unwrap();
+    }
+
+    // nothing has expired yet
+    let sweep_all = ExecuteMsg::SweepExpiredOperators {
+        granter: None,
+        limit: None,
+    };
+    let res = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), sweep_all.clone())
+        .unwrap();
+    assert_eq!(res.attributes[1].value, "0");
+
+    let mut late_env = mock_env();
+    late_env.block.height += 11;
+
+    // sweep only demeter, one at a time
+    let sweep_demeter = ExecuteMsg::SweepExpiredOperators {
+        granter: Some(String::from("demeter")),
+        limit: Some(1),
+    };
+    let res = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), sweep_demeter.clone())
+        .unwrap();
+    assert_eq!(
+        res,
+        Response::new()
+            .add_attribute("action", "sweep_expired_operators")
+            .add_attribute("count", "1")
+            .add_attribute("removed", "demeter:buddy")
+    );
+    contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), sweep_demeter)
+        .unwrap();
+
+    // the permanent grant survives, ceres untouched
+    let res = contract
+        .operators(deps.as_ref(), late_env.clone(), String::from("demeter"), true, None, None)
+        .unwrap();
+    assert_eq!(res.operators, vec![cw721::Approval {
+        spender: String::from("market"),
+        expires: Expiration::Never {},
+    }]);
+    let res = contract
+        .operators(deps.as_ref(), late_env.clone(), String::from("ceres"), true, None, None)
+        .unwrap();
+    assert_eq!(res.operators.len(), 1);
+
+    // global sweep picks up the rest
+    let res = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), sweep_all)
+        .unwrap();
+    assert_eq!(res.attributes[2].value, "ceres:buddy");
+    let res = contract
+        .operators(deps.as_ref(), late_env, String::from("ceres"), true, None, None)
+        .unwrap();
+    assert!(res.operators.is_empty());
+}
+
+#[test]
+fn validating_address_prefix() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        bech32_prefix: Some(String::from("sei")),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let wrong_prefix = ContractError::WrongAddressPrefix {
+        expected: String::from("sei"),
+        got: String::from("cosmos"),
+    };
+
+    // mint
+    let mint_msg = |owner: &str| ExecuteMsg::Mint {
+        owner: String::from(owner),
+        token_uri: None,
+        extension: None,
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg("cosmos1venus"))
+        .unwrap_err();
+    assert_eq!(err, wrong_prefix);
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg("sei1venus"))
+        .unwrap();
+
+    // transfer
+    let transfer_msg = |recipient: &str| ExecuteMsg::TransferNft {
+        recipient: String::from(recipient),
+        token_id: "0".to_string(),
+    };
+    let venus = mock_info("sei1venus", &[]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), transfer_msg("cosmos1mars"))
+        .unwrap_err();
+    assert_eq!(err, wrong_prefix);
+    contract.execute(deps.as_mut(), mock_env(), venus, transfer_msg("sei1mars")).unwrap();
+
+    // send
+    let send_msg = |contract: &str| ExecuteMsg::SendNft {
+        contract: String::from(contract),
+        token_id: "0".to_string(),
+        msg: to_json_binary("hold this").unwrap(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("sei1mars", &[]), send_msg("cosmos1market"))
+        .unwrap_err();
+    assert_eq!(err, wrong_prefix);
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("sei1mars", &[]), send_msg("sei1market"))
+        .unwrap();
+}
+
+#[test]
+fn redeeming_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        redemption_payout: Some(Coin::new(500, "usei")),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for owner in ["venus", "venus"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let redeem_msg = |token_id: &str| ExecuteMsg::Redeem {
+        token_id: token_id.to_string(),
+        redemption_data: Some("ship to olympus".to_string()),
+    };
+
+    // only the owner can redeem
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), redeem_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), redeem_msg("0")).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), redeem_msg("1")).unwrap();
+
+    // no double redeem, no transfers afterwards
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), redeem_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenRedeemed {});
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "0".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), transfer_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenRedeemed {});
+
+    // only the contract owner fulfills, paying out the configured coin
+    let fulfill_msg = ExecuteMsg::FulfillRedemption { token_id: "0".to_string() };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), fulfill_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), fulfill_msg.clone())
+        .unwrap();
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("venus"),
+            amount: vec![Coin::new(500, "usei")],
+        })
+    ]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), fulfill_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::RedemptionFulfilled {});
+
+    // the queue only holds the pending one
+    let res: RedemptionsResponse = from_json(
+        contract
+            .query(deps.as_ref(), mock_env(), QueryMsg::Redemptions {
+                status: Some(RedemptionStatus::Pending),
+                start_after: None,
+                limit: None,
+            })
+            .unwrap()
+    ).unwrap();
+    assert_eq!(res.redemptions.len(), 1);
+    assert_eq!(res.redemptions[0].token_id, "1");
+    assert_eq!(res.redemptions[0].redemption.redemption_data, Some("ship to olympus".to_string()));
+
+    let res = contract.redemption_status(deps.as_ref(), "0".to_string()).unwrap().unwrap();
+    assert_eq!(res.redemption.status, RedemptionStatus::Fulfilled);
+    assert_eq!(res.redemption.fulfilled_at, Some(mock_env().block.time));
+}
+
+#[test]
+fn transferring_from_expected_owner() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("alice"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), minter, mint_msg).unwrap();
+
+    // the operator is trusted by both alice and bob
+    for owner in ["alice", "bob"] {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from("operator"),
+            expires: None,
+            max_uses: None,
+            permissions: None,
+        };
+        contract
+            .execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), approve_all_msg)
+            .unwrap();
+    }
+
+    // alice sells to bob in the meantime
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("bob"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), transfer_msg).unwrap();
+
+    // the operator's stale intent to move alice's token no longer applies
+    let transfer_from_msg = ExecuteMsg::TransferFrom {
+        from: String::from("alice"),
+        recipient: String::from("carol"),
+        token_id: "0".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), transfer_from_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::UnexpectedOwner { actual: String::from("bob") });
+    let send_from_msg = ExecuteMsg::SendFrom {
+        from: String::from("alice"),
+        contract: String::from("market"),
+        token_id: "0".to_string(),
+        msg: to_json_binary("sold").unwrap(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), send_from_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::UnexpectedOwner { actual: String::from("bob") });
+
+    // with the right owner it goes through
+    let transfer_from_msg = ExecuteMsg::TransferFrom {
+        from: String::from("bob"),
+        recipient: String::from("carol"),
+        token_id: "0".to_string(),
+    };
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), transfer_from_msg)
+        .unwrap();
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "0".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "carol");
+}
+
+#[test]
+fn querying_approvals_by_spender() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+    for owner in ["demeter", "demeter", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg).unwrap();
+    }
+    let approve = |token_id: &str, expires: Option<Expiration>| ExecuteMsg::Approve {
+        spender: String::from("market"),
+        token_id: token_id.to_string(),
+        expires,
+    };
+    let expires = Expiration::AtHeight(mock_env().block.height + 10);
+    let demeter = mock_info("demeter", &[]);
+    contract.execute(deps.as_mut(), mock_env(), demeter.clone(), approve("0", None)).unwrap();
+    contract
+        .execute(deps.as_mut(), mock_env(), demeter.clone(), approve("1", Some(expires)))
+        .unwrap();
+    let ceres = mock_info("ceres", &[]);
+    contract.execute(deps.as_mut(), mock_env(), ceres, approve("2", None)).unwrap();
+
+    let by_spender = |deps: cosmwasm_std::Deps, env, start_after: Option<&str>, limit| {
+        contract
+            .approvals_by_spender(
+                deps,
+                env,
+                String::from("market"),
+                false,
+                start_after.map(String::from),
+                limit
+            )
+            .unwrap()
+            .approvals
+    };
+
+    // approve and paginate
+    assert_eq!(by_spender(deps.as_ref(), mock_env(), None, Some(2)), vec![
+        TokenApproval { token_id: "0".to_string(), expires: Expiration::Never {} },
+        TokenApproval { token_id: "1".to_string(), expires }
+    ]);
+    assert_eq!(by_spender(deps.as_ref(), mock_env(), Some("1"), Some(2)), vec![TokenApproval {
+        token_id: "2".to_string(),
+        expires: Expiration::Never {},
+    }]);
+
+    // expired approvals are filtered out
+    let mut late_env = mock_env();
+    late_env.block.height += 11;
+    assert_eq!(by_spender(deps.as_ref(), late_env, None, None).len(), 2);
+
+    // revoke removes the entry
+    let revoke_msg = ExecuteMsg::Revoke {
+        spender: String::from("market"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), demeter.clone(), revoke_msg).unwrap();
+
+    // transfer clears the entry
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "1".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), demeter, transfer_msg).unwrap();
+
+    assert_eq!(by_spender(deps.as_ref(), mock_env(), None, None), vec![TokenApproval {
+        token_id: "2".to_string(),
+        expires: Expiration::Never {},
+    }]);
+}
+
+#[test]
+fn approving_multiple_spenders() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("guild"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), minter, mint_msg).unwrap();
+
+    let expires = Expiration::AtHeight(mock_env().block.height + 10);
+    let approve_msg = |spenders: Vec<(&str, Option<Expiration>)>| ExecuteMsg::ApproveMultiple {
+        spenders: spenders
+            .into_iter()
+            .map(|(spender, expires)| (spender.to_string(), expires))
+            .collect(),
+        token_id: "0".to_string(),
+    };
+
+    // random cannot approve on behalf of the guild, nothing is written
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            approve_msg(vec![("alice", None), ("bob", None)])
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    // duplicates are rejected up front
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guild", &[]),
+            approve_msg(vec![("alice", None), ("bob", None), ("alice", Some(expires))])
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::DuplicateSpender { spender: String::from("alice") });
+
+    let approvals = contract
+        .approvals(deps.as_ref(), mock_env(), "0".to_string(), true)
+        .unwrap().approvals;
+    assert!(approvals.is_empty());
+
+    // mixed expirations
+    let res = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guild", &[]),
+            approve_msg(vec![("alice", None), ("bob", Some(expires)), ("carol", None)])
+        )
+        .unwrap();
+    assert_eq!(
+        res.attributes
+            .iter()
+            .filter(|attr| attr.key == "spender")
+            .map(|attr| attr.value.as_str())
+            .collect::<Vec<_>>(),
+        vec!["alice", "bob", "carol"]
+    );
+
+    let approvals = contract
+        .approvals(deps.as_ref(), mock_env(), "0".to_string(), true)
+        .unwrap().approvals;
+    assert_eq!(approvals, vec![
+        Approval { spender: String::from("alice"), expires: Expiration::Never {} },
+        Approval { spender: String::from("bob"), expires },
+        Approval { spender: String::from("carol"), expires: Expiration::Never {} }
+    ]);
+}
+
+#[test]
+fn capping_approvals_per_token() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), minter, mint_msg).unwrap();
+
+    let owner = mock_info("demeter", &[]);
+    let approve_msg = |spender: String| ExecuteMsg::Approve {
+        spender,
+        token_id: "0".to_string(),
+        expires: None,
+    };
+    for i in 0..DEFAULT_MAX_APPROVALS_PER_TOKEN {
+        let msg = approve_msg(format!("spender{}", i));
+        contract.execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+    }
+
+    // one more is too many
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), owner.clone(), approve_msg(String::from("late")))
+        .unwrap_err();
+    assert_eq!(err, ContractError::TooManyApprovals { max: DEFAULT_MAX_APPROVALS_PER_TOKEN });
+
+    // refreshing an existing spender does not grow the list
+    let msg = approve_msg(String::from("spender0"));
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+
+    // a batch overflowing the cap is rejected as a whole
+    let batch_msg = ExecuteMsg::ApproveMultiple {
+        spenders: vec![(String::from("spender1"), None), (String::from("late"), None)],
+        token_id: "0".to_string(),
+    };
+    let err = contract.execute(deps.as_mut(), mock_env(), owner.clone(), batch_msg).unwrap_err();
+    assert_eq!(err, ContractError::TooManyApprovals { max: DEFAULT_MAX_APPROVALS_PER_TOKEN });
+    let approvals = contract
+        .approvals(deps.as_ref(), mock_env(), "0".to_string(), true)
+        .unwrap().approvals;
+    assert_eq!(approvals.len() as u64, DEFAULT_MAX_APPROVALS_PER_TOKEN);
+    assert!(!approvals.iter().any(|apr| apr.spender == "late"));
+
+    // transferring still clears the full list
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("spender5", &[]), transfer_msg).unwrap();
+    let approvals = contract
+        .approvals(deps.as_ref(), mock_env(), "0".to_string(), true)
+        .unwrap().approvals;
+    assert!(approvals.is_empty());
+}
+
+#[test]
+fn defaulting_approval_expiration() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let ttl = 30 * 24 * 60 * 60;
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        default_approval_ttl: Some(ttl),
+        allow_never_expiration: Some(false),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let owner = mock_info("demeter", &[]);
+    let expected = Expiration::AtTime(mock_env().block.time.plus_seconds(ttl));
+
+    // approve without expiration gets the default ttl
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("random"),
+        token_id: "0".to_string(),
+        expires: None,
+    };
+    let res = contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_msg).unwrap();
+    let approvals = contract
+        .approvals(deps.as_ref(), mock_env(), "0".to_string(), true)
+        .unwrap().approvals;
+    assert_eq!(approvals, vec![Approval { spender: String::from("random"), expires: expected }]);
+    let expires_attr = format!("at_time:{}", mock_env().block.time.plus_seconds(ttl).nanos());
+    assert_eq!(res.events[0].attributes[3].value, expires_attr);
+
+    // so does approve_all
+    let approve_all_msg = ExecuteMsg::ApproveAll {
+        operator: String::from("operator"),
+        expires: None,
+        max_uses: None,
+        permissions: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg).unwrap();
+    let operator = contract.operators
+        .load(&deps.storage, (&Addr::unchecked("demeter"), &Addr::unchecked("operator")))
+        .unwrap();
+    assert_eq!(operator.expires, expected);
+
+    // an explicit never is refused
+    let never_msg = ExecuteMsg::ApproveAll {
+        operator: String::from("operator"),
+        expires: Some(Expiration::Never {}),
+        max_uses: None,
+        permissions: None,
+    };
+    let err = contract.execute(deps.as_mut(), mock_env(), owner, never_msg).unwrap_err();
+    assert_eq!(err, ContractError::NeverExpirationNotAllowed {});
+
+    // once the ttl passed, neither grant can move tokens
+    let mut late_env = mock_env();
+    late_env.block.time = late_env.block.time.plus_seconds(ttl);
+    let transfer_msg = |token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: token_id.to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("random", &[]), transfer_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let err = contract
+        .execute(deps.as_mut(), late_env, mock_info("operator", &[]), transfer_msg("1"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    // but could right before
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), transfer_msg("0"))
+        .unwrap();
+}
+
+#[test]
+fn revoking_all_token_approvals() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let minter = mock_info(
+        MINTER,
+        &[Coin { denom: "usei".to_string(), amount: Uint128::new(1000000) }]
+    );
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), minter.clone(), mint_msg).unwrap();
+    }
+
+    let owner = mock_info("demeter", &[]);
+    for token_id in ["0", "1"] {
+        let approve_msg = ExecuteMsg::ApproveMultiple {
+            spenders: vec![(String::from("market1"), None), (String::from("market2"), None)],
+            token_id: token_id.to_string(),
+        };
+        contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_msg).unwrap();
+    }
+
+    // random cannot revoke
+    let revoke_msg = ExecuteMsg::RevokeAllTokenApprovals { token_id: "0".to_string() };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), revoke_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    let res = contract.execute(deps.as_mut(), mock_env(), owner, revoke_msg).unwrap();
+    assert_eq!(
+        res,
+        Response::new()
+            .add_attribute("action", "revoke_all_token_approvals")
+            .add_attribute("sender", "demeter")
+            .add_attribute("token_id", "0")
+            .add_attribute("count", "2")
+            .add_attribute("spender", "market1")
+            .add_attribute("spender", "market2")
+    );
+
+    // the other token keeps its approvals
+    let approvals = contract
+        .approvals(deps.as_ref(), mock_env(), "1".to_string(), true)
+        .unwrap().approvals;
+    assert_eq!(approvals.len(), 2);
+    let approvals = contract
+        .approvals_by_spender(deps.as_ref(), mock_env(), String::from("market1"), true, None, None)
+        .unwrap().approvals;
+    assert_eq!(approvals, vec![TokenApproval {
+        token_id: "1".to_string(),
+        expires: Expiration::Never {},
+    }]);
+
+    // a formerly approved spender can no longer move the token
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("market1"),
+        token_id: "0".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("market1", &[]), transfer_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+}
+
+#[test]
+fn limiting_operator_uses() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..4 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let owner = mock_info("demeter", &[]);
+    let approve_all_msg = |operator: &str, max_uses| ExecuteMsg::ApproveAll {
+        operator: String::from(operator),
+        expires: None,
+        max_uses,
+        permissions: None,
+    };
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg("rental", Some(2)))
+        .unwrap();
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "max_uses")
+            .map(|attr| attr.value.as_str()),
+        Some("2")
+    );
+    contract
+        .execute(deps.as_mut(), mock_env(), owner, approve_all_msg("unlimited", None))
+        .unwrap();
+
+    let transfer_msg = |token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: token_id.to_string(),
+    };
+    let remaining = |deps: cosmwasm_std::Deps| {
+        contract.operators
+            .load(deps.storage, (&Addr::unchecked("demeter"), &Addr::unchecked("rental")))
+            .unwrap().remaining_uses
+    };
+
+    // every move by the operator spends one use
+    let rental = mock_info("rental", &[]);
+    contract.execute(deps.as_mut(), mock_env(), rental.clone(), transfer_msg("0")).unwrap();
+    assert_eq!(remaining(deps.as_ref()), Some(1));
+    let burn_msg = ExecuteMsg::Burn { token_id: "1".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), rental.clone(), burn_msg).unwrap();
+    assert_eq!(remaining(deps.as_ref()), Some(0));
+
+    // the budget is exhausted
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), rental.clone(), transfer_msg("2"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorBudgetExhausted {});
+
+    // nor can it approve itself, or anyone else, to move tokens outside the budget
+    for spender in ["rental", "accomplice"] {
+        let approve_msg = ExecuteMsg::Approve {
+            spender: String::from(spender),
+            token_id: "2".to_string(),
+            expires: None,
+        };
+        let err = contract
+            .execute(deps.as_mut(), mock_env(), rental.clone(), approve_msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::BudgetedOperatorApproval {});
+    }
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), rental, transfer_msg("2"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorBudgetExhausted {});
+
+    // an operator without a budget is not limited
+    let unlimited = mock_info("unlimited", &[]);
+    contract.execute(deps.as_mut(), mock_env(), unlimited.clone(), transfer_msg("2")).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), unlimited, transfer_msg("3")).unwrap();
+    let grant = contract.operators
+        .load(&deps.storage, (&Addr::unchecked("demeter"), &Addr::unchecked("unlimited")))
+        .unwrap();
+    assert_eq!(grant.remaining_uses, None);
+}
+
+#[test]
+fn migrating_operator_grants() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let legacy: cw_storage_plus::Map<(&Addr, &Addr), Expiration> = cw_storage_plus::Map::new(
+        "operators"
+    );
+    let owner = Addr::unchecked("demeter");
+    let operator = Addr::unchecked("operator");
+    legacy.save(&mut deps.storage, (&owner, &operator), &Expiration::AtHeight(100)).unwrap();
+
+    let migrated = crate::upgrades::v0_18::migrate_operator_grants(&mut deps.storage).unwrap();
+    assert_eq!(migrated, 1);
+    let grant = contract.operators.load(&deps.storage, (&owner, &operator)).unwrap();
+    assert_eq!(grant, OperatorGrant {
+        expires: Expiration::AtHeight(100),
+        remaining_uses: None,
+        permissions: OperatorPermissions::default(),
+    });
+    let indexed = contract.operator_granters.load(&deps.storage, (&operator, &owner)).unwrap();
+    assert_eq!(indexed, Expiration::AtHeight(100));
+}
+
+#[test]
+fn approving_with_permit() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    // fixed test vectors, signed with the secp256k1 private key [7u8; 32]
+    let owner = "sei150rtrmj2f8vl9tem8qpfw36ylw5jg9j270mllc";
+    let pubkey = Binary::from_base64("ApicC3bLVjlx/cm+8x7AbDVg8ySdbunl2DxXYlWW4F9v").unwrap();
+    let permit = |spender: &str, token_id: Option<&str>, height: u64, nonce, signature| {
+        ExecuteMsg::ApproveWithPermit {
+            permit: ApprovalPermit {
+                params: PermitParams {
+                    owner: String::from(owner),
+                    spender: String::from(spender),
+                    token_id: token_id.map(String::from),
+                    expires: Expiration::AtHeight(height),
+                    nonce,
+                },
+                pubkey: pubkey.clone(),
+                signature: Binary::from_base64(signature).unwrap(),
+            },
+        }
+    };
+    let token_permit = permit(
+        "market",
+        Some("0"),
+        12_445,
+        0,
+        "pElGx0Nvvs+HegGQm1RcPun2+7vAhThxUbq5MV8XiCdv5XmlAW0+rJ6CwAlDJCHtgoyktBMyOn1ZIkyuDJwy8Q=="
+    );
+    let operator_permit = permit(
+        "operator",
+        None,
+        12_445,
+        1,
+        "Bq+oamEpiAH7dDZjb/mIVLhDCnpPw1GAnKiKgiTt0MkLoW4cTokU7reNK6OupnJLVclYTb/Tbx6SnROnGHFrHA=="
+    );
+    let expired_permit = permit(
+        "market",
+        Some("0"),
+        12_345,
+        2,
+        "DoWDc7OvI1McPDYBwzeJ4lKFb3KB8/8ZhRUJPecGUdBPkkdSqrtEUpRj4FMXLjT2oyz+zmVrp/AMqQqU541+jQ=="
+    );
+
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from(owner),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+
+    // anyone can submit a valid permit
+    let relayer = mock_info("market", &[]);
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), relayer.clone(), token_permit.clone())
+        .unwrap();
+    assert_eq!(
+        res,
+        Response::new()
+            .add_event(
+                Event::new("cw721_approve")
+                    .add_attribute("granter", owner)
+                    .add_attribute("spender", "market")
+                    .add_attribute("token_id", "0")
+                    .add_attribute("expires", "at_height:12445")
+            )
+            .add_attribute("action", "approve")
+            .add_attribute("sender", owner)
+            .add_attribute("granter", owner)
+            .add_attribute("spender", "market")
+            .add_attribute("token_id", "0")
+            .add_attribute("expires", "at_height:12445")
+            .add_attribute("permit_nonce", "0")
+    );
+    let approvals = contract
+        .approvals(deps.as_ref(), mock_env(), "0".to_string(), false)
+        .unwrap().approvals;
+    assert_eq!(approvals, vec![Approval {
+        spender: String::from("market"),
+        expires: Expiration::AtHeight(12_445),
+    }]);
+
+    // a permit can only be used once
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), relayer.clone(), token_permit.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::PermitReplayed { nonce: 0 });
+
+    // operator permits work the same way
+    contract.execute(deps.as_mut(), mock_env(), relayer.clone(), operator_permit.clone()).unwrap();
+    let res = contract
+        .operator(
+            deps.as_ref(),
+            mock_env(),
+            String::from(owner),
+            String::from("operator"),
+            false
+        )
+        .unwrap();
+    assert_eq!(res.approval.expires, Expiration::AtHeight(12_445));
+
+    // expired permits are rejected
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), relayer.clone(), expired_permit)
+        .unwrap_err();
+    assert_eq!(err, ContractError::PermitExpired {});
+
+    // tampering with the params breaks the signature
+    let mut tampered = operator_permit;
+    if let ExecuteMsg::ApproveWithPermit { permit } = &mut tampered {
+        permit.params.spender = String::from("thief");
+        permit.params.nonce = 3;
+    }
+    let err = contract.execute(deps.as_mut(), mock_env(), relayer.clone(), tampered).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPermitSignature {});
+
+    // a valid signature from another key does not match the owner
+    let mut wrong_owner = token_permit;
+    if let ExecuteMsg::ApproveWithPermit { permit } = &mut wrong_owner {
+        permit.params.owner = String::from("demeter");
+    }
+    let err = contract.execute(deps.as_mut(), mock_env(), relayer, wrong_owner).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPermitSignature {});
+}
+
+#[test]
+fn redeeming_vouchers() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    // fixed test vectors, signed with the secp256k1 private key [8u8; 32]
+    let pubkey = Binary::from_base64("A/mR+UTR4ZVKf8i5v2Lg148BX0wHdi1QXiDmxFJgo2Yb").unwrap();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        price_per_nft: Some(Coin::new(1000, "usei")),
+        max_supply: Some(2),
+        voucher_pubkey: Some(pubkey),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let voucher = |collection: &str, token_id: Option<&str>, price, height, nonce| MintVoucher {
+        collection: String::from(collection),
+        token_id: token_id.map(String::from),
+        token_uri: Some(String::from("ipfs://voucher")),
+        extension: None,
+        price,
+        expires: Expiration::AtHeight(height),
+        nonce,
+    };
+    let redeem = |voucher: MintVoucher<Extension>, signature: &str| ExecuteMsg::RedeemVoucher {
+        voucher,
+        signature: Binary::from_base64(signature).unwrap(),
+    };
+    let collection = mock_env().contract.address.to_string();
+    let price = Coin::new(500, "usei");
+    let paid = redeem(
+        voucher(&collection, None, Some(price.clone()), 12_445, 0),
+        "7SqoH+o/QHYrynvvqjdlpsLhD1gxYkks5x/nLk1U+SJXtAkPlM5xWaqjXyPL2OUjDVFse+oeEScDLzoySTlODA=="
+    );
+    let named = redeem(
+        voucher(&collection, Some("genesis"), None, 12_445, 1),
+        "Z2vlN038O9uxC2Xhh0dydryJ6LOPMUM7Dt/k0RHA7n0H4JmOSeYq3PsuusewCO6VF2sGJyl7Qwu9ftrODa9HBQ=="
+    );
+    let expired = redeem(
+        voucher(&collection, None, Some(price.clone()), 12_345, 2),
+        "NaukbmvBET66U/8xc1fWsule//iwBVg59Ep5dsSkma4yLCyMzkeFRNkNNcXQQhkGXOLS7bGE2TmkChV4KsW+og=="
+    );
+    let numeric = redeem(
+        voucher(&collection, Some("7"), None, 12_445, 3),
+        "o0ZvB+DkbzQRZ/ve/lu7uUkA/+6+V7e0QmvOBCfd+xlPzT2p9UzVn00mvPL1E1NdjTcQyoQlHZWV8QT9V6M+LA=="
+    );
+    let elsewhere = redeem(
+        voucher("other_collection", None, None, 12_445, 4),
+        "g/O+Ym9iQBtruU4kD02K82HCoFyuBG/JJ2l+XSG2I000on/CzdvhZkKt+2zjb+KvHu9Jf/8YJpJzr3NMTkZ5/w=="
+    );
+    let sold_out = redeem(
+        voucher(&collection, None, None, 12_445, 5),
+        "0d+trnoHNpBRaYA0WzuB2pEApZidgL2yb2DZD8KLfjlpsFNTMdXF3BpkQr9ahM2hG7K48Q57sg0cjQbe/lUa7g=="
+    );
+
+    // the voucher price replaces the mint price and has to be paid exactly
+    let overpaid = mock_info("buyer", &[Coin::new(1000, "usei")]);
+    let err = contract.execute(deps.as_mut(), mock_env(), overpaid, paid.clone()).unwrap_err();
+    assert_eq!(err, ContractError::WrongVoucherPayment { price: price.clone() });
+    let buyer = mock_info("buyer", &[Coin::new(500, "usei")]);
+    contract.execute(deps.as_mut(), mock_env(), buyer.clone(), paid.clone()).unwrap();
+    let token = contract.nft_info(deps.as_ref(), "0".to_string()).unwrap();
+    assert_eq!(token.token_uri, Some(String::from("ipfs://voucher")));
+    assert_eq!(contract.tokens.load(&deps.storage, &token_key("0")).unwrap().owner, "buyer");
+    let err = contract.execute(deps.as_mut(), mock_env(), buyer.clone(), paid).unwrap_err();
+    assert_eq!(err, ContractError::VoucherReplayed { nonce: 0 });
+
+    // anything changed after signing breaks the signature
+    let tampered = redeem(
+        voucher(&collection, None, Some(Coin::new(1, "usei")), 12_445, 6),
+        "7SqoH+o/QHYrynvvqjdlpsLhD1gxYkks5x/nLk1U+SJXtAkPlM5xWaqjXyPL2OUjDVFse+oeEScDLzoySTlODA=="
+    );
+    let err = contract.execute(deps.as_mut(), mock_env(), buyer.clone(), tampered).unwrap_err();
+    assert_eq!(err, ContractError::InvalidVoucherSignature {});
+    let err = contract.execute(deps.as_mut(), mock_env(), buyer.clone(), expired).unwrap_err();
+    assert_eq!(err, ContractError::VoucherExpired {});
+    let free = mock_info("buyer", &[]);
+    let err = contract.execute(deps.as_mut(), mock_env(), free.clone(), elsewhere).unwrap_err();
+    assert_eq!(err, ContractError::VoucherForOtherCollection {});
+    let err = contract.execute(deps.as_mut(), mock_env(), free.clone(), numeric).unwrap_err();
+    assert_eq!(err, ContractError::TokenIdReserved { token_id: String::from("7") });
+
+    // a free voucher can name its token
+    let err = contract.execute(deps.as_mut(), mock_env(), buyer, named.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Payment(_)));
+    contract.execute(deps.as_mut(), mock_env(), free.clone(), named).unwrap();
+    assert_eq!(contract.tokens.load(&deps.storage, &token_key("genesis")).unwrap().owner, "buyer");
+
+    // vouchers count towards the max supply
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), free.clone(), sold_out.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::SupplyOverflow {});
+
+    let unset_msg = ExecuteMsg::SetVoucherPubkey { pubkey: None };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), unset_msg).unwrap();
+    let err = contract.execute(deps.as_mut(), mock_env(), free, sold_out).unwrap_err();
+    assert_eq!(err, ContractError::NoVoucherPubkey {});
+}
+
+#[test]
+fn querying_granters_of_operator() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let expires = Expiration::AtHeight(mock_env().block.height + 10);
+    let approve_all_msg = |expires| ExecuteMsg::ApproveAll {
+        operator: String::from("market"),
+        expires,
+        max_uses: None,
+        permissions: None,
+    };
+    for (granter, expires) in [("ceres", None), ("demeter", Some(expires)), ("apollo", None)] {
+        contract
+            .execute(deps.as_mut(), mock_env(), mock_info(granter, &[]), approve_all_msg(expires))
+            .unwrap();
+    }
+    // an unrelated grant
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), ExecuteMsg::ApproveAll {
+            operator: String::from("other"),
+            expires: None,
+            max_uses: None,
+            permissions: None,
+        })
+        .unwrap();
+
+    let granters = |deps: cosmwasm_std::Deps, env, include_expired, start_after: Option<&str>| {
+        contract
+            .granters_of_operator(
+                deps,
+                env,
+                String::from("market"),
+                include_expired,
+                start_after.map(String::from),
+                Some(2)
+            )
+            .unwrap()
+            .granters
+    };
+
+    // ordered by granter and paginated
+    assert_eq!(granters(deps.as_ref(), mock_env(), false, None), vec![
+        Granter { granter: String::from("apollo"), expires: Expiration::Never {} },
+        Granter { granter: String::from("ceres"), expires: Expiration::Never {} }
+    ]);
+    assert_eq!(granters(deps.as_ref(), mock_env(), false, Some("ceres")), vec![Granter {
+        granter: String::from("demeter"),
+        expires,
+    }]);
+
+    // expired grants are filtered out unless asked for
+    let mut late_env = mock_env();
+    late_env.block.height += 10;
+    assert_eq!(granters(deps.as_ref(), late_env.clone(), false, Some("ceres")), vec![]);
+    assert_eq!(granters(deps.as_ref(), late_env.clone(), true, Some("ceres")).len(), 1);
+
+    // revoking and sweeping keep the index in sync
+    let revoke_msg = ExecuteMsg::RevokeAll { operator: String::from("market") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("apollo", &[]), revoke_msg).unwrap();
+    let sweep_msg = ExecuteMsg::SweepExpiredOperators { granter: None, limit: None };
+    contract.execute(deps.as_mut(), late_env.clone(), mock_info("random", &[]), sweep_msg).unwrap();
+    assert_eq!(granters(deps.as_ref(), late_env, true, None), vec![Granter {
+        granter: String::from("ceres"),
+        expires: Expiration::Never {},
+    }]);
+}
+
+#[test]
+fn extending_operators() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let height = mock_env().block.height;
+    let owner = mock_info("demeter", &[]);
+    let approve_all_msg = ExecuteMsg::ApproveAll {
+        operator: String::from("market"),
+        expires: Some(Expiration::AtHeight(height + 10)),
+        max_uses: Some(3),
+        permissions: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg).unwrap();
+
+    let extend_msg = |operator: &str, new_expiration| ExecuteMsg::ExtendOperator {
+        operator: String::from(operator),
+        new_expiration,
+    };
+
+    // extend in place, keeping the budget
+    let res = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            extend_msg("market", Expiration::AtHeight(height + 100))
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        Response::new()
+            .add_attribute("action", "extend_operator")
+            .add_attribute("sender", "demeter")
+            .add_attribute("operator", "market")
+            .add_attribute("expires", format!("at_height:{}", height + 100))
+    );
+    let grant = contract.operators
+        .load(&deps.storage, (&Addr::unchecked("demeter"), &Addr::unchecked("market")))
+        .unwrap();
+    assert_eq!(grant, OperatorGrant {
+        expires: Expiration::AtHeight(height + 100),
+        remaining_uses: Some(3),
+        permissions: OperatorPermissions::default(),
+    });
+    let granters = contract
+        .granters_of_operator(deps.as_ref(), mock_env(), String::from("market"), false, None, None)
+        .unwrap().granters;
+    assert_eq!(granters[0].expires, Expiration::AtHeight(height + 100));
+
+    // shortening is not allowed
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            extend_msg("market", Expiration::AtHeight(height + 50))
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExpirationNotLater {});
+
+    // nor is extending a grant that doesn't exist
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), owner, extend_msg("random", Expiration::Never {}))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorNotFound { operator: String::from("random") });
+}
+
+#[test]
+fn scoping_operator_permissions() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..4 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let only = |can_transfer, can_approve, can_burn| OperatorPermissions {
+        can_transfer,
+        can_approve,
+        can_burn,
+    };
+    let owner = mock_info("demeter", &[]);
+    for (operator, permissions) in [
+        ("mover", only(true, false, false)),
+        ("approver", only(false, true, false)),
+        ("burner", only(false, false, true)),
+    ] {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from(operator),
+            expires: None,
+            max_uses: None,
+            permissions: Some(permissions),
+        };
+        contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg).unwrap();
+    }
+
+    let transfer_msg = |token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: token_id.to_string(),
+    };
+    let approve_msg = |token_id: &str| ExecuteMsg::Approve {
+        spender: String::from("random"),
+        token_id: token_id.to_string(),
+        expires: None,
+    };
+    let burn_msg = |token_id: &str| ExecuteMsg::Burn { token_id: token_id.to_string() };
+    let not_permitted = |action: &str| ContractError::OperatorNotPermitted {
+        action: String::from(action),
+    };
+
+    // each operator can only do what it was granted
+    let operators = [("mover", "transfer"), ("approver", "approve"), ("burner", "burn")];
+    for (operator, allowed) in operators {
+        let info = mock_info(operator, &[]);
+        let attempts = [
+            ("transfer", transfer_msg("0")),
+            ("approve", approve_msg("0")),
+            ("burn", burn_msg("0")),
+        ];
+        for (action, msg) in attempts {
+            if action == allowed {
+                continue;
+            }
+            let err = contract.execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+            assert_eq!(err, not_permitted(action));
+        }
+    }
+
+    let allowed = [
+        ("approver", approve_msg("1")),
+        ("burner", burn_msg("2")),
+        ("mover", transfer_msg("3")),
+        // spenders approved by the approver are not limited by its permissions
+        ("random", transfer_msg("1")),
+    ];
+    for (sender, msg) in allowed {
+        contract.execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg).unwrap();
+    }
+
+    // the approver can't make itself a spender to transfer anyway
+    let approve_self_msg = ExecuteMsg::Approve {
+        spender: String::from("approver"),
+        token_id: "0".to_string(),
+        expires: None,
+    };
+    let approver = mock_info("approver", &[]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), approver.clone(), approve_self_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorSelfApproval {});
+    let approve_multiple_msg = ExecuteMsg::ApproveMultiple {
+        spenders: vec![(String::from("random"), None), (String::from("approver"), None)],
+        token_id: "0".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), approver.clone(), approve_multiple_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorSelfApproval {});
+    let err = contract.execute(deps.as_mut(), mock_env(), approver, transfer_msg("0")).unwrap_err();
+    assert_eq!(err, not_permitted("transfer"));
+}
+
+#[test]
+fn migrating_token_approvals() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let legacy: cw_storage_plus::Map<&str, TokenInfo<Extension>> = cw_storage_plus::Map::new(
+        "tokens"
+    );
+    let approval = |spender: &str, expires| crate::state::Approval {
+        spender: Addr::unchecked(spender),
+        expires,
+    };
+    let token = TokenInfo {
+        owner: Addr::unchecked("demeter"),
+        approvals: vec![
+            approval("market", Expiration::Never {}),
+            approval("random", Expiration::AtHeight(100))
+        ],
+        token_uri: None,
+        extension: None,
+        parent: None,
+        redeemed: false,
+        transfer_count: 0,
+        last_transferred_at: None,
+        minted_at: None,
+        revealed: true,
+        user: None,
+        user_expires: None,
+        staked_since: None,
+        delegate: None,
+        delegate_expires: None,
+        accessories: vec![],
+        valid_until: None,
+        version: 0,
+    };
+    legacy.save(&mut deps.storage, "0", &token).unwrap();
+
+    let moved = crate::upgrades::v0_18
+        ::migrate_token_approvals::<Extension>(&mut deps.storage)
+        .unwrap();
+    assert_eq!(moved, 2);
+
+    assert!(contract.tokens.load(&deps.storage, "0").unwrap().approvals.is_empty());
+    assert_eq!(contract.approvals_of(&deps.storage, "0").unwrap(), vec![
+        approval("market", Expiration::Never {}),
+        approval("random", Expiration::AtHeight(100))
+    ]);
+    let by_spender = contract
+        .approvals_by_spender(deps.as_ref(), mock_env(), String::from("random"), true, None, None)
+        .unwrap().approvals;
+    assert_eq!(by_spender, vec![TokenApproval {
+        token_id: "0".to_string(),
+        expires: Expiration::AtHeight(100),
+    }]);
+}
+
+#[test]
+fn counting_operators() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let owner = mock_info("demeter", &[]);
+    let count = |deps: cosmwasm_std::Deps, env, include_expired| {
+        contract.operator_count(deps, env, String::from("demeter"), include_expired).unwrap()
+    };
+    assert_eq!(count(deps.as_ref(), mock_env(), false), OperatorCountResponse {
+        count: 0,
+        at_least: false,
+    });
+
+    let expires = Expiration::AtHeight(mock_env().block.height + 10);
+    for (operator, expires) in [("alpha", None), ("beta", Some(expires)), ("gamma", None)] {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from(operator),
+            expires,
+            max_uses: None,
+            permissions: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg).unwrap();
+    }
+    assert_eq!(count(deps.as_ref(), mock_env(), false).count, 3);
+
+    // revoked grants are gone, expired ones only count when asked for
+    let revoke_msg = ExecuteMsg::RevokeAll { operator: String::from("alpha") };
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), revoke_msg).unwrap();
+    let mut late_env = mock_env();
+    late_env.block.height += 10;
+    assert_eq!(count(deps.as_ref(), late_env.clone(), false).count, 1);
+    assert_eq!(count(deps.as_ref(), late_env, true).count, 2);
+
+    // counting stops at the cap
+    for i in 0..100 {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: format!("operator{}", i),
+            expires: None,
+            max_uses: None,
+            permissions: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg).unwrap();
+    }
+    assert_eq!(count(deps.as_ref(), mock_env(), false), OperatorCountResponse {
+        count: 100,
+        at_least: true,
+    });
+}
+
+#[test]
+fn enforcing_operator_allowlist() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        enforce_operator_allowlist: Some(true),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..4 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let owner = mock_info("demeter", &[]);
+    for operator in ["market", "shady"] {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from(operator),
+            expires: None,
+            max_uses: None,
+            permissions: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg).unwrap();
+    }
+
+    // only the collection owner manages the allowlist
+    let add_msg = |operator: &str| ExecuteMsg::AddAllowedOperator {
+        operator: String::from(operator),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), owner.clone(), add_msg("market"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let admin = mock_info(MINTER, &[]);
+    contract.execute(deps.as_mut(), mock_env(), admin.clone(), add_msg("market")).unwrap();
+
+    let allowed = contract.allowed_operators(deps.as_ref(), None, None).unwrap();
+    assert_eq!(allowed.operators, vec![String::from("market")]);
+
+    let transfer_msg = |token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: token_id.to_string(),
+    };
+    let market = mock_info("market", &[]);
+    contract.execute(deps.as_mut(), mock_env(), market.clone(), transfer_msg("0")).unwrap();
+
+    // a valid grant is not enough without the allowlist
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("shady", &[]), transfer_msg("1"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorNotAllowed { operator: String::from("shady") });
+
+    // the owner and per-token approvals are unaffected
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("shady"),
+        token_id: String::from("1"),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_msg).unwrap();
+    let shady = mock_info("shady", &[]);
+    contract.execute(deps.as_mut(), mock_env(), shady, transfer_msg("1")).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), owner, transfer_msg("2")).unwrap();
+
+    let remove_msg = ExecuteMsg::RemoveAllowedOperator { operator: String::from("market") };
+    contract.execute(deps.as_mut(), mock_env(), admin, remove_msg).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), market, transfer_msg("3"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorNotAllowed { operator: String::from("market") });
+    let allowed = contract.allowed_operators(deps.as_ref(), None, None).unwrap();
+    assert!(allowed.operators.is_empty());
+}
+
+#[test]
+fn paginating_tokens_in_both_directions() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for owner in ["demeter", "ceres", "demeter", "demeter", "ceres", "demeter", "demeter"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    // walks every page with a limit of 2, feeding the last id back as the cursor
+    let paginate = |owner: Option<&str>, order: Option<RangeOrder>| {
+        let mut pages: Vec<Vec<String>> = vec![];
+        let mut start_after = None;
+        loop {
+            let msg = match owner {
+                Some(owner) =>
+                    QueryMsg::Tokens {
+                        owner: String::from(owner),
+                        start_after: start_after.clone(),
+                        limit: Some(2),
+                        order,
+                        include_total: None,
+                    },
+                None =>
+                    QueryMsg::AllTokens {
+                        start_after: start_after.clone(),
+                        limit: Some(2),
+                        order,
+                    },
+            };
+            let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+            let page = from_json::<TokensResponse>(&res).unwrap().tokens;
+            if page.is_empty() {
+                return pages;
+            }
+            start_after = page.last().cloned();
+            pages.push(page);
+        }
+    };
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+    let pages = paginate(None, None);
+    assert_eq!(pages, vec![ids(&["0", "1"]), ids(&["2", "3"]), ids(&["4", "5"]), ids(&["6"])]);
+    assert_eq!(paginate(None, Some(RangeOrder::Asc)), pages);
+    let pages = paginate(None, Some(RangeOrder::Desc));
+    assert_eq!(pages, vec![ids(&["6", "5"]), ids(&["4", "3"]), ids(&["2", "1"]), ids(&["0"])]);
+
+    let pages = paginate(Some("demeter"), None);
+    assert_eq!(pages, vec![ids(&["0", "2"]), ids(&["3", "5"]), ids(&["6"])]);
+    let pages = paginate(Some("demeter"), Some(RangeOrder::Desc));
+    assert_eq!(pages, vec![ids(&["6", "5"]), ids(&["3", "2"]), ids(&["0"])]);
+}
+
+#[test]
+fn listing_tokens_in_numeric_order() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..12 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let expected = (0..12).map(|i: u64| i.to_string()).collect::<Vec<_>>();
+    let all = contract.all_tokens(deps.as_ref(), None, Some(20)).unwrap();
+    assert_eq!(all.tokens, expected);
+    let owned = contract.tokens(deps.as_ref(), String::from("demeter"), None, Some(20)).unwrap();
+    assert_eq!(owned.tokens, expected);
+
+    // cursors are plain token ids
+    let page = contract.all_tokens(deps.as_ref(), Some(String::from("9")), None).unwrap();
+    assert_eq!(page.tokens, vec![String::from("10"), String::from("11")]);
+    let page = contract
+        .all_tokens_in_order(deps.as_ref(), Some(String::from("10")), Some(3), RangeOrder::Desc)
+        .unwrap();
+    assert_eq!(page.tokens, vec![String::from("9"), String::from("8"), String::from("7")]);
+
+    // other ids round trip and list after the numeric ones
+    for token_id in ["abc", "007", "18446744073709551615"] {
+        assert_eq!(token_id_from_key(token_key(token_id)), token_id);
+    }
+    assert!(token_key("abc") > token_key("11"));
+    assert_ne!(token_key("007"), token_key("7"));
+}
+
+#[test]
+fn migrating_token_keys() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let token = |owner: &str| TokenInfo::<Extension> {
+        owner: Addr::unchecked(owner),
+        approvals: vec![],
+        token_uri: None,
+        extension: None,
+        parent: None,
+        redeemed: false,
+        transfer_count: 0,
+        last_transferred_at: None,
+        minted_at: None,
+        revealed: true,
+        user: None,
+        user_expires: None,
+        staked_since: None,
+        delegate: None,
+        delegate_expires: None,
+        accessories: vec![],
+        valid_until: None,
+        version: 0,
+    };
+    // tokens saved under their bare ids, like older versions did. "007" and "~x" look like
+    // keys of the new format
+    let legacy = [
+        ("0", "demeter"),
+        ("2", "ceres"),
+        ("10", "demeter"),
+        ("007", "venus"),
+        ("~x", "venus"),
+        ("~007", "venus"),
+    ];
+    for (token_id, owner) in legacy {
+        contract.tokens.save(&mut deps.storage, token_id, &token(owner)).unwrap();
+    }
+
+    let moved = crate::upgrades::v0_18::migrate_token_keys::<Extension>(&mut deps.storage).unwrap();
+    assert_eq!(moved, 6);
+    for (token_id, owner) in legacy {
+        let token = contract.load_token(&deps.storage, token_id).unwrap();
+        assert_eq!(token.owner, owner);
+    }
+    let owned = contract.tokens(deps.as_ref(), String::from("venus"), None, None).unwrap();
+    assert_eq!(owned.tokens, vec!["007", "~007", "~x"]);
+
+    let all = contract.all_tokens(deps.as_ref(), None, None).unwrap();
+    assert_eq!(all.tokens, vec!["0", "2", "10", "007", "~007", "~x"]);
+    let owned = contract.tokens(deps.as_ref(), String::from("demeter"), None, None).unwrap();
+    assert_eq!(owned.tokens, vec![String::from("0"), String::from("10")]);
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), String::from("2"), false).unwrap();
+    assert_eq!(owner.owner, "ceres");
+
+    let holders = crate::upgrades::v0_18::migrate_holdings::<Extension>(&mut deps.storage).unwrap();
+    assert_eq!(holders, 3);
+    let balance = contract.holder_balance(deps.as_ref(), String::from("demeter")).unwrap();
+    assert_eq!(balance.count, 2);
+    let second = contract
+        .token_of_owner_by_index(deps.as_ref(), String::from("demeter"), 1)
+        .unwrap();
+    assert_eq!(second.token_id, "10");
+
+    let enumerated = crate::upgrades::v0_18
+        ::migrate_token_positions::<Extension>(&mut deps.storage)
+        .unwrap();
+    assert_eq!(enumerated, 6);
+    assert_eq!(contract.token_by_index(deps.as_ref(), 1).unwrap().token_id, "2");
+    assert_eq!(contract.token_by_index(deps.as_ref(), 3).unwrap().token_id, "007");
+
+    // ids were handed out up to 10, so the numeric ids in between count as burned
+    contract.token_count.save(&mut deps.storage, &6).unwrap();
+    let minted = crate::upgrades::v0_18
+        ::migrate_supply_counters::<Extension>(&mut deps.storage)
+        .unwrap();
+    assert_eq!(minted, 11);
+    assert_eq!(contract.burned_total(&deps.storage).unwrap(), 5);
+}
+
+#[test]
+fn migrating_wallet_mints() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    // counts keyed by the owner string as sent to mint, like older versions did
+    let legacy: cw_storage_plus::Map<String, u64> = cw_storage_plus::Map::new(
+        "wallets_minted_amount"
+    );
+    for (wallet, minted) in [("DEMETER", 2), ("demeter", 1), ("ceres", 1)] {
+        legacy.save(&mut deps.storage, wallet.to_string(), &minted).unwrap();
+    }
+
+    let wallets = crate::upgrades::v0_19
+        ::migrate_wallet_mints(&mut deps.storage, &deps.api)
+        .unwrap();
+    assert_eq!(wallets, 2);
+    assert!(legacy.is_empty(&deps.storage));
+    assert_eq!(contract.wallet_mint_count.load(&deps.storage).unwrap(), 2);
+
+    // both spellings count against the same allocation
+    let demeter = Addr::unchecked("demeter");
+    let minted = contract.wallets_minted_amount.load(&deps.storage, &demeter).unwrap();
+    assert_eq!(minted, 3);
+    contract.max_nfts_per_wallet.save(&mut deps.storage, &3).unwrap();
+    assert_eq!(
+        contract.assert_wallet_allocation(&deps.storage, &demeter, 1).unwrap_err(),
+        ContractError::MintPerWalletOverflow {}
+    );
+    let ceres = Addr::unchecked("ceres");
+    assert_eq!(contract.remaining_allocation(&deps.storage, &ceres).unwrap(), Some(2));
+}
+
+#[test]
+fn migrating_tokens_lazily() {
+    use crate::state::{ token_key, TOKEN_STORAGE_VERSION };
+
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for owner in ["demeter", "ceres", "demeter", "ceres", "demeter"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: Some(format!("ipfs://{owner}")),
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let token_ids: Vec<String> = (0..5).map(|n| n.to_string()).collect();
+    let nft_info = |deps: cosmwasm_std::Deps, token_id: &str| {
+        let msg = QueryMsg::AllNftInfo { token_id: token_id.to_string(), include_expired: None };
+        contract.query(deps, mock_env(), msg).unwrap()
+    };
+    let before: Vec<Binary> = token_ids
+        .iter()
+        .map(|token_id| nft_info(deps.as_ref(), token_id))
+        .collect();
+
+    // nothing to do on a fresh deployment
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::MigrateBatch { limit: None }
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoMigrationInProgress {});
+
+    // rewrite every other token the way versions without the field stored them
+    let raw: cw_storage_plus::Map<&str, serde_json::Value> = cw_storage_plus::Map::new("tokens");
+    for token_id in ["0", "2", "4"] {
+        let key = token_key(token_id);
+        let mut token = raw.load(&deps.storage, &key).unwrap();
+        token.as_object_mut().unwrap().remove("version");
+        raw.save(&mut deps.storage, &key, &token).unwrap();
+    }
+    contract.storage_version.remove(&mut deps.storage);
+    let res = crate::upgrades::start_token_migration(&mut deps.storage, Response::new()).unwrap();
+    assert_eq!(res.attributes[0].value, TOKEN_STORAGE_VERSION.to_string());
+
+    // old and new records read the same
+    let stored = contract.tokens.load(&deps.storage, &token_key("2")).unwrap();
+    assert_eq!(stored.version, 0);
+    assert_eq!(contract.load_token(&deps.storage, "2").unwrap().version, TOKEN_STORAGE_VERSION);
+    for (token_id, info) in token_ids.iter().zip(&before) {
+        assert_eq!(&nft_info(deps.as_ref(), token_id), info);
+    }
+
+    let mut batches = 0;
+    let mut upgraded = 0;
+    loop {
+        let msg = ExecuteMsg::MigrateBatch { limit: Some(2) };
+        let res = contract
+            .execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg)
+            .unwrap();
+        batches += 1;
+        upgraded += res.attributes[1].value.parse::<u32>().unwrap();
+        if res.attributes[2].value == "true" {
+            break;
+        }
+    }
+    assert_eq!(batches, 3);
+    assert_eq!(upgraded, 3);
+    assert_eq!(contract.storage_version(&deps.storage).unwrap(), TOKEN_STORAGE_VERSION);
+    assert_eq!(contract.lazy_migration.may_load(&deps.storage).unwrap(), None);
+    for token_id in &token_ids {
+        let stored = contract.tokens.load(&deps.storage, &token_key(token_id)).unwrap();
+        assert_eq!(stored.version, TOKEN_STORAGE_VERSION);
+    }
+    for (token_id, info) in token_ids.iter().zip(&before) {
+        assert_eq!(&nft_info(deps.as_ref(), token_id), info);
+    }
+
+    // once converged there is nothing left to start
+    crate::upgrades::start_token_migration(&mut deps.storage, Response::new()).unwrap();
+    assert_eq!(contract.lazy_migration.may_load(&deps.storage).unwrap(), None);
+}
+
+#[test]
+fn selling_listed_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..3 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let list_msg = |token_id: &str, expires| ExecuteMsg::ListToken {
+        token_id: token_id.to_string(),
+        price: Coin::new(1000, "usei"),
+        expires,
+    };
+    let buy_msg = |token_id: &str| ExecuteMsg::Buy { token_id: token_id.to_string() };
+    let venus = mock_info("venus", &[]);
+
+    // only the token owner can list
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), list_msg("0", None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), list_msg("0", None)).unwrap();
+
+    // the token is locked while listed
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "0".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), transfer_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenListed {});
+    let burn_msg = ExecuteMsg::Burn { token_id: "0".to_string() };
+    let err = contract.execute(deps.as_mut(), mock_env(), venus.clone(), burn_msg).unwrap_err();
+    assert_eq!(err, ContractError::TokenListed {});
+
+    // underpaying or paying in another denom doesn't buy it
+    for funds in [vec![Coin::new(999, "usei")], vec![Coin::new(1000, "uatom")], vec![]] {
+        let err = contract
+            .execute(deps.as_mut(), mock_env(), mock_info("mars", &funds), buy_msg("0"))
+            .unwrap_err();
+        assert_eq!(err, ContractError::NotEnoughFunds {});
+    }
+
+    // overpaying refunds the difference, without royalty the seller gets the full price
+    let funds = vec![Coin::new(1500, "usei"), Coin::new(5, "uatom")];
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &funds), buy_msg("0"))
+        .unwrap();
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("venus"),
+            amount: vec![Coin::new(1000, "usei")],
+        }),
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("mars"),
+            amount: vec![Coin::new(500, "usei"), Coin::new(5, "uatom")],
+        })
+    ]);
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "0".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "mars");
+    let listing: Option<Listing> = from_json(
+        contract
+            .query(deps.as_ref(), mock_env(), QueryMsg::Listing { token_id: "0".to_string() })
+            .unwrap()
+    ).unwrap();
+    assert_eq!(listing, None);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &funds), buy_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotListed {});
+
+    // delisting unlocks the token
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), list_msg("1", None)).unwrap();
+    let delist_msg = ExecuteMsg::Delist { token_id: "1".to_string() };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), delist_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), delist_msg.clone()).unwrap();
+    let err = contract.execute(deps.as_mut(), mock_env(), venus.clone(), delist_msg).unwrap_err();
+    assert_eq!(err, ContractError::NotListed {});
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &funds), buy_msg("1"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotListed {});
+
+    // an expired listing can't be bought and no longer locks the token
+    let env = mock_env();
+    let expires = Some(Expiration::AtHeight(env.block.height + 10));
+    contract.execute(deps.as_mut(), env.clone(), venus.clone(), list_msg("2", expires)).unwrap();
+    let listings_msg = QueryMsg::Listings { start_after: None, limit: None };
+    let listings: ListingsResponse = from_json(
+        contract.query(deps.as_ref(), env.clone(), listings_msg).unwrap()
+    ).unwrap();
+    assert_eq!(
+        listings.listings.iter().map(|l| l.token_id.as_str()).collect::<Vec<_>>(),
+        vec!["2"]
+    );
+    assert_eq!(listings.listings[0].listing.seller, Addr::unchecked("venus"));
+    let mut later = env.clone();
+    later.block.height += 10;
+    let err = contract
+        .execute(deps.as_mut(), later.clone(), mock_info("mars", &funds), buy_msg("2"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::ListingExpired {});
+    let err = contract
+        .execute(deps.as_mut(), env, venus.clone(), list_msg("2", Some(Expiration::AtHeight(1))))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "2".to_string(),
+    };
+    contract.execute(deps.as_mut(), later, venus, transfer_msg).unwrap();
+    let listings = contract.listings(deps.as_ref(), None, None).unwrap();
+    assert!(listings.listings.is_empty());
+}
+
+#[test]
+fn selling_bundles() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        royalty: Some(Royalty {
+            payment_address: String::from("artist"),
+            share: Decimal::percent(10),
+        }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for owner in ["venus", "venus", "venus", "venus", "mars"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let list_msg = |token_ids: &[&str], expires| ExecuteMsg::ListBundle {
+        token_ids: token_ids.iter().map(|id| id.to_string()).collect(),
+        price: Coin::new(1000, "usei"),
+        expires,
+    };
+    let venus = mock_info("venus", &[]);
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), list_msg(&[], None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidBundleSize { max: 30 });
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), list_msg(&["0", "1", "0"], None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::DuplicateBundleToken { token_id: "0".to_string() });
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), list_msg(&["0", "4"], None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    // every token of an open bundle is locked
+    let expires = Some(Expiration::AtHeight(mock_env().block.height + 1));
+    contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), list_msg(&["0", "1", "2"], expires))
+        .unwrap();
+    let transfer_msg = |token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: token_id.to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), transfer_msg("1"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenBundled {});
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), venus.clone(), list_msg(&["2", "3"], None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenBundled {});
+    let bundles = contract.bundles(deps.as_ref(), None, None).unwrap();
+    assert_eq!(bundles.bundles.len(), 1);
+    assert_eq!(bundles.bundles[0].bundle.token_ids, vec!["0", "1", "2"]);
+
+    let buy_msg = |bundle_id| ExecuteMsg::BuyBundle { bundle_id };
+    let funds = [Coin::new(999, "usei")];
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &funds), buy_msg(0))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughFunds {});
+
+    // once it expires a token can leave, and the bundle can no longer be bought
+    let mut later = mock_env();
+    later.block.height += 1;
+    let burn_msg = ExecuteMsg::Burn { token_id: "1".to_string() };
+    contract.execute(deps.as_mut(), later.clone(), venus.clone(), burn_msg).unwrap();
+    let funds = [Coin::new(1000, "usei")];
+    let err = contract
+        .execute(deps.as_mut(), later.clone(), mock_info("mars", &funds), buy_msg(0))
+        .unwrap_err();
+    assert_eq!(err, ContractError::BundleTokenUnavailable { token_id: "1".to_string() });
+    let delist_msg = ExecuteMsg::DelistBundle { bundle_id: 0 };
+    let err = contract
+        .execute(deps.as_mut(), later.clone(), mock_info("mars", &[]), delist_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), later.clone(), venus.clone(), delist_msg).unwrap();
+    assert_eq!(contract.bundles.may_load(&deps.storage, 0).unwrap(), None);
+
+    // the royalty is taken once from the bundle price and the excess is refunded
+    contract
+        .execute(deps.as_mut(), later.clone(), venus.clone(), list_msg(&["0", "2", "3"], None))
+        .unwrap();
+    let funds = [Coin::new(1100, "usei")];
+    let res = contract
+        .execute(deps.as_mut(), later.clone(), mock_info("mars", &funds), buy_msg(1))
+        .unwrap();
+    let send = |to: &str, amount| {
+        SubMsg::new(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![Coin::new(amount, "usei")],
+        })
+    };
+    assert_eq!(res.messages, vec![send("venus", 900), send("artist", 100), send("mars", 100)]);
+    for token_id in ["0", "2", "3"] {
+        let owner = contract.owner_of(deps.as_ref(), later.clone(), token_id.to_string(), false);
+        assert_eq!(owner.unwrap().owner, "mars");
+    }
+    assert!(contract.bundles(deps.as_ref(), None, None).unwrap().bundles.is_empty());
+    contract.execute(deps.as_mut(), later, mock_info("mars", &[]), transfer_msg("0")).unwrap();
+}
+
+#[test]
+fn paying_royalties_on_sales() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        royalty: Some(Royalty {
+            payment_address: String::from("artist"),
+            share: Decimal::percent(7),
+        }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    let sell = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, seller: &str, buyer: &str, price| {
+        let list_msg = ExecuteMsg::ListToken {
+            token_id: "0".to_string(),
+            price: Coin::new(price, "usei"),
+            expires: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(seller, &[]), list_msg).unwrap();
+        let buy_msg = ExecuteMsg::Buy { token_id: "0".to_string() };
+        let funds = [Coin::new(price, "usei")];
+        contract.execute(deps.as_mut(), mock_env(), mock_info(buyer, &funds), buy_msg).unwrap()
+    };
+
+    // 7% of 1001 is 70.07, the royalty is rounded down and the rest goes to the seller
+    let res = sell(&mut deps, "venus", "mars", 1001);
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("venus"),
+            amount: vec![Coin::new(931, "usei")],
+        }),
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("artist"),
+            amount: vec![Coin::new(70, "usei")],
+        })
+    ]);
+
+    // too small to owe a royalty at all
+    let res = sell(&mut deps, "mars", "venus", 14);
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("mars"),
+            amount: vec![Coin::new(14, "usei")],
+        })
+    ]);
+
+    // the whole price can go to the collection, but not more
+    let set_msg = |share| ExecuteMsg::SetRoyalty {
+        royalty: Some(Royalty { payment_address: String::from("artist"), share }),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), set_msg(Decimal::one()))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_msg(Decimal::percent(101)))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidRoyaltyShare {});
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_msg(Decimal::one()))
+        .unwrap();
+    let res = sell(&mut deps, "venus", "mars", 500);
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("artist"),
+            amount: vec![Coin::new(500, "usei")],
+        })
+    ]);
+}
+
+#[test]
+fn auctioning_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        withdraw_address: Some(String::from("treasury")),
+        royalty: Some(Royalty {
+            payment_address: String::from("artist"),
+            share: Decimal::percent(10),
+        }),
+        auction_extension: Some(300),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let start = mock_env();
+    let at = |seconds: u64| {
+        let mut env = start.clone();
+        env.block.time = start.block.time.plus_seconds(seconds);
+        env.block.height += seconds / 5;
+        env
+    };
+    let create_msg = |token_id: &str| ExecuteMsg::CreateAuction {
+        token_id: token_id.to_string(),
+        min_bid: Coin::new(100, "usei"),
+        duration: 3600,
+    };
+    let bid = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, env, bidder: &str, amount| {
+        let msg = ExecuteMsg::PlaceBid { token_id: "0".to_string() };
+        let info = mock_info(bidder, &[Coin::new(amount, "usei")]);
+        contract.execute(deps.as_mut(), env, info, msg)
+    };
+    let settle_msg = |token_id: &str| ExecuteMsg::SettleAuction { token_id: token_id.to_string() };
+
+    let err = contract
+        .execute(deps.as_mut(), at(0), mock_info("mars", &[]), create_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), at(0), mock_info("venus", &[]), create_msg("0")).unwrap();
+    contract.execute(deps.as_mut(), at(0), mock_info("venus", &[]), create_msg("1")).unwrap();
+
+    // the token is locked for the whole auction
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "0".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), at(0), mock_info("venus", &[]), transfer_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenInAuction {});
+
+    // bids start at the minimum and have to beat the highest bid
+    let err = bid(&mut deps, at(10), "mars", 99).unwrap_err();
+    assert_eq!(err, ContractError::BidTooLow { min_bid: Coin::new(100, "usei") });
+    let err = bid(&mut deps, at(10), "venus", 200).unwrap_err();
+    assert_eq!(err, ContractError::SellerCannotBid {});
+    let res = bid(&mut deps, at(10), "mars", 100).unwrap();
+    assert!(res.messages.is_empty());
+    let err = bid(&mut deps, at(20), "jupiter", 100).unwrap_err();
+    assert_eq!(err, ContractError::BidTooLow { min_bid: Coin::new(101, "usei") });
+
+    // being outbid refunds the previous bid
+    let res = bid(&mut deps, at(20), "jupiter", 150).unwrap();
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("mars"),
+            amount: vec![Coin::new(100, "usei")],
+        })
+    ]);
+    assert_eq!(contract.escrowed_bids(&deps.storage, "usei").unwrap(), Uint128::new(150));
+
+    // escrowed bids can't be withdrawn
+    deps.querier.update_balance(
+        mock_env().contract.address,
+        vec![Coin::new(150 + 40, "usei")]
+    );
+    let withdraw_msg = |amount| ExecuteMsg::WithdrawFunds { amount: Coin::new(amount, "usei") };
+    let err = contract
+        .execute(deps.as_mut(), at(20), mock_info("anyone", &[]), withdraw_msg(41))
+        .unwrap_err();
+    assert_eq!(err, ContractError::FundsInEscrow { available: Coin::new(40, "usei") });
+    contract.execute(deps.as_mut(), at(20), mock_info("anyone", &[]), withdraw_msg(40)).unwrap();
+
+    // a bid in the last 300 seconds extends the auction to 300 seconds after it
+    let res = bid(&mut deps, at(3000), "mars", 200).unwrap();
+    assert!(res.attributes.iter().all(|attr| attr.key != "end_time"));
+    let res = bid(&mut deps, at(3550), "jupiter", 250).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "end_time"));
+    let auction: Option<Auction> = from_json(
+        contract
+            .query(deps.as_ref(), at(3550), QueryMsg::Auction { token_id: "0".to_string() })
+            .unwrap()
+    ).unwrap();
+    assert_eq!(auction.unwrap().end_time, start.block.time.plus_seconds(3850));
+
+    let err = contract
+        .execute(deps.as_mut(), at(3800), mock_info("anyone", &[]), settle_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::AuctionNotEnded {});
+    let err = bid(&mut deps, at(3850), "mars", 300).unwrap_err();
+    assert_eq!(err, ContractError::AuctionEnded {});
+
+    // the ended auctions are only listed on request
+    let auctions_msg = |include_ended| QueryMsg::Auctions {
+        start_after: None,
+        limit: None,
+        include_ended,
+    };
+    let auctions: AuctionsResponse = from_json(
+        contract.query(deps.as_ref(), at(3800), auctions_msg(None)).unwrap()
+    ).unwrap();
+    assert_eq!(
+        auctions.auctions.iter().map(|a| a.token_id.as_str()).collect::<Vec<_>>(),
+        vec!["0"]
+    );
+    let auctions: AuctionsResponse = from_json(
+        contract.query(deps.as_ref(), at(3850), auctions_msg(None)).unwrap()
+    ).unwrap();
+    assert!(auctions.auctions.is_empty());
+    let auctions: AuctionsResponse = from_json(
+        contract.query(deps.as_ref(), at(3850), auctions_msg(Some(true))).unwrap()
+    ).unwrap();
+    assert_eq!(auctions.auctions.len(), 2);
+    assert_eq!(auctions.auctions[0].auction.highest_bid, Some(Bid {
+        bidder: Addr::unchecked("jupiter"),
+        amount: Uint128::new(250),
+    }));
+
+    // settling pays the seller minus the royalty and hands over the token
+    let res = contract
+        .execute(deps.as_mut(), at(3850), mock_info("anyone", &[]), settle_msg("0"))
+        .unwrap();
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("venus"),
+            amount: vec![Coin::new(225, "usei")],
+        }),
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("artist"),
+            amount: vec![Coin::new(25, "usei")],
+        })
+    ]);
+    let owner = contract.owner_of(deps.as_ref(), at(3850), "0".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "jupiter");
+    assert_eq!(contract.escrowed_bids(&deps.storage, "usei").unwrap(), Uint128::zero());
+    let err = contract
+        .execute(deps.as_mut(), at(3850), mock_info("anyone", &[]), settle_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoAuction {});
+
+    // without bids the token stays with the seller and is free again
+    let res = contract
+        .execute(deps.as_mut(), at(3600), mock_info("anyone", &[]), settle_msg("1"))
+        .unwrap();
+    assert!(res.messages.is_empty());
+    let owner = contract.owner_of(deps.as_ref(), at(3600), "1".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "venus");
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "1".to_string(),
+    };
+    contract.execute(deps.as_mut(), at(3600), mock_info("venus", &[]), transfer_msg).unwrap();
+}
+
+#[test]
+fn staking_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        min_stake_period: Some(100),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let venus = mock_info("venus", &[]);
+    let stake_msg = ExecuteMsg::Stake { token_id: "0".to_string() };
+    let unstake_msg = ExecuteMsg::Unstake { token_id: "0".to_string() };
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), stake_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), stake_msg.clone()).unwrap();
+
+    // a staked token stays where it is
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "0".to_string(),
+    };
+    let list_msg = ExecuteMsg::ListToken {
+        token_id: "0".to_string(),
+        price: Coin::new(100, "usei"),
+        expires: None,
+    };
+    let burn_msg = ExecuteMsg::Burn { token_id: "0".to_string() };
+    for msg in [transfer_msg.clone(), list_msg, burn_msg, stake_msg] {
+        let err = contract.execute(deps.as_mut(), mock_env(), venus.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::TokenStaked {});
+    }
+
+    let mut later = mock_env();
+    later.block.time = later.block.time.plus_seconds(50);
+    let staked_tokens = |deps: cosmwasm_std::Deps, env| {
+        contract.staked_tokens(deps, env, "venus".to_string(), None, None).unwrap().tokens
+    };
+    let staked = staked_tokens(deps.as_ref(), later.clone());
+    assert_eq!(staked.len(), 1);
+    assert_eq!((staked[0].token_id.as_str(), staked[0].staked_for), ("0", 50));
+    let staked = contract.staked_token(deps.as_ref(), later.clone(), "1".to_string()).unwrap();
+    assert_eq!(staked, None);
+
+    // unstaking waits for the minimum period, which the owner can change
+    let err = contract
+        .execute(deps.as_mut(), later.clone(), venus.clone(), unstake_msg.clone())
+        .unwrap_err();
+    let unlocks_at = mock_env().block.time.plus_seconds(100);
+    assert_eq!(err, ContractError::StakeLocked { unlocks_at });
+    let period_msg = ExecuteMsg::SetMinStakePeriod { period: 30 };
+    let err = contract
+        .execute(deps.as_mut(), later.clone(), venus.clone(), period_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), later.clone(), mock_info(MINTER, &[]), period_msg).unwrap();
+    let res = contract
+        .execute(deps.as_mut(), later.clone(), venus.clone(), unstake_msg.clone())
+        .unwrap();
+    assert_eq!(res.attributes[3], ("staked_for", "50"));
+    let err = contract
+        .execute(deps.as_mut(), later.clone(), venus.clone(), unstake_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotStaked {});
+    assert!(staked_tokens(deps.as_ref(), later.clone()).is_empty());
+    contract.execute(deps.as_mut(), later, venus, transfer_msg).unwrap();
+}
+
+#[test]
+fn renting_tokens() {
+    let setup = |keep_user_on_transfer| {
+        let mut deps = mock_dependencies();
+        let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+        let msg = InstantiateMsg {
+            name: CONTRACT_NAME.to_string(),
+            symbol: SYMBOL.to_string(),
+            minter: Some(String::from(MINTER)),
+            keep_user_on_transfer,
+            ..Default::default()
+        };
+        contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+        deps
+    };
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let expires = Expiration::AtHeight(mock_env().block.height + 10);
+    let set_user_msg = |user: Option<&str>| ExecuteMsg::SetUser {
+        token_id: "0".to_string(),
+        user: user.map(String::from),
+        expires,
+    };
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "0".to_string(),
+    };
+    let user_of = |deps: cosmwasm_std::Deps, env| {
+        contract.user_of(deps, env, "0".to_string()).unwrap().user
+    };
+    let mut deps = setup(None);
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), set_user_msg(Some("gamer")))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let past = ExecuteMsg::SetUser {
+        token_id: "0".to_string(),
+        user: Some(String::from("gamer")),
+        expires: Expiration::AtHeight(1),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), past)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    // an approved spender can rent the token out, the user gets no transfer rights
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("market"),
+        token_id: "0".to_string(),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), approve_msg).unwrap();
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("market", &[]), set_user_msg(Some("gamer")))
+        .unwrap();
+    assert_eq!(user_of(deps.as_ref(), mock_env()), Some(String::from("gamer")));
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("gamer", &[]), transfer_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    // the role lapses on its own
+    let mut later = mock_env();
+    later.block.height += 10;
+    assert_eq!(user_of(deps.as_ref(), later), None);
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), set_user_msg(None))
+        .unwrap();
+    assert_eq!(user_of(deps.as_ref(), mock_env()), None);
+
+    // a transfer clears the user unless the collection keeps it
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), set_user_msg(Some("gamer")))
+        .unwrap();
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), transfer_msg.clone())
+        .unwrap();
+    assert_eq!(user_of(deps.as_ref(), mock_env()), None);
+
+    let mut deps = setup(Some(true));
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), set_user_msg(Some("gamer")))
+        .unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), transfer_msg).unwrap();
+    assert_eq!(user_of(deps.as_ref(), mock_env()), Some(String::from("gamer")));
+}
+
+#[test]
+fn delegating_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..3 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let delegate_msg = |token_id: &str, expires| ExecuteMsg::Delegate {
+        token_id: token_id.to_string(),
+        delegate: String::from("hot_wallet"),
+        expires,
+    };
+    let delegated = |deps: cosmwasm_std::Deps, env| {
+        contract
+            .tokens_delegated_to(deps, env, String::from("hot_wallet"), None, None)
+            .unwrap()
+            .tokens
+    };
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), delegate_msg("0", None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venus", &[]),
+            delegate_msg("0", Some(Expiration::AtHeight(1)))
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Expired {});
+
+    let expires = Expiration::AtHeight(mock_env().block.height + 10);
+    for (token_id, expires) in [("0", None), ("1", Some(expires)), ("2", None)] {
+        contract
+            .execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("venus", &[]),
+                delegate_msg(token_id, expires)
+            )
+            .unwrap();
+    }
+    let res = contract.delegate_of(deps.as_ref(), mock_env(), "1".to_string()).unwrap();
+    assert_eq!(res.delegate, Some(String::from("hot_wallet")));
+    assert_eq!(res.expires, Some(expires));
+    assert_eq!(delegated(deps.as_ref(), mock_env()), vec!["0", "1", "2"]);
+    let page = contract
+        .tokens_delegated_to(
+            deps.as_ref(),
+            mock_env(),
+            String::from("hot_wallet"),
+            Some("0".to_string()),
+            Some(1)
+        )
+        .unwrap();
+    assert_eq!(page.tokens, vec!["1"]);
+
+    // the delegate gets no transfer rights
+    let transfer_msg = |token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: token_id.to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("hot_wallet", &[]), transfer_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    // expired delegations drop out of both queries
+    let mut later = mock_env();
+    later.block.height += 10;
+    let res = contract.delegate_of(deps.as_ref(), later.clone(), "1".to_string()).unwrap();
+    assert_eq!(res.delegate, None);
+    assert_eq!(delegated(deps.as_ref(), later), vec!["0", "2"]);
+
+    // the owner can revoke at any time
+    let undelegate_msg = ExecuteMsg::Undelegate { token_id: "0".to_string() };
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), undelegate_msg.clone())
+        .unwrap();
+    let res = contract.delegate_of(deps.as_ref(), mock_env(), "0".to_string()).unwrap();
+    assert_eq!(res.delegate, None);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), undelegate_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotDelegated {});
+
+    // a transfer clears the delegate along with its index entry
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), transfer_msg("2"))
+        .unwrap();
+    let res = contract.delegate_of(deps.as_ref(), mock_env(), "2".to_string()).unwrap();
+    assert_eq!(res.delegate, None);
+    assert_eq!(delegated(deps.as_ref(), mock_env()), vec!["1"]);
+
+    // as does a burn
+    let burn_msg = ExecuteMsg::Burn { token_id: "1".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), burn_msg).unwrap();
+    assert!(delegated(deps.as_ref(), mock_env()).is_empty());
+}
+
+#[test]
+fn distributing_rewards() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        reward_asset: Some(RewardAsset::Native { denom: String::from("usei") }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let fund = |deps: DepsMut, amount: u128| {
+        let info = mock_info("treasury", &[Coin::new(amount, "usei")]);
+        contract.execute(deps, mock_env(), info, ExecuteMsg::FundRewards {})
+    };
+    let pending = |deps: cosmwasm_std::Deps, address: &str| {
+        let res = contract
+            .query(deps, mock_env(), QueryMsg::PendingRewards { address: address.to_string() })
+            .unwrap();
+        from_json::<Uint128>(&res).unwrap().u128()
+    };
+    let transfer_msg = |recipient: &str, token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from(recipient),
+        token_id: token_id.to_string(),
+    };
+
+    let err = fund(deps.as_mut(), 300).unwrap_err();
+    assert_eq!(err, ContractError::NoTokensToReward {});
+    for owner in ["demeter", "demeter", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let info = mock_info("treasury", &[Coin::new(300, "uatom")]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), info, ExecuteMsg::FundRewards {})
+        .unwrap_err();
+    assert!(matches!(err, ContractError::Payment(_)));
+
+    fund(deps.as_mut(), 300).unwrap();
+    assert_eq!(pending(deps.as_ref(), "demeter"), 200);
+    assert_eq!(pending(deps.as_ref(), "ceres"), 100);
+
+    // rewards earned before a transfer stay with the previous holder
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), transfer_msg("ceres", "0"))
+        .unwrap();
+    assert_eq!(pending(deps.as_ref(), "demeter"), 200);
+    assert_eq!(pending(deps.as_ref(), "ceres"), 100);
+    fund(deps.as_mut(), 300).unwrap();
+    assert_eq!(pending(deps.as_ref(), "demeter"), 300);
+    assert_eq!(pending(deps.as_ref(), "ceres"), 300);
+
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), ExecuteMsg::ClaimRewards {})
+        .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("ceres"),
+                amount: vec![Coin::new(300, "usei")],
+            })
+        ]
+    );
+    assert_eq!(pending(deps.as_ref(), "ceres"), 0);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), ExecuteMsg::ClaimRewards {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoRewards {});
+
+    // a holder who sold out keeps what they earned, burned tokens earn nothing more
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), transfer_msg("venus", "1"))
+        .unwrap();
+    let burn_msg = ExecuteMsg::Burn { token_id: "2".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), burn_msg).unwrap();
+    fund(deps.as_mut(), 100).unwrap();
+    assert_eq!(pending(deps.as_ref(), "demeter"), 300);
+    assert_eq!(pending(deps.as_ref(), "ceres"), 50);
+    assert_eq!(pending(deps.as_ref(), "venus"), 50);
+
+    // unclaimed rewards can't be withdrawn
+    assert_eq!(contract.escrowed_bids(&deps.storage, "usei").unwrap(), Uint128::new(400));
+    deps.querier.update_balance(mock_env().contract.address, vec![Coin::new(400, "usei")]);
+    let withdraw_msg = ExecuteMsg::WithdrawFunds { amount: Coin::new(1, "usei") };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), withdraw_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::FundsInEscrow { available: Coin::new(0, "usei") });
+
+    // nor does the asset change once rewards were funded
+    let set_msg = ExecuteMsg::SetRewardAsset { asset: None };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::RewardsFunded {});
+    let receive_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("treasury"),
+        amount: Uint128::new(100),
+        msg: to_json_binary(&Cw20HookMsg::FundRewards {}).unwrap(),
+    });
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("reward_token", &[]), receive_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::WrongRewardAsset {});
+
+    // CW20 rewards are funded through the hook and paid out with a transfer
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("reward_token", &[]), receive_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoRewardAsset {});
+    let set_msg = ExecuteMsg::SetRewardAsset {
+        asset: Some(RewardAsset::Cw20 { address: Addr::unchecked("reward_token") }),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_msg).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("fake_token", &[]), receive_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::WrongRewardAsset {});
+    let err = fund(deps.as_mut(), 100).unwrap_err();
+    assert_eq!(err, ContractError::WrongRewardAsset {});
+
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("reward_token", &[]), receive_msg)
+        .unwrap();
+    assert_eq!(pending(deps.as_ref(), "demeter"), 100);
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), ExecuteMsg::ClaimRewards {})
+        .unwrap();
+    let transfer = Cw20ExecuteMsg::Transfer {
+        recipient: String::from("demeter"),
+        amount: Uint128::new(100),
+    };
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("reward_token"),
+                msg: to_json_binary(&transfer).unwrap(),
+                funds: vec![],
+            })
+        ]
+    );
+}
+
+#[test]
+fn making_and_accepting_offers() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        royalty: Some(Royalty {
+            payment_address: String::from("artist"),
+            share: Decimal::percent(5),
+        }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let offer_msg = |token_id: &str, expires| ExecuteMsg::MakeOffer {
+        token_id: token_id.to_string(),
+        expires,
+    };
+    let accept_msg = |bidder: &str| ExecuteMsg::AcceptOffer {
+        token_id: "0".to_string(),
+        bidder: bidder.to_string(),
+    };
+    let send = |to: &str, amount| {
+        SubMsg::new(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![Coin::new(amount, "usei")],
+        })
+    };
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), offer_msg("0", None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OwnerCannotOffer {});
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), offer_msg("0", None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Payment(cw_utils::PaymentError::NoFunds {}));
+
+    // several bidders can offer on the same token, a new offer replaces the old one
+    let offer = |bidder: &str, amount| mock_info(bidder, &[Coin::new(amount, "usei")]);
+    contract.execute(deps.as_mut(), mock_env(), offer("mars", 100), offer_msg("0", None)).unwrap();
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), offer("mars", 120), offer_msg("0", None))
+        .unwrap();
+    assert_eq!(res.messages, vec![send("mars", 100)]);
+    contract.execute(deps.as_mut(), mock_env(), offer("mars", 40), offer_msg("1", None)).unwrap();
+    let expires = Some(Expiration::AtHeight(mock_env().block.height + 1));
+    contract
+        .execute(deps.as_mut(), mock_env(), offer("jupiter", 200), offer_msg("0", expires))
+        .unwrap();
+    assert_eq!(contract.escrowed_bids(&deps.storage, "usei").unwrap(), Uint128::new(360));
+
+    let by_token = contract.offers_by_token(deps.as_ref(), "0".to_string(), None, None).unwrap();
+    assert_eq!(
+        by_token
+            .offers
+            .iter()
+            .map(|o| (o.bidder.as_str(), o.offer.amount.amount.u128()))
+            .collect::<Vec<_>>(),
+        vec![("jupiter", 200), ("mars", 120)]
+    );
+    let by_bidder = contract
+        .offers_by_bidder(deps.as_ref(), "mars".to_string(), Some("0".to_string()), None)
+        .unwrap();
+    assert_eq!(
+        by_bidder.offers.iter().map(|o| o.token_id.as_str()).collect::<Vec<_>>(),
+        vec!["1"]
+    );
+
+    // only the bidder can take back an offer before it expires
+    let withdraw_msg = |bidder: Option<&str>| ExecuteMsg::WithdrawOffer {
+        token_id: "0".to_string(),
+        bidder: bidder.map(String::from),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), withdraw_msg(Some("jupiter")))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OfferNotExpired {});
+    let mut later = mock_env();
+    later.block.height += 1;
+    let err = contract
+        .execute(deps.as_mut(), later.clone(), mock_info("venus", &[]), accept_msg("jupiter"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::OfferExpired {});
+    let res = contract
+        .execute(deps.as_mut(), later, mock_info("anyone", &[]), withdraw_msg(Some("jupiter")))
+        .unwrap();
+    assert_eq!(res.messages, vec![send("jupiter", 200)]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("jupiter", &[]), withdraw_msg(None))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoOffer { bidder: String::from("jupiter") });
+
+    // a transfer landing first leaves the previous owner unable to accept
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), transfer_msg).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), accept_msg("mars"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    // the offer still stands for the new owner
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), accept_msg("mars"))
+        .unwrap();
+    assert_eq!(res.messages, vec![send("ceres", 114), send("artist", 6)]);
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "0".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "mars");
+    assert_eq!(contract.escrowed_bids(&deps.storage, "usei").unwrap(), Uint128::new(40));
+    let by_token = contract.offers_by_token(deps.as_ref(), "0".to_string(), None, None).unwrap();
+    assert!(by_token.offers.is_empty());
+}
+
+#[test]
+fn counting_holders() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let holders = |deps: cosmwasm_std::Deps| {
+        let res = contract.query(deps, mock_env(), QueryMsg::NumHolders {}).unwrap();
+        from_json::<NumHoldersResponse>(&res).unwrap().count
+    };
+    let balance = |deps: cosmwasm_std::Deps, address: &str| {
+        contract.holder_balance(deps, address.to_string()).unwrap().count
+    };
+    assert_eq!(holders(deps.as_ref()), 0);
+
+    for owner in ["demeter", "demeter", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    assert_eq!(holders(deps.as_ref()), 2);
+    assert_eq!(balance(deps.as_ref(), "demeter"), 2);
+    assert_eq!(balance(deps.as_ref(), "ceres"), 1);
+
+    let transfer_msg = |recipient: &str, token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from(recipient),
+        token_id: token_id.to_string(),
+    };
+    let demeter = mock_info("demeter", &[]);
+
+    // sending a token to yourself changes nothing
+    let msg = transfer_msg("demeter", "0");
+    contract.execute(deps.as_mut(), mock_env(), demeter.clone(), msg).unwrap();
+    assert_eq!(holders(deps.as_ref()), 2);
+    assert_eq!(balance(deps.as_ref(), "demeter"), 2);
+
+    // a new holder appears
+    let msg = transfer_msg("venus", "1");
+    contract.execute(deps.as_mut(), mock_env(), demeter.clone(), msg).unwrap();
+    assert_eq!(holders(deps.as_ref()), 3);
+    assert_eq!(balance(deps.as_ref(), "demeter"), 1);
+    assert_eq!(balance(deps.as_ref(), "venus"), 1);
+
+    // an operator moves demeter's last token, demeter is no longer a holder
+    let approve_all_msg = ExecuteMsg::ApproveAll {
+        operator: String::from("market"),
+        expires: None,
+        max_uses: None,
+        permissions: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), demeter, approve_all_msg).unwrap();
+    let market = mock_info("market", &[]);
+    contract.execute(deps.as_mut(), mock_env(), market, transfer_msg("ceres", "0")).unwrap();
+    assert_eq!(holders(deps.as_ref()), 2);
+    assert_eq!(balance(deps.as_ref(), "demeter"), 0);
+    assert_eq!(balance(deps.as_ref(), "ceres"), 2);
+
+    // an operator burns one of ceres' tokens, then ceres burns the other
+    let approve_all_msg = ExecuteMsg::ApproveAll {
+        operator: String::from("market"),
+        expires: None,
+        max_uses: None,
+        permissions: None,
+    };
+    let ceres = mock_info("ceres", &[]);
+    contract.execute(deps.as_mut(), mock_env(), ceres.clone(), approve_all_msg).unwrap();
+    let burn_msg = |token_id: &str| ExecuteMsg::Burn { token_id: token_id.to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("market", &[]), burn_msg("0")).unwrap();
+    assert_eq!(holders(deps.as_ref()), 2);
+    assert_eq!(balance(deps.as_ref(), "ceres"), 1);
+    contract.execute(deps.as_mut(), mock_env(), ceres, burn_msg("2")).unwrap();
+    assert_eq!(holders(deps.as_ref()), 1);
+    assert_eq!(balance(deps.as_ref(), "ceres"), 0);
+}
+
+#[test]
+fn snapshotting_holder_balances() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        balance_snapshots: Some(SnapshotStrategy::EveryBlock),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let start = mock_env().block.height;
+    let at = |offset: u64| {
+        let mut env = mock_env();
+        env.block.height = start + offset;
+        env
+    };
+    let mint_msg = |owner: &str| ExecuteMsg::Mint {
+        owner: String::from(owner),
+        token_uri: None,
+        extension: None,
+    };
+    let transfer_msg = |recipient: &str, token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from(recipient),
+        token_id: token_id.to_string(),
+    };
+
+    for _ in 0..2 {
+        let minter = mock_info(MINTER, &[]);
+        contract.execute(deps.as_mut(), at(1), minter, mint_msg("demeter")).unwrap();
+    }
+    contract.execute(deps.as_mut(), at(2), mock_info(MINTER, &[]), mint_msg("ceres")).unwrap();
+    let demeter = mock_info("demeter", &[]);
+    contract.execute(deps.as_mut(), at(2), demeter.clone(), transfer_msg("ceres", "0")).unwrap();
+    contract.execute(deps.as_mut(), at(5), demeter, transfer_msg("venus", "1")).unwrap();
+
+    let balance_at = |deps: cosmwasm_std::Deps, address: &str, height: u64| {
+        let msg = QueryMsg::HolderBalanceAt { address: address.to_string(), height };
+        let res = contract.query(deps, mock_env(), msg)?;
+        from_json::<HolderBalanceResponse>(&res).map(|res| res.count)
+    };
+    let holders_at = |deps: cosmwasm_std::Deps, height: u64| {
+        let res = contract.query(deps, mock_env(), QueryMsg::NumHoldersAt { height })?;
+        from_json::<NumHoldersResponse>(&res).map(|res| res.count)
+    };
+    // (height, demeter, ceres, venus, holders), balances as of the start of the block
+    let expected = [
+        (0, 0, 0, 0, 0),
+        (1, 0, 0, 0, 0),
+        (2, 2, 0, 0, 1),
+        (3, 1, 2, 0, 2),
+        (4, 1, 2, 0, 2),
+        (5, 1, 2, 0, 2),
+        (6, 0, 2, 1, 2),
+        (100, 0, 2, 1, 2),
+    ];
+    for (offset, demeter, ceres, venus, holders) in expected {
+        let height = start + offset;
+        assert_eq!(balance_at(deps.as_ref(), "demeter", height).unwrap(), demeter);
+        assert_eq!(balance_at(deps.as_ref(), "ceres", height).unwrap(), ceres);
+        assert_eq!(balance_at(deps.as_ref(), "venus", height).unwrap(), venus);
+        assert_eq!(holders_at(deps.as_ref(), height).unwrap(), holders);
+    }
+
+    // checkpoints are added automatically
+    let err = contract
+        .execute(deps.as_mut(), at(6), mock_info(MINTER, &[]), ExecuteMsg::CheckpointBalances {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::CheckpointsNotSelected {});
+
+    // with selected checkpoints only the checkpointed heights can be looked up
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        balance_snapshots: Some(SnapshotStrategy::Selected),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..2 {
+        let minter = mock_info(MINTER, &[]);
+        contract.execute(deps.as_mut(), at(1), minter, mint_msg("demeter")).unwrap();
+    }
+    let checkpoint_msg = ExecuteMsg::CheckpointBalances {};
+    let err = contract
+        .execute(deps.as_mut(), at(2), mock_info("demeter", &[]), checkpoint_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), at(2), mock_info(MINTER, &[]), checkpoint_msg).unwrap();
+    let demeter = mock_info("demeter", &[]);
+    contract.execute(deps.as_mut(), at(2), demeter.clone(), transfer_msg("ceres", "0")).unwrap();
+    contract.execute(deps.as_mut(), at(3), demeter, transfer_msg("ceres", "1")).unwrap();
+
+    assert_eq!(balance_at(deps.as_ref(), "demeter", start + 2).unwrap(), 2);
+    assert_eq!(balance_at(deps.as_ref(), "ceres", start + 2).unwrap(), 0);
+    assert_eq!(holders_at(deps.as_ref(), start + 2).unwrap(), 1);
+    balance_at(deps.as_ref(), "demeter", start + 1).unwrap_err();
+    holders_at(deps.as_ref(), start + 3).unwrap_err();
+
+    // snapshots are off by default
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    contract.execute(deps.as_mut(), at(1), mock_info(MINTER, &[]), mint_msg("demeter")).unwrap();
+    balance_at(deps.as_ref(), "demeter", start + 1).unwrap_err();
+    let snapshot = contract.holder_balance_snapshots
+        .may_load(&deps.storage, &Addr::unchecked("demeter"))
+        .unwrap();
+    assert_eq!(snapshot, None);
+}
+
+#[test]
+fn voting_power_at_heights() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        balance_snapshots: Some(SnapshotStrategy::EveryBlock),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let start = mock_env().block.height;
+    let at = |offset: u64| {
+        let mut env = mock_env();
+        env.block.height = start + offset;
+        env
+    };
+    let mint_msg = |owner: &str| ExecuteMsg::Mint {
+        owner: String::from(owner),
+        token_uri: None,
+        extension: None,
+    };
+    let transfer_msg = |recipient: &str, token_id: &str| ExecuteMsg::TransferNft {
+        recipient: String::from(recipient),
+        token_id: token_id.to_string(),
+    };
+
+    for _ in 0..3 {
+        let minter = mock_info(MINTER, &[]);
+        contract.execute(deps.as_mut(), at(1), minter, mint_msg("demeter")).unwrap();
+    }
+    let demeter = mock_info("demeter", &[]);
+    contract.execute(deps.as_mut(), at(3), demeter.clone(), transfer_msg("ceres", "0")).unwrap();
+    contract.execute(deps.as_mut(), at(3), demeter.clone(), transfer_msg("ceres", "1")).unwrap();
+    contract.execute(deps.as_mut(), at(4), mock_info(MINTER, &[]), mint_msg("ceres")).unwrap();
+    let burn_msg = ExecuteMsg::Burn { token_id: "2".to_string() };
+    contract.execute(deps.as_mut(), at(6), demeter, burn_msg).unwrap();
+    contract
+        .execute(deps.as_mut(), at(6), mock_info("ceres", &[]), transfer_msg("demeter", "3"))
+        .unwrap();
+
+    let power_at = |deps: cosmwasm_std::Deps, address: &str, height: u64| {
+        let msg = QueryMsg::VotingPowerAt { address: address.to_string(), height };
+        let res = contract.query(deps, mock_env(), msg)?;
+        from_json::<PowerResponse>(&res).map(|res| res.power)
+    };
+    let total_at = |deps: cosmwasm_std::Deps, height: u64| {
+        let res = contract.query(deps, mock_env(), QueryMsg::TotalPowerAt { height })?;
+        from_json::<PowerResponse>(&res).map(|res| res.power)
+    };
+    // (height, demeter, ceres, total), as of the start of the block
+    let expected = [
+        (0, 0, 0, 0),
+        (1, 0, 0, 0),
+        (2, 3, 0, 3),
+        (3, 3, 0, 3),
+        (4, 1, 2, 3),
+        (5, 1, 3, 4),
+        (6, 1, 3, 4),
+        (7, 1, 2, 3),
+        (100, 1, 2, 3),
+    ];
+    for (offset, demeter, ceres, total) in expected {
+        let height = start + offset;
+        assert_eq!(power_at(deps.as_ref(), "demeter", height).unwrap(), Uint128::new(demeter));
+        assert_eq!(power_at(deps.as_ref(), "ceres", height).unwrap(), Uint128::new(ceres));
+        assert_eq!(total_at(deps.as_ref(), height).unwrap(), Uint128::new(total));
+    }
+
+    // with selected checkpoints the total is only kept at checkpointed heights
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        balance_snapshots: Some(SnapshotStrategy::Selected),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    contract.execute(deps.as_mut(), at(1), mock_info(MINTER, &[]), mint_msg("demeter")).unwrap();
+    let checkpoint_msg = ExecuteMsg::CheckpointBalances {};
+    contract.execute(deps.as_mut(), at(2), mock_info(MINTER, &[]), checkpoint_msg).unwrap();
+    contract.execute(deps.as_mut(), at(2), mock_info(MINTER, &[]), mint_msg("ceres")).unwrap();
+    contract.execute(deps.as_mut(), at(3), mock_info(MINTER, &[]), mint_msg("ceres")).unwrap();
+    assert_eq!(total_at(deps.as_ref(), start + 2).unwrap(), Uint128::new(1));
+    assert_eq!(power_at(deps.as_ref(), "ceres", start + 2).unwrap(), Uint128::zero());
+    total_at(deps.as_ref(), start + 3).unwrap_err();
+
+    // without snapshots there is no voting power to look up
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    contract.execute(deps.as_mut(), at(1), mock_info(MINTER, &[]), mint_msg("demeter")).unwrap();
+    total_at(deps.as_ref(), start + 1).unwrap_err();
+    power_at(deps.as_ref(), "demeter", start + 1).unwrap_err();
+}
+
+#[test]
+fn listing_holders() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint = |deps: DepsMut, owner: &str| {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps, mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    };
+    for owner in ["ceres", "demeter", "ceres", "juno", "venus", "ceres"] {
+        mint(deps.as_mut(), owner);
+    }
+
+    // demeter gives away their only token and drops out
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("juno"),
+        token_id: String::from("1"),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), transfer_msg).unwrap();
+
+    let holder = |address: &str, count| Holder { address: address.to_string(), count };
+    let query = |deps: cosmwasm_std::Deps, msg| {
+        from_json::<HoldersResponse>(&contract.query(deps, mock_env(), msg).unwrap())
+            .unwrap()
+            .holders
+    };
+    let page = query(deps.as_ref(), QueryMsg::Holders { start_after: None, limit: Some(2) });
+    assert_eq!(page, vec![holder("ceres", 3), holder("juno", 2)]);
+    let msg = QueryMsg::Holders { start_after: Some(String::from("juno")), limit: Some(2) };
+    assert_eq!(query(deps.as_ref(), msg), vec![holder("venus", 1)]);
+
+    let msg = QueryMsg::HoldersByCount { min_count: 2, start_after: None, limit: None };
+    assert_eq!(query(deps.as_ref(), msg), vec![holder("ceres", 3), holder("juno", 2)]);
+    let msg = QueryMsg::HoldersByCount { min_count: 3, start_after: None, limit: None };
+    assert_eq!(query(deps.as_ref(), msg), vec![holder("ceres", 3)]);
+
+    // pages are capped at 100 holders
+    for i in 0..100 {
+        mint(deps.as_mut(), &format!("holder{:03}", i));
+    }
+    let page = query(deps.as_ref(), QueryMsg::Holders { start_after: None, limit: Some(500) });
+    assert_eq!(page.len(), 100);
+}
+
+#[test]
+fn querying_tokens_detailed() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for (i, owner) in ["demeter", "ceres", "demeter", "ceres", "demeter"].iter().enumerate() {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: owner.to_string(),
+            token_uri: Some(format!("ipfs://{}", i)),
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("market"),
+        token_id: String::from("2"),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), approve_msg).unwrap();
+
+    let detailed = |deps: cosmwasm_std::Deps, owner: &str, start_after: Option<String>| {
+        let msg = QueryMsg::TokensDetailed {
+            owner: owner.to_string(),
+            start_after,
+            limit: Some(2),
+            include_expired: None,
+        };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<TokensDetailedResponse<Extension>>(&res).unwrap().tokens
+    };
+
+    // pages and cursors line up with the plain Tokens query
+    for owner in ["demeter", "ceres"] {
+        let mut start_after = None;
+        loop {
+            let page = detailed(deps.as_ref(), owner, start_after.clone());
+            let ids = contract
+                .tokens(deps.as_ref(), owner.to_string(), start_after, Some(2))
+                .unwrap().tokens;
+            assert_eq!(page.iter().map(|t| t.token_id.clone()).collect::<Vec<_>>(), ids);
+            if page.is_empty() {
+                break;
+            }
+            start_after = ids.last().cloned();
+        }
+    }
+
+    let first = detailed(deps.as_ref(), "demeter", None);
+    assert_eq!(first.len(), 2);
+    assert_eq!(first[1].token_id, "2");
+    assert_eq!(first[1].token_uri, Some(String::from("ipfs://2")));
+    assert_eq!(first[1].approvals, vec![Approval {
+        spender: String::from("market"),
+        expires: Expiration::Never {},
+    }]);
+    let last = detailed(deps.as_ref(), "demeter", Some(String::from("2")));
+    assert_eq!(last.len(), 1);
+    assert_eq!(last[0].token_id, "4");
+    assert!(last[0].approvals.is_empty());
+}
+
+#[test]
+fn counting_minted_and_burned() {
+    for basis in [None, Some(SupplyCapBasis::Circulating)] {
+        let mut deps = mock_dependencies();
+        let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+        let msg = InstantiateMsg {
+            name: CONTRACT_NAME.to_string(),
+            symbol: SYMBOL.to_string(),
+            minter: Some(String::from(MINTER)),
+            max_supply: Some(3),
+            supply_cap_basis: basis,
+            ..Default::default()
+        };
+        contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        for _ in 0..3 {
+            let info = mock_info(MINTER, &[]);
+            contract.execute(deps.as_mut(), mock_env(), info, mint_msg.clone()).unwrap();
+        }
+        let burn_msg = ExecuteMsg::Burn { token_id: String::from("1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+
+        let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::Supply {}).unwrap();
+        assert_eq!(from_json::<SupplyResponse>(&res).unwrap(), SupplyResponse {
+            circulating: 2,
+            minted: 3,
+            burned: 1,
+            bridged_in: 0,
+            bridged_out: 0,
+            max_supply: Some(3),
+            cap_basis: basis.unwrap_or_default(),
+            sold_out: basis.is_none(),
+        });
+        assert_eq!(contract.num_tokens(deps.as_ref()).unwrap().count, 2);
+
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg);
+        match basis {
+            // the burned token doesn't free up supply under the default hard cap
+            None => assert_eq!(res.unwrap_err(), ContractError::SupplyOverflow {}),
+            // the new token gets a fresh id instead of reusing the burned one
+            Some(_) => {
+                let token_id = res.unwrap().attributes
+                    .into_iter()
+                    .find(|attr| attr.key == "token_id")
+                    .unwrap().value;
+                assert_eq!(token_id, "3");
+                assert_eq!(contract.supply(deps.as_ref()).unwrap().minted, 4);
+            }
+        }
+    }
+}
+
+#[test]
+fn emitting_sold_out_once() {
+    for basis in [None, Some(SupplyCapBasis::Circulating)] {
+        let mut deps = mock_dependencies();
+        let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+        let msg = InstantiateMsg {
+            name: CONTRACT_NAME.to_string(),
+            symbol: SYMBOL.to_string(),
+            minter: Some(String::from(MINTER)),
+            max_supply: Some(3),
+            supply_cap_basis: basis,
+            ..Default::default()
+        };
+        contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        let sold_out_events = |res: Response| {
+            res.events
+                .into_iter()
+                .filter(|event| event.ty == "cw721_sold_out")
+                .collect::<Vec<_>>()
+        };
+
+        for i in 0..3 {
+            let info = mock_info(MINTER, &[]);
+            let res = contract.execute(deps.as_mut(), mock_env(), info, mint_msg.clone()).unwrap();
+            let events = sold_out_events(res);
+            if i < 2 {
+                assert!(events.is_empty());
+                assert!(!contract.supply(deps.as_ref()).unwrap().sold_out);
+            } else {
+                assert_eq!(events, vec![
+                    Event::new("cw721_sold_out")
+                        .add_attribute("max_supply", "3")
+                        .add_attribute("last_token_id", "2")
+                ]);
+            }
+        }
+        assert!(contract.supply(deps.as_ref()).unwrap().sold_out);
+
+        let burn_msg = ExecuteMsg::Burn { token_id: String::from("1") };
+        contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg);
+        match basis {
+            // the hard cap stays sold out, there is nothing left to announce
+            None => {
+                assert_eq!(res.unwrap_err(), ContractError::SupplyOverflow {});
+                assert!(contract.supply(deps.as_ref()).unwrap().sold_out);
+            }
+            // the burn reopened the mint, so filling it again is a new sellout
+            Some(_) => {
+                let events = sold_out_events(res.unwrap());
+                assert_eq!(events.len(), 1);
+                assert_eq!(events[0].attributes[1].value, "3");
+            }
+        }
+    }
+}
+
+#[test]
+fn querying_batch_owner_of() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for owner in ["demeter", "ceres", "venus"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let expires = Expiration::AtHeight(mock_env().block.height + 5);
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("market"),
+        token_id: String::from("0"),
+        expires: Some(expires),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), approve_msg).unwrap();
+    let burn_msg = ExecuteMsg::Burn { token_id: String::from("1") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), burn_msg).unwrap();
+
+    // the approval has expired by now
+    let mut env = mock_env();
+    env.block.height += 10;
+    let token_ids = ["2", "1", "unknown", "0"].map(String::from).to_vec();
+    let batch = |deps: cosmwasm_std::Deps, include_expired| {
+        let msg = QueryMsg::BatchOwnerOf { token_ids: token_ids.clone(), include_expired };
+        let res = contract.query(deps, env.clone(), msg).unwrap();
+        from_json::<Vec<Option<OwnerOfResponse>>>(&res).unwrap()
+    };
+    let owner = |owner: &str, approvals| {
+        Some(OwnerOfResponse { owner: owner.to_string(), approvals })
+    };
+    assert_eq!(batch(deps.as_ref(), None), vec![
+        owner("venus", vec![]),
+        None,
+        None,
+        owner("demeter", vec![])
+    ]);
+    let approval = Approval { spender: String::from("market"), expires };
+    assert_eq!(batch(deps.as_ref(), Some(true)), vec![
+        owner("venus", vec![]),
+        None,
+        None,
+        owner("demeter", vec![approval])
+    ]);
+
+    let token_ids = (0..101).map(|i: u32| i.to_string()).collect();
+    let msg = QueryMsg::BatchOwnerOf { token_ids, include_expired: None };
+    contract.query(deps.as_ref(), mock_env(), msg).unwrap_err();
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct Character {
+    class: String,
+    level: u32,
+}
+
+fn index_character(character: &Character) -> Vec<(String, String)> {
+    vec![
+        (String::from("class"), character.class.clone()),
+        (String::from("level"), format!("{:03}", character.level))
+    ]
+}
+
+#[test]
+fn querying_tokens_by_extension_key() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Character, Empty, Empty, Empty>
+        ::default()
+        .with_extension_index(index_character);
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for (class, level) in [("mage", 5), ("warrior", 5), ("mage", 12), ("mage", 1)] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: Character { class: class.to_string(), level },
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let by_key = |deps: cosmwasm_std::Deps, key: &str, value: &str, start_after, limit| {
+        let msg = QueryMsg::TokensByExtensionKey {
+            key: key.to_string(),
+            value: value.to_string(),
+            start_after,
+            limit,
+        };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<TokensResponse>(&res).unwrap().tokens
+    };
+    assert_eq!(by_key(deps.as_ref(), "class", "mage", None, None), vec!["0", "2", "3"]);
+    assert_eq!(by_key(deps.as_ref(), "class", "mage", None, Some(2)), vec!["0", "2"]);
+    assert_eq!(by_key(deps.as_ref(), "class", "mage", Some(String::from("2")), None), vec!["3"]);
+    assert_eq!(by_key(deps.as_ref(), "level", "005", None, None), vec!["0", "1"]);
+    assert!(by_key(deps.as_ref(), "class", "rogue", None, None).is_empty());
+    // values only match exactly
+    assert!(by_key(deps.as_ref(), "level", "5", None, None).is_empty());
+
+    // changing the extension moves the token between index entries
+    let promoted = Character { class: String::from("archmage"), level: 12 };
+    contract.update_extension(deps.as_mut().storage, "0", promoted.clone()).unwrap();
+    let token = contract.nft_info(deps.as_ref(), String::from("0")).unwrap();
+    assert_eq!(token.extension, promoted);
+    assert_eq!(by_key(deps.as_ref(), "class", "mage", None, None), vec!["2", "3"]);
+    assert_eq!(by_key(deps.as_ref(), "class", "archmage", None, None), vec!["0"]);
+    assert_eq!(by_key(deps.as_ref(), "level", "012", None, None), vec!["0", "2"]);
+    assert_eq!(by_key(deps.as_ref(), "level", "005", None, None), vec!["1"]);
+
+    // burned tokens leave the index
+    let burn_msg = ExecuteMsg::Burn { token_id: String::from("2") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    assert_eq!(by_key(deps.as_ref(), "class", "mage", None, None), vec!["3"]);
+    assert_eq!(by_key(deps.as_ref(), "level", "012", None, None), vec!["0"]);
+
+    // without an index function the query is not available
+    let contract = Cw721Contract::<Character, Empty, Empty, Empty>::default();
+    let msg = QueryMsg::TokensByExtensionKey {
+        key: String::from("class"),
+        value: String::from("mage"),
+        start_after: None,
+        limit: None,
+    };
+    contract.query(deps.as_ref(), mock_env(), msg).unwrap_err();
+}
+
+#[test]
+fn retaining_burned_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        retain_burned_tokens: Some(true),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for owner in ["demeter", "ceres", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let mut env = mock_env();
+    env.block.height += 10;
+    env.block.time = env.block.time.plus_seconds(60);
+    let burn_msg = |token_id: &str| ExecuteMsg::Burn { token_id: token_id.to_string() };
+    contract.execute(deps.as_mut(), env.clone(), mock_info("ceres", &[]), burn_msg("2")).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg("0")).unwrap();
+
+    let burn_record = |deps: cosmwasm_std::Deps, token_id: &str| {
+        let msg = QueryMsg::BurnRecord { token_id: token_id.to_string() };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<Option<BurnRecord>>(&res).unwrap()
+    };
+    let first = BurnRecord {
+        owner: Addr::unchecked("demeter"),
+        burned_at_height: mock_env().block.height,
+        burned_at: mock_env().block.time,
+    };
+    let second = BurnRecord {
+        owner: Addr::unchecked("ceres"),
+        burned_at_height: env.block.height,
+        burned_at: env.block.time,
+    };
+    assert_eq!(burn_record(deps.as_ref(), "0"), Some(first.clone()));
+    assert_eq!(burn_record(deps.as_ref(), "2"), Some(second.clone()));
+    // still alive or never minted
+    assert_eq!(burn_record(deps.as_ref(), "1"), None);
+    assert_eq!(burn_record(deps.as_ref(), "3"), None);
+    // burned tokens are gone for the regular queries
+    contract.nft_info(deps.as_ref(), String::from("0")).unwrap_err();
+
+    let burned = |start_after: Option<&str>, limit| {
+        let msg = QueryMsg::BurnedTokens { start_after: start_after.map(String::from), limit };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        from_json::<BurnedTokensResponse>(&res).unwrap().tokens
+    };
+    assert_eq!(burned(None, None), vec![
+        BurnedTokenResponse { token_id: String::from("0"), record: first },
+        BurnedTokenResponse { token_id: String::from("2"), record: second.clone() }
+    ]);
+    assert_eq!(burned(None, Some(1)).len(), 1);
+    assert_eq!(burned(Some("0"), None), vec![BurnedTokenResponse {
+        token_id: String::from("2"),
+        record: second,
+    }]);
+
+    // nothing is kept unless asked for
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg("0")).unwrap();
+    assert!(contract.burned_tokens(deps.as_ref(), None, None).unwrap().tokens.is_empty());
+}
+
+#[test]
+fn looking_up_tokens_by_uri() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        delayed_reveal: Some(true),
+        index_token_uris: Some(true),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for token_uri in [Some("ipfs://hidden"), Some("ipfs://hidden"), None, Some("ipfs://hidden")] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: token_uri.map(String::from),
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let by_uri = |deps: cosmwasm_std::Deps, token_uri: &str, start_after: Option<&str>, limit| {
+        let msg = QueryMsg::TokensByUri {
+            token_uri: token_uri.to_string(),
+            start_after: start_after.map(String::from),
+            limit,
+        };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<TokensResponse>(&res).unwrap().tokens
+    };
+    assert_eq!(by_uri(deps.as_ref(), "ipfs://hidden", None, None), vec!["0", "1", "3"]);
+    assert_eq!(by_uri(deps.as_ref(), "ipfs://hidden", None, Some(2)), vec!["0", "1"]);
+    assert_eq!(by_uri(deps.as_ref(), "ipfs://hidden", Some("1"), None), vec!["3"]);
+    assert!(by_uri(deps.as_ref(), "ipfs://1", None, None).is_empty());
+
+    // revealing moves tokens out of the shared bucket, two may still share a uri
+    let reveal_msg = ExecuteMsg::RevealTokens {
+        tokens: vec![
+            (String::from("1"), String::from("ipfs://1")),
+            (String::from("2"), String::from("ipfs://dup")),
+            (String::from("3"), String::from("ipfs://dup"))
+        ],
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), reveal_msg).unwrap();
+    assert_eq!(by_uri(deps.as_ref(), "ipfs://hidden", None, None), vec!["0"]);
+    assert_eq!(by_uri(deps.as_ref(), "ipfs://1", None, None), vec!["1"]);
+    assert_eq!(by_uri(deps.as_ref(), "ipfs://dup", None, None), vec!["2", "3"]);
+
+    // burned tokens leave their bucket
+    let burn_msg = ExecuteMsg::Burn { token_id: String::from("3") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    assert_eq!(by_uri(deps.as_ref(), "ipfs://dup", None, None), vec!["2"]);
+
+    // the index is opt-in
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let msg = QueryMsg::TokensByUri {
+        token_uri: String::from("ipfs://hidden"),
+        start_after: None,
+        limit: None,
+    };
+    contract.query(deps.as_ref(), mock_env(), msg).unwrap_err();
+}
+
+#[test]
+fn configuring_pagination() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        pagination: Some(PaginationConfig { default_limit: 2, max_limit: 3 }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..5 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    for operator in ["hermes", "market", "venus", "zeus"] {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from(operator),
+            expires: None,
+            max_uses: None,
+            permissions: None,
+        };
+        let info = mock_info("demeter", &[]);
+        contract.execute(deps.as_mut(), mock_env(), info, approve_all_msg).unwrap();
+    }
+
+    let all_tokens = |limit| contract.all_tokens(deps.as_ref(), None, limit).unwrap().tokens;
+    assert_eq!(all_tokens(None), vec!["0", "1"]);
+    // larger limits are clamped instead of failing
+    assert_eq!(all_tokens(Some(100)), vec!["0", "1", "2"]);
+    assert_eq!(all_tokens(Some(1)), vec!["0"]);
+
+    let tokens = contract.tokens(deps.as_ref(), String::from("demeter"), None, Some(100)).unwrap();
+    assert_eq!(tokens.tokens.len(), 3);
+    let msg = QueryMsg::AllOperators {
+        owner: String::from("demeter"),
+        include_expired: None,
+        start_after: None,
+        limit: None,
+    };
+    let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+    assert_eq!(from_json::<OperatorsResponse>(&res).unwrap().operators.len(), 2);
+    let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::Holders {
+        start_after: None,
+        limit: Some(100),
+    });
+    assert_eq!(from_json::<HoldersResponse>(&res.unwrap()).unwrap().holders.len(), 1);
+    assert_eq!(contract.burned_tokens(deps.as_ref(), None, None).unwrap().tokens.len(), 0);
+
+    // listings with a tighter maximum of their own keep it
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        pagination: Some(PaginationConfig { default_limit: 50, max_limit: 5000 }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..40 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    assert_eq!(contract.all_tokens(deps.as_ref(), None, None).unwrap().tokens.len(), 40);
+    let msg = QueryMsg::TokensDetailed {
+        owner: String::from("demeter"),
+        start_after: None,
+        limit: None,
+        include_expired: None,
+    };
+    let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+    assert_eq!(from_json::<TokensDetailedResponse<Extension>>(&res).unwrap().tokens.len(), 30);
+
+    for pagination in [
+        PaginationConfig { default_limit: 0, max_limit: 10 },
+        PaginationConfig { default_limit: 20, max_limit: 10 },
+    ] {
+        let msg = InstantiateMsg {
+            name: CONTRACT_NAME.to_string(),
+            symbol: SYMBOL.to_string(),
+            pagination: Some(pagination),
+            ..Default::default()
+        };
+        let err = contract
+            .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPagination {});
+    }
+}
+
+#[test]
+fn listing_tokens_with_owner() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let owners = ["demeter", "ceres", "venus", "demeter", "ceres"];
+    for owner in owners {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let page = |start_after: Option<String>, limit| {
+        let msg = QueryMsg::AllTokensWithOwner { start_after, limit };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        from_json::<TokensWithOwnerResponse>(&res).unwrap().tokens
+    };
+    let token_owner = |token_id: &str, owner: &str| TokenOwner {
+        token_id: token_id.to_string(),
+        owner: owner.to_string(),
+    };
+    let first = page(None, Some(3));
+    assert_eq!(first, vec![
+        token_owner("0", "demeter"),
+        token_owner("1", "ceres"),
+        token_owner("2", "venus")
+    ]);
+    // the last token id is the cursor, as with AllTokens
+    let cursor = first.last().map(|token| token.token_id.clone());
+    let second = page(cursor.clone(), Some(3));
+    assert_eq!(second, vec![token_owner("3", "demeter"), token_owner("4", "ceres")]);
+    let all_tokens = contract.all_tokens(deps.as_ref(), cursor, Some(3)).unwrap();
+    let ids = second
+        .iter()
+        .map(|token| token.token_id.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(all_tokens.tokens, ids);
+
+    assert_eq!(page(None, Some(1000)).len(), owners.len());
+}
+
+#[test]
+fn querying_tokens_exist() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..3 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let burn_msg = ExecuteMsg::Burn { token_id: String::from("1") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+
+    let exist = |token_ids: Vec<String>| {
+        let msg = QueryMsg::TokensExist { token_ids };
+        let res = contract.query(deps.as_ref(), mock_env(), msg)?;
+        from_json::<Vec<bool>>(&res)
+    };
+    let token_ids = ["2", "1", "0", "2", "unknown", "00"].map(String::from).to_vec();
+    assert_eq!(exist(token_ids).unwrap(), vec![true, false, true, true, false, false]);
+    assert_eq!(exist(vec![]).unwrap(), Vec::<bool>::new());
+
+    let token_ids = (0..500).map(|i: u32| i.to_string()).collect();
+    assert_eq!(exist(token_ids).unwrap().iter().filter(|exists| **exists).count(), 2);
+    let token_ids = (0..501).map(|i: u32| i.to_string()).collect();
+    exist(token_ids).unwrap_err();
+}
+
+#[test]
+fn querying_tokens_minted_between() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let start = mock_env().block.time;
+    for offset in [0, 100, 100, 200, 300] {
+        let mut env = mock_env();
+        env.block.time = start.plus_seconds(offset);
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), env, mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let between = |deps: cosmwasm_std::Deps, from, to, start_after: Option<&str>, limit| {
+        let msg = QueryMsg::TokensMintedBetween {
+            start: start.plus_seconds(from),
+            end: start.plus_seconds(to),
+            start_after: start_after.map(String::from),
+            limit,
+        };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<TokensResponse>(&res).unwrap().tokens
+    };
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(between(deps.as_ref(), 0, 400, None, None), ids(&["0", "1", "2", "3", "4"]));
+    assert_eq!(between(deps.as_ref(), 100, 201, None, None), ids(&["1", "2", "3"]));
+    assert_eq!(between(deps.as_ref(), 50, 200, None, None), ids(&["1", "2"]));
+    assert!(between(deps.as_ref(), 400, 500, None, None).is_empty());
+    assert!(between(deps.as_ref(), 101, 200, None, None).is_empty());
+
+    // pages continue after the cursor, even within the same second
+    assert_eq!(between(deps.as_ref(), 0, 400, None, Some(2)), ids(&["0", "1"]));
+    assert_eq!(between(deps.as_ref(), 0, 400, Some("1"), Some(2)), ids(&["2", "3"]));
+    assert_eq!(between(deps.as_ref(), 0, 400, Some("3"), Some(2)), ids(&["4"]));
+    // a cursor before the window starts the window from the beginning
+    assert_eq!(between(deps.as_ref(), 200, 400, Some("0"), None), ids(&["3", "4"]));
+
+    // burned tokens leave the index
+    let burn_msg = ExecuteMsg::Burn { token_id: String::from("2") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    assert_eq!(between(deps.as_ref(), 100, 201, None, None), ids(&["1", "3"]));
+}
+
+#[test]
+fn ranking_top_holders() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    // alpha: 0, beta: 1 2 3, gamma: 4 5
+    for owner in ["alpha", "beta", "beta", "beta", "gamma", "gamma"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let top = |deps: cosmwasm_std::Deps, limit| {
+        let res = contract.query(deps, mock_env(), QueryMsg::TopHolders { limit }).unwrap();
+        from_json::<HoldersResponse>(&res)
+            .unwrap()
+            .holders.into_iter()
+            .map(|holder| (holder.address, holder.count))
+            .collect::<Vec<_>>()
+    };
+    let ranking = |ranking: &[(&str, u64)]| {
+        ranking
+            .iter()
+            .map(|(address, count)| (address.to_string(), *count))
+            .collect::<Vec<_>>()
+    };
+    let transfer = |deps: DepsMut, from: &str, to: &str, token_id: &str| {
+        let transfer_msg = ExecuteMsg::TransferNft {
+            recipient: String::from(to),
+            token_id: token_id.to_string(),
+        };
+        contract.execute(deps, mock_env(), mock_info(from, &[]), transfer_msg).unwrap();
+    };
+
+    assert_eq!(top(deps.as_ref(), None), ranking(&[("beta", 3), ("gamma", 2), ("alpha", 1)]));
+    assert_eq!(top(deps.as_ref(), Some(1)), ranking(&[("beta", 3)]));
+
+    // beta hands two tokens to alpha, which takes the lead
+    transfer(deps.as_mut(), "beta", "alpha", "1");
+    transfer(deps.as_mut(), "beta", "alpha", "2");
+    assert_eq!(top(deps.as_ref(), None), ranking(&[("alpha", 3), ("gamma", 2), ("beta", 1)]));
+
+    // gamma moves everything to a new wallet and drops off the board
+    transfer(deps.as_mut(), "gamma", "delta", "4");
+    transfer(deps.as_mut(), "gamma", "delta", "5");
+    assert_eq!(top(deps.as_ref(), None), ranking(&[("alpha", 3), ("delta", 2), ("beta", 1)]));
+
+    // ties list the later address first
+    transfer(deps.as_mut(), "alpha", "beta", "0");
+    assert_eq!(top(deps.as_ref(), None), ranking(&[("delta", 2), ("beta", 2), ("alpha", 2)]));
+
+    let burn_msg = ExecuteMsg::Burn { token_id: String::from("4") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("delta", &[]), burn_msg).unwrap();
+    assert_eq!(top(deps.as_ref(), None), ranking(&[("beta", 2), ("alpha", 2), ("delta", 1)]));
+}
+
+#[test]
+fn querying_is_transferable() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        enforce_operator_allowlist: Some(true),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..4 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let owner = mock_info("demeter", &[]);
+    let nest_msg = ExecuteMsg::NestToken {
+        child_id: String::from("1"),
+        parent_id: String::from("0"),
+    };
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), nest_msg).unwrap();
+    let redeem_msg = ExecuteMsg::Redeem { token_id: String::from("2"), redemption_data: None };
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), redeem_msg).unwrap();
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("friend"),
+        token_id: String::from("3"),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_msg).unwrap();
+    let operators = [
+        ("burner", None, Some(OperatorPermissions { can_transfer: false, ..Default::default() })),
+        ("spent", Some(0), None),
+        ("market", None, None),
+    ];
+    for (operator, max_uses, permissions) in operators {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from(operator),
+            expires: None,
+            max_uses,
+            permissions,
+        };
+        contract.execute(deps.as_mut(), mock_env(), owner.clone(), approve_all_msg).unwrap();
+    }
+    let admin = mock_info(MINTER, &[]);
+    for operator in ["burner", "spent"] {
+        let add_msg = ExecuteMsg::AddAllowedOperator { operator: String::from(operator) };
+        contract.execute(deps.as_mut(), mock_env(), admin.clone(), add_msg).unwrap();
+    }
+
+    let check = |token_id: &str, by: Option<&str>| {
+        let msg = QueryMsg::IsTransferable {
+            token_id: token_id.to_string(),
+            by: by.map(String::from),
+        };
+        from_json::<TransferableResponse>(
+            &contract.query(deps.as_ref(), mock_env(), msg).unwrap()
+        ).unwrap()
+    };
+    let blocked = |err: ContractError| TransferableResponse {
+        transferable: false,
+        reason: Some(err.to_string()),
+    };
+    let ok = TransferableResponse { transferable: true, reason: None };
+
+    assert_eq!(check("3", None), ok);
+    assert_eq!(check("3", Some("demeter")), ok);
+    assert_eq!(check("3", Some("friend")), ok);
+    assert_eq!(check("1", None), blocked(ContractError::TokenNested {}));
+    assert_eq!(check("2", Some("demeter")), blocked(ContractError::TokenRedeemed {}));
+    assert_eq!(
+        check("3", Some("random")),
+        blocked(ContractError::Ownership(OwnershipError::NotOwner))
+    );
+    assert_eq!(
+        check("3", Some("burner")),
+        blocked(ContractError::OperatorNotPermitted { action: String::from("transfer") })
+    );
+    assert_eq!(check("3", Some("spent")), blocked(ContractError::OperatorBudgetExhausted {}));
+    assert_eq!(
+        check("3", Some("market")),
+        blocked(ContractError::OperatorNotAllowed { operator: String::from("market") })
+    );
+
+    // the query agrees with an actual transfer
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: String::from("3"),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("market", &[]), transfer_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorNotAllowed { operator: String::from("market") });
+}
+
+#[test]
+fn querying_contract_status() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_supply: Some(2),
+        operators_can_burn: Some(false),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let status = |deps: cosmwasm_std::Deps| {
+        let res = contract.query(deps, mock_env(), QueryMsg::ContractStatus {}).unwrap();
+        from_json::<ContractStatusResponse>(&res).unwrap()
+    };
+    assert_eq!(status(deps.as_ref()), ContractStatusResponse {
+        sold_out: false,
+        owner: Some(String::from(MINTER)),
+        pending_owner: None,
+        ownership_renounced: false,
+        operators_can_burn: false,
+        enforce_operator_allowlist: false,
+        version: contract.contract_version(deps.as_ref()).unwrap(),
+    });
+
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    assert!(status(deps.as_ref()).sold_out);
+
+    // burning doesn't reopen the mint under the default hard cap
+    let burn_msg = ExecuteMsg::Burn { token_id: String::from("0") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    assert!(status(deps.as_ref()).sold_out);
+
+    let transfer_msg = ExecuteMsg::UpdateOwnership(cw_ownable::Action::TransferOwnership {
+        new_owner: String::from("ceres"),
+        expiry: None,
+    });
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), transfer_msg).unwrap();
+    let pending = status(deps.as_ref());
+    assert_eq!(pending.pending_owner, Some(String::from("ceres")));
+    assert!(!pending.ownership_renounced);
+
+    let renounce_msg = ExecuteMsg::UpdateOwnership(cw_ownable::Action::RenounceOwnership);
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), renounce_msg).unwrap();
+    let renounced = status(deps.as_ref());
+    assert_eq!(renounced.owner, None);
+    assert_eq!(renounced.pending_owner, None);
+    assert!(renounced.ownership_renounced);
+}
+
+#[test]
+fn querying_config() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_supply: Some(100),
+        max_nfts_per_wallet: Some(3),
+        price_per_nft: Some(Coin::new(5, "uatom")),
+        default_approval_ttl: Some(60),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let config = |deps: cosmwasm_std::Deps| {
+        let res = contract.query(deps, mock_env(), QueryMsg::Config {}).unwrap();
+        from_json::<ConfigResponse>(&res).unwrap()
+    };
+    assert_eq!(config(deps.as_ref()), ConfigResponse {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        owner: Some(String::from(MINTER)),
+        pending_owner: None,
+        withdraw_address: None,
+        max_supply: Some(100),
+        supply_cap_basis: SupplyCapBasis::Minted,
+        max_nfts_per_wallet: Some(3),
+        mint_price_per_nft: Some(Coin::new(5, "uatom")),
+        redemption_payout: None,
+        max_approvals_per_token: DEFAULT_MAX_APPROVALS_PER_TOKEN,
+        default_approval_ttl: Some(60),
+        allow_never_expiration: true,
+        operators_can_burn: true,
+        enforce_operator_allowlist: false,
+        record_token_history: false,
+        max_token_history: DEFAULT_MAX_TOKEN_HISTORY,
+        activity_buffer_size: DEFAULT_ACTIVITY_BUFFER_SIZE,
+        delayed_reveal: false,
+        balance_snapshots: SnapshotStrategy::Never,
+        retain_burned_tokens: false,
+        index_token_uris: false,
+        pagination: PaginationConfig::default(),
+        import_open: false,
+        bridge: None,
+        royalty: None,
+        auction_extension: DEFAULT_AUCTION_EXTENSION,
+        keep_user_on_transfer: false,
+        min_stake_period: 0,
+        wrapped_collection: None,
+        burn_to_mint: None,
+        reward_asset: None,
+        royalty_registry: None,
+        voucher_pubkey: None,
+        bridge_operator: None,
+        subscription: None,
+        co_signing: None,
+        proceeds_vesting: None,
+        open_edition: None,
+    });
+
+    // config changes show up next to the individual queries
+    let set_msg = ExecuteMsg::SetWithdrawAddress { address: String::from("treasury") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_msg).unwrap();
+    let transfer_msg = ExecuteMsg::UpdateOwnership(cw_ownable::Action::TransferOwnership {
+        new_owner: String::from("ceres"),
+        expiry: None,
+    });
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), transfer_msg).unwrap();
+    let updated = config(deps.as_ref());
+    assert_eq!(updated.withdraw_address, Some(String::from("treasury")));
+    assert_eq!(updated.pending_owner, Some(String::from("ceres")));
+    let res = contract.query(deps.as_ref(), mock_env(), QueryMsg::GetWithdrawAddress {}).unwrap();
+    assert_eq!(from_json::<Option<String>>(&res).unwrap(), updated.withdraw_address);
+
+    let accept_msg = ExecuteMsg::UpdateOwnership(cw_ownable::Action::AcceptOwnership);
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), accept_msg).unwrap();
+    let remove_msg = ExecuteMsg::RemoveWithdrawAddress {};
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), remove_msg).unwrap();
+    let updated = config(deps.as_ref());
+    assert_eq!(updated.owner, Some(String::from("ceres")));
+    assert_eq!(updated.pending_owner, None);
+    assert_eq!(updated.withdraw_address, None);
+    let minter = contract.minter(deps.as_ref()).unwrap();
+    assert_eq!(minter.minter, updated.owner);
+}
+
+#[test]
+fn querying_token_range() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for i in 0..12 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(if i % 2 == 0 { "demeter" } else { "ceres" }),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    for token_id in ["3", "4", "10"] {
+        let burn_msg = ExecuteMsg::Burn { token_id: token_id.to_string() };
+        let owner = if token_id == "3" { "ceres" } else { "demeter" };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), burn_msg).unwrap();
+    }
+
+    let range = |start, end, limit| {
+        let msg = QueryMsg::TokenRange { start, end, limit };
+        let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+        from_json::<TokenRangeResponse>(&res)
+            .unwrap()
+            .tokens
+            .into_iter()
+            .map(|TokenOwner { token_id, .. }| token_id)
+            .collect::<Vec<_>>()
+    };
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+    // burned ids are skipped and both ends are included
+    assert_eq!(range(2, 11, None), ids(&["2", "5", "6", "7", "8", "9", "11"]));
+    assert_eq!(range(2, 11, Some(3)), ids(&["2", "5", "6"]));
+    assert_eq!(range(7, 11, Some(3)), ids(&["7", "8", "9"]));
+    assert_eq!(range(10, 10, None), ids(&[]));
+    assert_eq!(range(100, 200, None), ids(&[]));
+
+    let msg = QueryMsg::TokenRange { start: 5, end: 5, limit: None };
+    let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+    assert_eq!(from_json::<TokenRangeResponse>(&res).unwrap().tokens, vec![TokenOwner {
+        token_id: String::from("5"),
+        owner: String::from("ceres"),
+    }]);
+
+    let msg = QueryMsg::TokenRange { start: 5, end: 4, limit: None };
+    contract.query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    let msg = QueryMsg::TokenRange { start: 0, end: 10_000, limit: None };
+    contract.query(deps.as_ref(), mock_env(), msg).unwrap_err();
+}
+
+#[test]
+fn querying_can_mint() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_nfts_per_wallet: Some(2),
+        price_per_nft: Some(Coin::new(100, "usei")),
+        max_supply: Some(3),
+        bech32_prefix: Some(String::from("sei")),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let can_mint = |deps: cosmwasm_std::Deps, address: &str, quantity| {
+        let msg = QueryMsg::CanMint { address: String::from(address), quantity };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<CanMintResponse>(&res).unwrap()
+    };
+    let mint = |deps: DepsMut, owner: &str| {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        let info = mock_info(MINTER, &[Coin::new(100, "usei")]);
+        contract.execute(deps, mock_env(), info, mint_msg)
+    };
+
+    assert_eq!(can_mint(deps.as_ref(), "sei1venus", Some(2)), CanMintResponse {
+        can_mint: true,
+        reason: None,
+        price: Some(Coin::new(200, "usei")),
+        remaining_allocation: Some(2),
+    });
+
+    // wrong prefix
+    let res = can_mint(deps.as_ref(), "cosmos1venus", None);
+    assert!(!res.can_mint);
+    let err = mint(deps.as_mut(), "cosmos1venus").unwrap_err();
+    assert_eq!(res.reason, Some(err.to_string()));
+
+    // per-wallet limit
+    let res = can_mint(deps.as_ref(), "sei1venus", Some(3));
+    assert!(!res.can_mint);
+    assert_eq!(res.reason, Some(ContractError::MintPerWalletOverflow {}.to_string()));
+    mint(deps.as_mut(), "sei1venus").unwrap();
+    mint(deps.as_mut(), "sei1venus").unwrap();
+    let res = can_mint(deps.as_ref(), "sei1venus", None);
+    assert!(!res.can_mint);
+    assert_eq!(res.remaining_allocation, Some(0));
+    let err = mint(deps.as_mut(), "sei1venus").unwrap_err();
+    assert_eq!(res.reason, Some(err.to_string()));
+    assert_eq!(err, ContractError::MintPerWalletOverflow {});
+
+    // supply cap
+    let res = can_mint(deps.as_ref(), "sei1mars", Some(2));
+    assert!(!res.can_mint);
+    assert_eq!(res.reason, Some(ContractError::SupplyOverflow {}.to_string()));
+    assert_eq!(res.remaining_allocation, Some(2));
+    assert!(can_mint(deps.as_ref(), "sei1mars", None).can_mint);
+    mint(deps.as_mut(), "sei1mars").unwrap();
+    let res = can_mint(deps.as_ref(), "sei1mars", None);
+    assert!(!res.can_mint);
+    let err = mint(deps.as_mut(), "sei1mars").unwrap_err();
+    assert_eq!(res.reason, Some(err.to_string()));
+    assert_eq!(err, ContractError::SupplyOverflow {});
+}
+
+#[test]
+fn recording_token_history() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        record_token_history: Some(true),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let history = |deps: cosmwasm_std::Deps, start_after, limit, order| {
+        let msg = QueryMsg::TokenHistory {
+            token_id: "0".to_string(),
+            start_after,
+            limit,
+            order,
+        };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<TokenHistoryResponse>(&res).unwrap().entries
+    };
+
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.height += 1;
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), env.clone(), mock_info("demeter", &[]), transfer_msg).unwrap();
+
+    env.block.height += 1;
+    let send_msg = ExecuteMsg::SendNft {
+        contract: String::from("vault"),
+        token_id: "0".to_string(),
+        msg: to_json_binary("hold this").unwrap(),
+    };
+    contract.execute(deps.as_mut(), env.clone(), mock_info("ceres", &[]), send_msg).unwrap();
+
+    env.block.height += 1;
+    let burn_msg = ExecuteMsg::Burn { token_id: "0".to_string() };
+    contract.execute(deps.as_mut(), env, mock_info("vault", &[]), burn_msg).unwrap();
+
+    // the history outlives the token
+    let entries = history(deps.as_ref(), None, None, None);
+    let addr = |addr: &str| Some(Addr::unchecked(addr));
+    assert_eq!(
+        entries
+            .iter()
+            .map(|e| (e.sequence, e.entry.action, e.entry.from.clone(), e.entry.to.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, HistoryAction::Mint, None, addr("demeter")),
+            (2, HistoryAction::Transfer, addr("demeter"), addr("ceres")),
+            (3, HistoryAction::Send, addr("ceres"), addr("vault")),
+            (4, HistoryAction::Burn, addr("vault"), None)
+        ]
+    );
+    let heights = entries
+        .iter()
+        .map(|e| e.entry.at_height - mock_env().block.height)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, vec![0, 1, 2, 3]);
+
+    // paging both ways
+    let seqs = |entries: Vec<crate::msg::TokenHistoryEntry>| {
+        entries
+            .iter()
+            .map(|e| e.sequence)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(seqs(history(deps.as_ref(), Some(1), Some(2), None)), vec![2, 3]);
+    assert_eq!(seqs(history(deps.as_ref(), None, Some(2), Some(RangeOrder::Desc))), vec![4, 3]);
+    assert_eq!(seqs(history(deps.as_ref(), Some(3), None, Some(RangeOrder::Desc))), vec![2, 1]);
+}
+
+#[test]
+fn pruning_token_history() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        record_token_history: Some(true),
+        max_token_history: Some(3),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    for (from, to) in [("demeter", "ceres"), ("ceres", "demeter"), ("demeter", "ceres")] {
+        let transfer_msg = ExecuteMsg::TransferNft {
+            recipient: String::from(to),
+            token_id: "0".to_string(),
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(from, &[]), transfer_msg).unwrap();
+    }
+
+    // the mint was pruned, only the newest entries are kept
+    let msg = QueryMsg::TokenHistory {
+        token_id: "0".to_string(),
+        start_after: None,
+        limit: None,
+        order: None,
+    };
+    let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+    let entries = from_json::<TokenHistoryResponse>(&res).unwrap().entries;
+    assert_eq!(
+        entries
+            .iter()
+            .map(|e| e.sequence)
+            .collect::<Vec<_>>(),
+        vec![2, 3, 4]
+    );
+    assert!(entries.iter().all(|e| e.entry.action == HistoryAction::Transfer));
+
+    // nothing is recorded unless enabled
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    let msg = QueryMsg::TokenHistory {
+        token_id: "0".to_string(),
+        start_after: None,
+        limit: None,
+        order: None,
+    };
+    let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+    assert!(from_json::<TokenHistoryResponse>(&res).unwrap().entries.is_empty());
+}
+
+#[test]
+fn wrapping_activity_feed() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        activity_buffer_size: Some(4),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let activity = |deps: cosmwasm_std::Deps, start_after, limit, order| {
+        let msg = QueryMsg::Activity { start_after, limit, order };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<ActivityResponse>(&res)
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|e| (e.id, e.action, e.token_id))
+            .collect::<Vec<_>>()
+    };
+    assert!(activity(deps.as_ref(), None, None, None).is_empty());
+
+    // 0: mint, 1: mint, 2: approve, 3: transfer
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("random"),
+        token_id: "1".to_string(),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), approve_msg).unwrap();
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), transfer_msg).unwrap();
+    assert_eq!(activity(deps.as_ref(), None, None, None), vec![
+        (3, ActivityAction::Transfer, "0".to_string()),
+        (2, ActivityAction::Approve, "1".to_string()),
+        (1, ActivityAction::Mint, "1".to_string()),
+        (0, ActivityAction::Mint, "0".to_string())
+    ]);
+
+    // 4: burn, 5: burn overwrite the two mints
+    for (owner, token_id) in [("ceres", "0"), ("demeter", "1")] {
+        let burn_msg = ExecuteMsg::Burn { token_id: token_id.to_string() };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), burn_msg).unwrap();
+    }
+    assert_eq!(activity(deps.as_ref(), None, None, None), vec![
+        (5, ActivityAction::Burn, "1".to_string()),
+        (4, ActivityAction::Burn, "0".to_string()),
+        (3, ActivityAction::Transfer, "0".to_string()),
+        (2, ActivityAction::Approve, "1".to_string())
+    ]);
+    let ids = |entries: Vec<(u64, ActivityAction, String)>| {
+        entries
+            .into_iter()
+            .map(|(id, ..)| id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(activity(deps.as_ref(), None, Some(2), Some(RangeOrder::Asc))), vec![2, 3]);
+    assert_eq!(ids(activity(deps.as_ref(), Some(3), None, Some(RangeOrder::Asc))), vec![4, 5]);
+    assert_eq!(ids(activity(deps.as_ref(), Some(0), Some(1), Some(RangeOrder::Asc))), vec![2]);
+    assert_eq!(ids(activity(deps.as_ref(), Some(4), Some(1), None)), vec![3]);
+    // cursors pointing at overwritten events only return what is left
+    assert!(activity(deps.as_ref(), Some(2), None, None).is_empty());
+
+    // a buffer size of 0 disables the feed
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        activity_buffer_size: Some(0),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    assert!(activity(deps.as_ref(), None, None, None).is_empty());
+}
+
+#[test]
+fn enumerating_tokens_by_index() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for i in 0..6 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(if i % 2 == 0 { "demeter" } else { "ceres" }),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    // every live token exactly once, in any order
+    let enumerate = |deps: cosmwasm_std::Deps| {
+        let count = contract.num_tokens(deps).unwrap().count;
+        let mut token_ids = (0..count)
+            .map(|index| contract.token_by_index(deps, index).unwrap().token_id)
+            .collect::<Vec<_>>();
+        contract.token_by_index(deps, count).unwrap_err();
+        token_ids.sort_by_key(|id| id.parse::<u64>().unwrap());
+        token_ids
+    };
+    let enumerate_owner = |deps: cosmwasm_std::Deps, owner: &str| {
+        let count = contract.holder_balance(deps, owner.to_string()).unwrap().count;
+        let mut token_ids = (0..count)
+            .map(|index| {
+                contract.token_of_owner_by_index(deps, owner.to_string(), index).unwrap().token_id
+            })
+            .collect::<Vec<_>>();
+        contract.token_of_owner_by_index(deps, owner.to_string(), count).unwrap_err();
+        token_ids.sort_by_key(|id| id.parse::<u64>().unwrap());
+        token_ids
+    };
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    assert_eq!(enumerate(deps.as_ref()), ids(&["0", "1", "2", "3", "4", "5"]));
+    assert_eq!(enumerate_owner(deps.as_ref(), "demeter"), ids(&["0", "2", "4"]));
+
+    // burning a middle token moves the last one into its position
+    let burn_msg = ExecuteMsg::Burn { token_id: "2".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    assert_eq!(contract.token_by_index(deps.as_ref(), 2).unwrap().token_id, "5");
+    assert_eq!(enumerate(deps.as_ref()), ids(&["0", "1", "3", "4", "5"]));
+    assert_eq!(enumerate_owner(deps.as_ref(), "demeter"), ids(&["0", "4"]));
+
+    // transfers move tokens between the owners' enumerations
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("demeter"),
+        token_id: "1".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), transfer_msg).unwrap();
+    assert_eq!(enumerate_owner(deps.as_ref(), "demeter"), ids(&["0", "1", "4"]));
+    assert_eq!(enumerate_owner(deps.as_ref(), "ceres"), ids(&["3", "5"]));
+    assert_eq!(enumerate(deps.as_ref()), ids(&["0", "1", "3", "4", "5"]));
+
+    // burning the last position needs no swap
+    let burn_msg = ExecuteMsg::Burn { token_id: "1".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    assert_eq!(enumerate(deps.as_ref()), ids(&["0", "3", "4", "5"]));
+    assert_eq!(enumerate_owner(deps.as_ref(), "demeter"), ids(&["0", "4"]));
+}
+
+#[test]
+fn counting_owner_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let count = |deps: cosmwasm_std::Deps, owner: &str| {
+        let msg = QueryMsg::NumTokensOfOwner { owner: String::from(owner) };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<NumTokensResponse>(&res).unwrap().count
+    };
+    let page = |deps: cosmwasm_std::Deps, owner: &str, include_total| {
+        let msg = QueryMsg::Tokens {
+            owner: String::from(owner),
+            start_after: None,
+            limit: Some(1),
+            order: None,
+            include_total,
+        };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<OwnerTokensResponse>(&res).unwrap()
+    };
+    assert_eq!(count(deps.as_ref(), "demeter"), 0);
+
+    for owner in ["demeter", "demeter", "demeter", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    assert_eq!(count(deps.as_ref(), "demeter"), 3);
+    assert_eq!(count(deps.as_ref(), "ceres"), 1);
+
+    // the total covers every page, not just the one returned
+    let res = page(deps.as_ref(), "demeter", Some(true));
+    assert_eq!(res.tokens, vec![String::from("0")]);
+    assert_eq!(res.total, Some(3));
+    assert_eq!(page(deps.as_ref(), "demeter", Some(false)).total, None);
+    // left out of the json unless asked for, so plain cw721 clients still parse it
+    let msg = QueryMsg::Tokens {
+        owner: String::from("demeter"),
+        start_after: None,
+        limit: None,
+        order: None,
+        include_total: None,
+    };
+    let res = contract.query(deps.as_ref(), mock_env(), msg).unwrap();
+    from_json::<TokensResponse>(&res).unwrap();
+
+    // transfer
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), transfer_msg).unwrap();
+    assert_eq!(count(deps.as_ref(), "demeter"), 2);
+    assert_eq!(count(deps.as_ref(), "ceres"), 2);
+
+    // transferring to yourself changes nothing
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), transfer_msg).unwrap();
+    assert_eq!(count(deps.as_ref(), "ceres"), 2);
+    assert_eq!(page(deps.as_ref(), "ceres", Some(true)).total, Some(2));
+
+    // burn
+    let burn_msg = ExecuteMsg::Burn { token_id: "0".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), burn_msg).unwrap();
+    assert_eq!(count(deps.as_ref(), "ceres"), 1);
+    assert_eq!(count(deps.as_ref(), "demeter"), 2);
+}
+
+#[test]
+fn revealing_tokens_partially() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        delayed_reveal: Some(true),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..12 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: Some(String::from("ipfs://placeholder")),
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+
+    let unrevealed = |deps: cosmwasm_std::Deps, start_after: Option<&str>, limit| {
+        let msg = QueryMsg::UnrevealedTokens {
+            start_after: start_after.map(String::from),
+            limit,
+        };
+        let res = contract.query(deps, mock_env(), msg).unwrap();
+        from_json::<UnrevealedTokensResponse>(&res).unwrap()
+    };
+    let res = unrevealed(deps.as_ref(), None, Some(5));
+    assert_eq!(res.count, 12);
+    assert_eq!(res.tokens, vec!["0", "1", "2", "3", "4"]);
+
+    // only the owner reveals
+    let reveal_msg = ExecuteMsg::RevealTokens {
+        tokens: vec![
+            (String::from("1"), String::from("ipfs://final/1")),
+            (String::from("10"), String::from("ipfs://final/10"))
+        ],
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), reveal_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), reveal_msg).unwrap();
+
+    let info = contract.nft_info(deps.as_ref(), String::from("10")).unwrap();
+    assert_eq!(info.token_uri, Some(String::from("ipfs://final/10")));
+    let info = contract.nft_info(deps.as_ref(), String::from("2")).unwrap();
+    assert_eq!(info.token_uri, Some(String::from("ipfs://placeholder")));
+
+    // revealed tokens drop out of the listing, pages follow numeric order
+    let res = unrevealed(deps.as_ref(), None, Some(5));
+    assert_eq!(res.count, 10);
+    assert_eq!(res.tokens, vec!["0", "2", "3", "4", "5"]);
+    let res = unrevealed(deps.as_ref(), Some("5"), Some(5));
+    assert_eq!(res.tokens, vec!["6", "7", "8", "9", "11"]);
+    assert!(unrevealed(deps.as_ref(), Some("11"), None).tokens.is_empty());
+
+    // a token is only revealed once, and the batch fails as a whole
+    let reveal_msg = ExecuteMsg::RevealTokens {
+        tokens: vec![
+            (String::from("0"), String::from("ipfs://final/0")),
+            (String::from("1"), String::from("ipfs://final/1"))
+        ],
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), reveal_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AlreadyRevealed { token_id: String::from("1") });
+
+    // burning an unrevealed token drops it too
+    let burn_msg = ExecuteMsg::Burn { token_id: "0".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    let burn_msg = ExecuteMsg::Burn { token_id: "1".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("demeter", &[]), burn_msg).unwrap();
+    let res = unrevealed(deps.as_ref(), None, Some(2));
+    assert_eq!(res.count, 9);
+    assert_eq!(res.tokens, vec!["2", "3"]);
+}
+
+#[test]
+fn auditing_all_operator_grants() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let grants = [
+        ("ceres", "market", None),
+        ("demeter", "vault", Some(10)),
+        ("ceres", "bridge", None),
+        ("demeter", "market", None),
+        ("venus", "market", None),
+    ];
+    for (granter, operator, max_uses) in grants {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            operator: String::from(operator),
+            expires: None,
+            max_uses,
+            permissions: None,
+        };
+        let info = mock_info(granter, &[]);
+        contract.execute(deps.as_mut(), mock_env(), info, approve_all_msg).unwrap();
+    }
+    // expires right away
+    let approve_all_msg = ExecuteMsg::ApproveAll {
+        operator: String::from("market"),
+        expires: Some(Expiration::AtHeight(mock_env().block.height + 1)),
+        max_uses: None,
+        permissions: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), approve_all_msg).unwrap();
+    let mut later = mock_env();
+    later.block.height += 1;
+
+    let page = |start_after: Option<(&str, &str)>, include_expired| {
+        let msg = QueryMsg::AllOperatorGrants {
+            start_after: start_after.map(|(granter, operator)| {
+                (String::from(granter), String::from(operator))
+            }),
+            include_expired: Some(include_expired),
+            limit: Some(2),
+        };
+        let res = contract.query(deps.as_ref(), later.clone(), msg).unwrap();
+        from_json::<OperatorGrantsResponse>(&res).unwrap().grants
+    };
+
+    // walks every page with the composite cursor
+    let mut all = vec![];
+    let mut cursor: Option<(String, String)> = None;
+    loop {
+        let grants = page(
+            cursor.as_ref().map(|(granter, operator)| (granter.as_str(), operator.as_str())),
+            false
+        );
+        if grants.is_empty() {
+            break;
+        }
+        let last = grants.last().unwrap();
+        cursor = Some((last.granter.clone(), last.operator.clone()));
+        all.extend(grants);
+    }
+    assert_eq!(
+        all
+            .iter()
+            .map(|g| (g.granter.as_str(), g.operator.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("ceres", "bridge"),
+            ("ceres", "market"),
+            ("venus", "market"),
+            ("demeter", "market"),
+            ("demeter", "vault")
+        ]
+    );
+    assert_eq!(all[4].grant.remaining_uses, Some(10));
+
+    // expired grants only when asked for
+    let grants = page(None, true);
+    assert_eq!(
+        grants
+            .iter()
+            .map(|g| (g.granter.as_str(), g.operator.as_str()))
+            .collect::<Vec<_>>(),
+        vec![("mars", "market"), ("ceres", "bridge")]
+    );
+}
+
+/// The same api `cargo schema` writes
+fn contract_api() -> cosmwasm_schema::Api {
+    cosmwasm_schema::generate_api! {
+        instantiate: InstantiateMsg,
+        execute: crate::Cw721CustomExecuteMsg,
+        query: crate::Cw721CustomQueryMsg,
+        migrate: crate::MigrateMsg,
+    }
+}
+
+#[test]
+fn schema_declares_every_query_response() {
+    let generated: serde_json::Value = serde_json
+        ::from_str(&contract_api().render().to_string().unwrap())
+        .unwrap();
+
+    let queries = generated["query"]["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|variant| variant["required"][0].as_str().unwrap())
+        .collect::<Vec<_>>();
+    let responses = generated["responses"].as_object().unwrap();
+    assert!(queries.contains(&"owner_of") && queries.contains(&"ownership"));
+    for query in &queries {
+        assert!(responses.contains_key(*query), "no response type for {}", query);
+    }
+    assert_eq!(responses.len(), queries.len());
+    assert_eq!(responses["owner_of"]["title"], "OwnerOfResponse");
+    assert_eq!(responses["tokens"]["title"], "OwnerTokensResponse");
+
+    // the committed schema must be regenerated with `cargo schema` after message changes
+    let committed: serde_json::Value = serde_json
+        ::from_str(include_str!("../schema/cw721-custom.json"))
+        .unwrap();
+    assert_eq!(committed, generated);
+}
+
+#[test]
+fn schema_files_are_valid_json_schema() {
+    let files = contract_api().render().to_schema_files().unwrap();
+    let names = files
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    for name in ["instantiate.json", "execute.json", "query.json", "migrate.json"] {
+        assert!(names.contains(&name), "{} is missing", name);
+    }
+    assert!(names.contains(&"response_to_supply.json"));
+    for (name, content) in &files {
+        serde_json::from_str::<schemars::schema::RootSchema>(content).unwrap_or_else(|err| {
+            panic!("{} is not a json schema: {}", name, err)
+        });
+    }
+
+    // the mint extension is the deployed Option<Empty>
+    let (_, execute) = files
+        .iter()
+        .find(|(name, _)| name == "execute.json")
+        .unwrap();
+    let execute = serde_json::from_str::<serde_json::Value>(execute).unwrap();
+    let mint = execute["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|variant| variant["required"][0] == "mint")
+        .unwrap();
+    let required = mint["properties"]["mint"]["required"].as_array().unwrap();
+    assert!(!required.contains(&serde_json::json!("extension")));
+}
+
+#[test]
+fn bridge_locking_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let lock_msg = |token_id: &str| ExecuteMsg::BridgeLock { token_id: token_id.to_string() };
+    let release_msg = |recipient: &str| ExecuteMsg::BridgeRelease {
+        token_id: "0".to_string(),
+        recipient: recipient.to_string(),
+    };
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), lock_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoBridgeOperator {});
+    let set_operator = ExecuteMsg::SetBridgeOperator { operator: Some(String::from("relayer")) };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_operator).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), lock_msg("0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), lock_msg("0")).unwrap();
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "0".to_string(), false).unwrap();
+    assert_eq!(owner.owner, MOCK_CONTRACT_ADDR);
+    // the locked token no longer shows up with the owner's tokens
+    let tokens = contract.tokens(deps.as_ref(), String::from("venus"), None, None).unwrap();
+    assert_eq!(tokens.tokens, vec!["1"]);
+    let locks = contract.bridge_locks(deps.as_ref(), None, None).unwrap().locks;
+    assert_eq!(locks, vec![BridgeLockResponse {
+        token_id: "0".to_string(),
+        original_owner: String::from("venus"),
+    }]);
+
+    for sender in ["venus", MINTER] {
+        let err = contract
+            .execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), release_msg("venus"))
+            .unwrap_err();
+        assert_eq!(err, ContractError::NotBridgeOperator {});
+    }
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), release_msg("venus"))
+        .unwrap();
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "0".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "venus");
+    assert!(contract.bridge_locks(deps.as_ref(), None, None).unwrap().locks.is_empty());
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), release_msg("venus"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotBridgeLocked {});
+}
+
+#[test]
+fn rewards_skip_bridge_locked_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        reward_asset: Some(RewardAsset::Native { denom: String::from("usei") }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for owner in ["venus", "venus", "ceres"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let set_operator = ExecuteMsg::SetBridgeOperator { operator: Some(String::from("relayer")) };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_operator).unwrap();
+    let fund = |deps: DepsMut, amount: u128| {
+        let info = mock_info("treasury", &[Coin::new(amount, "usei")]);
+        contract.execute(deps, mock_env(), info, ExecuteMsg::FundRewards {}).unwrap();
+    };
+    let pending = |deps: cosmwasm_std::Deps, address: &str| {
+        contract.pending_rewards(deps.storage, &Addr::unchecked(address)).unwrap().u128()
+    };
+
+    // the locked token is held by the contract and earns nothing
+    let lock_msg = ExecuteMsg::BridgeLock { token_id: "0".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), lock_msg).unwrap();
+    fund(deps.as_mut(), 200);
+    assert_eq!(pending(deps.as_ref(), "venus"), 100);
+    assert_eq!(pending(deps.as_ref(), "ceres"), 100);
+    assert_eq!(pending(deps.as_ref(), MOCK_CONTRACT_ADDR), 0);
+
+    // back on this chain it earns again
+    let release_msg = ExecuteMsg::BridgeRelease {
+        token_id: "0".to_string(),
+        recipient: String::from("venus"),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("relayer", &[]), release_msg).unwrap();
+    fund(deps.as_mut(), 300);
+    assert_eq!(pending(deps.as_ref(), "venus"), 300);
+    assert_eq!(pending(deps.as_ref(), "ceres"), 200);
+    assert_eq!(pending(deps.as_ref(), MOCK_CONTRACT_ADDR), 0);
+
+    // every funded reward can be claimed, nothing stays in escrow
+    for holder in ["venus", "ceres"] {
+        let info = mock_info(holder, &[]);
+        contract.execute(deps.as_mut(), mock_env(), info, ExecuteMsg::ClaimRewards {}).unwrap();
+    }
+    assert!(contract.escrowed_bids(&deps.storage, "usei").unwrap().is_zero());
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+struct Outfit {
+    traits: Vec<String>,
+}
+
+fn wear(mut outfit: Outfit, accessory: &Outfit) -> Outfit {
+    outfit.traits.extend(accessory.traits.iter().cloned());
+    outfit
+}
+
+#[test]
+fn attaching_accessories() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Outfit, Empty, Empty, Empty>
+        ::default()
+        .with_accessory_merge(wear);
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for item in ["avatar", "hat", "sunglasses"] {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: Outfit { traits: vec![item.to_string()] },
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let attach_msg = |parent_id: &str, accessory_id: &str| ExecuteMsg::Attach {
+        parent_id: parent_id.to_string(),
+        accessory_id: accessory_id.to_string(),
+    };
+    let traits = |deps: cosmwasm_std::Deps| {
+        contract.nft_info(deps, "0".to_string()).unwrap().extension.traits
+    };
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), attach_msg("0", "1"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), attach_msg("0", "0"))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NestingCycle {});
+    for accessory_id in ["1", "2"] {
+        let attach_msg = attach_msg("0", accessory_id);
+        contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), attach_msg).unwrap();
+    }
+    assert_eq!(traits(deps.as_ref()), vec!["avatar", "hat", "sunglasses"]);
+    // stored extensions are left alone
+    let hat = contract.nft_info(deps.as_ref(), "1".to_string()).unwrap();
+    assert_eq!(hat.extension.traits, vec!["hat"]);
+
+    // an accessory can't be worn twice, and can't wear the token it is attached to
+    for (parent_id, accessory_id) in [("2", "1"), ("1", "0")] {
+        let err = contract
+            .execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("venus", &[]),
+                attach_msg(parent_id, accessory_id)
+            )
+            .unwrap_err();
+        assert!(matches!(err, ContractError::TokenNested {} | ContractError::TokenHasChildren {}));
+    }
+    let transfer_hat = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "1".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), transfer_hat)
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenNested {});
+
+    // accessories go along with the token
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), transfer_msg).unwrap();
+    let hat = contract.all_nft_info(deps.as_ref(), mock_env(), "1".to_string(), false).unwrap();
+    assert_eq!(hat.access.owner, "mars");
+
+    let detach_msg = ExecuteMsg::Detach {
+        parent_id: "0".to_string(),
+        accessory_id: "1".to_string(),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), detach_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let info = mock_info("mars", &[]);
+    contract.execute(deps.as_mut(), mock_env(), info, detach_msg.clone()).unwrap();
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "1".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "mars");
+    assert_eq!(traits(deps.as_ref()), vec!["avatar", "sunglasses"]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), detach_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotAttached {});
+}
+
+#[test]
+fn raffle_positions_are_a_permutation() {
+    for seed in [&b"first"[..], &b"second"[..]] {
+        for entries in 1..70 {
+            let mut positions = (0..entries)
+                .map(|index| crate::execute::raffle_position(seed, index, entries))
+                .collect::<Vec<_>>();
+            positions.sort_unstable();
+            assert_eq!(positions, (0..entries).collect::<Vec<_>>());
+        }
+    }
+}
+
+#[test]
+fn raffle_mint() {
+    use sha2::{ Digest, Sha256 };
+
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_supply: Some(3),
+        price_per_nft: Some(Coin::new(100, "usei")),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let ends_at = mock_env().block.time.plus_seconds(100);
+    let start_msg = ExecuteMsg::StartRaffle { ends_at };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), start_msg).unwrap();
+    let price = [Coin::new(100, "usei")];
+
+    // the whole supply is up for the raffle
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &price), mint_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleInProgress {});
+
+    let entrants = (0..8).map(|i| format!("entrant{i}")).collect::<Vec<_>>();
+    for entrant in &entrants {
+        let info = mock_info(entrant, &price);
+        contract.execute(deps.as_mut(), mock_env(), info, ExecuteMsg::EnterRaffle {}).unwrap();
+    }
+    let info = mock_info("entrant0", &price);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), info, ExecuteMsg::EnterRaffle {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::AlreadyEntered {});
+    let info = mock_info("late", &[Coin::new(50, "usei")]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), info, ExecuteMsg::EnterRaffle {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughFunds {});
+    assert_eq!(contract.escrowed_bids(&deps.storage, "usei").unwrap().u128(), 800);
+
+    let seed = Binary::from(b"drop day".to_vec());
+    let hash = Binary::from(Sha256::digest(seed.as_slice()).to_vec());
+    let commit_msg = ExecuteMsg::CommitRaffleSeed { hash };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), commit_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleStillOpen {});
+    let mut env = mock_env();
+    env.block.time = ends_at;
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("late", &price), ExecuteMsg::EnterRaffle {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleNotOpen {});
+    contract.execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), commit_msg).unwrap();
+
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("entrant0", &[]), ExecuteMsg::ClaimMint {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleNotRevealed {});
+    let reveal_msg = ExecuteMsg::RevealRaffleSeed { seed: Binary::from(b"other".to_vec()) };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), reveal_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidRaffleSeed {});
+    let reveal_msg = ExecuteMsg::RevealRaffleSeed { seed };
+    contract.execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), reveal_msg).unwrap();
+
+    let won = |deps: cosmwasm_std::Deps| {
+        entrants
+            .iter()
+            .map(|entrant| contract.raffle_entry(deps, entrant.clone()).unwrap().unwrap().won)
+            .collect::<Vec<_>>()
+    };
+    let drawn = won(deps.as_ref());
+    assert_eq!(drawn.iter().filter(|won| **won == Some(true)).count(), 3);
+    assert_eq!(drawn.iter().filter(|won| **won == Some(false)).count(), 5);
+
+    for (entrant, won) in entrants.iter().zip(&drawn) {
+        let claim = |deps: DepsMut, msg| {
+            contract.execute(deps, env.clone(), mock_info(entrant, &[]), msg)
+        };
+        if *won == Some(true) {
+            let err = claim(deps.as_mut(), ExecuteMsg::ClaimRefund {}).unwrap_err();
+            assert_eq!(err, ContractError::RaffleWon {});
+            let res = claim(deps.as_mut(), ExecuteMsg::ClaimMint {}).unwrap();
+            let token_id = res.attributes
+                .iter()
+                .find(|attr| attr.key == "token_id")
+                .unwrap()
+                .value.clone();
+            let owner = contract.owner_of(deps.as_ref(), env.clone(), token_id, false).unwrap();
+            assert_eq!(owner.owner, *entrant);
+        } else {
+            let err = claim(deps.as_mut(), ExecuteMsg::ClaimMint {}).unwrap_err();
+            assert_eq!(err, ContractError::RaffleLost {});
+            let res = claim(deps.as_mut(), ExecuteMsg::ClaimRefund {}).unwrap();
+            assert_eq!(res.messages, vec![
+                SubMsg::new(BankMsg::Send { to_address: entrant.clone(), amount: price.to_vec() })
+            ]);
+        }
+        // every entry is claimed once, the draw doesn't move in between
+        for msg in [ExecuteMsg::ClaimMint {}, ExecuteMsg::ClaimRefund {}] {
+            assert_eq!(claim(deps.as_mut(), msg).unwrap_err(), ContractError::NoRaffleEntry {});
+        }
+        let remaining = entrants
+            .iter()
+            .zip(&drawn)
+            .filter_map(|(entrant, won)| {
+                let entry = contract.raffle_entry(deps.as_ref(), entrant.clone()).unwrap();
+                entry.map(|entry| (entry.won, *won))
+            })
+            .collect::<Vec<_>>();
+        assert!(remaining.iter().all(|(now, before)| now == before));
+    }
+
+    // the winners paid for their tokens and the losers got everything back
+    assert!(contract.escrowed_bids(&deps.storage, "usei").unwrap().is_zero());
+    let raffle = contract.raffle(deps.as_ref()).unwrap();
+    assert_eq!((raffle.entries, raffle.claimed), (8, 3));
+    let count = contract.num_tokens(deps.as_ref()).unwrap();
+    assert_eq!(count.count, 3);
+    let err = contract
+        .execute(deps.as_mut(), env, mock_info("venus", &price), mint_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::SupplyOverflow {});
+}
+
+#[test]
+fn raffle_winners_over_the_wallet_limit() {
+    use sha2::{ Digest, Sha256 };
+
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_supply: Some(3),
+        max_nfts_per_wallet: Some(1),
+        price_per_nft: Some(Coin::new(100, "usei")),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let ends_at = mock_env().block.time.plus_seconds(100);
+    let start_msg = ExecuteMsg::StartRaffle { ends_at };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), start_msg).unwrap();
+    let price = [Coin::new(100, "usei")];
+    let info = mock_info("venus", &price);
+    contract.execute(deps.as_mut(), mock_env(), info, ExecuteMsg::EnterRaffle {}).unwrap();
+    let seed = Binary::from(b"drop day".to_vec());
+    let hash = Binary::from(Sha256::digest(seed.as_slice()).to_vec());
+    let mut env = mock_env();
+    env.block.time = ends_at;
+    let commit_msg = ExecuteMsg::CommitRaffleSeed { hash };
+    contract.execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), commit_msg).unwrap();
+    let reveal_msg = ExecuteMsg::RevealRaffleSeed { seed };
+    contract.execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), reveal_msg).unwrap();
+
+    // the only entry won, but its wallet fills up with a regular mint before claiming
+    let mint_msg = |owner: &str| ExecuteMsg::Mint {
+        owner: String::from(owner),
+        token_uri: None,
+        extension: None,
+    };
+    let info = mock_info("venus", &price);
+    contract.execute(deps.as_mut(), env.clone(), info, mint_msg("venus")).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("venus", &[]), ExecuteMsg::ClaimMint {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::MintPerWalletOverflow {});
+    let res = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("venus", &[]), ExecuteMsg::ClaimRefund {})
+        .unwrap();
+    assert_eq!(res.messages, vec![
+        SubMsg::new(BankMsg::Send { to_address: "venus".to_string(), amount: price.to_vec() })
+    ]);
+    let tokens = contract.tokens(deps.as_ref(), "venus".to_string(), None, None).unwrap();
+    assert_eq!(tokens.tokens, vec!["0"]);
+
+    // the token given up goes back to the supply
+    let raffle = contract.raffle(deps.as_ref()).unwrap();
+    assert_eq!((raffle.entries, raffle.claimed), (1, 1));
+    for owner in ["ceres", "demeter"] {
+        let info = mock_info(owner, &price);
+        contract.execute(deps.as_mut(), env.clone(), info, mint_msg(owner)).unwrap();
+    }
+    assert_eq!(contract.num_tokens(deps.as_ref()).unwrap().count, 3);
+}
+
+#[test]
+fn unrevealed_raffles_are_called_off() {
+    use sha2::{ Digest, Sha256 };
+
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_supply: Some(3),
+        price_per_nft: Some(Coin::new(100, "usei")),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let ends_at = mock_env().block.time.plus_seconds(100);
+    let start_msg = ExecuteMsg::StartRaffle { ends_at };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), start_msg).unwrap();
+    let price = [Coin::new(100, "usei")];
+    for entrant in ["entrant0", "entrant1"] {
+        let info = mock_info(entrant, &price);
+        contract.execute(deps.as_mut(), mock_env(), info, ExecuteMsg::EnterRaffle {}).unwrap();
+    }
+    let seed = Binary::from(b"drop day".to_vec());
+    let hash = Binary::from(Sha256::digest(seed.as_slice()).to_vec());
+    let mut env = mock_env();
+    env.block.time = ends_at;
+    let commit_msg = ExecuteMsg::CommitRaffleSeed { hash: hash.clone() };
+    contract.execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), commit_msg).unwrap();
+
+    // up to the deadline the supply stays reserved and entries wait for the draw
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+    };
+    env.block.time = ends_at.plus_seconds(RAFFLE_REVEAL_PERIOD - 1);
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("venus", &price), mint_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleInProgress {});
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("entrant0", &[]), ExecuteMsg::ClaimRefund {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleNotRevealed {});
+
+    // then the seed can't be revealed anymore and every entry is refunded
+    env.block.time = ends_at.plus_seconds(RAFFLE_REVEAL_PERIOD);
+    let reveal_msg = ExecuteMsg::RevealRaffleSeed { seed };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), reveal_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleAbandoned {});
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("entrant0", &[]), ExecuteMsg::ClaimMint {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleLost {});
+    for entrant in ["entrant0", "entrant1"] {
+        let info = mock_info(entrant, &[]);
+        let res = contract.execute(deps.as_mut(), env.clone(), info, ExecuteMsg::ClaimRefund {});
+        assert_eq!(res.unwrap().messages, vec![
+            SubMsg::new(BankMsg::Send { to_address: entrant.to_string(), amount: price.to_vec() })
+        ]);
+    }
+    assert!(contract.escrowed_bids(&deps.storage, "usei").unwrap().is_zero());
+
+    // the supply is free again
+    contract.execute(deps.as_mut(), env.clone(), mock_info("venus", &price), mint_msg).unwrap();
+
+    // a raffle the owner never commits to is called off the same way
+    let ends_at = env.block.time.plus_seconds(100);
+    let start_msg = ExecuteMsg::StartRaffle { ends_at };
+    contract.execute(deps.as_mut(), env.clone(), mock_info(MINTER, &[]), start_msg).unwrap();
+    env.block.time = ends_at.plus_seconds(RAFFLE_REVEAL_PERIOD);
+    let commit_msg = ExecuteMsg::CommitRaffleSeed { hash };
+    let err = contract
+        .execute(deps.as_mut(), env, mock_info(MINTER, &[]), commit_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::RaffleAbandoned {});
+}
+
+#[test]
+fn renewing_subscriptions() {
+    const DAY: u64 = 24 * 60 * 60;
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        subscription: Some(SubscriptionConfig {
+            period: 30 * DAY,
+            renewal_price: Some(Coin::new(10, "usei")),
+            reap_after: Some(7 * DAY),
+        }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let minted_at = mock_env().block.time;
+    for _ in 0..3 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let at = |days: u64| {
+        let mut env = mock_env();
+        env.block.time = minted_at.plus_seconds(days * DAY);
+        env
+    };
+    let renew_msg = |token_id: &str| ExecuteMsg::Renew { token_id: token_id.to_string() };
+    let fee = [Coin::new(10, "usei")];
+    let active = |deps: cosmwasm_std::Deps, env, token_id: &str| {
+        contract.is_active(deps, env, token_id.to_string()).unwrap()
+    };
+
+    let pass = active(deps.as_ref(), at(29), "0");
+    assert!(pass.active);
+    assert_eq!(pass.valid_until, Some(minted_at.plus_seconds(30 * DAY)));
+    let err = contract.execute(deps.as_mut(), at(10), mock_info("venus", &[]), renew_msg("0"));
+    assert_eq!(err.unwrap_err(), ContractError::NotEnoughFunds {});
+
+    // renewing early extends from the current expiry
+    contract.execute(deps.as_mut(), at(10), mock_info("venus", &fee), renew_msg("0")).unwrap();
+    let pass = active(deps.as_ref(), at(59), "0");
+    assert!(pass.active);
+    assert_eq!(pass.valid_until, Some(minted_at.plus_seconds(60 * DAY)));
+
+    // expired passes stay with their owner and can move, but don't count for gating
+    assert!(!active(deps.as_ref(), at(30), "1").active);
+    let holder = contract.is_holder(deps.as_ref(), at(40), String::from("venus"), Some(2)).unwrap();
+    assert!(!holder.is_holder);
+    assert_eq!(holder.count, 3);
+    let holder = contract.is_holder(deps.as_ref(), at(40), String::from("venus"), None).unwrap();
+    assert!(holder.is_holder);
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mars"),
+        token_id: "1".to_string(),
+    };
+    contract.execute(deps.as_mut(), at(40), mock_info("venus", &[]), transfer_msg).unwrap();
+
+    // renewing late extends from now, the lapsed days are not paid for
+    contract.execute(deps.as_mut(), at(40), mock_info("mars", &fee), renew_msg("1")).unwrap();
+    let pass = active(deps.as_ref(), at(40), "1");
+    assert!(pass.active);
+    assert_eq!(pass.valid_until, Some(minted_at.plus_seconds(70 * DAY)));
+
+    // only passes expired for longer than `reap_after` go
+    let reap_msg = ExecuteMsg::ReapExpired { limit: None };
+    let err = contract
+        .execute(deps.as_mut(), at(40), mock_info("venus", &[]), reap_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    let res = contract
+        .execute(deps.as_mut(), at(36), mock_info(MINTER, &[]), reap_msg.clone())
+        .unwrap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("count", "0")));
+    let res = contract.execute(deps.as_mut(), at(40), mock_info(MINTER, &[]), reap_msg).unwrap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("reaped", "2")));
+    contract.nft_info(deps.as_ref(), "2".to_string()).unwrap_err();
+    assert_eq!(contract.num_tokens(deps.as_ref()).unwrap().count, 2);
+
+    let set_msg = ExecuteMsg::SetSubscription {
+        config: Some(SubscriptionConfig { period: 0, renewal_price: None, reap_after: None }),
+    };
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_msg);
+    assert_eq!(err.unwrap_err(), ContractError::ZeroSubscriptionPeriod {});
+}
+
+#[test]
+fn co_signed_minting() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        co_signing: Some(CoSignConfig {
+            co_signers: vec![Addr::unchecked("artist"), Addr::unchecked("platform")],
+            proposal_ttl: 3600,
+        }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let propose_msg = ExecuteMsg::ProposeMint {
+        owner: String::from("collector"),
+        token_uri: Some(String::from("ipfs://one-of-one")),
+        extension: None,
+    };
+    let approve_msg = |proposal_id| ExecuteMsg::ApproveMint { proposal_id };
+    let pending = |deps: cosmwasm_std::Deps, env| {
+        contract
+            .mint_proposals(deps, env, None, None)
+            .unwrap()
+            .proposals.into_iter()
+            .map(|proposal| (proposal.id, proposal.approvals))
+            .collect::<Vec<_>>()
+    };
+
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("collector"),
+        token_uri: None,
+        extension: None,
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::MintNeedsCoSigners {});
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), propose_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotCoSigner {});
+
+    // the proposer approves right away, the token waits for the other co-signer
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("artist", &[]), propose_msg.clone())
+        .unwrap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("minted", "false")));
+    assert_eq!(contract.num_tokens(deps.as_ref()).unwrap().count, 0);
+    assert_eq!(pending(deps.as_ref(), mock_env()), vec![(1, vec![String::from("artist")])]);
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("artist", &[]), approve_msg(1))
+        .unwrap_err();
+    assert_eq!(err, ContractError::AlreadyApproved {});
+
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("platform", &[]), approve_msg(1))
+        .unwrap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("minted", "true")));
+    let owner = contract.owner_of(deps.as_ref(), mock_env(), "0".to_string(), false).unwrap();
+    assert_eq!(owner.owner, "collector");
+    assert!(pending(deps.as_ref(), mock_env()).is_empty());
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("platform", &[]), approve_msg(1))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoMintProposal { id: 1 });
+
+    // proposals left alone for too long can't be approved anymore
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("artist", &[]), propose_msg.clone())
+        .unwrap();
+    let mut later = mock_env();
+    later.block.time = later.block.time.plus_seconds(3600);
+    assert!(pending(deps.as_ref(), later.clone()).is_empty());
+    let err = contract
+        .execute(deps.as_mut(), later, mock_info("platform", &[]), approve_msg(2))
+        .unwrap_err();
+    assert_eq!(err, ContractError::MintProposalExpired {});
+
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("platform", &[]), propose_msg)
+        .unwrap();
+    let cancel_msg = ExecuteMsg::CancelMint { proposal_id: 3 };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("artist", &[]), cancel_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotProposer {});
+    contract.execute(deps.as_mut(), mock_env(), mock_info("platform", &[]), cancel_msg).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("artist", &[]), approve_msg(3))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoMintProposal { id: 3 });
+    assert_eq!(contract.num_tokens(deps.as_ref()).unwrap().count, 1);
+}
+
+#[test]
+fn vesting_mint_proceeds() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let start = mock_env().block.time;
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        withdraw_address: Some(String::from("treasury")),
+        price_per_nft: Some(Coin::new(100, "usei")),
+        proceeds_vesting: Some(VestingConfig { start, duration: 0 }),
+        ..Default::default()
+    };
+    let err = contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::ZeroVestingDuration {});
+    let msg = InstantiateMsg {
+        proceeds_vesting: Some(VestingConfig { start, duration: 1000 }),
+        ..msg
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let at = |seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = start.plus_seconds(seconds);
+        env
+    };
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("buyer"),
+        token_uri: None,
+        extension: None,
+    };
+    let withdraw_msg = |amount| ExecuteMsg::WithdrawFunds { amount: Coin::new(amount, "usei") };
+    let vested = |deps: cosmwasm_std::Deps, seconds| {
+        let res: VestedProceedsResponse = from_json(
+            contract.query(deps, at(seconds), QueryMsg::VestedProceeds {}).unwrap()
+        ).unwrap();
+        res.proceeds
+    };
+
+    // paid mints credit the proceeds, including what was paid above the price
+    let info = mock_info(MINTER, &[Coin::new(100, "usei")]);
+    contract.execute(deps.as_mut(), at(0), info, mint_msg.clone()).unwrap();
+    let info = mock_info(MINTER, &[Coin::new(150, "usei")]);
+    contract.execute(deps.as_mut(), at(0), info, mint_msg).unwrap();
+    deps.querier.update_balance(mock_env().contract.address, vec![Coin::new(250, "usei")]);
+
+    // nothing is unlocked at the start
+    let err = contract
+        .execute(deps.as_mut(), at(0), mock_info("anyone", &[]), withdraw_msg(1))
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExceedsVestedAmount { available: Coin::new(0, "usei") });
+
+    // a quarter of the way in, a quarter can be withdrawn
+    let err = contract
+        .execute(deps.as_mut(), at(250), mock_info("anyone", &[]), withdraw_msg(63))
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExceedsVestedAmount { available: Coin::new(62, "usei") });
+    contract.execute(deps.as_mut(), at(250), mock_info("anyone", &[]), withdraw_msg(62)).unwrap();
+    deps.querier.update_balance(mock_env().contract.address, vec![Coin::new(188, "usei")]);
+    assert_eq!(vested(deps.as_ref(), 500), vec![VestedProceeds {
+        denom: String::from("usei"),
+        total: Uint128::new(250),
+        vested: Uint128::new(125),
+        withdrawn: Uint128::new(62),
+    }]);
+    let err = contract
+        .execute(deps.as_mut(), at(500), mock_info("anyone", &[]), withdraw_msg(64))
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExceedsVestedAmount { available: Coin::new(63, "usei") });
+
+    // everything unlocks once the duration is over
+    assert_eq!(vested(deps.as_ref(), 5000)[0].vested, Uint128::new(250));
+    let err = contract
+        .execute(deps.as_mut(), at(1000), mock_info("anyone", &[]), withdraw_msg(189))
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExceedsVestedAmount { available: Coin::new(188, "usei") });
+    contract.execute(deps.as_mut(), at(1000), mock_info("anyone", &[]), withdraw_msg(188)).unwrap();
+    assert_eq!(vested(deps.as_ref(), 1000)[0].withdrawn, Uint128::new(250));
+
+    // funds that didn't come from mints aren't withdrawable while proceeds vest
+    deps.querier.update_balance(mock_env().contract.address, vec![Coin::new(10, "uatom")]);
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            at(1000),
+            mock_info("anyone", &[]),
+            ExecuteMsg::WithdrawFunds { amount: Coin::new(10, "uatom") }
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExceedsVestedAmount { available: Coin::new(0, "uatom") });
+}
+
+#[test]
+fn open_edition_mint() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let start = mock_env().block.time;
+    let at = |seconds: u64| {
+        let mut env = mock_env();
+        env.block.time = start.plus_seconds(seconds);
+        env
+    };
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_nfts_per_wallet: Some(2),
+        max_supply: Some(10),
+        open_edition: Some((start.plus_seconds(100), start.plus_seconds(1000))),
+        ..Default::default()
+    };
+    let err = contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::OpenEditionWithMaxSupply {});
+    let err = contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), InstantiateMsg {
+            max_supply: None,
+            open_edition: Some((start.plus_seconds(100), start.plus_seconds(100))),
+            ..msg.clone()
+        })
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidOpenEdition {});
+    let msg = InstantiateMsg { max_supply: None, ..msg };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let mint = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, seconds, owner: &str| {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), at(seconds), mock_info(MINTER, &[]), mint_msg)
+    };
+    let supply = |deps: cosmwasm_std::Deps| -> SupplyResponse {
+        from_json(contract.query(deps, mock_env(), QueryMsg::Supply {}).unwrap()).unwrap()
+    };
+
+    let err = mint(&mut deps, 99, "venus").unwrap_err();
+    assert_eq!(err, ContractError::MintNotStarted {});
+
+    // no supply cap in the window, but the wallet limit still applies
+    for owner in ["venus", "venus", "mars", "jupiter", "saturn"] {
+        mint(&mut deps, 100, owner).unwrap();
+    }
+    let err = mint(&mut deps, 999, "venus").unwrap_err();
+    assert_eq!(err, ContractError::MintPerWalletOverflow {});
+    let burn_msg = ExecuteMsg::Burn { token_id: "4".to_string() };
+    contract.execute(deps.as_mut(), at(999), mock_info("saturn", &[]), burn_msg).unwrap();
+    assert_eq!(supply(deps.as_ref()).max_supply, None);
+
+    // the first call after the close fixes the cap to the tokens in circulation
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("mercury"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), at(1000), mock_info("venus", &[]), transfer_msg).unwrap();
+    let res = supply(deps.as_ref());
+    assert_eq!(res.max_supply, Some(4));
+    assert!(res.sold_out);
+    let config = contract.config(deps.as_ref()).unwrap();
+    assert_eq!(
+        config.open_edition,
+        Some((start.plus_seconds(100), start.plus_seconds(1000)))
+    );
+    let err = mint(&mut deps, 1000, "mercury").unwrap_err();
+    assert_eq!(err, ContractError::MintEnded {});
+}
+
+#[test]
+fn recording_sales() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let last_sale = |deps: cosmwasm_std::Deps, token_id: &str| -> Option<SaleRecord> {
+        let msg = QueryMsg::LastSale { token_id: token_id.to_string() };
+        from_json(contract.query(deps, mock_env(), msg).unwrap()).unwrap()
+    };
+    let sale_stats = |deps: cosmwasm_std::Deps| -> SaleStats {
+        from_json(contract.query(deps, mock_env(), QueryMsg::SaleStats {}).unwrap()).unwrap()
+    };
+    assert_eq!(last_sale(deps.as_ref(), "0"), None);
+    assert_eq!(sale_stats(deps.as_ref()), SaleStats::default());
+
+    // buying a listed token records what the buyer paid
+    let list_msg = ExecuteMsg::ListToken {
+        token_id: "0".to_string(),
+        price: Coin::new(1000, "usei"),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), list_msg).unwrap();
+    let buy_msg = ExecuteMsg::Buy { token_id: "0".to_string() };
+    let info = mock_info("mars", &[Coin::new(1000, "usei")]);
+    contract.execute(deps.as_mut(), mock_env(), info, buy_msg).unwrap();
+    assert_eq!(
+        last_sale(deps.as_ref(), "0"),
+        Some(SaleRecord {
+            price: Uint128::new(1000),
+            denom: String::from("usei"),
+            at_time: mock_env().block.time,
+            buyer: Addr::unchecked("mars"),
+        })
+    );
+
+    // only allowlisted marketplaces can report their own sales
+    let report_msg = |token_id: &str, price| ExecuteMsg::ReportSale {
+        token_id: token_id.to_string(),
+        price,
+    };
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("market", &[]),
+            report_msg("1", Coin::new(50, "uatom"))
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::OperatorNotAllowed { operator: String::from("market") });
+    let add_msg = ExecuteMsg::AddAllowedOperator { operator: String::from("market") };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), add_msg).unwrap();
+    let mut later = mock_env();
+    later.block.time = later.block.time.plus_seconds(60);
+    for price in [Coin::new(50, "uatom"), Coin::new(500, "usei")] {
+        let info = mock_info("market", &[]);
+        contract.execute(deps.as_mut(), later.clone(), info, report_msg("1", price)).unwrap();
+    }
+    assert_eq!(
+        last_sale(deps.as_ref(), "1"),
+        Some(SaleRecord {
+            price: Uint128::new(500),
+            denom: String::from("usei"),
+            at_time: later.block.time,
+            buyer: Addr::unchecked("venus"),
+        })
+    );
+    assert_eq!(sale_stats(deps.as_ref()), SaleStats {
+        sale_count: 3,
+        volume: vec![Coin::new(1500, "usei"), Coin::new(50, "uatom")],
+    });
+
+    // burning forgets the last sale, not the stats
+    let burn_msg = ExecuteMsg::Burn { token_id: "1".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), burn_msg).unwrap();
+    assert_eq!(last_sale(deps.as_ref(), "1"), None);
+    assert_eq!(sale_stats(deps.as_ref()).sale_count, 3);
+}
+
+#[test]
+fn paging_the_mint_ledger() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    // a dozen wallets minted in reverse order, the first one of them twice
+    let wallets = (0..12).map(|i| format!("wallet{i:02}")).collect::<Vec<_>>();
+    for wallet in wallets.iter().rev().chain([&wallets[11]]) {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: wallet.clone(),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    let ledger = |deps: cosmwasm_std::Deps, start_after: Option<&str>| {
+        let msg = QueryMsg::MintLedger {
+            start_after: start_after.map(String::from),
+            limit: Some(5),
+        };
+        contract.query(deps, mock_env(), msg).and_then(from_json::<MintLedgerResponse>)
+    };
+
+    // pages follow address order and never repeat the cursor
+    let mut entries = vec![];
+    let mut start_after = None;
+    loop {
+        let page = ledger(deps.as_ref(), start_after.as_deref()).unwrap();
+        assert_eq!(page.total_entries, 12);
+        let Some(last) = page.entries.last() else {
+            break;
+        };
+        start_after = Some(last.wallet.clone());
+        entries.extend(page.entries);
+    }
+    let expected = wallets
+        .iter()
+        .map(|wallet| WalletMints {
+            wallet: wallet.clone(),
+            minted: if wallet == "wallet11" { 2 } else { 1 },
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(entries, expected);
+
+    let page = ledger(deps.as_ref(), Some("wallet04")).unwrap();
+    assert_eq!(page.entries[0].wallet, "wallet05");
+    // a cursor that isn't a canonical address is rejected
+    ledger(deps.as_ref(), Some("WALLET04")).unwrap_err();
+}
+
+#[test]
+fn holder_count_follows_random_ownership_changes() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let wallets = ["venus", "mars", "jupiter", "saturn"];
+    for wallet in wallets {
+        let approve_msg = ExecuteMsg::ApproveAll {
+            operator: String::from("operator"),
+            expires: None,
+            max_uses: None,
+            permissions: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(wallet, &[]), approve_msg).unwrap();
+    }
+
+    // a fixed linear congruential sequence keeps the run reproducible
+    let mut seed = 0x2545_f491_u64;
+    let mut next = |bound: usize| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((seed >> 33) as usize) % bound
+    };
+    let mut live: Vec<String> = vec![];
+    for _ in 0..300 {
+        let action = if live.is_empty() { 0 } else { next(5) };
+        let wallet = wallets[next(wallets.len())];
+        match action {
+            0 => {
+                let mint_msg = ExecuteMsg::Mint {
+                    owner: String::from(wallet),
+                    token_uri: None,
+                    extension: None,
+                };
+                let info = mock_info(MINTER, &[]);
+                let res = contract.execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+                let token_id = res.attributes
+                    .iter()
+                    .find(|attr| attr.key == "token_id")
+                    .unwrap()
+                    .value.clone();
+                live.push(token_id);
+            }
+            // transfers by the owner or the operator, to themselves included
+            1 | 2 => {
+                let token_id = live[next(live.len())].clone();
+                let owner = contract.load_token(&deps.storage, &token_id).unwrap().owner;
+                let sender = if action == 1 { owner.as_str() } else { "operator" };
+                let transfer_msg = ExecuteMsg::TransferNft {
+                    recipient: String::from(wallet),
+                    token_id,
+                };
+                let info = mock_info(sender, &[]);
+                contract.execute(deps.as_mut(), mock_env(), info, transfer_msg).unwrap();
+            }
+            // burns by the owner or the operator
+            _ => {
+                let token_id = live.swap_remove(next(live.len()));
+                let owner = contract.load_token(&deps.storage, &token_id).unwrap().owner;
+                let sender = if action == 3 { owner.as_str() } else { "operator" };
+                let burn_msg = ExecuteMsg::Burn { token_id };
+                let info = mock_info(sender, &[]);
+                contract.execute(deps.as_mut(), mock_env(), info, burn_msg).unwrap();
+            }
+        }
+
+        let holders = wallets
+            .iter()
+            .filter(|wallet| {
+                contract.tokens.idx.owner
+                    .prefix(Addr::unchecked(**wallet))
+                    .keys(&deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                    .next()
+                    .is_some()
+            })
+            .count();
+        assert_eq!(contract.holder_count(&deps.storage).unwrap(), holders as u64);
+    }
+
+    // the stored balances agree with the owner index after the whole run
+    let verify = |deps: cosmwasm_std::Deps, wallet: &str| -> VerifyHoldingsResponse {
+        let msg = QueryMsg::VerifyHoldings { address: String::from(wallet) };
+        from_json(contract.query(deps, mock_env(), msg).unwrap()).unwrap()
+    };
+    for wallet in wallets {
+        let res = verify(deps.as_ref(), wallet);
+        assert!(res.consistent, "{wallet}: {res:?}");
+    }
+    let indexed = verify(deps.as_ref(), "venus").indexed;
+    contract.holdings.save(&mut deps.storage, &Addr::unchecked("venus"), &(indexed + 1)).unwrap();
+    assert_eq!(verify(deps.as_ref(), "venus"), VerifyHoldingsResponse {
+        recorded: indexed + 1,
+        indexed,
+        consistent: false,
+    });
+}
+
+#[test]
+fn querying_num_tokens_at_height() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        balance_snapshots: Some(SnapshotStrategy::EveryBlock),
+        ..Default::default()
+    };
+    let creator = mock_info("creator", &[]);
+    contract.instantiate(deps.as_mut(), mock_env(), creator.clone(), msg.clone()).unwrap();
+    let start = mock_env().block.height;
+    let at = |offset: u64| {
+        let mut env = mock_env();
+        env.block.height = start + offset;
+        env
+    };
+    let mint_msg = ExecuteMsg::Mint {
+        owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+    };
+    let burn_msg = |token_id: &str| ExecuteMsg::Burn { token_id: token_id.to_string() };
+    let num_tokens_at = |deps: cosmwasm_std::Deps, height: u64| {
+        let res = contract.query(deps, mock_env(), QueryMsg::NumTokensAt { height })?;
+        from_json::<NumTokensResponse>(&res).map(|res| res.count)
+    };
+
+    let minter = mock_info(MINTER, &[]);
+    let demeter = mock_info("demeter", &[]);
+    for _ in 0..2 {
+        contract.execute(deps.as_mut(), at(1), minter.clone(), mint_msg.clone()).unwrap();
+    }
+    contract.execute(deps.as_mut(), at(2), demeter.clone(), burn_msg("0")).unwrap();
+    contract.execute(deps.as_mut(), at(2), minter.clone(), mint_msg.clone()).unwrap();
+    contract.execute(deps.as_mut(), at(4), minter.clone(), mint_msg.clone()).unwrap();
+    contract.execute(deps.as_mut(), at(5), demeter.clone(), burn_msg("1")).unwrap();
+    contract.execute(deps.as_mut(), at(5), demeter.clone(), burn_msg("2")).unwrap();
+
+    // as of the start of each block
+    for (offset, count) in [(0, 0), (1, 0), (2, 2), (3, 2), (4, 2), (5, 3), (6, 1), (50, 1)] {
+        assert_eq!(num_tokens_at(deps.as_ref(), start + offset).unwrap(), count);
+    }
+
+    // a deployment upgraded with tokens but no supply history reports its supply until the
+    // first change, once the migration seeded it
+    let mut deps = mock_dependencies();
+    contract.instantiate(deps.as_mut(), mock_env(), creator, msg).unwrap();
+    contract.token_count.save(&mut deps.storage, &5).unwrap();
+    let seeded = crate::upgrades::v0_19::seed_token_count_snapshot(&mut deps.storage).unwrap();
+    assert_eq!(seeded, 5);
+    contract.execute(deps.as_mut(), at(3), minter, mint_msg).unwrap();
+    assert_eq!(num_tokens_at(deps.as_ref(), start + 1).unwrap(), 5);
+    assert_eq!(num_tokens_at(deps.as_ref(), start + 3).unwrap(), 5);
+    assert_eq!(num_tokens_at(deps.as_ref(), start + 4).unwrap(), 6);
+}
+
+#[test]
+fn transferring_clears_many_spender_approvals() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        max_approvals_per_token: Some(200),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("demeter"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    // both tokens approved to every spender, in batches
+    let spenders = (0..200).map(|i| format!("spender{i:03}")).collect::<Vec<_>>();
+    let demeter = mock_info("demeter", &[]);
+    for token_id in ["0", "1"] {
+        for batch in spenders.chunks(50) {
+            let approve_msg = ExecuteMsg::ApproveMultiple {
+                spenders: batch
+                    .iter()
+                    .map(|spender| (spender.clone(), None))
+                    .collect(),
+                token_id: token_id.to_string(),
+            };
+            contract.execute(deps.as_mut(), mock_env(), demeter.clone(), approve_msg).unwrap();
+        }
+    }
+    let approved_tokens = |deps: cosmwasm_std::Deps, spender: &str| {
+        contract
+            .approvals_by_spender(deps, mock_env(), String::from(spender), true, None, None)
+            .unwrap()
+            .approvals.into_iter()
+            .map(|approval| approval.token_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(approved_tokens(deps.as_ref(), "spender123"), vec!["0", "1"]);
+
+    // the transfer drops every approval of the token from the spender index at once
+    let transfer_msg = ExecuteMsg::TransferNft {
+        recipient: String::from("ceres"),
+        token_id: "0".to_string(),
+    };
+    contract.execute(deps.as_mut(), mock_env(), demeter, transfer_msg).unwrap();
+    for spender in &spenders {
+        assert_eq!(approved_tokens(deps.as_ref(), spender), vec!["1"]);
+    }
+    let indexed = contract.spender_approvals
+        .range(&deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<cosmwasm_std::StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(indexed.len(), spenders.len());
+    assert!(indexed.iter().all(|((_, token_id), _)| token_id == "1"));
+
+    // the new owner starts over
+    let approve_msg = ExecuteMsg::Approve {
+        spender: String::from("spender000"),
+        token_id: "0".to_string(),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("ceres", &[]), approve_msg).unwrap();
+    assert_eq!(approved_tokens(deps.as_ref(), "spender000"), vec!["0", "1"]);
+}
+
+#[test]
+fn wrapper_ids_round_trip() {
+    use crate::state::{ wrapped_original_id, wrapper_token_id };
+
+    for (original, wrapper) in [
+        ("0", "wrapped-0"),
+        ("42", "wrapped-42"),
+        ("042", "wrapped-042"),
+        ("sword", "wrapped-sword"),
+        ("wrapped-sword", "wrapped-wrapped-sword"),
+    ] {
+        assert_eq!(wrapper_token_id(original), wrapper);
+        assert_eq!(wrapped_original_id(wrapper), original);
+    }
+}
+
+#[test]
+fn minting_and_sending_from_a_partner() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        price_per_nft: Some(Coin::new(400, "usei")),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let set_partner = ExecuteMsg::SetPartnerMinter {
+        address: String::from("launchpad"),
+        config: Some(PartnerConfig { fee_bps: 500, counts_against_recipient: true }),
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), set_partner).unwrap();
+
+    let mint_and_send = ExecuteMsg::MintAndSend {
+        contract: String::from("vault"),
+        msg: Binary::default(),
+        token_uri: None,
+        extension: None,
+    };
+    let info = mock_info("launchpad", &[Coin::new(400, "usei")]);
+    let res = contract.execute(deps.as_mut(), mock_env(), info, mint_and_send).unwrap();
+
+    // the partner is paid before the token goes out
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(BankMsg::Send {
+            to_address: String::from("launchpad"),
+            amount: vec![Coin::new(20, "usei")],
+        })
+    );
+    assert!(matches!(res.messages[1].msg, CosmosMsg::Wasm(WasmMsg::Execute { .. })));
+    let attribute = |key: &str| {
+        res.attributes
+            .iter()
+            .filter(|attr| attr.key == key)
+            .map(|attr| attr.value.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(attribute("action"), vec!["mint", "send_nft"]);
+    assert_eq!(attribute("owner"), vec!["launchpad"]);
+    assert_eq!(attribute("partner_fee"), vec!["20usei"]);
+    assert_eq!(attribute("token_id"), vec!["0", "0"]);
+    assert_eq!(attribute("minted"), vec!["true"]);
+}
+
+#[test]
+fn reaping_skips_blocked_passes() {
+    const DAY: u64 = 24 * 60 * 60;
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        minter: Some(String::from(MINTER)),
+        subscription: Some(SubscriptionConfig {
+            period: 30 * DAY,
+            renewal_price: None,
+            reap_after: Some(DAY),
+        }),
+        ..Default::default()
+    };
+    contract.instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    for _ in 0..4 {
+        let mint_msg = ExecuteMsg::Mint {
+            owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+        };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), mint_msg).unwrap();
+    }
+    // the oldest passes are staked and listed
+    let venus = mock_info("venus", &[]);
+    let stake_msg = ExecuteMsg::Stake { token_id: "0".to_string() };
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), stake_msg).unwrap();
+    let list_msg = ExecuteMsg::ListToken {
+        token_id: "1".to_string(),
+        price: Coin::new(100, "usei"),
+        expires: None,
+    };
+    contract.execute(deps.as_mut(), mock_env(), venus, list_msg).unwrap();
+
+    let mut later = mock_env();
+    later.block.time = later.block.time.plus_seconds(40 * DAY);
+    let reap_msg = ExecuteMsg::ReapExpired { limit: Some(2) };
+    let minter = mock_info(MINTER, &[]);
+    let mut reap = || {
+        contract.execute(deps.as_mut(), later.clone(), minter.clone(), reap_msg.clone()).unwrap()
+    };
+    // the blocked passes fill the first batch, the cursor moves past them
+    let res = reap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("count", "0")));
+    assert!(res.attributes.contains(&cosmwasm_std::attr("done", "false")));
+    let res = reap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("reaped", "2,3")));
+    let res = reap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("count", "0")));
+    assert!(res.attributes.contains(&cosmwasm_std::attr("done", "true")));
+    // and the next sweep starts over
+    let res = reap();
+    assert!(res.attributes.contains(&cosmwasm_std::attr("done", "false")));
+    assert_eq!(contract.num_tokens(deps.as_ref()).unwrap().count, 2);
+}
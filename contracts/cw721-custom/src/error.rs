@@ -1,5 +1,6 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{ Coin, StdError, Timestamp };
 use cw_ownable::OwnershipError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -14,13 +15,216 @@ pub enum ContractError {
 
     #[error("Cannot set approval that is already expired")] Expired {},
 
+    #[error("Approvals must have an expiration")] NeverExpirationNotAllowed {},
+
+    #[error("Operator has no uses left")] OperatorBudgetExhausted {},
+    #[error("Operators with a use budget can't approve spenders")] BudgetedOperatorApproval {},
+    #[error("Operators can't approve themselves")] OperatorSelfApproval {},
+    #[error("Operator is not allowed to {action}")] OperatorNotPermitted {
+        action: String,
+    },
+    #[error("Operator not found: {operator}")] OperatorNotFound {
+        operator: String,
+    },
+    #[error("New expiration must be later than the current one")] ExpirationNotLater {},
+
+    #[error("Permit has expired")] PermitExpired {},
+    #[error("Permit nonce {nonce} was already used")] PermitReplayed {
+        nonce: u64,
+    },
+    #[error("Permit signature is invalid")] InvalidPermitSignature {},
+
+    #[error("Voucher has expired")] VoucherExpired {},
+    #[error("Voucher nonce {nonce} was already redeemed")] VoucherReplayed {
+        nonce: u64,
+    },
+    #[error("Voucher signature is invalid")] InvalidVoucherSignature {},
+    #[error("Voucher was signed for another collection")] VoucherForOtherCollection {},
+    #[error("Voucher must be paid with exactly {price}")] WrongVoucherPayment {
+        price: Coin,
+    },
+    #[error("Vouchers are not enabled")] NoVoucherPubkey {},
+    #[error("Partner fee must not exceed 10000 basis points")] InvalidPartnerFee {},
+
     #[error("Approval not found for: {spender}")] ApprovalNotFound {
         spender: String,
     },
 
+    #[error("A token cannot have more than {max} approvals")] TooManyApprovals {
+        max: u64,
+    },
+
+    #[error("Spender {spender} is listed more than once")] DuplicateSpender {
+        spender: String,
+    },
+
     #[error("No withdraw address set")] NoWithdrawAddress {},
 
     #[error("Supply overflow")] SupplyOverflow {},
     #[error("User already minted max amount")] MintPerWalletOverflow {},
     #[error("Not enough funds")] NotEnoughFunds {},
+    #[error("Supply is reserved for a raffle")] RaffleInProgress {},
+    #[error("Minting has not started yet")] MintNotStarted {},
+    #[error("Minting has ended")] MintEnded {},
+    #[error("Open edition must end after it starts")] InvalidOpenEdition {},
+    #[error("Open edition supply is set when it closes")] OpenEditionWithMaxSupply {},
+    #[error("Only {available} of the proceeds is unlocked")] ExceedsVestedAmount {
+        available: Coin,
+    },
+    #[error("Vesting duration must not be zero")] ZeroVestingDuration {},
+    #[error("Mints must be proposed to the co-signers")] MintNeedsCoSigners {},
+    #[error("Co-signing needs at least one co-signer")] NoCoSigners {},
+    #[error("Mint proposals need a lifetime")] ZeroProposalTtl {},
+    #[error("Only co-signers can call this")] NotCoSigner {},
+    #[error("Only the proposer can call this")] NotProposer {},
+    #[error("Mint proposal {id} not found")] NoMintProposal {
+        id: u64,
+    },
+    #[error("Mint proposal has expired")] MintProposalExpired {},
+    #[error("Mint proposal is already approved by the sender")] AlreadyApproved {},
+    #[error("Tokens are not subscriptions")] NoSubscription {},
+    #[error("Subscription period must not be zero")] ZeroSubscriptionPeriod {},
+    #[error("A raffle needs a max supply")] RaffleNeedsMaxSupply {},
+    #[error("Raffle is not taking entries")] RaffleNotOpen {},
+    #[error("Raffle is still taking entries")] RaffleStillOpen {},
+    #[error("No raffle seed was committed")] RaffleNotCommitted {},
+    #[error("Raffle winners are not drawn yet")] RaffleNotRevealed {},
+    #[error("Seed doesn't match the committed hash")] InvalidRaffleSeed {},
+    #[error("Raffle seed wasn't revealed in time")] RaffleAbandoned {},
+    #[error("Already entered the raffle")] AlreadyEntered {},
+    #[error("No unclaimed raffle entry")] NoRaffleEntry {},
+    #[error("Entry didn't win the raffle")] RaffleLost {},
+    #[error("Winning entries get a token instead of a refund")] RaffleWon {},
+
+    #[error("Only the token owner can burn")] OnlyOwnerCanBurn {},
+
+    #[error("Token is owned by {actual}")] UnexpectedOwner {
+        actual: String,
+    },
+
+    #[error("Wrong address prefix, expected {expected} but got {got}")] WrongAddressPrefix {
+        expected: String,
+        got: String,
+    },
+
+    #[error("Token has been redeemed")] TokenRedeemed {},
+    #[error("Redemption has already been fulfilled")] RedemptionFulfilled {},
+
+    #[error("Token is nested into another token")] TokenNested {},
+    #[error("Token is not nested")] NotNested {},
+    #[error("Token has nested children")] TokenHasChildren {},
+    #[error("Nesting would create a cycle")] NestingCycle {},
+    #[error("Accessory is not attached to this token")] NotAttached {},
+    #[error("Operator {operator} is not on the allowlist")] OperatorNotAllowed {
+        operator: String,
+    },
+    #[error("Operator is not royalty compliant")] OperatorNotCompliant {},
+
+    #[error("Nesting deeper than {max} levels is not allowed")] MaxNestingDepth {
+        max: u32,
+    },
+
+    #[error("Token {token_id} is already revealed")] AlreadyRevealed {
+        token_id: String,
+    },
+
+    #[error("Pagination needs a default limit between 1 and the max limit")]
+    InvalidPagination {},
+
+    #[error("Balances can only be checkpointed with the selected snapshot strategy")]
+    CheckpointsNotSelected {},
+
+    #[error("Semver parsing error: {0}")] SemVer(String),
+
+    #[error("Stored version {from} is not older than the code version {to}")]
+    MigrationNotNewer {
+        from: String,
+        to: String,
+    },
+
+    #[error("Max supply {max_supply} is below the {token_count} tokens in circulation")]
+    MaxSupplyBelowTokenCount {
+        max_supply: u64,
+        token_count: u64,
+    },
+
+    #[error("State import is closed")] ImportClosed {},
+    #[error("Export version {version} is not supported")] UnsupportedExportVersion {
+        version: u32,
+    },
+    #[error("Exported data doesn't belong to the given section")] ImportSectionMismatch {},
+
+    #[error("No token migration is in progress")] NoMigrationInProgress {},
+
+    #[error("Only the bridge can call this")] NotBridge {},
+    #[error("Only the bridge operator can call this")] NotBridgeOperator {},
+    #[error("Lock/release bridging is not enabled")] NoBridgeOperator {},
+    #[error("Token is not locked for the bridge")] NotBridgeLocked {},
+
+    #[error("Token id {token_id} is reserved for minting")] TokenIdReserved {
+        token_id: String,
+    },
+
+    #[error("Token is listed for sale")] TokenListed {},
+    #[error("Token is not listed for sale")] NotListed {},
+    #[error("Listing has expired")] ListingExpired {},
+    #[error("Listing price must not be zero")] ZeroListingPrice {},
+    #[error("Royalty share must not exceed 1")] InvalidRoyaltyShare {},
+    #[error("Token is part of a bundle for sale")] TokenBundled {},
+    #[error("Token is staked")] TokenStaked {},
+    #[error("Tokens of this collection can't be sent here")] UnknownCollection {},
+    #[error("Token {token_id} was already used to mint")] TokenConsumed {
+        token_id: String,
+    },
+    #[error("Burn-to-mint ratio must be at least 1")] ZeroBurnToMintRatio {},
+    #[error("Token is not a wrapper")] NotWrapped {},
+    #[error("Wrappers can only be burned by unwrapping them")] TokenWrapped {},
+    #[error("Token is not staked")] NotStaked {},
+    #[error("Token is not delegated")] NotDelegated {},
+    #[error("Token can't be unstaked before {unlocks_at}")] StakeLocked {
+        unlocks_at: Timestamp,
+    },
+    #[error("Bundle not found")] NoBundle {},
+    #[error("Bundle has expired")] BundleExpired {},
+    #[error("A bundle needs between 1 and {max} tokens")] InvalidBundleSize {
+        max: u32,
+    },
+    #[error("Token {token_id} appears twice in the bundle")] DuplicateBundleToken {
+        token_id: String,
+    },
+    #[error("Token {token_id} is no longer held for the bundle")] BundleTokenUnavailable {
+        token_id: String,
+    },
+
+    #[error(transparent)] Payment(#[from] PaymentError),
+
+    #[error("Token is being auctioned")] TokenInAuction {},
+    #[error("Token is not being auctioned")] NoAuction {},
+    #[error("Auction has ended")] AuctionEnded {},
+    #[error("Auction has not ended yet")] AuctionNotEnded {},
+    #[error("Auction must last at least a second")] ZeroAuctionDuration {},
+    #[error("Bid must be at least {min_bid}")] BidTooLow {
+        min_bid: Coin,
+    },
+    #[error("Seller cannot bid on their own auction")] SellerCannotBid {},
+    #[error("Owner cannot make an offer on their own token")] OwnerCannotOffer {},
+    #[error("No offer from {bidder}")] NoOffer {
+        bidder: String,
+    },
+    #[error("Offer has expired")] OfferExpired {},
+    #[error("Only the bidder can withdraw an offer before it expires")] OfferNotExpired {},
+    #[error("Only {available} is withdrawable, the rest is held in escrow")] FundsInEscrow {
+        available: Coin,
+    },
+    #[error("Rewards are not enabled")] NoRewardAsset {},
+    #[error("Rewards are paid in a different asset")] WrongRewardAsset {},
+    #[error("The reward asset can't change once rewards were funded")] RewardsFunded {},
+    #[error("There are no tokens to reward")] NoTokensToReward {},
+    #[error("No rewards to claim")] NoRewards {},
+}
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
 }
@@ -7,21 +7,112 @@ use cosmwasm_std::{
     Api,
     BankMsg,
     Binary,
+    BlockInfo,
     Coin,
+    CosmosMsg,
     CustomMsg,
+    Decimal,
     Deps,
     DepsMut,
+    Empty,
     Env,
+    Event,
     MessageInfo,
+    Order,
+    QuerierWrapper,
+    Reply,
     Response,
+    StdResult,
     Storage,
+    SubMsg,
+    SubMsgResult,
+    Timestamp,
+    Uint128,
+    WasmMsg,
+    from_json,
+    to_json_binary,
+    to_json_vec,
 };
 
-use cw721::{ ContractInfoResponse, Cw721Execute, Cw721ReceiveMsg, Expiration };
+use bech32::{ ToBase32, Variant };
+use cw721::{
+    ContractInfoResponse,
+    Cw721Execute,
+    Cw721ExecuteMsg,
+    Cw721QueryMsg,
+    Cw721ReceiveMsg,
+    Expiration,
+    NftInfoResponse,
+    TokensResponse,
+};
+use cw20::{ Cw20ExecuteMsg, Cw20ReceiveMsg };
+use cw_storage_plus::Bound;
+use ripemd::Ripemd160;
+use sha2::{ Digest, Sha256 };
 
 use crate::error::ContractError;
-use crate::msg::{ ExecuteMsg, InstantiateMsg };
-use crate::state::{ Approval, Cw721Contract, TokenInfo };
+use crate::msg::{
+    ApprovalPermit,
+    Cw20HookMsg,
+    Cw721BurnReceiveMsg,
+    ExecuteMsg,
+    ExportData,
+    ExportSection,
+    ExportedConfig,
+    ExportedToken,
+    InstantiateMsg,
+    MintVoucher,
+    RoyaltyRegistryQueryMsg,
+    StateExport,
+};
+use crate::state::{
+    ActivityAction,
+    Approval,
+    Auction,
+    Bid,
+    Bundle,
+    BurnRecord,
+    BurnToMintConfig,
+    CoSignConfig,
+    Cw721Contract,
+    HistoryAction,
+    Listing,
+    MintProposal,
+    Offer,
+    OperatorGrant,
+    OperatorPermissions,
+    PartnerConfig,
+    PendingSend,
+    RaffleEntry,
+    RafflePhase,
+    SubscriptionConfig,
+    Redemption,
+    RedemptionStatus,
+    RewardAsset,
+    Royalty,
+    SnapshotStrategy,
+    SupplyCapBasis,
+    TokenInfo,
+    WithdrawRecord,
+    MAX_NEST_DEPTH,
+    RAFFLE_REVEAL_PERIOD,
+    STATE_EXPORT_VERSION,
+    TOKEN_STORAGE_VERSION,
+    token_id_from_key,
+    token_key,
+    wrapped_original_id,
+    wrapper_token_id,
+};
+
+const MAX_BURN_ALL_LIMIT: u32 = 50;
+const DEFAULT_SWEEP_LIMIT: u32 = 30;
+const MAX_SWEEP_LIMIT: u32 = 100;
+const DEFAULT_MIGRATE_BATCH_LIMIT: u32 = 50;
+const MAX_MIGRATE_BATCH_LIMIT: u32 = 200;
+const MAX_BUNDLE_SIZE: u32 = 30;
+/// Reply id for burn notifications that must not roll back the burn,
+/// generated reply ids start at 1 so this never collides
+const BURN_NOTIFY_REPLY_ID: u64 = 0;
 
 impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
     where T: Serialize + DeserializeOwned + Clone, C: CustomMsg, E: CustomMsg, Q: CustomMsg
@@ -50,11 +141,34 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
         }
 
         let mut resp: Response<C> = Response::new();
+        if let Some((start, end)) = msg.open_edition {
+            // the supply of an open edition is whatever was minted by the end
+            if msg.max_supply.is_some() {
+                return Err(ContractError::OpenEditionWithMaxSupply {});
+            }
+            if end <= start {
+                return Err(ContractError::InvalidOpenEdition {});
+            }
+            resp = resp
+                .add_attribute("open_edition_start", start.seconds().to_string())
+                .add_attribute("open_edition_end", end.seconds().to_string());
+            self.open_edition.save(deps.storage, &(start, end))?;
+        }
+
         if let Some(max_supply) = msg.max_supply {
             self.max_supply.save(deps.storage, &max_supply)?;
             resp = resp.add_attribute("max_supply", max_supply.to_string());
         }
 
+        if let Some(supply_cap_basis) = msg.supply_cap_basis {
+            self.supply_cap_basis.save(deps.storage, &supply_cap_basis)?;
+            let basis = match supply_cap_basis {
+                SupplyCapBasis::Minted => "minted",
+                SupplyCapBasis::Circulating => "circulating",
+            };
+            resp = resp.add_attribute("supply_cap_basis", basis);
+        }
+
         if let Some(max_nfts_per_wallet) = msg.max_nfts_per_wallet {
             self.max_nfts_per_wallet.save(deps.storage, &max_nfts_per_wallet)?;
             resp = resp.add_attribute("max_nfts_per_wallet", max_nfts_per_wallet.to_string());
@@ -66,6 +180,180 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
             resp = resp.add_attribute("price_per_nft", str);
         }
 
+        if let Some(operators_can_burn) = msg.operators_can_burn {
+            self.operators_can_burn.save(deps.storage, &operators_can_burn)?;
+            resp = resp.add_attribute("operators_can_burn", operators_can_burn.to_string());
+        }
+
+        if let Some(redemption_payout) = msg.redemption_payout {
+            self.redemption_payout.save(deps.storage, &redemption_payout)?;
+            resp = resp.add_attribute("redemption_payout", redemption_payout.to_string());
+        }
+
+        if let Some(max_approvals_per_token) = msg.max_approvals_per_token {
+            self.max_approvals_per_token.save(deps.storage, &max_approvals_per_token)?;
+            resp = resp.add_attribute(
+                "max_approvals_per_token",
+                max_approvals_per_token.to_string()
+            );
+        }
+
+        if let Some(default_approval_ttl) = msg.default_approval_ttl {
+            self.default_approval_ttl.save(deps.storage, &default_approval_ttl)?;
+            resp = resp.add_attribute("default_approval_ttl", default_approval_ttl.to_string());
+        }
+
+        if let Some(allow_never_expiration) = msg.allow_never_expiration {
+            self.allow_never_expiration.save(deps.storage, &allow_never_expiration)?;
+            resp = resp.add_attribute("allow_never_expiration", allow_never_expiration.to_string());
+        }
+
+        if let Some(enforce_operator_allowlist) = msg.enforce_operator_allowlist {
+            self.enforce_operator_allowlist.save(deps.storage, &enforce_operator_allowlist)?;
+            resp = resp.add_attribute(
+                "enforce_operator_allowlist",
+                enforce_operator_allowlist.to_string()
+            );
+        }
+
+        if let Some(record_token_history) = msg.record_token_history {
+            self.record_token_history.save(deps.storage, &record_token_history)?;
+            resp = resp.add_attribute("record_token_history", record_token_history.to_string());
+        }
+
+        if let Some(max_token_history) = msg.max_token_history {
+            self.max_token_history.save(deps.storage, &max_token_history)?;
+            resp = resp.add_attribute("max_token_history", max_token_history.to_string());
+        }
+
+        if let Some(activity_buffer_size) = msg.activity_buffer_size {
+            self.activity_buffer_size.save(deps.storage, &activity_buffer_size)?;
+            resp = resp.add_attribute("activity_buffer_size", activity_buffer_size.to_string());
+        }
+
+        if let Some(delayed_reveal) = msg.delayed_reveal {
+            self.delayed_reveal.save(deps.storage, &delayed_reveal)?;
+            resp = resp.add_attribute("delayed_reveal", delayed_reveal.to_string());
+        }
+
+        if let Some(pagination) = msg.pagination {
+            if pagination.default_limit == 0 || pagination.default_limit > pagination.max_limit {
+                return Err(ContractError::InvalidPagination {});
+            }
+            self.pagination.save(deps.storage, &pagination)?;
+            resp = resp
+                .add_attribute("default_limit", pagination.default_limit.to_string())
+                .add_attribute("max_limit", pagination.max_limit.to_string());
+        }
+
+        if let Some(index_token_uris) = msg.index_token_uris {
+            self.index_token_uris.save(deps.storage, &index_token_uris)?;
+            resp = resp.add_attribute("index_token_uris", index_token_uris.to_string());
+        }
+
+        if let Some(retain_burned_tokens) = msg.retain_burned_tokens {
+            self.retain_burned_tokens.save(deps.storage, &retain_burned_tokens)?;
+            resp = resp.add_attribute("retain_burned_tokens", retain_burned_tokens.to_string());
+        }
+
+        if let Some(balance_snapshots) = msg.balance_snapshots {
+            self.balance_snapshots.save(deps.storage, &balance_snapshots)?;
+            let strategy = match balance_snapshots {
+                SnapshotStrategy::Never => "never",
+                SnapshotStrategy::EveryBlock => "every_block",
+                SnapshotStrategy::Selected => "selected",
+            };
+            resp = resp.add_attribute("balance_snapshots", strategy);
+        }
+
+        if let Some(import_open) = msg.import_open {
+            self.import_open.save(deps.storage, &import_open)?;
+            resp = resp.add_attribute("import_open", import_open.to_string());
+        }
+
+        if let Some(bech32_prefix) = msg.bech32_prefix {
+            self.bech32_prefix.save(deps.storage, &bech32_prefix)?;
+            resp = resp.add_attribute("bech32_prefix", bech32_prefix);
+        }
+
+        if let Some(auction_extension) = msg.auction_extension {
+            self.auction_extension.save(deps.storage, &auction_extension)?;
+            resp = resp.add_attribute("auction_extension", auction_extension.to_string());
+        }
+
+        if let Some(keep_user_on_transfer) = msg.keep_user_on_transfer {
+            self.keep_user_on_transfer.save(deps.storage, &keep_user_on_transfer)?;
+            resp = resp.add_attribute("keep_user_on_transfer", keep_user_on_transfer.to_string());
+        }
+
+        if let Some(min_stake_period) = msg.min_stake_period {
+            self.min_stake_period.save(deps.storage, &min_stake_period)?;
+            resp = resp.add_attribute("min_stake_period", min_stake_period.to_string());
+        }
+
+        if let Some(wrapped_collection) = msg.wrapped_collection {
+            let wrapped_collection = deps.api.addr_validate(&wrapped_collection)?;
+            self.wrapped_collection.save(deps.storage, &wrapped_collection)?;
+            resp = resp.add_attribute("wrapped_collection", wrapped_collection);
+        }
+
+        if let Some(burn_to_mint) = msg.burn_to_mint {
+            self.validate_burn_to_mint(deps.api, &burn_to_mint)?;
+            resp = resp
+                .add_attribute("burn_to_mint_source", &burn_to_mint.source_collection)
+                .add_attribute("burn_to_mint_ratio", burn_to_mint.ratio.to_string());
+            self.burn_to_mint.save(deps.storage, &burn_to_mint)?;
+        }
+
+        if let Some(vesting) = msg.proceeds_vesting {
+            if vesting.duration == 0 {
+                return Err(ContractError::ZeroVestingDuration {});
+            }
+            resp = resp.add_attribute("proceeds_vesting_end", vesting.end().seconds().to_string());
+            self.proceeds_vesting.save(deps.storage, &vesting)?;
+        }
+
+        if let Some(co_signing) = msg.co_signing {
+            self.validate_co_signing(deps.api, &co_signing)?;
+            resp = resp.add_attribute("co_signers", co_signing.co_signers.len().to_string());
+            self.co_signing.save(deps.storage, &co_signing)?;
+        }
+
+        if let Some(subscription) = msg.subscription {
+            if subscription.period == 0 {
+                return Err(ContractError::ZeroSubscriptionPeriod {});
+            }
+            resp = resp.add_attribute("subscription_period", subscription.period.to_string());
+            self.subscription.save(deps.storage, &subscription)?;
+        }
+
+        if let Some(voucher_pubkey) = msg.voucher_pubkey {
+            self.voucher_pubkey.save(deps.storage, &voucher_pubkey)?;
+            resp = resp.add_attribute("voucher_pubkey", voucher_pubkey.to_base64());
+        }
+
+        if let Some(royalty_registry) = msg.royalty_registry {
+            let royalty_registry = deps.api.addr_validate(&royalty_registry)?;
+            self.royalty_registry.save(deps.storage, &royalty_registry)?;
+            resp = resp.add_attribute("royalty_registry", royalty_registry);
+        }
+
+        if let Some(reward_asset) = msg.reward_asset {
+            self.validate_reward_asset(deps.api, &reward_asset)?;
+            self.reward_asset.save(deps.storage, &reward_asset)?;
+            resp = resp.add_attribute("reward_asset", reward_asset_to_string(&reward_asset));
+        }
+
+        if let Some(royalty) = msg.royalty {
+            self.validate_royalty(deps.api, &royalty)?;
+            self.royalty.save(deps.storage, &royalty)?;
+            resp = resp
+                .add_attribute("royalty_payment_address", &royalty.payment_address)
+                .add_attribute("royalty_share", royalty.share.to_string());
+        }
+
+        self.storage_version.save(deps.storage, &TOKEN_STORAGE_VERSION)?;
+
         Ok(resp)
     }
 
@@ -76,23 +364,59 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
         info: MessageInfo,
         msg: ExecuteMsg<T, E>
     ) -> Result<Response<C>, ContractError> {
+        self.freeze_open_edition(deps.storage, &env.block)?;
         match msg {
             ExecuteMsg::Mint { owner, token_uri, extension } =>
-                self.mint(deps, info, owner, token_uri, extension),
+                self.mint(deps, env, info, owner, token_uri, extension),
+            ExecuteMsg::MintAndSend { contract, msg, token_uri, extension } =>
+                self.mint_and_send(deps, env, info, contract, msg, token_uri, extension),
             ExecuteMsg::Approve { spender, token_id, expires } =>
                 self.approve(deps, env, info, spender, token_id, expires),
+            ExecuteMsg::ApproveMultiple { spenders, token_id } =>
+                self.approve_multiple(deps, env, info, spenders, token_id),
             ExecuteMsg::Revoke { spender, token_id } => {
                 self.revoke(deps, env, info, spender, token_id)
             }
-            ExecuteMsg::ApproveAll { operator, expires } => {
-                self.approve_all(deps, env, info, operator, expires)
+            ExecuteMsg::RevokeAllTokenApprovals { token_id } =>
+                self.revoke_all_token_approvals(deps, env, info, token_id),
+            ExecuteMsg::ApproveAll { operator, expires, max_uses, permissions } => {
+                self._approve_all(deps, env, info, operator, expires, max_uses, permissions)
             }
+            ExecuteMsg::ExtendOperator { operator, new_expiration } =>
+                self.extend_operator(deps, env, info, operator, new_expiration),
             ExecuteMsg::RevokeAll { operator } => self.revoke_all(deps, env, info, operator),
+            ExecuteMsg::SweepExpiredOperators { granter, limit } =>
+                self.sweep_expired_operators(deps, env, granter, limit),
             ExecuteMsg::TransferNft { recipient, token_id } =>
                 self.transfer_nft(deps, env, info, recipient, token_id),
             ExecuteMsg::SendNft { contract, token_id, msg } =>
                 self.send_nft(deps, env, info, contract, token_id, msg),
+            ExecuteMsg::ApproveAndSend { contract, token_id, msg, expires } =>
+                self.approve_and_send(deps, env, info, contract, token_id, msg, expires),
+            ExecuteMsg::TransferFrom { from, recipient, token_id } => {
+                self.assert_token_owner(deps.as_ref(), &from, &token_id)?;
+                self.transfer_nft(deps, env, info, recipient, token_id)
+            }
+            ExecuteMsg::SendFrom { from, contract, token_id, msg } => {
+                self.assert_token_owner(deps.as_ref(), &from, &token_id)?;
+                self.send_nft(deps, env, info, contract, token_id, msg)
+            }
             ExecuteMsg::Burn { token_id } => self.burn(deps, env, info, token_id),
+            ExecuteMsg::BurnAll { start_after, limit } =>
+                self.burn_all(deps, env, info, start_after, limit),
+            ExecuteMsg::BurnAndNotify { token_id, contract, msg, rollback_on_error } =>
+                self.burn_and_notify(deps, env, info, token_id, contract, msg, rollback_on_error),
+            ExecuteMsg::Redeem { token_id, redemption_data } =>
+                self.redeem(deps, env, info, token_id, redemption_data),
+            ExecuteMsg::FulfillRedemption { token_id } =>
+                self.fulfill_redemption(deps, env, info, token_id),
+            ExecuteMsg::NestToken { child_id, parent_id } =>
+                self.nest_token(deps, env, info, child_id, parent_id),
+            ExecuteMsg::UnnestToken { child_id } => self.unnest_token(deps, env, info, child_id),
+            ExecuteMsg::Attach { parent_id, accessory_id } =>
+                self.attach(deps, env, info, parent_id, accessory_id),
+            ExecuteMsg::Detach { parent_id, accessory_id } =>
+                self.detach(deps, env, info, parent_id, accessory_id),
             ExecuteMsg::UpdateOwnership(action) => Self::update_ownership(deps, env, info, action),
             ExecuteMsg::Extension { msg: _ } => Ok(Response::default()),
             ExecuteMsg::SetWithdrawAddress { address } => {
@@ -101,7 +425,90 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
             ExecuteMsg::RemoveWithdrawAddress {} => {
                 self.remove_withdraw_address(deps.storage, &info.sender)
             }
-            ExecuteMsg::WithdrawFunds { amount } => self.withdraw_funds(deps.storage, &amount),
+            ExecuteMsg::WithdrawFunds { amount } => {
+                self.assert_not_escrowed(deps.as_ref(), &env, &amount)?;
+                self.assert_vested(deps.storage, &env.block, &amount)?;
+                self.withdraw_funds(deps.storage, &env.block, &amount)
+            }
+            ExecuteMsg::AddAllowedOperator { operator } => {
+                self.add_allowed_operator(deps, &info.sender, operator)
+            }
+            ExecuteMsg::RemoveAllowedOperator { operator } => {
+                self.remove_allowed_operator(deps, &info.sender, operator)
+            }
+            ExecuteMsg::RevealTokens { tokens } => self.reveal_tokens(deps, &info.sender, tokens),
+            ExecuteMsg::CheckpointBalances {} => self.checkpoint_balances(deps, env, info),
+            ExecuteMsg::ApproveWithPermit { permit } => self.approve_with_permit(deps, env, permit),
+            ExecuteMsg::FinalizeImport {} => self.finalize_import(deps, info),
+            ExecuteMsg::MigrateBatch { limit } => self.migrate_batch(deps, limit),
+            ExecuteMsg::SetBridge { bridge } => self.set_bridge(deps, info, bridge),
+            ExecuteMsg::BridgeMint { token_id, owner, token_uri, extension } =>
+                self.bridge_mint(deps, env, info, token_id, owner, token_uri, extension),
+            ExecuteMsg::BridgeBurn { token_id } => self.bridge_burn(deps, env, info, token_id),
+            ExecuteMsg::SetBridgeOperator { operator } =>
+                self.set_bridge_operator(deps, info, operator),
+            ExecuteMsg::BridgeLock { token_id } => self.bridge_lock(deps, env, info, token_id),
+            ExecuteMsg::BridgeRelease { token_id, recipient } =>
+                self.bridge_release(deps, env, info, token_id, recipient),
+            ExecuteMsg::ListToken { token_id, price, expires } =>
+                self.list_token(deps, env, info, token_id, price, expires),
+            ExecuteMsg::Delist { token_id } => self.delist(deps, info, token_id),
+            ExecuteMsg::Buy { token_id } => self.buy(deps, env, info, token_id),
+            ExecuteMsg::ListBundle { token_ids, price, expires } =>
+                self.list_bundle(deps, env, info, token_ids, price, expires),
+            ExecuteMsg::DelistBundle { bundle_id } => self.delist_bundle(deps, info, bundle_id),
+            ExecuteMsg::BuyBundle { bundle_id } => self.buy_bundle(deps, env, info, bundle_id),
+            ExecuteMsg::SetRoyalty { royalty } => self.set_royalty(deps, info, royalty),
+            ExecuteMsg::CreateAuction { token_id, min_bid, duration } =>
+                self.create_auction(deps, env, info, token_id, min_bid, duration),
+            ExecuteMsg::PlaceBid { token_id } => self.place_bid(deps, env, info, token_id),
+            ExecuteMsg::SettleAuction { token_id } => self.settle_auction(deps, env, token_id),
+            ExecuteMsg::MakeOffer { token_id, expires } =>
+                self.make_offer(deps, env, info, token_id, expires),
+            ExecuteMsg::WithdrawOffer { token_id, bidder } =>
+                self.withdraw_offer(deps, env, info, token_id, bidder),
+            ExecuteMsg::AcceptOffer { token_id, bidder } =>
+                self.accept_offer(deps, env, info, token_id, bidder),
+            ExecuteMsg::SetUser { token_id, user, expires } =>
+                self.set_user(deps, env, info, token_id, user, expires),
+            ExecuteMsg::Stake { token_id } => self.stake(deps, env, info, token_id),
+            ExecuteMsg::Unstake { token_id } => self.unstake(deps, env, info, token_id),
+            ExecuteMsg::SetMinStakePeriod { period } =>
+                self.set_min_stake_period(deps, info, period),
+            ExecuteMsg::ReceiveNft(receive) => self.receive_nft(deps, env, info, receive),
+            ExecuteMsg::Unwrap { token_id } => self.unwrap(deps, env, info, token_id),
+            ExecuteMsg::SetBurnToMint { config } => self.set_burn_to_mint(deps, info, config),
+            ExecuteMsg::Delegate { token_id, delegate, expires } =>
+                self.delegate(deps, env, info, token_id, delegate, expires),
+            ExecuteMsg::Undelegate { token_id } => self.undelegate(deps, info, token_id),
+            ExecuteMsg::FundRewards {} => self.fund_rewards(deps, env, info),
+            ExecuteMsg::Receive(receive) => self.receive_cw20(deps, env, info, receive),
+            ExecuteMsg::ClaimRewards {} => self.claim_rewards(deps, info),
+            ExecuteMsg::SetRewardAsset { asset } => self.set_reward_asset(deps, info, asset),
+            ExecuteMsg::SetRoyaltyRegistry { registry } =>
+                self.set_royalty_registry(deps, info, registry),
+            ExecuteMsg::RedeemVoucher { voucher, signature } =>
+                self.redeem_voucher(deps, env, info, voucher, signature),
+            ExecuteMsg::SetVoucherPubkey { pubkey } => self.set_voucher_pubkey(deps, info, pubkey),
+            ExecuteMsg::SetPartnerMinter { address, config } =>
+                self.set_partner_minter(deps, info, address, config),
+            ExecuteMsg::StartRaffle { ends_at } => self.start_raffle(deps, env, info, ends_at),
+            ExecuteMsg::EnterRaffle {} => self.enter_raffle(deps, env, info),
+            ExecuteMsg::CommitRaffleSeed { hash } => self.commit_raffle_seed(deps, env, info, hash),
+            ExecuteMsg::RevealRaffleSeed { seed } => self.reveal_raffle_seed(deps, env, info, seed),
+            ExecuteMsg::ClaimMint {} => self.claim_mint(deps, env, info),
+            ExecuteMsg::ClaimRefund {} => self.claim_refund(deps, env, info),
+            ExecuteMsg::SetSubscription { config } => self.set_subscription(deps, info, config),
+            ExecuteMsg::Renew { token_id } => self.renew(deps, env, info, token_id),
+            ExecuteMsg::ReapExpired { limit } => self.reap_expired(deps, env, info, limit),
+            ExecuteMsg::SetCoSigning { config } => self.set_co_signing(deps, info, config),
+            ExecuteMsg::ProposeMint { owner, token_uri, extension } =>
+                self.propose_mint(deps, env, info, owner, token_uri, extension),
+            ExecuteMsg::ApproveMint { proposal_id } =>
+                self.approve_mint(deps, env, info, proposal_id),
+            ExecuteMsg::CancelMint { proposal_id } => self.cancel_mint(deps, info, proposal_id),
+            ExecuteMsg::ReportSale { token_id, price } =>
+                self.report_sale(deps, env, info, token_id, price),
         }
     }
 }
@@ -113,321 +520,3766 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
     pub fn mint(
         &self,
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         owner: String,
         token_uri: Option<String>,
         extension: T
     ) -> Result<Response<C>, ContractError> {
         // cw_ownable::assert_owner(deps.storage, &info.sender)?;
-
-        //Check totalSupply <= maxSupply
-        let total = self.token_count(deps.storage)?;
-        let token_id = total.clone().to_string();
-        let max = self.max_supply.may_load(deps.storage);
-
-        if let Ok(Some(max_supply)) = max {
-            if total >= max_supply {
-                return Err(ContractError::SupplyOverflow {});
-            }
+        if self.co_signing.exists(deps.storage) {
+            return Err(ContractError::MintNeedsCoSigners {});
         }
-        //Check user does not minting more that max_nfts_per_wallet
-        let user_minted = self.wallets_minted_amount.load(deps.storage, owner.clone()).unwrap_or(0);
-        let max_mint = self.max_nfts_per_wallet.may_load(deps.storage);
-        if let Ok(Some(max)) = max_mint {
-            if user_minted >= max {
-                return Err(ContractError::MintPerWalletOverflow {});
-            }
+
+        let token_id = self.minted_total(deps.storage)?.to_string();
+        let owner_addr = self.validate_mint_owner(deps.as_ref(), &owner)?;
+        let partner = self.partner_minters.may_load(deps.storage, &info.sender)?;
+        let counted = partner.as_ref().map_or(true, |p| p.counts_against_recipient);
+        self.assert_supply_available(deps.storage, &env.block, 1)?;
+        if counted {
+            self.assert_wallet_allocation(deps.storage, &owner_addr, 1)?;
         }
         //Check sent coins for mint_price_per_nft
-        let coins_want = self.mint_price_per_nft.may_load(deps.storage);
-        if let Ok(Some(want)) = coins_want {
+        let price = self.mint_price(deps.storage, 1)?;
+        let mut proceeds = None;
+        if let Some(want) = &price {
             let coins_sent = info.funds
                 .iter()
                 .find(|&x| x.denom == want.denom && x.amount >= want.amount);
 
-            if coins_sent.is_none() {
-                return Err(ContractError::NotEnoughFunds {});
+            match coins_sent {
+                Some(sent) => {
+                    proceeds = Some(sent.clone());
+                }
+                None => {
+                    return Err(ContractError::NotEnoughFunds {});
+                }
             }
         }
 
-        // create the token
-        let token = TokenInfo {
-            owner: deps.api.addr_validate(&owner)?,
-            approvals: vec![],
-            token_uri,
-            extension,
-        };
-        self.tokens.update(deps.storage, &token_id, |old| {
-            match old {
-                Some(_) => Err(ContractError::Claimed {}),
-                None => Ok(token),
+        let mut resp = Response::new()
+            .add_attribute("action", "mint")
+            .add_attribute("minter", &info.sender)
+            .add_attribute("owner", owner)
+            .add_attribute("token_id", &token_id);
+        if let Some(partner) = partner {
+            resp = resp.add_attribute("partner", &info.sender);
+            // the partner's cut of the price, the rest stays with the contract as usual
+            let fee = price.map(|price| Coin {
+                amount: price.amount.multiply_ratio(partner.fee_bps, 10_000u128),
+                denom: price.denom,
+            });
+            if let Some(fee) = fee.filter(|fee| !fee.amount.is_zero()) {
+                if let Some(proceeds) = &mut proceeds {
+                    proceeds.amount -= fee.amount;
+                }
+                resp = resp
+                    .add_attribute("partner_fee", fee.to_string())
+                    .add_message(BankMsg::Send {
+                        to_address: info.sender.to_string(),
+                        amount: vec![fee],
+                    });
             }
-        })?;
-
-        self.increment_tokens(deps.storage, &owner)?;
-
-        Ok(
-            Response::new()
-                .add_attribute("action", "mint")
-                .add_attribute("minter", info.sender)
-                .add_attribute("owner", owner)
-                .add_attribute("token_id", token_id)
-        )
+        }
+        if counted {
+            self.count_wallet_mint(deps.storage, &owner_addr)?;
+        }
+        if let Some(proceeds) = &proceeds {
+            self.credit_proceeds(deps.storage, proceeds)?;
+        }
+        self.mint_next(deps.storage, &env.block, resp, owner_addr, token_uri, extension)
     }
 
-    pub fn update_ownership(
-        deps: DepsMut,
+    #[allow(clippy::too_many_arguments)]
+    pub fn mint_and_send(
+        &self,
+        mut deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        action: cw_ownable::Action
+        contract: String,
+        msg: Binary,
+        token_uri: Option<String>,
+        extension: T
     ) -> Result<Response<C>, ContractError> {
-        let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
-        Ok(Response::new().add_attributes(ownership.into_attributes()))
-    }
+        // the sender owns the token in between, so it is minted against their allocation
+        let token_id = self.minted_total(deps.storage)?.to_string();
+        let owner = info.sender.to_string();
+        let minted = self.mint(
+            deps.branch(),
+            env.clone(),
+            info.clone(),
+            owner,
+            token_uri,
+            extension
+        )?;
 
-    pub fn set_withdraw_address(
-        &self,
-        storage: &mut dyn Storage,
-        api: &dyn Api,
-        sender: &Addr,
-        address: String
-    ) -> Result<Response<C>, ContractError> {
-        cw_ownable::assert_owner(storage, sender)?;
-        api.addr_validate(&address)?;
-        self.withdraw_address.save(storage, &address)?;
+        // the mint's payouts, like a partner fee, go out before the token is sent
+        let sent = self._send_nft(deps, env, info, contract, token_id, msg, vec![])?;
         Ok(
-            Response::new()
-                .add_attribute("action", "set_withdraw_address")
-                .add_attribute("address", address)
+            minted
+                .add_submessages(sent.messages)
+                .add_attributes(sent.attributes)
+                .add_events(sent.events)
+                .add_attribute("minted", "true")
         )
     }
 
-    pub fn remove_withdraw_address(
+    pub fn set_co_signing(
         &self,
-        storage: &mut dyn Storage,
-        sender: &Addr
+        deps: DepsMut,
+        info: MessageInfo,
+        config: Option<CoSignConfig>
     ) -> Result<Response<C>, ContractError> {
-        cw_ownable::assert_owner(storage, sender)?;
-        let address = self.withdraw_address.may_load(storage)?;
-        match address {
-            Some(address) => {
-                self.withdraw_address.remove(storage);
-                Ok(
-                    Response::new()
-                        .add_attribute("action", "remove_withdraw_address")
-                        .add_attribute("address", address)
-                )
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_co_signing");
+        match config {
+            Some(config) => {
+                self.validate_co_signing(deps.api, &config)?;
+                self.co_signing.save(deps.storage, &config)?;
+                Ok(resp.add_attribute("co_signers", config.co_signers.len().to_string()))
+            }
+            None => {
+                self.co_signing.remove(deps.storage);
+                Ok(resp.add_attribute("co_signers", "none"))
             }
-            None => Err(ContractError::NoWithdrawAddress {}),
         }
     }
 
-    pub fn withdraw_funds(
+    fn validate_co_signing(
         &self,
-        storage: &mut dyn Storage,
-        amount: &Coin
-    ) -> Result<Response<C>, ContractError> {
-        let address = self.withdraw_address.may_load(storage)?;
-        match address {
-            Some(address) => {
-                let msg = BankMsg::Send {
-                    to_address: address,
-                    amount: vec![amount.clone()],
-                };
-                Ok(
-                    Response::new()
-                        .add_message(msg)
-                        .add_attribute("action", "withdraw_funds")
-                        .add_attribute("amount", amount.amount.to_string())
-                        .add_attribute("denom", amount.denom.to_string())
-                )
-            }
-            None => Err(ContractError::NoWithdrawAddress {}),
+        api: &dyn Api,
+        config: &CoSignConfig
+    ) -> Result<(), ContractError> {
+        if config.co_signers.is_empty() {
+            return Err(ContractError::NoCoSigners {});
         }
+        for co_signer in &config.co_signers {
+            api.addr_validate(co_signer.as_str())?;
+        }
+        if config.proposal_ttl == 0 {
+            return Err(ContractError::ZeroProposalTtl {});
+        }
+        Ok(())
     }
-}
 
-impl<'a, T, C, E, Q> Cw721Execute<T, C>
-    for Cw721Contract<'a, T, C, E, Q>
-    where T: Serialize + DeserializeOwned + Clone, C: CustomMsg, E: CustomMsg, Q: CustomMsg
-{
-    type Err = ContractError;
+    fn assert_co_signer(
+        &self,
+        storage: &dyn Storage,
+        sender: &Addr
+    ) -> Result<CoSignConfig, ContractError> {
+        match self.co_signing.may_load(storage)? {
+            Some(config) if config.co_signers.contains(sender) => Ok(config),
+            _ => Err(ContractError::NotCoSigner {}),
+        }
+    }
 
-    fn transfer_nft(
+    #[allow(clippy::too_many_arguments)]
+    pub fn propose_mint(
         &self,
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        recipient: String,
-        token_id: String
+        owner: String,
+        token_uri: Option<String>,
+        extension: T
     ) -> Result<Response<C>, ContractError> {
-        self._transfer_nft(deps, &env, &info, &recipient, &token_id)?;
+        let config = self.assert_co_signer(deps.storage, &info.sender)?;
+        let owner = self.validate_mint_owner(deps.as_ref(), &owner)?;
+        self.assert_supply_available(deps.storage, &env.block, 1)?;
 
-        Ok(
-            Response::new()
-                .add_attribute("action", "transfer_nft")
-                .add_attribute("sender", info.sender)
-                .add_attribute("recipient", recipient)
-                .add_attribute("token_id", token_id)
-        )
+        let id = self.next_proposal_id.may_load(deps.storage)?.unwrap_or_default() + 1;
+        self.next_proposal_id.save(deps.storage, &id)?;
+        let proposal = MintProposal {
+            proposer: info.sender.clone(),
+            owner,
+            token_uri,
+            extension,
+            approvals: vec![info.sender.clone()],
+            expires: env.block.time.plus_seconds(config.proposal_ttl),
+        };
+        let resp = Response::new()
+            .add_attribute("action", "propose_mint")
+            .add_attribute("proposer", info.sender)
+            .add_attribute("proposal_id", id.to_string());
+        self.complete_mint_proposal(deps.storage, &env.block, resp, &config, id, proposal)
     }
 
-    fn send_nft(
+    pub fn approve_mint(
         &self,
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        contract: String,
-        token_id: String,
-        msg: Binary
+        proposal_id: u64
     ) -> Result<Response<C>, ContractError> {
-        // Transfer token
-        self._transfer_nft(deps, &env, &info, &contract, &token_id)?;
-
-        let send = Cw721ReceiveMsg {
-            sender: info.sender.to_string(),
-            token_id: token_id.clone(),
-            msg,
-        };
+        let config = self.assert_co_signer(deps.storage, &info.sender)?;
+        let mut proposal = self.mint_proposals
+            .may_load(deps.storage, proposal_id)?
+            .ok_or(ContractError::NoMintProposal { id: proposal_id })?;
+        if env.block.time >= proposal.expires {
+            return Err(ContractError::MintProposalExpired {});
+        }
+        if proposal.approvals.contains(&info.sender) {
+            return Err(ContractError::AlreadyApproved {});
+        }
 
-        // Send message
-        Ok(
-            Response::new()
-                .add_message(send.into_cosmos_msg(contract.clone())?)
-                .add_attribute("action", "send_nft")
-                .add_attribute("sender", info.sender)
-                .add_attribute("recipient", contract)
-                .add_attribute("token_id", token_id)
-        )
+        proposal.approvals.push(info.sender.clone());
+        let resp = Response::new()
+            .add_attribute("action", "approve_mint")
+            .add_attribute("co_signer", info.sender)
+            .add_attribute("proposal_id", proposal_id.to_string());
+        self.complete_mint_proposal(deps.storage, &env.block, resp, &config, proposal_id, proposal)
     }
 
-    fn approve(
+    /// Mints once every current co-signer approved, otherwise keeps the proposal waiting
+    fn complete_mint_proposal(
         &self,
-        deps: DepsMut,
-        env: Env,
-        info: MessageInfo,
-        spender: String,
-        token_id: String,
-        expires: Option<Expiration>
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        resp: Response<C>,
+        config: &CoSignConfig,
+        id: u64,
+        proposal: MintProposal<T>
     ) -> Result<Response<C>, ContractError> {
-        self._update_approvals(deps, &env, &info, &spender, &token_id, true, expires)?;
+        let approved = config.co_signers
+            .iter()
+            .all(|co_signer| proposal.approvals.contains(co_signer));
+        if !approved {
+            self.mint_proposals.save(storage, id, &proposal)?;
+            return Ok(resp.add_attribute("minted", "false"));
+        }
 
-        Ok(
-            Response::new()
-                .add_attribute("action", "approve")
-                .add_attribute("sender", info.sender)
-                .add_attribute("spender", spender)
-                .add_attribute("token_id", token_id)
-        )
+        self.mint_proposals.remove(storage, id);
+        self.assert_supply_available(storage, block, 1)?;
+        let resp = resp
+            .add_attribute("minted", "true")
+            .add_attribute("owner", &proposal.owner)
+            .add_attribute("token_id", self.minted_total(storage)?.to_string());
+        let MintProposal { owner, token_uri, extension, .. } = proposal;
+        self.mint_next(storage, block, resp, owner, token_uri, extension)
     }
 
-    fn revoke(
+    pub fn cancel_mint(
         &self,
         deps: DepsMut,
-        env: Env,
         info: MessageInfo,
-        spender: String,
-        token_id: String
+        proposal_id: u64
     ) -> Result<Response<C>, ContractError> {
-        self._update_approvals(deps, &env, &info, &spender, &token_id, false, None)?;
+        let proposal = self.mint_proposals
+            .may_load(deps.storage, proposal_id)?
+            .ok_or(ContractError::NoMintProposal { id: proposal_id })?;
+        if proposal.proposer != info.sender {
+            return Err(ContractError::NotProposer {});
+        }
+        self.mint_proposals.remove(deps.storage, proposal_id);
 
         Ok(
             Response::new()
-                .add_attribute("action", "revoke")
-                .add_attribute("sender", info.sender)
-                .add_attribute("spender", spender)
-                .add_attribute("token_id", token_id)
+                .add_attribute("action", "cancel_mint")
+                .add_attribute("proposal_id", proposal_id.to_string())
         )
     }
 
-    fn approve_all(
+    pub fn redeem_voucher(
         &self,
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        operator: String,
-        expires: Option<Expiration>
+        voucher: MintVoucher<T>,
+        signature: Binary
     ) -> Result<Response<C>, ContractError> {
-        // reject expired data as invalid
-        let expires = expires.unwrap_or_default();
-        if expires.is_expired(&env.block) {
-            return Err(ContractError::Expired {});
+        let Some(pubkey) = self.voucher_pubkey.may_load(deps.storage)? else {
+            return Err(ContractError::NoVoucherPubkey {});
+        };
+        let hash = Sha256::digest(to_json_vec(&voucher)?);
+        let valid = deps.api.secp256k1_verify(&hash, &signature, &pubkey).unwrap_or(false);
+        if !valid {
+            return Err(ContractError::InvalidVoucherSignature {});
         }
-
-        // set the operator for us
+        if voucher.collection != env.contract.address {
+            return Err(ContractError::VoucherForOtherCollection {});
+        }
+        if voucher.expires.is_expired(&env.block) {
+            return Err(ContractError::VoucherExpired {});
+        }
+        if self.used_voucher_nonces.has(deps.storage, voucher.nonce) {
+            return Err(ContractError::VoucherReplayed { nonce: voucher.nonce });
+        }
+
+        match &voucher.price {
+            Some(price) => {
+                let paid = cw_utils::must_pay(&info, &price.denom)?;
+                if paid != price.amount {
+                    return Err(ContractError::WrongVoucherPayment { price: price.clone() });
+                }
+                self.credit_proceeds(deps.storage, price)?;
+            }
+            None => {
+                cw_utils::nonpayable(&info)?;
+            }
+        }
+        self.assert_supply_available(deps.storage, &env.block, 1)?;
+        self.assert_wallet_allocation(deps.storage, &info.sender, 1)?;
+
+        let token_id = match voucher.token_id {
+            Some(token_id) => {
+                // the mint sequence would run into it sooner or later
+                if token_id.parse::<u64>().map_or(false, |n| n.to_string() == token_id) {
+                    return Err(ContractError::TokenIdReserved { token_id });
+                }
+                token_id
+            }
+            None => self.minted_total(deps.storage)?.to_string(),
+        };
+        self.used_voucher_nonces.save(deps.storage, voucher.nonce, &Empty {})?;
+        self.count_wallet_mint(deps.storage, &info.sender)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "redeem_voucher")
+            .add_attribute("owner", &info.sender)
+            .add_attribute("token_id", &token_id)
+            .add_attribute("voucher_nonce", voucher.nonce.to_string());
+        self.mint_token(
+            deps.storage,
+            &env.block,
+            resp,
+            token_id,
+            info.sender,
+            voucher.token_uri,
+            voucher.extension
+        )
+    }
+
+    pub fn set_partner_minter(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        address: String,
+        config: Option<PartnerConfig>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let partner = deps.api.addr_validate(&address)?;
+        let resp = Response::new()
+            .add_attribute("action", "set_partner_minter")
+            .add_attribute("partner", &partner);
+        match config {
+            Some(config) => {
+                if config.fee_bps > 10_000 {
+                    return Err(ContractError::InvalidPartnerFee {});
+                }
+                self.partner_minters.save(deps.storage, &partner, &config)?;
+                Ok(resp.add_attribute("partner_fee_bps", config.fee_bps.to_string()))
+            }
+            None => {
+                self.partner_minters.remove(deps.storage, &partner);
+                Ok(resp.add_attribute("partner_fee_bps", "none"))
+            }
+        }
+    }
+
+    pub fn set_voucher_pubkey(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        pubkey: Option<Binary>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_voucher_pubkey");
+        match pubkey {
+            Some(pubkey) => {
+                self.voucher_pubkey.save(deps.storage, &pubkey)?;
+                Ok(resp.add_attribute("voucher_pubkey", pubkey.to_base64()))
+            }
+            None => {
+                self.voucher_pubkey.remove(deps.storage);
+                Ok(resp.add_attribute("voucher_pubkey", "none"))
+            }
+        }
+    }
+
+    pub fn start_raffle(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        ends_at: Timestamp
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        if !self.max_supply.exists(deps.storage) {
+            return Err(ContractError::RaffleNeedsMaxSupply {});
+        }
+        // unclaimed entries of the last raffle would mix with the new ones
+        if self.raffle_phase.exists(deps.storage) && !self.raffle_entries.is_empty(deps.storage) {
+            return Err(ContractError::RaffleInProgress {});
+        }
+        if ends_at <= env.block.time {
+            return Err(ContractError::Expired {});
+        }
+
+        let reveal_by = ends_at.plus_seconds(RAFFLE_REVEAL_PERIOD);
+        self.raffle_phase.save(deps.storage, &(RafflePhase::Entry { ends_at, reveal_by }))?;
+        self.raffle_entry_count.save(deps.storage, &0)?;
+        self.raffle_claimed.save(deps.storage, &0)?;
+        Ok(
+            Response::new()
+                .add_attribute("action", "start_raffle")
+                .add_attribute("ends_at", ends_at.seconds().to_string())
+        )
+    }
+
+    pub fn enter_raffle(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response<C>, ContractError> {
+        match self.raffle_phase.may_load(deps.storage)? {
+            Some(RafflePhase::Entry { ends_at, .. }) if env.block.time < ends_at => {}
+            _ => {
+                return Err(ContractError::RaffleNotOpen {});
+            }
+        }
+        if self.raffle_entries.has(deps.storage, &info.sender) {
+            return Err(ContractError::AlreadyEntered {});
+        }
+        self.assert_wallet_allocation(deps.storage, &info.sender, 1)?;
+        let paid = match self.mint_price(deps.storage, 1)? {
+            Some(price) => {
+                let amount = cw_utils::must_pay(&info, &price.denom)?;
+                if amount < price.amount {
+                    return Err(ContractError::NotEnoughFunds {});
+                }
+                let paid = Coin { denom: price.denom, amount };
+                self.escrow(deps.storage, &paid)?;
+                Some(paid)
+            }
+            None => {
+                cw_utils::nonpayable(&info)?;
+                None
+            }
+        };
+
+        let index = self.raffle_entry_count.load(deps.storage)?;
+        self.raffle_entries.save(deps.storage, &info.sender, &(RaffleEntry { index, paid }))?;
+        self.raffle_entry_count.save(deps.storage, &(index + 1))?;
+        Ok(
+            Response::new()
+                .add_attribute("action", "enter_raffle")
+                .add_attribute("entrant", info.sender)
+                .add_attribute("index", index.to_string())
+        )
+    }
+
+    /// Every entry competes for the supply left when entries close
+    pub fn commit_raffle_seed(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        hash: Binary
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let reveal_by = match self.raffle_phase.may_load(deps.storage)? {
+            Some(RafflePhase::Entry { ends_at, .. }) if env.block.time < ends_at => {
+                return Err(ContractError::RaffleStillOpen {});
+            }
+            Some(RafflePhase::Entry { reveal_by, .. }) => reveal_by,
+            _ => {
+                return Err(ContractError::RaffleNotOpen {});
+            }
+        };
+        if env.block.time >= reveal_by {
+            return Err(ContractError::RaffleAbandoned {});
+        }
+
+        let max_supply = self.max_supply.load(deps.storage)?;
+        let remaining = max_supply.saturating_sub(self.capped_supply(deps.storage)?);
+        let winners = remaining.min(self.raffle_entry_count.load(deps.storage)?);
+        self.raffle_phase.save(deps.storage, &(RafflePhase::Committed {
+            hash,
+            winners,
+            reveal_by,
+        }))?;
+        Ok(
+            Response::new()
+                .add_attribute("action", "commit_raffle_seed")
+                .add_attribute("winners", winners.to_string())
+        )
+    }
+
+    pub fn reveal_raffle_seed(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        seed: Binary
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let Some(RafflePhase::Committed { hash, winners, reveal_by }) = self.raffle_phase.may_load(
+            deps.storage
+        )? else {
+            return Err(ContractError::RaffleNotCommitted {});
+        };
+        // entrants may already be taking their refunds
+        if env.block.time >= reveal_by {
+            return Err(ContractError::RaffleAbandoned {});
+        }
+        if Sha256::digest(seed.as_slice()).as_slice() != hash.as_slice() {
+            return Err(ContractError::InvalidRaffleSeed {});
+        }
+
+        self.raffle_phase.save(deps.storage, &(RafflePhase::Revealed { seed, winners }))?;
+        Ok(Response::new().add_attribute("action", "reveal_raffle_seed"))
+    }
+
+    pub fn claim_mint(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response<C>, ContractError> {
+        let entry = self.load_raffle_entry(deps.storage, &env.block, &info.sender)?;
+        if !self.raffle_won(deps.storage, &entry)? {
+            return Err(ContractError::RaffleLost {});
+        }
+        // the wallet may have minted elsewhere since entering, then only a refund is left
+        self.assert_wallet_allocation(deps.storage, &info.sender, 1)?;
+        self.raffle_entries.remove(deps.storage, &info.sender);
+        let claimed = self.raffle_claimed.load(deps.storage)? + 1;
+        self.raffle_claimed.save(deps.storage, &claimed)?;
+        // the price becomes mint proceeds
+        if let Some(paid) = &entry.paid {
+            self.release_escrow(deps.storage, paid)?;
+            self.credit_proceeds(deps.storage, paid)?;
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "claim_mint")
+            .add_attribute("owner", &info.sender)
+            .add_attribute("token_id", self.minted_total(deps.storage)?.to_string());
+        self.count_wallet_mint(deps.storage, &info.sender)?;
+        self.mint_next(deps.storage, &env.block, resp, info.sender, None, from_json(b"null")?)
+    }
+
+    pub fn claim_refund(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response<C>, ContractError> {
+        let entry = self.load_raffle_entry(deps.storage, &env.block, &info.sender)?;
+        if self.raffle_won(deps.storage, &entry)? {
+            if self.assert_wallet_allocation(deps.storage, &info.sender, 1).is_ok() {
+                return Err(ContractError::RaffleWon {});
+            }
+            // the token is given up, which frees its share of the supply
+            let claimed = self.raffle_claimed.load(deps.storage)? + 1;
+            self.raffle_claimed.save(deps.storage, &claimed)?;
+        }
+        self.raffle_entries.remove(deps.storage, &info.sender);
+
+        let mut resp = Response::new()
+            .add_attribute("action", "claim_refund")
+            .add_attribute("entrant", &info.sender);
+        if let Some(paid) = entry.paid {
+            self.release_escrow(deps.storage, &paid)?;
+            resp = resp.add_attribute("refund", paid.to_string()).add_message(BankMsg::Send {
+                to_address: info.sender.into_string(),
+                amount: vec![paid],
+            });
+        }
+        Ok(resp)
+    }
+
+    /// Claims only open once the winners are drawn, or nobody won since the seed was never
+    /// revealed
+    fn load_raffle_entry(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        entrant: &Addr
+    ) -> Result<RaffleEntry, ContractError> {
+        match self.raffle_phase.may_load(storage)? {
+            Some(RafflePhase::Revealed { .. }) => {}
+            Some(phase) if phase.is_abandoned(block) => {}
+            _ => {
+                return Err(ContractError::RaffleNotRevealed {});
+            }
+        }
+        self.raffle_entries.may_load(storage, entrant)?.ok_or(ContractError::NoRaffleEntry {})
+    }
+
+    /// Whether the entry won, which is only known once the seed is revealed
+    pub fn raffle_won(&self, storage: &dyn Storage, entry: &RaffleEntry) -> StdResult<bool> {
+        let phase = self.raffle_phase.may_load(storage)?;
+        let Some(RafflePhase::Revealed { seed, winners }) = phase else {
+            return Ok(false);
+        };
+        let entries = self.raffle_entry_count.load(storage)?;
+        Ok(raffle_position(&seed, entry.index, entries) < winners)
+    }
+
+    /// Mints the next token of the sequence and counts it, limits are checked by the caller,
+    /// who also counts it towards a wallet if it should.
+    /// Adds the sold out event if this was the last token
+    fn mint_next(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        resp: Response<C>,
+        owner: Addr,
+        token_uri: Option<String>,
+        extension: T
+    ) -> Result<Response<C>, ContractError> {
+        let token_id = self.minted_total(storage)?.to_string();
+        self.mint_token(storage, block, resp, token_id, owner, token_uri, extension)
+    }
+
+    /// Like `mint_next` under the given id, which still takes a place in the sequence
+    #[allow(clippy::too_many_arguments)]
+    fn mint_token(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        mut resp: Response<C>,
+        token_id: String,
+        owner: Addr,
+        token_uri: Option<String>,
+        extension: T
+    ) -> Result<Response<C>, ContractError> {
+        let token = TokenInfo {
+            owner,
+            approvals: vec![],
+            token_uri,
+            extension,
+            parent: None,
+            redeemed: false,
+            transfer_count: 0,
+            last_transferred_at: None,
+            minted_at: Some(block.time),
+            revealed: !self.delayed_reveal(storage)?,
+            user: None,
+            user_expires: None,
+            staked_since: None,
+            delegate: None,
+            delegate_expires: None,
+            accessories: vec![],
+            valid_until: self.subscription
+                .may_load(storage)?
+                .map(|subscription| block.time.plus_seconds(subscription.period)),
+            version: TOKEN_STORAGE_VERSION,
+        };
+        self.store_new_token(storage, block, &token_id, token)?;
+        self.increment_tokens(storage, block)?;
+
+        if self.mark_sold_out(storage)? {
+            let max_supply = self.max_supply.load(storage)?;
+            resp = resp.add_event(
+                Event::new("cw721_sold_out")
+                    .add_attribute("max_supply", max_supply.to_string())
+                    .add_attribute("last_token_id", token_id)
+            );
+        }
+        Ok(resp)
+    }
+
+    /// Saves a token that doesn't exist yet with everything indexed, leaving the supply
+    /// counters to the caller
+    fn store_new_token(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        token: TokenInfo<T>
+    ) -> Result<TokenInfo<T>, ContractError> {
+        let token = self.tokens.update(storage, &token_key(token_id), |old| {
+            match old {
+                Some(_) => Err(ContractError::Claimed {}),
+                None => Ok(token),
+            }
+        })?;
+        self.add_holding(storage, block, &token.owner, token_id)?;
+        self.index_token_extension(storage, token_id, &token.extension)?;
+        self.update_uri_index(storage, token_id, None, token.token_uri.as_deref())?;
+        self.record_move(
+            storage,
+            block,
+            token_id,
+            HistoryAction::Mint,
+            None,
+            Some(token.owner.clone())
+        )?;
+        self.mint_times.save(storage, (block.time.seconds(), &token_key(token_id)), &Empty {})?;
+        if let Some(valid_until) = token.valid_until {
+            let key = token_key(token_id);
+            self.subscription_expiries.save(storage, (valid_until.seconds(), &key), &Empty {})?;
+        }
+
+        self.enumerate_token(storage, token_id)?;
+        if !token.revealed {
+            self.add_unrevealed(storage, token_id)?;
+        }
+        Ok(token)
+    }
+
+    pub fn set_bridge(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        bridge: Option<String>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_bridge");
+        match bridge {
+            Some(bridge) => {
+                let bridge = deps.api.addr_validate(&bridge)?;
+                self.bridge.save(deps.storage, &bridge)?;
+                Ok(resp.add_attribute("bridge", bridge))
+            }
+            None => {
+                self.bridge.remove(deps.storage);
+                Ok(resp.add_attribute("bridge", "none"))
+            }
+        }
+    }
+
+    /// Tokens leave for another chain by `SendNft` to the bridge, which escrows them.
+    /// Tokens from elsewhere arrive through here and count towards the circulating supply,
+    /// but not towards `max_supply` under the minted basis or any wallet allocation
+    #[allow(clippy::too_many_arguments)]
+    pub fn bridge_mint(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: T
+    ) -> Result<Response<C>, ContractError> {
+        self.assert_bridge(deps.storage, &info.sender)?;
+        // the mint sequence would run into it sooner or later
+        if token_id.parse::<u64>().map_or(false, |n| n.to_string() == token_id) {
+            return Err(ContractError::TokenIdReserved { token_id });
+        }
+        let owner_addr = deps.api.addr_validate(&owner)?;
+
+        let token = TokenInfo {
+            owner: owner_addr,
+            approvals: vec![],
+            token_uri,
+            extension,
+            parent: None,
+            redeemed: false,
+            transfer_count: 0,
+            last_transferred_at: None,
+            minted_at: Some(env.block.time),
+            revealed: true,
+            user: None,
+            user_expires: None,
+            staked_since: None,
+            delegate: None,
+            delegate_expires: None,
+            accessories: vec![],
+            valid_until: None,
+            version: TOKEN_STORAGE_VERSION,
+        };
+        self.store_new_token(deps.storage, &env.block, &token_id, token)?;
+        let token_count = self.token_count(deps.storage)? + 1;
+        self.save_token_count(deps.storage, env.block.height, token_count)?;
+        let bridged_in = self.bridged_in_total(deps.storage)? + 1;
+        self.bridged_in_total.save(deps.storage, &bridged_in)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "bridge_mint")
+                .add_attribute("owner", owner)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    pub fn bridge_burn(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        self.assert_bridge(deps.storage, &info.sender)?;
+        let token = self.load_token(deps.storage, &token_id)?;
+        // only what was escrowed with the bridge can leave
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        // the token lives on elsewhere, so it isn't counted or recorded as burned
+        self.assert_burnable(deps.storage, &env.block, &token_id, &token)?;
+        self._remove_token(deps.storage, &env.block, &token_id, token, HistoryAction::BridgeBurn)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "bridge_burn")
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    pub fn set_bridge_operator(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        operator: Option<String>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_bridge_operator");
+        match operator {
+            Some(operator) => {
+                let operator = deps.api.addr_validate(&operator)?;
+                self.bridge_operator.save(deps.storage, &operator)?;
+                Ok(resp.add_attribute("bridge_operator", operator))
+            }
+            None => {
+                self.bridge_operator.remove(deps.storage);
+                Ok(resp.add_attribute("bridge_operator", "none"))
+            }
+        }
+    }
+
+    /// Unlike `BridgeBurn`, the token stays here with its history while it is on the
+    /// other chain, held by the contract itself
+    pub fn bridge_lock(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        if !self.bridge_operator.exists(deps.storage) {
+            return Err(ContractError::NoBridgeOperator {});
+        }
+        let token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        self.assert_transferable(deps.storage, &env.block, &token_id, &token)?;
+
+        self.bridge_locks.save(deps.storage, &token_key(&token_id), &info.sender)?;
+        self._move_token(
+            deps.storage,
+            &env.block,
+            &token_id,
+            token,
+            env.contract.address,
+            HistoryAction::BridgeLock
+        )?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "bridge_lock")
+                .add_attribute("owner", info.sender)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    pub fn bridge_release(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        recipient: String
+    ) -> Result<Response<C>, ContractError> {
+        if self.bridge_operator.may_load(deps.storage)? != Some(info.sender) {
+            return Err(ContractError::NotBridgeOperator {});
+        }
+        let key = token_key(&token_id);
+        let original_owner = self.bridge_locks
+            .may_load(deps.storage, &key)?
+            .ok_or(ContractError::NotBridgeLocked {})?;
+        self.assert_address_prefix(deps.storage, &recipient)?;
+        let recipient = deps.api.addr_validate(&recipient)?;
+
+        self.bridge_locks.remove(deps.storage, &key);
+        let token = self.load_token(deps.storage, &token_id)?;
+        self._move_token(
+            deps.storage,
+            &env.block,
+            &token_id,
+            token,
+            recipient.clone(),
+            HistoryAction::BridgeRelease
+        )?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "bridge_release")
+                .add_attribute("original_owner", original_owner)
+                .add_attribute("recipient", recipient)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    fn assert_bridge(&self, storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+        match self.bridge(storage)? {
+            Some(bridge) if bridge == *sender => Ok(()),
+            _ => Err(ContractError::NotBridge {}),
+        }
+    }
+
+    /// Only the wrapped collection and the burn-to-mint source can send tokens here
+    pub fn receive_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        receive: Cw721ReceiveMsg
+    ) -> Result<Response<C>, ContractError> {
+        let wrapped_collection = self.wrapped_collection.may_load(deps.storage)?;
+        if wrapped_collection.map_or(false, |collection| collection == info.sender) {
+            return self.wrap(deps, env, info, receive);
+        }
+        match self.burn_to_mint.may_load(deps.storage)? {
+            Some(config) if config.source_collection == info.sender => {
+                self.trade_in(deps, env, config, receive)
+            }
+            _ => Err(ContractError::UnknownCollection {}),
+        }
+    }
+
+    /// Mints a wrapper for a token the wrapped collection just sent here. Like bridged
+    /// tokens, wrappers count towards the circulating supply but not towards `max_supply`
+    /// under the minted basis or any wallet allocation
+    fn wrap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        receive: Cw721ReceiveMsg
+    ) -> Result<Response<C>, ContractError> {
+        let owner = deps.api.addr_validate(&receive.sender)?;
+        let original: NftInfoResponse<T> = deps.querier.query_wasm_smart(
+            &info.sender,
+            &(Cw721QueryMsg::NftInfo { token_id: receive.token_id.clone() })
+        )?;
+
+        // the original id could run into minted or bridged ones, the wrapper's can't
+        let token_id = wrapper_token_id(&receive.token_id);
+        let token = TokenInfo {
+            owner,
+            approvals: vec![],
+            token_uri: original.token_uri,
+            extension: original.extension,
+            parent: None,
+            redeemed: false,
+            transfer_count: 0,
+            last_transferred_at: None,
+            minted_at: Some(env.block.time),
+            revealed: true,
+            user: None,
+            user_expires: None,
+            staked_since: None,
+            delegate: None,
+            delegate_expires: None,
+            accessories: vec![],
+            valid_until: None,
+            version: TOKEN_STORAGE_VERSION,
+        };
+        let token = self.store_new_token(deps.storage, &env.block, &token_id, token)?;
+        let token_count = self.token_count(deps.storage)? + 1;
+        self.save_token_count(deps.storage, env.block.height, token_count)?;
+        self.wrapped_tokens.save(deps.storage, &token_key(&token_id), &info.sender)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "wrap")
+                .add_attribute("owner", token.owner)
+                .add_attribute("token_id", token_id)
+                .add_attribute("wrapped_token_id", receive.token_id)
+        )
+    }
+
+    /// Counts a token the burn-to-mint source just sent here towards a new token for its
+    /// sender, minting once `ratio` tokens are in. No payment is due and the wallet limit
+    /// doesn't apply, `max_supply` does
+    fn trade_in(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        config: BurnToMintConfig,
+        receive: Cw721ReceiveMsg
+    ) -> Result<Response<C>, ContractError> {
+        let source = config.source_collection;
+        let token_id = receive.token_id;
+        if self.consumed_tokens.has(deps.storage, (&source, &token_id)) {
+            return Err(ContractError::TokenConsumed { token_id });
+        }
+        self.consumed_tokens.save(deps.storage, (&source, &token_id), &Empty {})?;
+        let owner = deps.api.addr_validate(&receive.sender)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "burn_to_mint")
+            .add_attribute("owner", &owner)
+            .add_attribute("source_token_id", &token_id);
+        if config.burn {
+            resp = resp.add_message(WasmMsg::Execute {
+                contract_addr: source.into_string(),
+                msg: to_json_binary(&(Cw721ExecuteMsg::Burn { token_id }))?,
+                funds: vec![],
+            });
+        }
+
+        let credits = self.burn_to_mint_credits.may_load(deps.storage, &owner)?.unwrap_or_default();
+        if credits + 1 < config.ratio {
+            self.burn_to_mint_credits.save(deps.storage, &owner, &(credits + 1))?;
+            return Ok(resp.add_attribute("credits", (credits + 1).to_string()));
+        }
+        self.burn_to_mint_credits.remove(deps.storage, &owner);
+        self.assert_supply_available(deps.storage, &env.block, 1)?;
+        let resp = resp.add_attribute("token_id", self.minted_total(deps.storage)?.to_string());
+        let extension = from_json(b"null")?;
+        self.count_wallet_mint(deps.storage, &owner)?;
+        self.mint_next(deps.storage, &env.block, resp, owner, config.token_uri, extension)
+    }
+
+    pub fn set_subscription(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        config: Option<SubscriptionConfig>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_subscription");
+        match config {
+            Some(config) => {
+                if config.period == 0 {
+                    return Err(ContractError::ZeroSubscriptionPeriod {});
+                }
+                self.subscription.save(deps.storage, &config)?;
+                Ok(resp.add_attribute("subscription_period", config.period.to_string()))
+            }
+            None => {
+                self.subscription.remove(deps.storage);
+                Ok(resp.add_attribute("subscription_period", "none"))
+            }
+        }
+    }
+
+    /// Renewing early adds the period to the current expiry, so no paid time is lost
+    pub fn renew(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let subscription = self.subscription
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoSubscription {})?;
+        if let Some(price) = &subscription.renewal_price {
+            let paid = info.funds
+                .iter()
+                .any(|coin| coin.denom == price.denom && coin.amount >= price.amount);
+            if !paid {
+                return Err(ContractError::NotEnoughFunds {});
+            }
+        }
+
+        let mut token = self.load_token(deps.storage, &token_id)?;
+        let key = token_key(&token_id);
+        let starts_at = match token.valid_until {
+            Some(valid_until) => {
+                self.subscription_expiries.remove(deps.storage, (valid_until.seconds(), &key));
+                valid_until.max(env.block.time)
+            }
+            None => env.block.time,
+        };
+        let valid_until = starts_at.plus_seconds(subscription.period);
+        token.valid_until = Some(valid_until);
+        self.tokens.save(deps.storage, &key, &token)?;
+        self.subscription_expiries.save(deps.storage, (valid_until.seconds(), &key), &Empty {})?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "renew")
+                .add_attribute("token_id", token_id)
+                .add_attribute("valid_until", valid_until.seconds().to_string())
+        )
+    }
+
+    pub fn reap_expired(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        limit: Option<u32>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let reap_after = self.subscription
+            .may_load(deps.storage)?
+            .and_then(|subscription| subscription.reap_after)
+            .ok_or(ContractError::NoSubscription {})?;
+        let Some(expired_before) = env.block.time.seconds().checked_sub(reap_after) else {
+            return Ok(Response::new().add_attribute("action", "reap_expired"));
+        };
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        // reaping doesn't undo nesting, staking, sales or bridge locks. Those passes are
+        // skipped but count against the limit, and the cursor moves past them so they can't
+        // hold up the ones behind them. Once a call reaches the end the next starts over
+        let cursor = self.reap_cursor.may_load(deps.storage)?;
+        let start = cursor.as_ref().map(|(expiry, key)| Bound::exclusive((*expiry, key.as_str())));
+        let end = Bound::exclusive((expired_before, ""));
+        let scanned = self.subscription_expiries
+            .keys(deps.storage, start, Some(end), Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let done = scanned.len() < limit;
+        match scanned.last() {
+            Some(last) if !done => self.reap_cursor.save(deps.storage, last)?,
+            _ => self.reap_cursor.remove(deps.storage),
+        }
+
+        let mut reaped = vec![];
+        for (_, key) in scanned {
+            let token_id = token_id_from_key(key);
+            let token = self.load_token(deps.storage, &token_id)?;
+            let reapable =
+                self.assert_burnable(deps.storage, &env.block, &token_id, &token).is_ok() &&
+                !self.bridge_locks.has(deps.storage, &token_key(&token_id));
+            if reapable {
+                reaped.push(token_id);
+            }
+        }
+        for token_id in &reaped {
+            self._burn_nft(deps.storage, &env.block, token_id)?;
+        }
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "reap_expired")
+                .add_attribute("count", reaped.len().to_string())
+                .add_attribute("done", done.to_string())
+                .add_attributes((!reaped.is_empty()).then(|| ("reaped", reaped.join(","))))
+        )
+    }
+
+    pub fn set_burn_to_mint(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        config: Option<BurnToMintConfig>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_burn_to_mint");
+        match config {
+            Some(config) => {
+                self.validate_burn_to_mint(deps.api, &config)?;
+                self.burn_to_mint.save(deps.storage, &config)?;
+                Ok(
+                    resp
+                        .add_attribute("burn_to_mint_source", config.source_collection)
+                        .add_attribute("burn_to_mint_ratio", config.ratio.to_string())
+                )
+            }
+            None => {
+                self.burn_to_mint.remove(deps.storage);
+                Ok(resp.add_attribute("burn_to_mint_source", "none"))
+            }
+        }
+    }
+
+    fn validate_burn_to_mint(
+        &self,
+        api: &dyn Api,
+        config: &BurnToMintConfig
+    ) -> Result<(), ContractError> {
+        api.addr_validate(config.source_collection.as_str())?;
+        if config.ratio == 0 {
+            return Err(ContractError::ZeroBurnToMintRatio {});
+        }
+        Ok(())
+    }
+
+    pub fn fund_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response<C>, ContractError> {
+        let denom = match self.reward_asset.may_load(deps.storage)? {
+            Some(RewardAsset::Native { denom }) => denom,
+            Some(RewardAsset::Cw20 { .. }) => {
+                return Err(ContractError::WrongRewardAsset {});
+            }
+            None => {
+                return Err(ContractError::NoRewardAsset {});
+            }
+        };
+        let amount = cw_utils::must_pay(&info, &denom)?;
+        self.add_rewards(deps.storage, &env.contract.address, amount)?;
+        // held back from withdrawals until claimed
+        self.escrow(deps.storage, &(Coin { denom, amount }))?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "fund_rewards")
+                .add_attribute("sender", info.sender)
+                .add_attribute("amount", amount)
+        )
+    }
+
+    /// Only the CW20 reward asset can be sent here, to fund rewards
+    pub fn receive_cw20(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        receive: Cw20ReceiveMsg
+    ) -> Result<Response<C>, ContractError> {
+        match self.reward_asset.may_load(deps.storage)? {
+            Some(RewardAsset::Cw20 { address }) if address == info.sender => {}
+            Some(_) => {
+                return Err(ContractError::WrongRewardAsset {});
+            }
+            None => {
+                return Err(ContractError::NoRewardAsset {});
+            }
+        }
+        let Cw20HookMsg::FundRewards {} = from_json(&receive.msg)?;
+        let sender = deps.api.addr_validate(&receive.sender)?;
+        self.add_rewards(deps.storage, &env.contract.address, receive.amount)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "fund_rewards")
+                .add_attribute("sender", sender)
+                .add_attribute("amount", receive.amount)
+        )
+    }
+
+    fn add_rewards(
+        &self,
+        storage: &mut dyn Storage,
+        contract: &Addr,
+        amount: Uint128
+    ) -> Result<(), ContractError> {
+        if self.rewarded_supply(storage, contract)? == 0 {
+            return Err(ContractError::NoTokensToReward {});
+        }
+        self.distribute_rewards(storage, contract, amount)?;
+        Ok(())
+    }
+
+    pub fn claim_rewards(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response<C>, ContractError> {
+        let Some(asset) = self.reward_asset.may_load(deps.storage)? else {
+            return Err(ContractError::NoRewardAsset {});
+        };
+        let amount = self.settle_rewards(deps.storage, &info.sender)?;
+        if amount.is_zero() {
+            return Err(ContractError::NoRewards {});
+        }
+        self.accrued_rewards.remove(deps.storage, &info.sender);
+
+        let payout: CosmosMsg<C> = match asset {
+            RewardAsset::Native { denom } => {
+                let funds = Coin { denom, amount };
+                self.release_escrow(deps.storage, &funds)?;
+                BankMsg::Send { to_address: info.sender.to_string(), amount: vec![funds] }.into()
+            }
+            RewardAsset::Cw20 { address } => {
+                let msg = Cw20ExecuteMsg::Transfer { recipient: info.sender.to_string(), amount };
+                WasmMsg::Execute {
+                    contract_addr: address.into_string(),
+                    msg: to_json_binary(&msg)?,
+                    funds: vec![],
+                }.into()
+            }
+        };
+
+        Ok(
+            Response::new()
+                .add_message(payout)
+                .add_attribute("action", "claim_rewards")
+                .add_attribute("recipient", info.sender)
+                .add_attribute("amount", amount)
+        )
+    }
+
+    pub fn set_reward_asset(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        asset: Option<RewardAsset>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        if !self.reward_per_token(deps.storage)?.is_zero() {
+            return Err(ContractError::RewardsFunded {});
+        }
+        let resp = Response::new().add_attribute("action", "set_reward_asset");
+        match asset {
+            Some(asset) => {
+                self.validate_reward_asset(deps.api, &asset)?;
+                self.reward_asset.save(deps.storage, &asset)?;
+                Ok(resp.add_attribute("reward_asset", reward_asset_to_string(&asset)))
+            }
+            None => {
+                self.reward_asset.remove(deps.storage);
+                Ok(resp.add_attribute("reward_asset", "none"))
+            }
+        }
+    }
+
+    fn validate_reward_asset(&self, api: &dyn Api, asset: &RewardAsset) -> StdResult<()> {
+        if let RewardAsset::Cw20 { address } = asset {
+            api.addr_validate(address.as_str())?;
+        }
+        Ok(())
+    }
+
+    pub fn unwrap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        let key = token_key(&token_id);
+        let collection = self.wrapped_tokens
+            .may_load(deps.storage, &key)?
+            .ok_or(ContractError::NotWrapped {})?;
+        self.wrapped_tokens.remove(deps.storage, &key);
+        self._burn_nft(deps.storage, &env.block, &token_id)?;
+
+        let release = WasmMsg::Execute {
+            contract_addr: collection.into_string(),
+            msg: to_json_binary(
+                &(Cw721ExecuteMsg::TransferNft {
+                    recipient: info.sender.to_string(),
+                    token_id: wrapped_original_id(&token_id),
+                })
+            )?,
+            funds: vec![],
+        };
+        Ok(
+            Response::new()
+                .add_message(release)
+                .add_attribute("action", "unwrap")
+                .add_attribute("owner", info.sender)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    pub fn set_user(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        user: Option<String>,
+        expires: Expiration
+    ) -> Result<Response<C>, ContractError> {
+        let mut token = self.load_token(deps.storage, &token_id)?;
+        self.check_can_send(deps.storage, &deps.querier, &env, &info, &token_id, &token)?;
+        let user = user.map(|user| deps.api.addr_validate(&user)).transpose()?;
+        if user.is_some() && expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        let mut resp = Response::new()
+            .add_attribute("action", "set_user")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", &token_id);
+        if let Some(user) = &user {
+            resp = resp
+                .add_attribute("user", user)
+                .add_attribute("expires", expiration_to_string(&expires));
+        }
+        token.user_expires = user.as_ref().map(|_| expires);
+        token.user = user;
+        self.tokens.save(deps.storage, &token_key(&token_id), &token)?;
+
+        Ok(resp)
+    }
+
+    pub fn delegate(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        delegate: String,
+        expires: Option<Expiration>
+    ) -> Result<Response<C>, ContractError> {
+        let mut token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        let delegate = deps.api.addr_validate(&delegate)?;
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        self.clear_delegate(deps.storage, &token_id, &mut token);
+        self.delegated_tokens.save(deps.storage, (&delegate, &token_key(&token_id)), &expires)?;
+        token.delegate = Some(delegate.clone());
+        token.delegate_expires = Some(expires);
+        self.tokens.save(deps.storage, &token_key(&token_id), &token)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "delegate")
+                .add_attribute("owner", info.sender)
+                .add_attribute("token_id", token_id)
+                .add_attribute("delegate", delegate)
+                .add_attribute("expires", expiration_to_string(&expires))
+        )
+    }
+
+    pub fn undelegate(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let mut token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        if token.delegate.is_none() {
+            return Err(ContractError::NotDelegated {});
+        }
+        self.clear_delegate(deps.storage, &token_id, &mut token);
+        self.tokens.save(deps.storage, &token_key(&token_id), &token)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "undelegate")
+                .add_attribute("owner", info.sender)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    /// Drops the delegate of the token along with its reverse index entry, saving the token
+    /// is left to the caller
+    fn clear_delegate(&self, storage: &mut dyn Storage, token_id: &str, token: &mut TokenInfo<T>) {
+        if let Some(delegate) = token.delegate.take() {
+            self.delegated_tokens.remove(storage, (&delegate, &token_key(token_id)));
+        }
+        token.delegate_expires = None;
+    }
+
+    pub fn stake(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let mut token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        self.assert_transferable(deps.storage, &env.block, &token_id, &token)?;
+
+        token.staked_since = Some(env.block.time);
+        self.tokens.save(deps.storage, &token_key(&token_id), &token)?;
+        self.staked_tokens.save(
+            deps.storage,
+            (&token.owner, &token_key(&token_id)),
+            &env.block.time
+        )?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "stake")
+                .add_attribute("owner", info.sender)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    pub fn unstake(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let mut token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        let staked_since = token.staked_since.ok_or(ContractError::NotStaked {})?;
+        let unlocks_at = staked_since.plus_seconds(self.min_stake_period(deps.storage)?);
+        if env.block.time < unlocks_at {
+            return Err(ContractError::StakeLocked { unlocks_at });
+        }
+
+        token.staked_since = None;
+        self.tokens.save(deps.storage, &token_key(&token_id), &token)?;
+        self.staked_tokens.remove(deps.storage, (&token.owner, &token_key(&token_id)));
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "unstake")
+                .add_attribute("owner", info.sender)
+                .add_attribute("token_id", token_id)
+                .add_attribute(
+                    "staked_for",
+                    (env.block.time.seconds() - staked_since.seconds()).to_string()
+                )
+        )
+    }
+
+    pub fn set_min_stake_period(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        period: u64
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        self.min_stake_period.save(deps.storage, &period)?;
+        Ok(
+            Response::new()
+                .add_attribute("action", "set_min_stake_period")
+                .add_attribute("period", period.to_string())
+        )
+    }
+
+    pub fn list_token(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        price: Coin,
+        expires: Option<Expiration>
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        if token.parent.is_some() {
+            return Err(ContractError::TokenNested {});
+        }
+        if token.redeemed {
+            return Err(ContractError::TokenRedeemed {});
+        }
+        if token.staked_since.is_some() {
+            return Err(ContractError::TokenStaked {});
+        }
+        if self.auctions.has(deps.storage, &token_key(&token_id)) {
+            return Err(ContractError::TokenInAuction {});
+        }
+        if self.active_bundle(deps.storage, &env.block, &token_id)?.is_some() {
+            return Err(ContractError::TokenBundled {});
+        }
+        if price.amount.is_zero() {
+            return Err(ContractError::ZeroListingPrice {});
+        }
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        self.listings.save(deps.storage, &token_key(&token_id), &(Listing {
+            seller: info.sender.clone(),
+            price: price.clone(),
+            expires,
+        }))?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "list_token")
+                .add_attribute("seller", info.sender)
+                .add_attribute("token_id", token_id)
+                .add_attribute("price", price.to_string())
+                .add_attribute("expires", expiration_to_string(&expires))
+        )
+    }
+
+    pub fn delist(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        let key = token_key(&token_id);
+        if !self.listings.has(deps.storage, &key) {
+            return Err(ContractError::NotListed {});
+        }
+        self.listings.remove(deps.storage, &key);
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "delist")
+                .add_attribute("sender", info.sender)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    /// Pays the seller and the royalty out of the attached funds and moves the token to the
+    /// buyer, skipping the usual transfer permissions since the seller agreed by listing
+    pub fn buy(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let key = token_key(&token_id);
+        let listing = self.listings
+            .may_load(deps.storage, &key)?
+            .ok_or(ContractError::NotListed {})?;
+        if listing.expires.is_expired(&env.block) {
+            return Err(ContractError::ListingExpired {});
+        }
+        let price = listing.price;
+        let paid = info.funds
+            .iter()
+            .find(|coin| coin.denom == price.denom)
+            .map_or(Uint128::zero(), |coin| coin.amount);
+        if paid < price.amount {
+            return Err(ContractError::NotEnoughFunds {});
+        }
+
+        self.listings.remove(deps.storage, &key);
+        let token = self.load_token(deps.storage, &token_id)?;
+        let token = self._move_token(
+            deps.storage,
+            &env.block,
+            &token_id,
+            token,
+            info.sender.clone(),
+            HistoryAction::Sale
+        )?;
+        self.record_sale(deps.storage, &env.block, &token_id, &info.sender, &price)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "buy")
+            .add_attribute("buyer", info.sender.clone())
+            .add_attribute("seller", listing.seller.clone())
+            .add_attribute("token_id", token_id)
+            .add_attribute("price", price.to_string());
+        let resp = self.pay_sale(deps.storage, resp, &listing.seller, &price)?;
+        Ok(refund_excess(resp, &token.owner, info.funds, &price))
+    }
+
+    pub fn list_bundle(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+        price: Coin,
+        expires: Option<Expiration>
+    ) -> Result<Response<C>, ContractError> {
+        if token_ids.is_empty() || token_ids.len() > (MAX_BUNDLE_SIZE as usize) {
+            return Err(ContractError::InvalidBundleSize { max: MAX_BUNDLE_SIZE });
+        }
+        if price.amount.is_zero() {
+            return Err(ContractError::ZeroListingPrice {});
+        }
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        let bundle_id = self.bundle_count.may_load(deps.storage)?.unwrap_or_default();
+        for (i, token_id) in token_ids.iter().enumerate() {
+            if token_ids[..i].contains(token_id) {
+                return Err(ContractError::DuplicateBundleToken { token_id: token_id.clone() });
+            }
+            let token = self.load_token(deps.storage, token_id)?;
+            if token.owner != info.sender {
+                return Err(ContractError::Ownership(OwnershipError::NotOwner));
+            }
+            self.assert_transferable(deps.storage, &env.block, token_id, &token)?;
+            self.bundled_tokens.save(deps.storage, &token_key(token_id), &bundle_id)?;
+        }
+        self.bundle_count.save(deps.storage, &(bundle_id + 1))?;
+        self.bundles.save(deps.storage, bundle_id, &(Bundle {
+            seller: info.sender.clone(),
+            token_ids: token_ids.clone(),
+            price: price.clone(),
+            expires,
+        }))?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "list_bundle")
+                .add_attribute("seller", info.sender)
+                .add_attribute("bundle_id", bundle_id.to_string())
+                .add_attribute("token_ids", token_ids.join(","))
+                .add_attribute("price", price.to_string())
+                .add_attribute("expires", expiration_to_string(&expires))
+        )
+    }
+
+    /// Works whatever happened to the tokens since listing, so a bundle that can no longer be
+    /// bought can always be cleared
+    pub fn delist_bundle(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        bundle_id: u64
+    ) -> Result<Response<C>, ContractError> {
+        let bundle = self.bundles
+            .may_load(deps.storage, bundle_id)?
+            .ok_or(ContractError::NoBundle {})?;
+        if bundle.seller != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        self.remove_bundle(deps.storage, bundle_id, &bundle)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "delist_bundle")
+                .add_attribute("sender", info.sender)
+                .add_attribute("bundle_id", bundle_id.to_string())
+        )
+    }
+
+    /// Moves every token of the bundle to the buyer and pays the seller once for all of
+    /// them. Fails if any token was burned or left the seller since listing
+    pub fn buy_bundle(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        bundle_id: u64
+    ) -> Result<Response<C>, ContractError> {
+        let bundle = self.bundles
+            .may_load(deps.storage, bundle_id)?
+            .ok_or(ContractError::NoBundle {})?;
+        let mut tokens = Vec::with_capacity(bundle.token_ids.len());
+        for token_id in &bundle.token_ids {
+            let locked = self.bundled_tokens.may_load(deps.storage, &token_key(token_id))?;
+            let token = self
+                .may_load_token(deps.storage, token_id)?
+                .filter(|token| token.owner == bundle.seller && locked == Some(bundle_id))
+                .ok_or_else(|| ContractError::BundleTokenUnavailable {
+                    token_id: token_id.clone(),
+                })?;
+            tokens.push(token);
+        }
+        if bundle.expires.is_expired(&env.block) {
+            return Err(ContractError::BundleExpired {});
+        }
+        let price = &bundle.price;
+        let paid = info.funds
+            .iter()
+            .find(|coin| coin.denom == price.denom)
+            .map_or(Uint128::zero(), |coin| coin.amount);
+        if paid < price.amount {
+            return Err(ContractError::NotEnoughFunds {});
+        }
+
+        self.remove_bundle(deps.storage, bundle_id, &bundle)?;
+        for (token_id, token) in bundle.token_ids.iter().zip(tokens) {
+            self._move_token(
+                deps.storage,
+                &env.block,
+                token_id,
+                token,
+                info.sender.clone(),
+                HistoryAction::Sale
+            )?;
+        }
+
+        // a bundle price says nothing about its tokens, so only the stats count it
+        self.record_sale_volume(deps.storage, price)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "buy_bundle")
+            .add_attribute("buyer", info.sender.clone())
+            .add_attribute("seller", bundle.seller.clone())
+            .add_attribute("bundle_id", bundle_id.to_string())
+            .add_attribute("price", price.to_string());
+        let resp = self.pay_sale(deps.storage, resp, &bundle.seller, price)?;
+        Ok(refund_excess(resp, &info.sender, info.funds, price))
+    }
+
+    /// Drops the bundle and unlocks the tokens still locked for it
+    fn remove_bundle(
+        &self,
+        storage: &mut dyn Storage,
+        bundle_id: u64,
+        bundle: &Bundle
+    ) -> StdResult<()> {
+        self.bundles.remove(storage, bundle_id);
+        for token_id in &bundle.token_ids {
+            let key = token_key(token_id);
+            if self.bundled_tokens.may_load(storage, &key)? == Some(bundle_id) {
+                self.bundled_tokens.remove(storage, &key);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn create_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        min_bid: Coin,
+        duration: u64
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        self.assert_transferable(deps.storage, &env.block, &token_id, &token)?;
+        if duration == 0 {
+            return Err(ContractError::ZeroAuctionDuration {});
+        }
+
+        let end_time = env.block.time.plus_seconds(duration);
+        self.auctions.save(deps.storage, &token_key(&token_id), &(Auction {
+            seller: info.sender.clone(),
+            min_bid: min_bid.clone(),
+            end_time,
+            highest_bid: None,
+        }))?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "create_auction")
+                .add_attribute("seller", info.sender)
+                .add_attribute("token_id", token_id)
+                .add_attribute("min_bid", min_bid.to_string())
+                .add_attribute("end_time", end_time.seconds().to_string())
+        )
+    }
+
+    pub fn place_bid(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let key = token_key(&token_id);
+        let mut auction = self.auctions
+            .may_load(deps.storage, &key)?
+            .ok_or(ContractError::NoAuction {})?;
+        if auction.is_ended(&env.block) {
+            return Err(ContractError::AuctionEnded {});
+        }
+        if auction.seller == info.sender {
+            return Err(ContractError::SellerCannotBid {});
+        }
+        let denom = auction.min_bid.denom.clone();
+        let amount = cw_utils::must_pay(&info, &denom)?;
+        let min_bid = match &auction.highest_bid {
+            Some(highest) => highest.amount + Uint128::one(),
+            None => auction.min_bid.amount,
+        };
+        if amount < min_bid {
+            return Err(ContractError::BidTooLow { min_bid: Coin { denom, amount: min_bid } });
+        }
+
+        let mut resp = Response::new()
+            .add_attribute("action", "place_bid")
+            .add_attribute("bidder", info.sender.clone())
+            .add_attribute("token_id", token_id)
+            .add_attribute("amount", amount.to_string());
+        self.escrow(deps.storage, &(Coin { denom: denom.clone(), amount }))?;
+        if let Some(outbid) = auction.highest_bid.take() {
+            let refund = Coin { denom: denom.clone(), amount: outbid.amount };
+            self.release_escrow(deps.storage, &refund)?;
+            resp = resp.add_message(BankMsg::Send {
+                to_address: outbid.bidder.to_string(),
+                amount: vec![refund],
+            });
+        }
+
+        // a bid just before the end leaves the others time to answer
+        let extended_end = env.block.time.plus_seconds(self.auction_extension(deps.storage)?);
+        if extended_end > auction.end_time {
+            auction.end_time = extended_end;
+            resp = resp.add_attribute("end_time", extended_end.seconds().to_string());
+        }
+        auction.highest_bid = Some(Bid { bidder: info.sender, amount });
+        self.auctions.save(deps.storage, &key, &auction)?;
+
+        Ok(resp)
+    }
+
+    pub fn settle_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let key = token_key(&token_id);
+        let auction = self.auctions
+            .may_load(deps.storage, &key)?
+            .ok_or(ContractError::NoAuction {})?;
+        if !auction.is_ended(&env.block) {
+            return Err(ContractError::AuctionNotEnded {});
+        }
+        self.auctions.remove(deps.storage, &key);
+
+        let resp = Response::new()
+            .add_attribute("action", "settle_auction")
+            .add_attribute("seller", auction.seller.clone())
+            .add_attribute("token_id", &token_id);
+        let Some(winner) = auction.highest_bid else {
+            return Ok(resp.add_attribute("winner", "none"));
+        };
+
+        let price = Coin { denom: auction.min_bid.denom, amount: winner.amount };
+        self.release_escrow(deps.storage, &price)?;
+        let token = self.load_token(deps.storage, &token_id)?;
+        self._move_token(
+            deps.storage,
+            &env.block,
+            &token_id,
+            token,
+            winner.bidder.clone(),
+            HistoryAction::Sale
+        )?;
+        self.record_sale(deps.storage, &env.block, &token_id, &winner.bidder, &price)?;
+
+        let resp = resp
+            .add_attribute("winner", winner.bidder)
+            .add_attribute("price", price.to_string());
+        self.pay_sale(deps.storage, resp, &auction.seller, &price)
+    }
+
+    pub fn make_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        expires: Option<Expiration>
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        if token.owner == info.sender {
+            return Err(ContractError::OwnerCannotOffer {});
+        }
+        let amount = cw_utils::one_coin(&info)?;
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        let mut resp = Response::new()
+            .add_attribute("action", "make_offer")
+            .add_attribute("bidder", info.sender.clone())
+            .add_attribute("token_id", &token_id)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("expires", expiration_to_string(&expires));
+        if let Some(previous) = self.offers.may_load(deps.storage, (&token_id, &info.sender))? {
+            self.release_escrow(deps.storage, &previous.amount)?;
+            resp = resp.add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![previous.amount],
+            });
+        }
+        self.escrow(deps.storage, &amount)?;
+        self.save_offer(deps.storage, &token_id, &info.sender, &(Offer { amount, expires }))?;
+
+        Ok(resp)
+    }
+
+    pub fn withdraw_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        bidder: Option<String>
+    ) -> Result<Response<C>, ContractError> {
+        let bidder = match bidder {
+            Some(bidder) => deps.api.addr_validate(&bidder)?,
+            None => info.sender.clone(),
+        };
+        let offer = self.offers
+            .may_load(deps.storage, (&token_id, &bidder))?
+            .ok_or_else(|| ContractError::NoOffer { bidder: bidder.to_string() })?;
+        if bidder != info.sender && !offer.expires.is_expired(&env.block) {
+            return Err(ContractError::OfferNotExpired {});
+        }
+
+        self.remove_offer(deps.storage, &token_id, &bidder);
+        self.release_escrow(deps.storage, &offer.amount)?;
+
+        Ok(
+            Response::new()
+                .add_message(BankMsg::Send {
+                    to_address: bidder.to_string(),
+                    amount: vec![offer.amount],
+                })
+                .add_attribute("action", "withdraw_offer")
+                .add_attribute("sender", info.sender)
+                .add_attribute("bidder", bidder)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    /// Only the owner at the time of the call can accept, so an offer can't be taken by a
+    /// previous owner whose transfer landed first
+    pub fn accept_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        bidder: String
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        self.assert_transferable(deps.storage, &env.block, &token_id, &token)?;
+        let bidder = deps.api.addr_validate(&bidder)?;
+        let offer = self.offers
+            .may_load(deps.storage, (&token_id, &bidder))?
+            .ok_or_else(|| ContractError::NoOffer { bidder: bidder.to_string() })?;
+        if offer.expires.is_expired(&env.block) {
+            return Err(ContractError::OfferExpired {});
+        }
+
+        self.remove_offer(deps.storage, &token_id, &bidder);
+        self.release_escrow(deps.storage, &offer.amount)?;
+        self._move_token(
+            deps.storage,
+            &env.block,
+            &token_id,
+            token,
+            bidder.clone(),
+            HistoryAction::Sale
+        )?;
+        self.record_sale(deps.storage, &env.block, &token_id, &bidder, &offer.amount)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "accept_offer")
+            .add_attribute("seller", info.sender.clone())
+            .add_attribute("bidder", bidder)
+            .add_attribute("token_id", token_id)
+            .add_attribute("price", offer.amount.to_string());
+        self.pay_sale(deps.storage, resp, &info.sender, &offer.amount)
+    }
+
+    /// Lets an allowlisted marketplace record a sale it settled itself, after the token
+    /// was moved to the buyer
+    pub fn report_sale(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        price: Coin
+    ) -> Result<Response<C>, ContractError> {
+        if !self.operator_allowlist.has(deps.storage, &info.sender) {
+            return Err(ContractError::OperatorNotAllowed { operator: info.sender.to_string() });
+        }
+        let token = self.load_token(deps.storage, &token_id)?;
+        self.record_sale(deps.storage, &env.block, &token_id, &token.owner, &price)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "report_sale")
+                .add_attribute("sender", info.sender)
+                .add_attribute("token_id", token_id)
+                .add_attribute("buyer", token.owner)
+                .add_attribute("price", price.to_string())
+        )
+    }
+
+    /// Splits the price of a sale between the seller and the royalty payment address
+    fn pay_sale(
+        &self,
+        storage: &dyn Storage,
+        mut resp: Response<C>,
+        seller: &Addr,
+        price: &Coin
+    ) -> Result<Response<C>, ContractError> {
+        let royalty = self.royalty.may_load(storage)?;
+        let royalty_amount = royalty
+            .as_ref()
+            .map_or(Uint128::zero(), |royalty| royalty.amount(price));
+        let proceeds = price.amount - royalty_amount;
+
+        resp = resp.add_attribute("royalty", royalty_amount.to_string());
+        if !proceeds.is_zero() {
+            resp = resp.add_message(BankMsg::Send {
+                to_address: seller.to_string(),
+                amount: vec![Coin { denom: price.denom.clone(), amount: proceeds }],
+            });
+        }
+        if let Some(royalty) = royalty.filter(|_| !royalty_amount.is_zero()) {
+            resp = resp.add_message(BankMsg::Send {
+                to_address: royalty.payment_address,
+                amount: vec![Coin { denom: price.denom.clone(), amount: royalty_amount }],
+            });
+        }
+        Ok(resp)
+    }
+
+    /// Bids held for running auctions can't be withdrawn
+    fn assert_not_escrowed(
+        &self,
+        deps: Deps,
+        env: &Env,
+        amount: &Coin
+    ) -> Result<(), ContractError> {
+        let escrowed = self.escrowed_bids(deps.storage, &amount.denom)?;
+        if escrowed.is_zero() {
+            return Ok(());
+        }
+        let balance = deps.querier.query_balance(&env.contract.address, &amount.denom)?;
+        let available = balance.amount.saturating_sub(escrowed);
+        if amount.amount > available {
+            return Err(ContractError::FundsInEscrow {
+                available: Coin { denom: amount.denom.clone(), amount: available },
+            });
+        }
+        Ok(())
+    }
+
+    pub fn set_royalty(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        royalty: Option<Royalty>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_royalty");
+        match royalty {
+            Some(royalty) => {
+                self.validate_royalty(deps.api, &royalty)?;
+                self.royalty.save(deps.storage, &royalty)?;
+                Ok(
+                    resp
+                        .add_attribute("royalty_payment_address", &royalty.payment_address)
+                        .add_attribute("royalty_share", royalty.share.to_string())
+                )
+            }
+            None => {
+                self.royalty.remove(deps.storage);
+                Ok(resp.add_attribute("royalty_share", "0"))
+            }
+        }
+    }
+
+    fn validate_royalty(&self, api: &dyn Api, royalty: &Royalty) -> Result<(), ContractError> {
+        api.addr_validate(&royalty.payment_address)?;
+        if royalty.share > Decimal::one() {
+            return Err(ContractError::InvalidRoyaltyShare {});
+        }
+        Ok(())
+    }
+
+    pub fn burn_all(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        start_after: Option<String>,
+        limit: Option<u32>
+    ) -> Result<Response<C>, ContractError> {
+        let limit = limit.unwrap_or(MAX_BURN_ALL_LIMIT).clamp(1, MAX_BURN_ALL_LIMIT) as usize;
+
+        // only tokens owned by the sender are ever selected, so operators can't use this.
+        // Tokens that can't be burned are passed over but still count against the limit,
+        // so a wallet full of them can't make a call run out of gas
+        let start = start_after.map(|token_id| Bound::ExclusiveRaw(token_key(&token_id).into()));
+        let scanned = self.tokens.idx.owner
+            .prefix(info.sender.clone())
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let done = scanned.len() < limit;
+        let next_start_after = scanned.last().map(|(key, _)| token_id_from_key(key.clone()));
+
+        let mut token_ids = vec![];
+        for (key, token) in scanned {
+            let token_id = token_id_from_key(key);
+            if self.assert_burnable(deps.storage, &env.block, &token_id, &token).is_ok() {
+                token_ids.push(token_id);
+            }
+        }
+        for token_id in &token_ids {
+            self._burn_nft(deps.storage, &env.block, token_id)?;
+        }
+
+        Ok(
+            Response::new()
+                .set_data(to_json_binary(&(TokensResponse { tokens: token_ids.clone() }))?)
+                .add_attribute("action", "burn_all")
+                .add_attribute("sender", info.sender)
+                .add_attribute("count", token_ids.len().to_string())
+                .add_attribute("done", done.to_string())
+                .add_attributes(
+                    next_start_after.filter(|_| !done).map(|id| ("next_start_after", id))
+                )
+                // attribute values can't be empty, so the ids are left out when none burned
+                .add_attributes(
+                    (!token_ids.is_empty()).then(|| ("token_ids", token_ids.join(",")))
+                )
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn burn_and_notify(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        contract: String,
+        msg: Binary,
+        rollback_on_error: bool
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        self.check_can_burn(deps.storage, &env, &info, &token_id, &token)?;
+        deps.api.addr_validate(&contract)?;
+
+        self._burn_nft(deps.storage, &env.block, &token_id)?;
+
+        let notify = Cw721BurnReceiveMsg {
+            sender: info.sender.to_string(),
+            token_id: token_id.clone(),
+            msg,
+        }.into_cosmos_msg(contract.clone())?;
+        // a plain submessage aborts the whole transaction (and so the burn) on failure
+        let notify_msg = if rollback_on_error {
+            SubMsg::new(notify)
+        } else {
+            SubMsg::reply_on_error(notify, BURN_NOTIFY_REPLY_ID)
+        };
+
+        Ok(
+            Response::new()
+                .add_submessage(notify_msg)
+                .add_attribute("action", "burn_and_notify")
+                .add_attribute("sender", info.sender)
+                .add_attribute("contract", contract)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    pub fn approve_multiple(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spenders: Vec<(String, Option<Expiration>)>,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        // validate the whole batch before writing anything
+        let token = self.load_token(deps.storage, &token_id)?;
+        let mut seen: Vec<Addr> = vec![];
+        for (spender, expires) in &spenders {
+            let spender_addr = deps.api.addr_validate(spender)?;
+            self.check_can_approve(deps.as_ref(), &env, &info, &token, Some(&spender_addr))?;
+            if seen.contains(&spender_addr) {
+                return Err(ContractError::DuplicateSpender { spender: spender.clone() });
+            }
+            self.resolve_expiration(deps.storage, &env, *expires)?;
+            seen.push(spender_addr);
+        }
+        let max = self.max_approvals_per_token(deps.storage)?;
+        let kept = self
+            .approvals_of(deps.storage, &token_id)?
+            .iter()
+            .filter(|apr| !seen.contains(&apr.spender))
+            .count();
+        if ((kept + seen.len()) as u64) > max {
+            return Err(ContractError::TooManyApprovals { max });
+        }
+
+        let mut res = Response::new()
+            .add_attribute("action", "approve_multiple")
+            .add_attribute("sender", info.sender.clone())
+            .add_attribute("token_id", token_id.clone());
+        for (spender, expires) in spenders {
+            self._update_approvals(deps.branch(), &env, &info, &spender, &token_id, true, expires)?;
+            res = res.add_attribute("spender", spender);
+        }
+
+        Ok(res)
+    }
+
+    pub fn revoke_all_token_approvals(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        self.check_can_approve(deps.as_ref(), &env, &info, &token, None)?;
+
+        let spenders = self._clear_approvals(deps.storage, &token_id)?;
+
+        let mut res = Response::new()
+            .add_attribute("action", "revoke_all_token_approvals")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("count", spenders.len().to_string());
+        for spender in spenders {
+            res = res.add_attribute("spender", spender);
+        }
+
+        Ok(res)
+    }
+
+    pub fn approve_with_permit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        permit: ApprovalPermit
+    ) -> Result<Response<C>, ContractError> {
+        let ApprovalPermit { params, pubkey, signature } = permit;
+        if params.expires.is_expired(&env.block) {
+            return Err(ContractError::PermitExpired {});
+        }
+
+        // the signature must be valid and made by the owner's key
+        let owner = deps.api.addr_validate(&params.owner)?;
+        let hash = Sha256::digest(to_json_vec(&params)?);
+        let valid = deps.api.secp256k1_verify(&hash, &signature, &pubkey).unwrap_or(false);
+        if !valid || pubkey_to_address(&pubkey, owner.as_str()) != Some(owner.to_string()) {
+            return Err(ContractError::InvalidPermitSignature {});
+        }
+
+        if self.used_permit_nonces.has(deps.storage, (&owner, params.nonce)) {
+            return Err(ContractError::PermitReplayed { nonce: params.nonce });
+        }
+        self.used_permit_nonces.save(deps.storage, (&owner, params.nonce), &Empty {})?;
+
+        // from here on this is the owner approving
+        let info = MessageInfo { sender: owner, funds: vec![] };
+        let res = match params.token_id {
+            Some(token_id) =>
+                self.approve(deps, env, info, params.spender, token_id, Some(params.expires))?,
+            None =>
+                self._approve_all(
+                    deps,
+                    env,
+                    info,
+                    params.spender,
+                    Some(params.expires),
+                    None,
+                    None
+                )?,
+        };
+
+        Ok(res.add_attribute("permit_nonce", params.nonce.to_string()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn approve_and_send(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+        msg: Binary,
+        expires: Option<Expiration>
+    ) -> Result<Response<C>, ContractError> {
+        // a failed send restores the approvals from before this message
+        let approvals = self.approvals_of(deps.storage, &token_id)?;
+        self._update_approvals(deps.branch(), &env, &info, &contract, &token_id, true, expires)?;
+
+        let res = self._send_nft(deps, env, info, contract, token_id, msg, approvals)?;
+        Ok(res.add_attribute("approved", "true"))
+    }
+
+    pub fn redeem(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        redemption_data: Option<String>
+    ) -> Result<Response<C>, ContractError> {
+        let mut token = self.load_token(deps.storage, &token_id)?;
+        if token.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        if token.redeemed {
+            return Err(ContractError::TokenRedeemed {});
+        }
+        self.assert_not_for_sale(deps.storage, &env.block, &token_id)?;
+
+        token.redeemed = true;
+        self._clear_approvals(deps.storage, &token_id)?;
+        self.tokens.save(deps.storage, &token_key(&token_id), &token)?;
+        self.redemptions.save(deps.storage, &token_id, &(Redemption {
+            redeemer: info.sender.clone(),
+            redemption_data,
+            status: RedemptionStatus::Pending,
+            redeemed_at: env.block.time,
+            fulfilled_at: None,
+        }))?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "redeem")
+                .add_attribute("sender", info.sender)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    pub fn fulfill_redemption(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        let mut redemption = self.redemptions.load(deps.storage, &token_id)?;
+        if redemption.status == RedemptionStatus::Fulfilled {
+            return Err(ContractError::RedemptionFulfilled {});
+        }
+        redemption.status = RedemptionStatus::Fulfilled;
+        redemption.fulfilled_at = Some(env.block.time);
+        self.redemptions.save(deps.storage, &token_id, &redemption)?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "fulfill_redemption")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id);
+        if let Some(payout) = self.redemption_payout.may_load(deps.storage)? {
+            resp = resp
+                .add_message(BankMsg::Send {
+                    to_address: redemption.redeemer.to_string(),
+                    amount: vec![payout.clone()],
+                })
+                .add_attribute("payout", payout.to_string());
+        }
+
+        Ok(resp)
+    }
+
+    pub fn nest_token(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        child_id: String,
+        parent_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let mut child = self.load_token(deps.storage, &child_id)?;
+        if child.parent.is_some() {
+            return Err(ContractError::TokenNested {});
+        }
+        if child.staked_since.is_some() {
+            return Err(ContractError::TokenStaked {});
+        }
+        self.assert_not_for_sale(deps.storage, &env.block, &child_id)?;
+        self.check_can_send(deps.storage, &deps.querier, &env, &info, &child_id, &child)?;
+
+        // walk up from the new parent, meeting the child on the way means a cycle
+        let mut depth = 1;
+        let mut current_id = parent_id.clone();
+        let parent_root = loop {
+            if current_id == child_id {
+                return Err(ContractError::NestingCycle {});
+            }
+            let current = self.load_token(deps.storage, &current_id)?;
+            match current.parent {
+                Some(ref next) => {
+                    current_id = next.clone();
+                    depth += 1;
+                }
+                None => {
+                    break current;
+                }
+            }
+        };
+        if depth + self.nested_height(deps.storage, &child_id, MAX_NEST_DEPTH)? > MAX_NEST_DEPTH {
+            return Err(ContractError::MaxNestingDepth { max: MAX_NEST_DEPTH });
+        }
+        self.check_can_send(deps.storage, &deps.querier, &env, &info, &current_id, &parent_root)?;
+
+        child.parent = Some(parent_id.clone());
+        self._clear_approvals(deps.storage, &child_id)?;
+        self.tokens.save(deps.storage, &token_key(&child_id), &child)?;
+        self.token_children.save(deps.storage, (&parent_id, &child_id), &Empty {})?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "nest_token")
+                .add_attribute("sender", info.sender)
+                .add_attribute("child_id", child_id)
+                .add_attribute("parent_id", parent_id)
+        )
+    }
+
+    pub fn unnest_token(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        child_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let mut child = self.load_token(deps.storage, &child_id)?;
+        let parent_id = child.parent.clone().ok_or(ContractError::NotNested {})?;
+        let (root_id, root) = self.root_token(deps.storage, &child_id, child.clone())?;
+        self.check_can_send(deps.storage, &deps.querier, &env, &info, &root_id, &root)?;
+
+        // the child goes to whoever owns the tree now
+        self.move_holding(deps.storage, &env.block, &child.owner, &root.owner, &child_id)?;
+        child.owner = root.owner;
+        child.parent = None;
+        self.tokens.save(deps.storage, &token_key(&child_id), &child)?;
+        self.token_children.remove(deps.storage, (&parent_id, &child_id));
+        // accessories can be taken off like any nested token
+        let mut parent = self.load_token(deps.storage, &parent_id)?;
+        if parent.accessories.contains(&child_id) {
+            parent.accessories.retain(|accessory_id| *accessory_id != child_id);
+            self.tokens.save(deps.storage, &token_key(&parent_id), &parent)?;
+        }
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "unnest_token")
+                .add_attribute("sender", info.sender)
+                .add_attribute("child_id", child_id)
+                .add_attribute("parent_id", parent_id)
+                .add_attribute("owner", child.owner)
+        )
+    }
+
+    /// Accessories are nested one level deep only, so they can't form cycles or end up on
+    /// two tokens
+    pub fn attach(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        parent_id: String,
+        accessory_id: String
+    ) -> Result<Response<C>, ContractError> {
+        if parent_id == accessory_id {
+            return Err(ContractError::NestingCycle {});
+        }
+        let mut parent = self.load_token(deps.storage, &parent_id)?;
+        let mut accessory = self.load_token(deps.storage, &accessory_id)?;
+        if parent.owner != info.sender || accessory.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+        if parent.parent.is_some() {
+            return Err(ContractError::TokenNested {});
+        }
+        self.assert_transferable(deps.storage, &env.block, &accessory_id, &accessory)?;
+        if self.has_children(deps.storage, &accessory_id) {
+            return Err(ContractError::TokenHasChildren {});
+        }
+
+        accessory.parent = Some(parent_id.clone());
+        self._clear_approvals(deps.storage, &accessory_id)?;
+        self.tokens.save(deps.storage, &token_key(&accessory_id), &accessory)?;
+        self.token_children.save(deps.storage, (&parent_id, &accessory_id), &Empty {})?;
+        parent.accessories.push(accessory_id.clone());
+        self.tokens.save(deps.storage, &token_key(&parent_id), &parent)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "attach")
+                .add_attribute("owner", info.sender)
+                .add_attribute("parent_id", parent_id)
+                .add_attribute("accessory_id", accessory_id)
+        )
+    }
+
+    pub fn detach(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        parent_id: String,
+        accessory_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let mut parent = self.load_token(deps.storage, &parent_id)?;
+        if !parent.accessories.contains(&accessory_id) {
+            return Err(ContractError::NotAttached {});
+        }
+        let (_, root) = self.root_token(deps.storage, &parent_id, parent.clone())?;
+        if root.owner != info.sender {
+            return Err(ContractError::Ownership(OwnershipError::NotOwner));
+        }
+
+        // the accessory goes to whoever owns the token now
+        let mut accessory = self.load_token(deps.storage, &accessory_id)?;
+        self.move_holding(deps.storage, &env.block, &accessory.owner, &root.owner, &accessory_id)?;
+        accessory.owner = root.owner;
+        accessory.parent = None;
+        self.tokens.save(deps.storage, &token_key(&accessory_id), &accessory)?;
+        self.token_children.remove(deps.storage, (&parent_id, &accessory_id));
+        parent.accessories.retain(|id| *id != accessory_id);
+        self.tokens.save(deps.storage, &token_key(&parent_id), &parent)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "detach")
+                .add_attribute("owner", accessory.owner)
+                .add_attribute("parent_id", parent_id)
+                .add_attribute("accessory_id", accessory_id)
+        )
+    }
+
+    pub fn reply(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        msg: Reply
+    ) -> Result<Response<C>, ContractError> {
+        if msg.id == BURN_NOTIFY_REPLY_ID {
+            // only errors are replied to, the burn itself stands
+            let err = msg.result.unwrap_err();
+            return Ok(
+                Response::new().add_event(
+                    Event::new("burn_notify_failed").add_attribute("error", err)
+                )
+            );
+        }
+
+        let pending = self.pending_sends.load(deps.storage, msg.id)?;
+        self.pending_sends.remove(deps.storage, msg.id);
+
+        match msg.result {
+            SubMsgResult::Ok(_) => Ok(Response::new()),
+            SubMsgResult::Err(err) => {
+                // the receiver rejected the token, give it back to the previous owner
+                let key = token_key(&pending.token_id);
+                let sent = self.tokens.load(deps.storage, &key)?.from_legacy();
+                let (from, to) = (&sent.owner, &pending.previous.owner);
+                self.move_holding(deps.storage, &env.block, from, to, &pending.token_id)?;
+                self.record_move(
+                    deps.storage,
+                    &env.block,
+                    &pending.token_id,
+                    HistoryAction::SendReverted,
+                    Some(sent.owner),
+                    Some(pending.previous.owner.clone())
+                )?;
+                self.tokens.save(deps.storage, &key, &pending.previous)?;
+                // the move dropped the delegation from the index, the restored token keeps it
+                if let Some(delegate) = &pending.previous.delegate {
+                    let expires = pending.previous.delegate_expires.unwrap_or_default();
+                    self.delegated_tokens.save(deps.storage, (delegate, &key), &expires)?;
+                }
+                for approval in &pending.approvals {
+                    self.token_approvals.save(
+                        deps.storage,
+                        (&pending.token_id, &approval.spender),
+                        &approval.expires
+                    )?;
+                    self.spender_approvals.save(
+                        deps.storage,
+                        (&approval.spender, &pending.token_id),
+                        &approval.expires
+                    )?;
+                }
+                Ok(
+                    Response::new().add_event(
+                        Event::new("send_failed")
+                            .add_attribute("token_id", pending.token_id)
+                            .add_attribute("owner", pending.previous.owner)
+                            .add_attribute("error", err)
+                    )
+                )
+            }
+        }
+    }
+
+    pub fn extend_operator(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operator: String,
+        new_expiration: Expiration
+    ) -> Result<Response<C>, ContractError> {
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        let mut grant = self.operators
+            .may_load(deps.storage, (&info.sender, &operator_addr))?
+            .ok_or_else(|| ContractError::OperatorNotFound { operator: operator.clone() })?;
+
+        let new_expiration = self.resolve_expiration(deps.storage, &env, Some(new_expiration))?;
+        // mismatched heights and times can't be compared, so they are refused too
+        if new_expiration.partial_cmp(&grant.expires) != Some(std::cmp::Ordering::Greater) {
+            return Err(ContractError::ExpirationNotLater {});
+        }
+
+        grant.expires = new_expiration;
+        self.operators.save(deps.storage, (&info.sender, &operator_addr), &grant)?;
+        self.operator_granters.save(
+            deps.storage,
+            (&operator_addr, &info.sender),
+            &new_expiration
+        )?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "extend_operator")
+                .add_attribute("sender", info.sender)
+                .add_attribute("operator", operator)
+                .add_attribute("expires", expiration_to_string(&new_expiration))
+        )
+    }
+
+    /// Saves the next tokens after the cursor back in the current shape. Once a batch comes
+    /// up short every token has been rewritten and the migration is closed
+    pub fn migrate_batch(
+        &self,
+        deps: DepsMut,
+        limit: Option<u32>
+    ) -> Result<Response<C>, ContractError> {
+        let mut migration = self.lazy_migration
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoMigrationInProgress {})?;
+        let limit = limit.unwrap_or(DEFAULT_MIGRATE_BATCH_LIMIT).min(MAX_MIGRATE_BATCH_LIMIT);
+        let limit = limit.max(1) as usize;
+
+        let start = migration.cursor.as_deref().map(Bound::exclusive);
+        let batch = self.tokens
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut upgraded = 0u32;
+        for (key, token) in &batch {
+            if token.version < migration.target {
+                self.tokens.replace(
+                    deps.storage,
+                    key,
+                    Some(&token.clone().from_legacy()),
+                    Some(token)
+                )?;
+                upgraded += 1;
+            }
+        }
+
+        let done = batch.len() < limit;
+        if done {
+            self.storage_version.save(deps.storage, &migration.target)?;
+            self.lazy_migration.remove(deps.storage);
+        } else {
+            migration.cursor = batch.last().map(|(key, _)| key.clone());
+            self.lazy_migration.save(deps.storage, &migration)?;
+        }
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "migrate_batch")
+                .add_attribute("upgraded", upgraded.to_string())
+                .add_attribute("done", done.to_string())
+        )
+    }
+
+    pub fn sweep_expired_operators(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        granter: Option<String>,
+        limit: Option<u32>
+    ) -> Result<Response<C>, ContractError> {
+        let limit = limit.unwrap_or(DEFAULT_SWEEP_LIMIT).min(MAX_SWEEP_LIMIT) as usize;
+
+        let expired: Vec<(Addr, Addr)> = match granter {
+            Some(granter) => {
+                let granter_addr = deps.api.addr_validate(&granter)?;
+                self.operators
+                    .prefix(&granter_addr)
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .filter(|r| r.as_ref().map_or(true, |(_, grant)| grant.is_expired(&env.block)))
+                    .take(limit)
+                    .map(|r| r.map(|(operator, _)| (granter_addr.clone(), operator)))
+                    .collect::<StdResult<Vec<_>>>()?
+            }
+            None =>
+                self.operators
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .filter(|r| r.as_ref().map_or(true, |(_, grant)| grant.is_expired(&env.block)))
+                    .take(limit)
+                    .map(|r| r.map(|(pair, _)| pair))
+                    .collect::<StdResult<Vec<_>>>()?,
+        };
+
+        let mut resp = Response::new()
+            .add_attribute("action", "sweep_expired_operators")
+            .add_attribute("count", expired.len().to_string());
+        for (granter, operator) in expired {
+            self.operators.remove(deps.storage, (&granter, &operator));
+            self.operator_granters.remove(deps.storage, (&operator, &granter));
+            resp = resp.add_attribute("removed", format!("{granter}:{operator}"));
+        }
+
+        Ok(resp)
+    }
+
+    pub fn update_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        action: cw_ownable::Action
+    ) -> Result<Response<C>, ContractError> {
+        let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+        Ok(Response::new().add_attributes(ownership.into_attributes()))
+    }
+
+    pub fn set_withdraw_address(
+        &self,
+        storage: &mut dyn Storage,
+        api: &dyn Api,
+        sender: &Addr,
+        address: String
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(storage, sender)?;
+        api.addr_validate(&address)?;
+        self.withdraw_address.save(storage, &address)?;
+        Ok(
+            Response::new()
+                .add_attribute("action", "set_withdraw_address")
+                .add_attribute("address", address)
+        )
+    }
+
+    pub fn remove_withdraw_address(
+        &self,
+        storage: &mut dyn Storage,
+        sender: &Addr
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(storage, sender)?;
+        let address = self.withdraw_address.may_load(storage)?;
+        match address {
+            Some(address) => {
+                self.withdraw_address.remove(storage);
+                Ok(
+                    Response::new()
+                        .add_attribute("action", "remove_withdraw_address")
+                        .add_attribute("address", address)
+                )
+            }
+            None => Err(ContractError::NoWithdrawAddress {}),
+        }
+    }
+
+    /// With proceeds vesting, only the unlocked part of what mints paid can be withdrawn
+    pub fn assert_vested(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        amount: &Coin
+    ) -> Result<(), ContractError> {
+        let Some(vesting) = self.proceeds_vesting.may_load(storage)? else {
+            return Ok(());
+        };
+        let total = self.vesting_proceeds.may_load(storage, &amount.denom)?.unwrap_or_default();
+        let withdrawn = self.total_withdrawn.may_load(storage, &amount.denom)?.unwrap_or_default();
+        let available = vesting.vested(total, block.time).saturating_sub(withdrawn);
+        if amount.amount > available {
+            return Err(ContractError::ExceedsVestedAmount {
+                available: Coin { denom: amount.denom.clone(), amount: available },
+            });
+        }
+        Ok(())
+    }
+
+    pub fn withdraw_funds(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        amount: &Coin
+    ) -> Result<Response<C>, ContractError> {
+        let address = self.withdraw_address.may_load(storage)?;
+        match address {
+            Some(address) => {
+                let record = WithdrawRecord {
+                    amount: amount.amount,
+                    denom: amount.denom.clone(),
+                    to: address.clone(),
+                    at_height: block.height,
+                    at_time: block.time,
+                };
+                let id = self.record_withdrawal(storage, &record)?;
+                let msg = BankMsg::Send {
+                    to_address: address,
+                    amount: vec![amount.clone()],
+                };
+                Ok(
+                    Response::new()
+                        .add_message(msg)
+                        .add_attribute("action", "withdraw_funds")
+                        .add_attribute("amount", amount.amount.to_string())
+                        .add_attribute("denom", amount.denom.to_string())
+                        .add_attribute("withdrawal_id", id.to_string())
+                )
+            }
+            None => Err(ContractError::NoWithdrawAddress {}),
+        }
+    }
+
+    pub fn checkpoint_balances(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        if self.balance_snapshots(deps.storage)? != SnapshotStrategy::Selected {
+            return Err(ContractError::CheckpointsNotSelected {});
+        }
+        self.add_balance_checkpoint(deps.storage, env.block.height)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "checkpoint_balances")
+                .add_attribute("height", env.block.height.to_string())
+        )
+    }
+
+    pub fn finalize_import(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        if !self.import_open(deps.storage)? {
+            return Err(ContractError::ImportClosed {});
+        }
+        self.import_open.save(deps.storage, &false)?;
+
+        Ok(Response::new().add_attribute("action", "finalize_import"))
+    }
+
+    /// Writes a page of `ExportState` from another deployment. Only reachable through
+    /// migrate, so the chain admin stands in for the owner
+    pub fn import_state(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        section: ExportSection,
+        export: StateExport<T>
+    ) -> Result<Response<C>, ContractError> {
+        if !self.import_open(deps.storage)? {
+            return Err(ContractError::ImportClosed {});
+        }
+        if export.version != STATE_EXPORT_VERSION {
+            return Err(ContractError::UnsupportedExportVersion { version: export.version });
+        }
+        if export.data.section() != section {
+            return Err(ContractError::ImportSectionMismatch {});
+        }
+
+        let count = match export.data {
+            ExportData::Tokens(tokens) => {
+                let count = tokens.len();
+                for token in tokens {
+                    self.import_token(deps.storage, deps.api, &env.block, token)?;
+                }
+                count
+            }
+            ExportData::Operators(operators) => {
+                for exported in &operators {
+                    let granter = deps.api.addr_validate(&exported.granter)?;
+                    let operator = deps.api.addr_validate(&exported.operator)?;
+                    self.operators.save(deps.storage, (&granter, &operator), &exported.grant)?;
+                    self.operator_granters.save(
+                        deps.storage,
+                        (&operator, &granter),
+                        &exported.grant.expires
+                    )?;
+                }
+                operators.len()
+            }
+            ExportData::WalletMints(wallets) => {
+                for wallet in &wallets {
+                    let owner = deps.api.addr_validate(&wallet.wallet)?;
+                    self.save_wallet_mints(deps.storage, &owner, wallet.minted)?;
+                }
+                wallets.len()
+            }
+            ExportData::Config(config) => {
+                self.import_config(deps.storage, deps.api, config)?;
+                1
+            }
+        };
+
+        let section = match section {
+            ExportSection::Tokens => "tokens",
+            ExportSection::Operators => "operators",
+            ExportSection::WalletMints => "wallet_mints",
+            ExportSection::Config => "config",
+        };
+        Ok(
+            Response::new()
+                .add_attribute("action", "import_state")
+                .add_attribute("section", section)
+                .add_attribute("count", count.to_string())
+        )
+    }
+
+    /// Stores an exported token with every index a mint would write. Ids keep counting
+    /// after the highest imported numeric id
+    fn import_token(
+        &self,
+        storage: &mut dyn Storage,
+        api: &dyn Api,
+        block: &BlockInfo,
+        exported: ExportedToken<T>
+    ) -> Result<(), ContractError> {
+        let token_id = exported.token_id;
+        if self.tokens.has(storage, &token_key(&token_id)) {
+            return Err(ContractError::Claimed {});
+        }
+        let token = TokenInfo {
+            owner: api.addr_validate(&exported.owner)?,
+            approvals: vec![],
+            token_uri: exported.token_uri,
+            extension: exported.extension,
+            parent: exported.parent,
+            redeemed: exported.redeemed,
+            transfer_count: 0,
+            last_transferred_at: None,
+            minted_at: None,
+            revealed: exported.revealed,
+            user: None,
+            user_expires: None,
+            staked_since: None,
+            delegate: None,
+            delegate_expires: None,
+            accessories: exported.accessories,
+            valid_until: exported.valid_until,
+            version: TOKEN_STORAGE_VERSION,
+        };
+        self.tokens.save(storage, &token_key(&token_id), &token)?;
+        self.add_holding(storage, block, &token.owner, &token_id)?;
+        self.index_token_extension(storage, &token_id, &token.extension)?;
+        self.update_uri_index(storage, &token_id, None, token.token_uri.as_deref())?;
+        self.enumerate_token(storage, &token_id)?;
+        if !token.revealed {
+            self.add_unrevealed(storage, &token_id)?;
+        }
+        if let Some(parent) = &token.parent {
+            self.token_children.save(storage, (parent, &token_id), &Empty {})?;
+        }
+        for approval in exported.approvals {
+            let spender = api.addr_validate(&approval.spender)?;
+            self.token_approvals.save(storage, (&token_id, &spender), &approval.expires)?;
+            self.spender_approvals.save(storage, (&spender, &token_id), &approval.expires)?;
+        }
+
+        let token_count = self.token_count(storage)? + 1;
+        self.save_token_count(storage, block.height, token_count)?;
+        if let Ok(id) = token_id.parse::<u64>() {
+            if id >= self.minted_total(storage)? {
+                self.minted_total.save(storage, &(id + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn import_config(
+        &self,
+        storage: &mut dyn Storage,
+        api: &dyn Api,
+        config: ExportedConfig
+    ) -> Result<(), ContractError> {
+        self.contract_info.save(storage, &(ContractInfoResponse {
+            name: config.name,
+            symbol: config.symbol,
+        }))?;
+        match config.withdraw_address {
+            Some(address) => {
+                api.addr_validate(&address)?;
+                self.withdraw_address.save(storage, &address)?;
+            }
+            None => self.withdraw_address.remove(storage),
+        }
+        match config.max_supply {
+            Some(max_supply) => self.max_supply.save(storage, &max_supply)?,
+            None => self.max_supply.remove(storage),
+        }
+        match config.max_nfts_per_wallet {
+            Some(max) => self.max_nfts_per_wallet.save(storage, &max)?,
+            None => self.max_nfts_per_wallet.remove(storage),
+        }
+        match config.price_per_nft {
+            Some(price) => self.mint_price_per_nft.save(storage, &price)?,
+            None => self.mint_price_per_nft.remove(storage),
+        }
+        let minted_total = config.minted_total.max(self.minted_total(storage)?);
+        self.minted_total.save(storage, &minted_total)?;
+        self.burned_total.save(storage, &config.burned_total)?;
+        self.bridged_in_total.save(storage, &config.bridged_in_total)?;
+        self.bridged_out_total.save(storage, &config.bridged_out_total)?;
+        Ok(())
+    }
+
+    pub fn reveal_tokens(
+        &self,
+        deps: DepsMut,
+        sender: &Addr,
+        tokens: Vec<(String, String)>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, sender)?;
+
+        let mut res = Response::new()
+            .add_attribute("action", "reveal_tokens")
+            .add_attribute("count", tokens.len().to_string());
+        for (token_id, token_uri) in tokens {
+            let key = token_key(&token_id);
+            let mut token = self.tokens.load(deps.storage, &key)?.from_legacy();
+            if token.revealed {
+                return Err(ContractError::AlreadyRevealed { token_id });
+            }
+            self.update_uri_index(
+                deps.storage,
+                &token_id,
+                token.token_uri.as_deref(),
+                Some(&token_uri)
+            )?;
+            token.token_uri = Some(token_uri);
+            token.revealed = true;
+            self.tokens.save(deps.storage, &key, &token)?;
+            self.remove_unrevealed(deps.storage, &token_id)?;
+            res = res.add_attribute("token_id", token_id);
+        }
+
+        Ok(res)
+    }
+
+    pub fn add_allowed_operator(
+        &self,
+        deps: DepsMut,
+        sender: &Addr,
+        operator: String
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, sender)?;
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        self.operator_allowlist.save(deps.storage, &operator_addr, &Empty {})?;
+        Ok(
+            Response::new()
+                .add_attribute("action", "add_allowed_operator")
+                .add_attribute("operator", operator)
+        )
+    }
+
+    pub fn set_royalty_registry(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        registry: Option<String>
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let resp = Response::new().add_attribute("action", "set_royalty_registry");
+        match registry {
+            Some(registry) => {
+                let registry = deps.api.addr_validate(&registry)?;
+                self.royalty_registry.save(deps.storage, &registry)?;
+                Ok(resp.add_attribute("royalty_registry", registry))
+            }
+            None => {
+                self.royalty_registry.remove(deps.storage);
+                Ok(resp.add_attribute("royalty_registry", "none"))
+            }
+        }
+    }
+
+    pub fn remove_allowed_operator(
+        &self,
+        deps: DepsMut,
+        sender: &Addr,
+        operator: String
+    ) -> Result<Response<C>, ContractError> {
+        cw_ownable::assert_owner(deps.storage, sender)?;
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        if !self.operator_allowlist.has(deps.storage, &operator_addr) {
+            return Err(ContractError::OperatorNotAllowed { operator });
+        }
+        self.operator_allowlist.remove(deps.storage, &operator_addr);
+        Ok(
+            Response::new()
+                .add_attribute("action", "remove_allowed_operator")
+                .add_attribute("operator", operator)
+        )
+    }
+}
+
+impl<'a, T, C, E, Q> Cw721Execute<T, C>
+    for Cw721Contract<'a, T, C, E, Q>
+    where T: Serialize + DeserializeOwned + Clone, C: CustomMsg, E: CustomMsg, Q: CustomMsg
+{
+    type Err = ContractError;
+
+    fn transfer_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        self._transfer_nft(deps, &env, &info, &recipient, &token_id, HistoryAction::Transfer)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "transfer_nft")
+                .add_attribute("sender", info.sender)
+                .add_attribute("recipient", recipient)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    fn send_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+        msg: Binary
+    ) -> Result<Response<C>, ContractError> {
+        let approvals = self.approvals_of(deps.storage, &token_id)?;
+        self._send_nft(deps, env, info, contract, token_id, msg, approvals)
+    }
+
+    fn approve(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>
+    ) -> Result<Response<C>, ContractError> {
+        let token = self._update_approvals(
+            deps.branch(),
+            &env,
+            &info,
+            &spender,
+            &token_id,
+            true,
+            expires
+        )?;
+        let spender_addr = deps.api.addr_validate(&spender)?;
+        let expires = self.token_approvals
+            .may_load(deps.storage, (&token_id, &spender_addr))?
+            .map(|expires| expiration_to_string(&expires))
+            .unwrap_or_default();
+
+        Ok(
+            Response::new()
+                .add_event(
+                    Event::new("cw721_approve")
+                        .add_attribute("granter", token.owner.to_string())
+                        .add_attribute("spender", spender.clone())
+                        .add_attribute("token_id", token_id.clone())
+                        .add_attribute("expires", expires.clone())
+                )
+                .add_attribute("action", "approve")
+                .add_attribute("sender", info.sender)
+                .add_attribute("granter", token.owner)
+                .add_attribute("spender", spender)
+                .add_attribute("token_id", token_id)
+                .add_attribute("expires", expires)
+        )
+    }
+
+    fn revoke(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        self._update_approvals(deps, &env, &info, &spender, &token_id, false, None)?;
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "revoke")
+                .add_attribute("sender", info.sender)
+                .add_attribute("spender", spender)
+                .add_attribute("token_id", token_id)
+        )
+    }
+
+    fn approve_all(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operator: String,
+        expires: Option<Expiration>
+    ) -> Result<Response<C>, ContractError> {
+        self._approve_all(deps, env, info, operator, expires, None, None)
+    }
+
+    fn revoke_all(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        operator: String
+    ) -> Result<Response<C>, ContractError> {
         let operator_addr = deps.api.addr_validate(&operator)?;
-        self.operators.save(deps.storage, (&info.sender, &operator_addr), &expires)?;
+        self.operators.remove(deps.storage, (&info.sender, &operator_addr));
+        self.operator_granters.remove(deps.storage, (&operator_addr, &info.sender));
+
+        Ok(
+            Response::new()
+                .add_attribute("action", "revoke_all")
+                .add_attribute("sender", info.sender)
+                .add_attribute("operator", operator)
+        )
+    }
+
+    fn burn(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        self.check_can_burn(deps.storage, &env, &info, &token_id, &token)?;
+
+        self._burn_nft(deps.storage, &env.block, &token_id)?;
+
+        // sender may be an operator, so name the owner whose balance went down
+        let mut event = Event::new("cw721_burn")
+            .add_attribute("owner", token.owner.to_string())
+            .add_attribute("token_id", token_id.clone());
+        let mut resp = Response::new()
+            .add_attribute("action", "burn")
+            .add_attribute("sender", info.sender)
+            .add_attribute("owner", token.owner)
+            .add_attribute("token_id", token_id);
+        if let Some(token_uri) = token.token_uri {
+            event = event.add_attribute("token_uri", token_uri.clone());
+            resp = resp.add_attribute("token_uri", token_uri);
+        }
+
+        Ok(resp.add_event(event))
+    }
+}
+
+// helpers
+impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
+    where T: Serialize + DeserializeOwned + Clone, C: CustomMsg, E: CustomMsg, Q: CustomMsg
+{
+    pub fn _transfer_nft(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        recipient: &str,
+        token_id: &str,
+        action: HistoryAction
+    ) -> Result<TokenInfo<T>, ContractError> {
+        let token = self.load_token(deps.storage, token_id)?;
+        self.assert_transferable(deps.storage, &env.block, token_id, &token)?;
+        // ensure we have permissions
+        self.check_can_send(deps.storage, &deps.querier, env, info, token_id, &token)?;
+        self.assert_address_prefix(deps.storage, recipient)?;
+        let recipient = deps.api.addr_validate(recipient)?;
+        self._move_token(deps.storage, &env.block, token_id, token, recipient, action)
+    }
 
-        Ok(
-            Response::new()
-                .add_attribute("action", "approve_all")
-                .add_attribute("sender", info.sender)
-                .add_attribute("operator", operator)
-        )
+    /// Hands the token to `recipient` and removes existing approvals, whoever asked for it
+    pub fn _move_token(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        mut token: TokenInfo<T>,
+        recipient: Addr,
+        action: HistoryAction
+    ) -> Result<TokenInfo<T>, ContractError> {
+        self.move_holding(storage, block, &token.owner, &recipient, token_id)?;
+        self.record_move(
+            storage,
+            block,
+            token_id,
+            action,
+            Some(token.owner.clone()),
+            Some(recipient.clone())
+        )?;
+        token.owner = recipient;
+        self._clear_approvals(storage, token_id)?;
+        if !self.keep_user_on_transfer(storage)? {
+            token.user = None;
+            token.user_expires = None;
+        }
+        self.clear_delegate(storage, token_id, &mut token);
+        // a listing or bundle that ran out doesn't carry over to the new owner
+        self.listings.remove(storage, &token_key(token_id));
+        self.bundled_tokens.remove(storage, &token_key(token_id));
+        token.transfer_count += 1;
+        token.last_transferred_at = Some(block.time);
+        self.tokens.save(storage, &token_key(token_id), &token)?;
+        Ok(token)
     }
 
-    fn revoke_all(
+    #[allow(clippy::too_many_arguments)]
+    pub fn _approve_all(
         &self,
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         info: MessageInfo,
-        operator: String
+        operator: String,
+        expires: Option<Expiration>,
+        max_uses: Option<u64>,
+        permissions: Option<OperatorPermissions>
     ) -> Result<Response<C>, ContractError> {
+        let expires = self.resolve_expiration(deps.storage, &env, expires)?;
+
+        // set the operator for us
         let operator_addr = deps.api.addr_validate(&operator)?;
-        self.operators.remove(deps.storage, (&info.sender, &operator_addr));
+        self.operators.save(deps.storage, (&info.sender, &operator_addr), &(OperatorGrant {
+            expires,
+            remaining_uses: max_uses,
+            permissions: permissions.unwrap_or_default(),
+        }))?;
+        self.operator_granters.save(deps.storage, (&operator_addr, &info.sender), &expires)?;
 
-        Ok(
-            Response::new()
-                .add_attribute("action", "revoke_all")
-                .add_attribute("sender", info.sender)
-                .add_attribute("operator", operator)
-        )
+        let expires = expiration_to_string(&expires);
+        let mut res = Response::new()
+            .add_event(
+                Event::new("cw721_approve_all")
+                    .add_attribute("granter", info.sender.to_string())
+                    .add_attribute("operator", operator.clone())
+                    .add_attribute("expires", expires.clone())
+            )
+            .add_attribute("action", "approve_all")
+            .add_attribute("sender", info.sender.clone())
+            .add_attribute("granter", info.sender)
+            .add_attribute("operator", operator)
+            .add_attribute("expires", expires);
+        if let Some(max_uses) = max_uses {
+            res = res.add_attribute("max_uses", max_uses.to_string());
+        }
+        if let Some(permissions) = permissions {
+            res = res
+                .add_attribute("can_transfer", permissions.can_transfer.to_string())
+                .add_attribute("can_approve", permissions.can_approve.to_string())
+                .add_attribute("can_burn", permissions.can_burn.to_string());
+        }
+
+        Ok(res)
     }
 
-    fn burn(
+    /// Sends the token, `approvals` are restored along with the token if the receiver fails
+    #[allow(clippy::too_many_arguments)]
+    pub fn _send_nft(
         &self,
-        deps: DepsMut,
+        mut deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        token_id: String
+        contract: String,
+        token_id: String,
+        msg: Binary,
+        approvals: Vec<Approval>
     ) -> Result<Response<C>, ContractError> {
-        let token = self.tokens.load(deps.storage, &token_id)?;
-        self.check_can_send(deps.as_ref(), &env, &info, &token)?;
+        // Transfer token, remembering the previous state in case the receiver fails
+        let previous = self.load_token(deps.storage, &token_id)?;
+        self._transfer_nft(deps.branch(), &env, &info, &contract, &token_id, HistoryAction::Send)?;
+
+        let reply_id = self.next_reply_id(deps.storage)?;
+        self.pending_sends.save(deps.storage, reply_id, &(PendingSend {
+            token_id: token_id.clone(),
+            previous,
+            approvals,
+        }))?;
 
-        self.tokens.remove(deps.storage, &token_id)?;
-        self.decrement_tokens(deps.storage)?;
+        let send = Cw721ReceiveMsg {
+            sender: info.sender.to_string(),
+            token_id: token_id.clone(),
+            msg,
+        };
 
+        // Send message, we always want the reply to clean up the pending send
+        let send_msg = SubMsg::reply_always(send.into_cosmos_msg(contract.clone())?, reply_id);
         Ok(
             Response::new()
-                .add_attribute("action", "burn")
+                .add_submessage(send_msg)
+                .add_attribute("action", "send_nft")
                 .add_attribute("sender", info.sender)
+                .add_attribute("recipient", contract)
                 .add_attribute("token_id", token_id)
         )
     }
-}
 
-// helpers
-impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
-    where T: Serialize + DeserializeOwned + Clone, C: CustomMsg, E: CustomMsg, Q: CustomMsg
-{
-    pub fn _transfer_nft(
+    /// Drops every approval of the token, keeping the spender index in sync.
+    /// Returns the spenders that were approved
+    pub fn _clear_approvals(
         &self,
-        deps: DepsMut,
-        env: &Env,
-        info: &MessageInfo,
-        recipient: &str,
+        storage: &mut dyn Storage,
+        token_id: &str
+    ) -> StdResult<Vec<Addr>> {
+        let spenders = self.token_approvals
+            .prefix(token_id)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for spender in &spenders {
+            self.token_approvals.remove(storage, (token_id, spender));
+            self.spender_approvals.remove(storage, (spender, token_id));
+        }
+        Ok(spenders)
+    }
+
+    /// Whatever the sender may do, nested, parent, staked, wrapped and for sale tokens
+    /// can't be burned
+    pub fn assert_burnable(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        token: &TokenInfo<T>
+    ) -> Result<(), ContractError> {
+        if token.parent.is_some() {
+            return Err(ContractError::TokenNested {});
+        }
+        if self.has_children(storage, token_id) {
+            return Err(ContractError::TokenHasChildren {});
+        }
+        if token.staked_since.is_some() {
+            return Err(ContractError::TokenStaked {});
+        }
+        // the escrowed original would be stranded, `unwrap` drops the entry first
+        if self.wrapped_tokens.has(storage, &token_key(token_id)) {
+            return Err(ContractError::TokenWrapped {});
+        }
+        self.assert_not_for_sale(storage, block, token_id)
+    }
+
+    /// Removes the token and updates the counters, permissions must be checked by the caller
+    pub fn _burn_nft(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
         token_id: &str
     ) -> Result<TokenInfo<T>, ContractError> {
-        let mut token = self.tokens.load(deps.storage, token_id)?;
-        // ensure we have permissions
-        self.check_can_send(deps.as_ref(), env, info, &token)?;
-        // set owner and remove existing approvals
-        token.owner = deps.api.addr_validate(recipient)?;
-        token.approvals = vec![];
-        self.tokens.save(deps.storage, token_id, &token)?;
+        let token = self.load_token(storage, token_id)?;
+        self.assert_burnable(storage, block, token_id, &token)?;
+        self._remove_token(storage, block, token_id, token, HistoryAction::Burn)
+    }
+
+    /// Removes the token with everything kept about it, checks are up to the caller.
+    /// `action` is either a burn or the bridge taking the token to another chain
+    fn _remove_token(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        token: TokenInfo<T>,
+        action: HistoryAction
+    ) -> Result<TokenInfo<T>, ContractError> {
+        let bridged = action == HistoryAction::BridgeBurn;
+        self.listings.remove(storage, &token_key(token_id));
+        self.bundled_tokens.remove(storage, &token_key(token_id));
+        self.last_sale.remove(storage, &token_key(token_id));
+        if let Some(delegate) = &token.delegate {
+            self.delegated_tokens.remove(storage, (delegate, &token_key(token_id)));
+        }
+        self._clear_approvals(storage, token_id)?;
+        self.tokens.remove(storage, &token_key(token_id))?;
+        self.unenumerate_token(storage, token_id)?;
+        if !token.revealed {
+            self.remove_unrevealed(storage, token_id)?;
+        }
+        self.decrement_tokens(storage, block, bridged)?;
+        self.remove_holding(storage, block, &token.owner, token_id)?;
+        self.unindex_token_extension(storage, token_id, &token.extension)?;
+        self.update_uri_index(storage, token_id, token.token_uri.as_deref(), None)?;
+        if !bridged && self.retain_burned_tokens(storage)? {
+            let record = BurnRecord {
+                owner: token.owner.clone(),
+                burned_at_height: block.height,
+                burned_at: block.time,
+            };
+            self.burned_tokens.save(storage, &token_key(token_id), &record)?;
+        }
+        self.record_move(storage, block, token_id, action, Some(token.owner.clone()), None)?;
+        if let Some(minted_at) = token.minted_at {
+            self.mint_times.remove(storage, (minted_at.seconds(), &token_key(token_id)));
+        }
+        if let Some(valid_until) = token.valid_until {
+            let key = token_key(token_id);
+            self.subscription_expiries.remove(storage, (valid_until.seconds(), &key));
+        }
         Ok(token)
     }
 
+    /// Adds a change of custody to both the token history and the activity feed
+    pub fn record_move(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        action: HistoryAction,
+        from: Option<Addr>,
+        to: Option<Addr>
+    ) -> StdResult<()> {
+        self.push_history(storage, block, token_id, action, from.clone(), to.clone())?;
+        self.record_activity(storage, block, action.into(), token_id, from, to)
+    }
+
+    /// Fails unless `from` is the current owner of the token, guards against ownership races
+    pub fn assert_token_owner(
+        &self,
+        deps: Deps,
+        from: &str,
+        token_id: &str
+    ) -> Result<(), ContractError> {
+        let from = deps.api.addr_validate(from)?;
+        let token = self.load_token(deps.storage, token_id)?;
+        if token.owner != from {
+            return Err(ContractError::UnexpectedOwner {
+                actual: token.owner.into_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Rejects addresses whose human readable part differs from the configured bech32 prefix
+    pub fn assert_address_prefix(
+        &self,
+        storage: &dyn Storage,
+        address: &str
+    ) -> Result<(), ContractError> {
+        if let Some(expected) = self.bech32_prefix.may_load(storage)? {
+            let got = address.rsplit_once('1').map_or("", |(hrp, _)| hrp);
+            if got != expected {
+                return Err(ContractError::WrongAddressPrefix {
+                    expected,
+                    got: got.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `quantity` more tokens fit under `max_supply`, and that an open edition
+    /// is open
+    pub fn assert_supply_available(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        quantity: u64
+    ) -> Result<(), ContractError> {
+        if let Some((start, end)) = self.open_edition.may_load(storage)? {
+            if block.time < start {
+                return Err(ContractError::MintNotStarted {});
+            }
+            if block.time >= end {
+                return Err(ContractError::MintEnded {});
+            }
+        }
+        // winners that haven't claimed yet keep their share of the supply
+        let reserved = match self.raffle_phase.may_load(storage)? {
+            Some(RafflePhase::Revealed { winners, .. }) => {
+                winners - self.raffle_claimed.load(storage)?
+            }
+            Some(phase) if phase.is_abandoned(block) => 0,
+            Some(_) => {
+                return Err(ContractError::RaffleInProgress {});
+            }
+            None => 0,
+        };
+        if let Some(max_supply) = self.max_supply.may_load(storage)? {
+            let supply = self.capped_supply(storage)?.saturating_add(reserved);
+            if supply.saturating_add(quantity) > max_supply {
+                return Err(ContractError::SupplyOverflow {});
+            }
+        }
+        Ok(())
+    }
+
+    /// How many more tokens `owner` may mint, if there is a per-wallet limit
+    pub fn remaining_allocation(
+        &self,
+        storage: &dyn Storage,
+        owner: &Addr
+    ) -> StdResult<Option<u64>> {
+        let Some(max) = self.max_nfts_per_wallet.may_load(storage)? else {
+            return Ok(None);
+        };
+        let user_minted = self.wallets_minted_amount.may_load(storage, owner)?.unwrap_or(0);
+        Ok(Some(max.saturating_sub(user_minted)))
+    }
+
+    /// Checks `owner` may mint `quantity` more tokens under `max_nfts_per_wallet`
+    pub fn assert_wallet_allocation(
+        &self,
+        storage: &dyn Storage,
+        owner: &Addr,
+        quantity: u64
+    ) -> Result<(), ContractError> {
+        if let Some(remaining) = self.remaining_allocation(storage, owner)? {
+            if quantity > remaining {
+                return Err(ContractError::MintPerWalletOverflow {});
+            }
+        }
+        Ok(())
+    }
+
+    /// Total price of minting `quantity` tokens, if minting costs anything
+    pub fn mint_price(&self, storage: &dyn Storage, quantity: u64) -> StdResult<Option<Coin>> {
+        let Some(price) = self.mint_price_per_nft.may_load(storage)? else {
+            return Ok(None);
+        };
+        Ok(
+            Some(Coin {
+                amount: price.amount.checked_mul(quantity.into())?,
+                denom: price.denom,
+            })
+        )
+    }
+
+    /// Validates the address a token is minted to
+    pub fn validate_mint_owner(&self, deps: Deps, owner: &str) -> Result<Addr, ContractError> {
+        self.assert_address_prefix(deps.storage, owner)?;
+        Ok(deps.api.addr_validate(owner)?)
+    }
+
+    pub fn has_children(&self, storage: &dyn Storage, token_id: &str) -> bool {
+        self.token_children
+            .prefix(token_id)
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+            .is_some()
+    }
+
+    /// How many levels of tokens are nested below this one, stops counting past `max`
+    fn nested_height(&self, storage: &dyn Storage, token_id: &str, max: u32) -> StdResult<u32> {
+        if max == 0 {
+            return Ok(0);
+        }
+        let children = self.token_children
+            .prefix(token_id)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut height = 0;
+        for child in children {
+            height = height.max(1 + self.nested_height(storage, &child, max - 1)?);
+        }
+        Ok(height)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn _update_approvals(
         &self,
@@ -440,40 +4292,72 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
         add: bool,
         expires: Option<Expiration>
     ) -> Result<TokenInfo<T>, ContractError> {
-        let mut token = self.tokens.load(deps.storage, token_id)?;
+        let token = self.load_token(deps.storage, token_id)?;
+        let spender_addr = deps.api.addr_validate(spender)?;
         // ensure we have permissions
-        self.check_can_approve(deps.as_ref(), env, info, &token)?;
+        self.check_can_approve(deps.as_ref(), env, info, &token, add.then_some(&spender_addr))?;
 
         // update the approval list (remove any for the same spender before adding)
-        let spender_addr = deps.api.addr_validate(spender)?;
-        token.approvals.retain(|apr| apr.spender != spender_addr);
+        self.token_approvals.remove(deps.storage, (token_id, &spender_addr));
+        self.spender_approvals.remove(deps.storage, (&spender_addr, token_id));
 
         // only difference between approve and revoke
         if add {
-            // reject expired data as invalid
-            let expires = expires.unwrap_or_default();
-            if expires.is_expired(&env.block) {
-                return Err(ContractError::Expired {});
-            }
-            let approval = Approval {
-                spender: spender_addr,
-                expires,
-            };
-            token.approvals.push(approval);
+            let expires = self.resolve_expiration(deps.storage, env, expires)?;
+            let max = self.max_approvals_per_token(deps.storage)?;
+            let count = self.token_approvals
+                .prefix(token_id)
+                .keys_raw(deps.storage, None, None, Order::Ascending)
+                .count();
+            if (count as u64) >= max {
+                return Err(ContractError::TooManyApprovals { max });
+            }
+            self.token_approvals.save(deps.storage, (token_id, &spender_addr), &expires)?;
+            self.spender_approvals.save(deps.storage, (&spender_addr, token_id), &expires)?;
+            self.record_activity(
+                deps.storage,
+                &env.block,
+                ActivityAction::Approve,
+                token_id,
+                Some(token.owner.clone()),
+                Some(spender_addr)
+            )?;
         }
 
-        self.tokens.save(deps.storage, token_id, &token)?;
-
         Ok(token)
     }
 
+    /// Applies the collection's expiration policy to an approval or operator grant
+    pub fn resolve_expiration(
+        &self,
+        storage: &dyn Storage,
+        env: &Env,
+        expires: Option<Expiration>
+    ) -> Result<Expiration, ContractError> {
+        let expires = match (expires, self.default_approval_ttl.may_load(storage)?) {
+            (Some(expires), _) => expires,
+            (None, Some(ttl)) => Expiration::AtTime(env.block.time.plus_seconds(ttl)),
+            (None, None) => Expiration::Never {},
+        };
+        if expires == (Expiration::Never {}) && !self.allow_never_expiration(storage)? {
+            return Err(ContractError::NeverExpirationNotAllowed {});
+        }
+        // reject expired data as invalid
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+        Ok(expires)
+    }
+
     /// returns true iff the sender can execute approve or reject on the contract
+    /// `spender` is the one being approved, none when approvals are revoked
     pub fn check_can_approve(
         &self,
         deps: Deps,
         env: &Env,
         info: &MessageInfo,
-        token: &TokenInfo<T>
+        token: &TokenInfo<T>,
+        spender: Option<&Addr>
     ) -> Result<(), ContractError> {
         // owner can approve
         if token.owner == info.sender {
@@ -482,9 +4366,18 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
         // operator can approve
         let op = self.operators.may_load(deps.storage, (&token.owner, &info.sender))?;
         match op {
-            Some(ex) => {
-                if ex.is_expired(&env.block) {
+            Some(grant) => {
+                if grant.is_expired(&env.block) {
                     Err(ContractError::Ownership(OwnershipError::NotOwner))
+                } else if !grant.permissions.can_approve {
+                    Err(ContractError::OperatorNotPermitted { action: String::from("approve") })
+                } else if spender.is_some() && grant.remaining_uses.is_some() {
+                    // moves through an approval don't spend the budget
+                    Err(ContractError::BudgetedOperatorApproval {})
+                } else if spender == Some(&info.sender) {
+                    // an approval would let it transfer without `can_transfer`, and outlive
+                    // the grant
+                    Err(ContractError::OperatorSelfApproval {})
                 } else {
                     Ok(())
                 }
@@ -493,39 +4386,282 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
         }
     }
 
-    /// returns true iff the sender can transfer ownership of the token
-    pub fn check_can_send(
+    /// Fails if the token can't move no matter who sends it
+    pub fn assert_transferable(
         &self,
-        deps: Deps,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        token: &TokenInfo<T>
+    ) -> Result<(), ContractError> {
+        // nested tokens move together with their parent only
+        if token.parent.is_some() {
+            return Err(ContractError::TokenNested {});
+        }
+        if token.redeemed {
+            return Err(ContractError::TokenRedeemed {});
+        }
+        if token.staked_since.is_some() {
+            return Err(ContractError::TokenStaked {});
+        }
+        self.assert_not_for_sale(storage, block, token_id)
+    }
+
+    /// Listed and bundled tokens are locked until they are sold, delisted or the sale expires.
+    /// Auctioned tokens are locked until the auction is settled
+    pub fn assert_not_for_sale(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        token_id: &str
+    ) -> Result<(), ContractError> {
+        if self.active_listing(storage, block, token_id)?.is_some() {
+            return Err(ContractError::TokenListed {});
+        }
+        if self.auctions.has(storage, &token_key(token_id)) {
+            return Err(ContractError::TokenInAuction {});
+        }
+        if self.active_bundle(storage, block, token_id)?.is_some() {
+            return Err(ContractError::TokenBundled {});
+        }
+        Ok(())
+    }
+
+    /// returns true iff the sender can burn the token. Unlike transfers, this can be
+    /// restricted to the owner alone by disabling `operators_can_burn`
+    pub fn check_can_burn(
+        &self,
+        storage: &mut dyn Storage,
         env: &Env,
         info: &MessageInfo,
+        token_id: &str,
         token: &TokenInfo<T>
     ) -> Result<(), ContractError> {
-        // owner can send
+        if self.operators_can_burn(storage)? {
+            self.check_can_move(storage, env, info, token_id, token, true)?;
+            return Ok(());
+        }
         if token.owner == info.sender {
+            Ok(())
+        } else {
+            Err(ContractError::OnlyOwnerCanBurn {})
+        }
+    }
+
+    /// returns true iff the sender can transfer ownership of the token.
+    /// When the sender is an operator with a limited budget, one use is spent.
+    /// Operators also need the royalty registry to vouch for them, if there is one
+    pub fn check_can_send(
+        &self,
+        storage: &mut dyn Storage,
+        querier: &QuerierWrapper,
+        env: &Env,
+        info: &MessageInfo,
+        token_id: &str,
+        token: &TokenInfo<T>
+    ) -> Result<(), ContractError> {
+        if !self.check_can_move(storage, env, info, token_id, token, false)? {
+            return Ok(());
+        }
+        let Some(registry) = self.royalty_registry.may_load(storage)? else {
             return Ok(());
+        };
+        // batches move many tokens through the same operator, ask once per block
+        let compliant = match self.registry_answers.may_load(storage, &info.sender)? {
+            Some((height, compliant)) if height == env.block.height => compliant,
+            _ => {
+                let compliant = query_compliance(querier, &registry, &info.sender)?;
+                let answer = (env.block.height, compliant);
+                self.registry_answers.save(storage, &info.sender, &answer)?;
+                compliant
+            }
+        };
+        if !compliant {
+            return Err(ContractError::OperatorNotCompliant {});
         }
+        Ok(())
+    }
 
-        // any non-expired token approval can send
-        if
-            token.approvals
-                .iter()
-                .any(|apr| apr.spender == info.sender && !apr.is_expired(&env.block))
-        {
+    /// shared by send and burn, which only differ in the operator permission they need.
+    /// Returns whether the move goes through an operator
+    fn check_can_move(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        info: &MessageInfo,
+        token_id: &str,
+        token: &TokenInfo<T>,
+        burn: bool
+    ) -> Result<bool, ContractError> {
+        let grant = self.can_move(storage, &env.block, &info.sender, token_id, token, burn)?;
+        let Some(mut grant) = grant else {
+            return Ok(false);
+        };
+        if let Some(remaining) = grant.remaining_uses {
+            grant.remaining_uses = Some(remaining - 1);
+            self.operators.save(storage, (&token.owner, &info.sender), &grant)?;
+        }
+        Ok(true)
+    }
+
+    /// Read-only version of the registry check in `check_can_send`, reusing an answer from
+    /// the same block if there is one
+    pub fn assert_royalty_compliant(
+        &self,
+        deps: Deps,
+        block: &BlockInfo,
+        operator: &Addr
+    ) -> Result<(), ContractError> {
+        let Some(registry) = self.royalty_registry.may_load(deps.storage)? else {
             return Ok(());
+        };
+        let compliant = match self.registry_answers.may_load(deps.storage, operator)? {
+            Some((height, compliant)) if height == block.height => compliant,
+            _ => query_compliance(&deps.querier, &registry, operator)?,
+        };
+        if !compliant {
+            return Err(ContractError::OperatorNotCompliant {});
+        }
+        Ok(())
+    }
+
+    /// Read-only part of `check_can_move`, returns the operator grant the move goes through
+    /// so the caller can spend a use of it
+    pub fn can_move(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        sender: &Addr,
+        token_id: &str,
+        token: &TokenInfo<T>,
+        burn: bool
+    ) -> Result<Option<OperatorGrant>, ContractError> {
+        // owner can send
+        if token.owner == *sender {
+            return Ok(None);
+        }
+
+        // any non-expired token approval can send
+        let approval = self.token_approvals.may_load(storage, (token_id, sender))?;
+        if approval.map_or(false, |expires| !expires.is_expired(block)) {
+            return Ok(None);
         }
 
         // operator can send
-        let op = self.operators.may_load(deps.storage, (&token.owner, &info.sender))?;
+        let op = self.operators.may_load(storage, (&token.owner, sender))?;
         match op {
-            Some(ex) => {
-                if ex.is_expired(&env.block) {
-                    Err(ContractError::Ownership(OwnershipError::NotOwner))
+            Some(grant) => {
+                if grant.is_expired(block) {
+                    return Err(ContractError::Ownership(OwnershipError::NotOwner));
+                }
+                let (permitted, action) = if burn {
+                    (grant.permissions.can_burn, "burn")
                 } else {
-                    Ok(())
+                    (grant.permissions.can_transfer, "transfer")
+                };
+                if !permitted {
+                    return Err(ContractError::OperatorNotPermitted { action: action.to_string() });
+                }
+                // royalty enforcing collections only let allowlisted marketplaces transfer
+                let allowed =
+                    !self.enforce_operator_allowlist(storage)? ||
+                    self.operator_allowlist.has(storage, sender);
+                if !burn && !allowed {
+                    return Err(ContractError::OperatorNotAllowed {
+                        operator: sender.to_string(),
+                    });
+                }
+                if grant.remaining_uses == Some(0) {
+                    return Err(ContractError::OperatorBudgetExhausted {});
                 }
+                Ok(Some(grant))
             }
             None => Err(ContractError::Ownership(OwnershipError::NotOwner)),
         }
     }
 }
+
+/// Asks the royalty registry whether it vouches for `operator`
+fn query_compliance(querier: &QuerierWrapper, registry: &Addr, operator: &Addr) -> StdResult<bool> {
+    let query = RoyaltyRegistryQueryMsg::IsCompliant { operator: operator.to_string() };
+    querier.query_wasm_smart(registry, &query)
+}
+
+/// Derives the bech32 address of a secp256k1 public key, using the prefix of `like`
+fn pubkey_to_address(pubkey: &[u8], like: &str) -> Option<String> {
+    let (hrp, _, _) = bech32::decode(like).ok()?;
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    bech32::encode(&hrp, hash.to_base32(), Variant::Bech32).ok()
+}
+
+/// Sends back to `recipient` whatever was paid on top of `price`
+fn refund_excess<C>(
+    resp: Response<C>,
+    recipient: &Addr,
+    funds: Vec<Coin>,
+    price: &Coin
+) -> Response<C> {
+    let refund: Vec<Coin> = funds
+        .into_iter()
+        .map(|mut coin| {
+            if coin.denom == price.denom {
+                coin.amount -= price.amount;
+            }
+            coin
+        })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+    if refund.is_empty() {
+        return resp;
+    }
+    resp.add_message(BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: refund,
+    })
+}
+
+/// Renders an expiration as `never`, `at_height:<height>` or `at_time:<nanos>` for event consumers
+pub fn expiration_to_string(expires: &Expiration) -> String {
+    match expires {
+        Expiration::Never {} => String::from("never"),
+        Expiration::AtHeight(height) => format!("at_height:{height}"),
+        Expiration::AtTime(time) => format!("at_time:{}", time.nanos()),
+    }
+}
+
+/// Where entry `index` lands in a shuffle of `entries` entries seeded by `seed`. A Feistel
+/// network over the next power of 4 permutes the indexes, values out of range are fed back
+/// in until they land in range, which takes a few rounds at most
+pub fn raffle_position(seed: &[u8], index: u64, entries: u64) -> u64 {
+    let mut half_bits = 1;
+    while half_bits < 31 && 1u64 << (2 * half_bits) < entries {
+        half_bits += 1;
+    }
+    let mask = (1u64 << half_bits) - 1;
+    let mut position = index;
+    loop {
+        let (mut left, mut right) = (position >> half_bits, position & mask);
+        for round in 0..4u8 {
+            let hash = Sha256::new()
+                .chain_update(seed)
+                .chain_update([round])
+                .chain_update(right.to_be_bytes())
+                .finalize();
+            let mut word = [0u8; 8];
+            word.copy_from_slice(&hash[..8]);
+            (left, right) = (right, left ^ (u64::from_be_bytes(word) & mask));
+        }
+        position = (left << half_bits) | right;
+        if position < entries {
+            return position;
+        }
+    }
+}
+
+/// Renders a reward asset as `native:<denom>` or `cw20:<address>` for event consumers
+pub fn reward_asset_to_string(asset: &RewardAsset) -> String {
+    match asset {
+        RewardAsset::Native { denom } => format!("native:{denom}"),
+        RewardAsset::Cw20 { address } => format!("cw20:{address}"),
+    }
+}
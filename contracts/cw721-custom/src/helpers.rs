@@ -11,6 +11,10 @@ use cw721::{
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::msg::{
+    CanMintResponse, ContractStatusResponse, IsHolderResponse, SupplyResponse,
+    TransferableResponse, TreasuryResponse,
+};
 use crate::{ExecuteMsg, QueryMsg};
 
 #[cw_serde]
@@ -162,6 +166,8 @@ impl<Q: CustomMsg, E: CustomMsg> Cw721Contract<Q, E> {
             owner: owner.into(),
             start_after,
             limit,
+            order: None,
+            include_total: None,
         };
         self.query(querier, req)
     }
@@ -173,7 +179,92 @@ impl<Q: CustomMsg, E: CustomMsg> Cw721Contract<Q, E> {
         start_after: Option<String>,
         limit: Option<u32>,
     ) -> StdResult<TokensResponse> {
-        let req = QueryMsg::AllTokens { start_after, limit };
+        let req = QueryMsg::AllTokens {
+            start_after,
+            limit,
+            order: None,
+        };
+        self.query(querier, req)
+    }
+
+    pub fn num_tokens_of_owner<T: Into<String>>(
+        &self,
+        querier: &QuerierWrapper,
+        owner: T,
+    ) -> StdResult<u64> {
+        let req = QueryMsg::NumTokensOfOwner {
+            owner: owner.into(),
+        };
+        let res: NumTokensResponse = self.query(querier, req)?;
+        Ok(res.count)
+    }
+
+    pub fn supply(&self, querier: &QuerierWrapper) -> StdResult<SupplyResponse> {
+        self.query(querier, QueryMsg::Supply {})
+    }
+
+    pub fn treasury(&self, querier: &QuerierWrapper) -> StdResult<TreasuryResponse> {
+        self.query(querier, QueryMsg::Treasury {})
+    }
+
+    pub fn contract_status(&self, querier: &QuerierWrapper) -> StdResult<ContractStatusResponse> {
+        self.query(querier, QueryMsg::ContractStatus {})
+    }
+
+    /// Whether `address` could mint `quantity` tokens right now, and what it would cost
+    pub fn can_mint<T: Into<String>>(
+        &self,
+        querier: &QuerierWrapper,
+        address: T,
+        quantity: Option<u64>,
+    ) -> StdResult<CanMintResponse> {
+        let req = QueryMsg::CanMint {
+            address: address.into(),
+            quantity,
+        };
+        self.query(querier, req)
+    }
+
+    /// Whether the token can be transferred right now, by `by` if given
+    pub fn is_transferable<T: Into<String>>(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: T,
+        by: Option<String>,
+    ) -> StdResult<TransferableResponse> {
+        let req = QueryMsg::IsTransferable {
+            token_id: token_id.into(),
+            by,
+        };
+        self.query(querier, req)
+    }
+
+    /// Whether `address` holds at least `min_count` tokens of the collection, 1 by default
+    pub fn is_holder<T: Into<String>>(
+        &self,
+        querier: &QuerierWrapper,
+        address: T,
+        min_count: Option<u64>,
+    ) -> StdResult<bool> {
+        let req = QueryMsg::IsHolder {
+            address: address.into(),
+            min_count,
+        };
+        let res: IsHolderResponse = self.query(querier, req)?;
+        Ok(res.is_holder)
+    }
+
+    /// Whether `address` owns the token, false if it doesn't exist
+    pub fn holds_token<T: Into<String>>(
+        &self,
+        querier: &QuerierWrapper,
+        address: T,
+        token_id: T,
+    ) -> StdResult<bool> {
+        let req = QueryMsg::HoldsToken {
+            address: address.into(),
+            token_id: token_id.into(),
+        };
         self.query(querier, req)
     }
 
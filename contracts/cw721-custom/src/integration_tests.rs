@@ -0,0 +1,958 @@
+use cosmwasm_std::{ coin, Addr, Binary, Empty, Uint128 };
+use cw721::{ NftInfoResponse, NumTokensResponse, OwnerOfResponse };
+use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+use cw_ownable::{ Action, Ownership };
+
+use crate::testing::{ bridge, gatekeeper, partner, receiver, registry, staking };
+use crate::state::{ ActivityAction, BurnToMintConfig, PartnerConfig };
+use crate::{ ContractError, Cw721CustomExecuteMsg, Cw721CustomQueryMsg, InstantiateMsg };
+
+fn cw721_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::entry::execute,
+        crate::entry::instantiate,
+        crate::entry::query
+    )
+        .with_reply(crate::entry::reply)
+        .with_migrate(crate::entry::migrate);
+    Box::new(contract)
+}
+
+fn instantiate_cw721(app: &mut App, owner: &Addr, msg: InstantiateMsg) -> Addr {
+    let code_id = app.store_code(cw721_contract());
+    app.instantiate_contract(code_id, owner.clone(), &msg, &[], "cw721-custom", None).unwrap()
+}
+
+fn mint(app: &mut App, cw721: &Addr, sender: &str, funds: &[cosmwasm_std::Coin]) -> String {
+    let res = app
+        .execute_contract(
+            Addr::unchecked(sender),
+            cw721.clone(),
+            &(Cw721CustomExecuteMsg::Mint {
+                owner: sender.to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            funds
+        )
+        .unwrap();
+    res.events
+        .iter()
+        .flat_map(|e| &e.attributes)
+        .find(|a| a.key == "token_id")
+        .unwrap()
+        .value.clone()
+}
+
+fn owner_of(app: &App, cw721: &Addr, token_id: &str) -> String {
+    let res: OwnerOfResponse = app
+        .wrap()
+        .query_wasm_smart(cw721, &(Cw721CustomQueryMsg::OwnerOf {
+            token_id: token_id.to_string(),
+            include_expired: None,
+        }))
+        .unwrap();
+    res.owner
+}
+
+#[test]
+fn paid_mint_moves_funds() {
+    let mut app = App::new(|router, _, storage| {
+        router.bank
+            .init_balance(storage, &Addr::unchecked("buyer"), vec![coin(1000, "usei")])
+            .unwrap();
+    });
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        price_per_nft: Some(coin(300, "usei")),
+        ..Default::default()
+    });
+
+    let token_id = mint(&mut app, &cw721, "buyer", &[coin(300, "usei")]);
+    assert_eq!(owner_of(&app, &cw721, &token_id), "buyer");
+    assert_eq!(app.wrap().query_balance(&cw721, "usei").unwrap(), coin(300, "usei"));
+    assert_eq!(app.wrap().query_balance("buyer", "usei").unwrap(), coin(700, "usei"));
+
+    // underpaying is refused and the funds stay with the buyer
+    let err = app
+        .execute_contract(
+            Addr::unchecked("buyer"),
+            cw721.clone(),
+            &(Cw721CustomExecuteMsg::Mint {
+                owner: "buyer".to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[coin(100, "usei")]
+        )
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NotEnoughFunds {});
+    assert_eq!(app.wrap().query_balance("buyer", "usei").unwrap(), coin(700, "usei"));
+    assert_eq!(app.wrap().query_balance(&cw721, "usei").unwrap(), coin(300, "usei"));
+}
+
+#[test]
+fn send_nft_delivers_to_receiver() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        ..Default::default()
+    });
+    let accepting = receiver::instantiate_receiver(&mut app, false);
+    let rejecting = receiver::instantiate_receiver(&mut app, true);
+    let first = mint(&mut app, &cw721, "holder", &[]);
+    let second = mint(&mut app, &cw721, "holder", &[]);
+
+    let send = |contract: &Addr, token_id: &str| Cw721CustomExecuteMsg::SendNft {
+        contract: contract.to_string(),
+        token_id: token_id.to_string(),
+        msg: cosmwasm_std::to_json_binary("hello").unwrap(),
+    };
+    let res = app
+        .execute_contract(Addr::unchecked("holder"), cw721.clone(), &send(&accepting, &first), &[])
+        .unwrap();
+    let delivered = res.events
+        .iter()
+        .find(|e| e.ty == "wasm" && e.attributes.iter().any(|a| a.key == "received"))
+        .unwrap();
+    assert!(delivered.attributes.iter().any(|a| a.key == "sender" && a.value == "holder"));
+    assert_eq!(receiver::received(&app, &accepting), vec![first.clone()]);
+    assert_eq!(owner_of(&app, &cw721, &first), accepting.to_string());
+
+    // a failing receiver leaves the token with its owner
+    let res = app
+        .execute_contract(Addr::unchecked("holder"), cw721.clone(), &send(&rejecting, &second), &[])
+        .unwrap();
+    assert!(res.events.iter().any(|e| e.ty == "wasm-send_failed"));
+    assert!(receiver::received(&app, &rejecting).is_empty());
+    assert_eq!(owner_of(&app, &cw721, &second), "holder");
+
+    // and goes through once the receiver accepts again
+    app.execute_contract(
+        Addr::unchecked("deployer"),
+        rejecting.clone(),
+        &(receiver::ExecuteMsg::SetReject { reject: false }),
+        &[]
+    ).unwrap();
+    app.execute_contract(Addr::unchecked("holder"), cw721.clone(), &send(&rejecting, &second), &[])
+        .unwrap();
+    assert_eq!(receiver::received(&app, &rejecting), vec![second.clone()]);
+    assert_eq!(owner_of(&app, &cw721, &second), rejecting.to_string());
+}
+
+#[test]
+fn failed_send_keeps_the_delegation() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        ..Default::default()
+    });
+    let rejecting = receiver::instantiate_receiver(&mut app, true);
+    let token_id = mint(&mut app, &cw721, "holder", &[]);
+    let delegate_msg = Cw721CustomExecuteMsg::Delegate {
+        token_id: token_id.clone(),
+        delegate: "player".to_string(),
+        expires: None,
+    };
+    app.execute_contract(Addr::unchecked("holder"), cw721.clone(), &delegate_msg, &[]).unwrap();
+
+    let send_msg = Cw721CustomExecuteMsg::SendNft {
+        contract: rejecting.to_string(),
+        token_id: token_id.clone(),
+        msg: cosmwasm_std::to_json_binary("hello").unwrap(),
+    };
+    let res = app
+        .execute_contract(Addr::unchecked("holder"), cw721.clone(), &send_msg, &[])
+        .unwrap();
+    assert!(res.events.iter().any(|e| e.ty == "wasm-send_failed"));
+
+    // the token came back delegated, and is still listed with the delegate
+    let delegate: crate::msg::DelegateOfResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::DelegateOf { token_id: token_id.clone() }))
+        .unwrap();
+    assert_eq!(delegate.delegate, Some("player".to_string()));
+    let delegated: cw721::TokensResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &cw721,
+            &(Cw721CustomQueryMsg::TokensDelegatedTo {
+                delegate: "player".to_string(),
+                start_after: None,
+                limit: None,
+            })
+        )
+        .unwrap();
+    assert_eq!(delegated.tokens, vec![token_id]);
+}
+
+#[test]
+fn withdraw_pays_withdraw_address() {
+    let mut app = App::new(|router, _, storage| {
+        router.bank
+            .init_balance(storage, &Addr::unchecked("buyer"), vec![coin(1000, "usei")])
+            .unwrap();
+    });
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        price_per_nft: Some(coin(250, "usei")),
+        withdraw_address: Some("treasurer".to_string()),
+        ..Default::default()
+    });
+    for _ in 0..2 {
+        mint(&mut app, &cw721, "buyer", &[coin(250, "usei")]);
+    }
+
+    // whoever triggers it, the funds only ever reach the withdraw address
+    app.execute_contract(
+        Addr::unchecked("buyer"),
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::WithdrawFunds { amount: coin(400, "usei") }),
+        &[]
+    ).unwrap();
+    assert_eq!(app.wrap().query_balance("treasurer", "usei").unwrap(), coin(400, "usei"));
+    assert_eq!(app.wrap().query_balance(&cw721, "usei").unwrap(), coin(100, "usei"));
+    assert_eq!(app.wrap().query_balance("buyer", "usei").unwrap(), coin(500, "usei"));
+
+    // more than the contract holds fails without moving anything
+    app.execute_contract(
+        Addr::unchecked("buyer"),
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::WithdrawFunds { amount: coin(101, "usei") }),
+        &[]
+    ).unwrap_err();
+    assert_eq!(app.wrap().query_balance(&cw721, "usei").unwrap().amount, Uint128::new(100));
+}
+
+#[test]
+fn ownership_transfer_moves_owner_rights() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        minter: Some(owner.to_string()),
+        ..Default::default()
+    });
+    let set_withdraw = |address: &str| Cw721CustomExecuteMsg::SetWithdrawAddress {
+        address: address.to_string(),
+    };
+
+    app.execute_contract(
+        owner.clone(),
+        cw721.clone(),
+        &Cw721CustomExecuteMsg::UpdateOwnership(Action::TransferOwnership {
+            new_owner: "successor".to_string(),
+            expiry: None,
+        }),
+        &[]
+    ).unwrap();
+    // nothing changes until the transfer is accepted
+    let ownership: Ownership<Addr> = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::Ownership {}))
+        .unwrap();
+    assert_eq!(ownership.owner, Some(owner.clone()));
+    assert_eq!(ownership.pending_owner, Some(Addr::unchecked("successor")));
+
+    app.execute_contract(
+        Addr::unchecked("successor"),
+        cw721.clone(),
+        &Cw721CustomExecuteMsg::UpdateOwnership(Action::AcceptOwnership),
+        &[]
+    ).unwrap();
+    let ownership: Ownership<Addr> = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::Ownership {}))
+        .unwrap();
+    assert_eq!(ownership.owner, Some(Addr::unchecked("successor")));
+    assert_eq!(ownership.pending_owner, None);
+
+    let err = app
+        .execute_contract(owner, cw721.clone(), &set_withdraw("treasurer"), &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Ownership(cw_ownable::OwnershipError::NotOwner)
+    );
+    app.execute_contract(
+        Addr::unchecked("successor"),
+        cw721,
+        &set_withdraw("treasurer"),
+        &[]
+    ).unwrap();
+}
+
+#[test]
+fn bridging_tokens_out_and_in() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        ..Default::default()
+    });
+    let bridge = bridge::instantiate_bridge(&mut app);
+    let native = mint(&mut app, &cw721, "holder", &[]);
+    let num_tokens = |app: &App| -> u64 {
+        let res: NumTokensResponse = app
+            .wrap()
+            .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::NumTokens {}))
+            .unwrap();
+        res.count
+    };
+    let bridge_mint = |token_id: &str| bridge::ExecuteMsg::Mint {
+        cw721: cw721.to_string(),
+        token_id: token_id.to_string(),
+        owner: "holder".to_string(),
+    };
+
+    // nothing comes in until the owner names the bridge
+    let err = app
+        .execute_contract(Addr::unchecked("anyone"), bridge.clone(), &bridge_mint("osmo/7"), &[])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NotBridge {});
+    app.execute_contract(
+        owner,
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::SetBridge { bridge: Some(bridge.to_string()) }),
+        &[]
+    ).unwrap();
+
+    // escrow-out of a native token and its way back
+    app.execute_contract(
+        Addr::unchecked("holder"),
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::SendNft {
+            contract: bridge.to_string(),
+            token_id: native.clone(),
+            msg: cosmwasm_std::to_json_binary("channel-0").unwrap(),
+        }),
+        &[]
+    ).unwrap();
+    assert_eq!(owner_of(&app, &cw721, &native), bridge.to_string());
+    app.execute_contract(
+        Addr::unchecked("relayer"),
+        bridge.clone(),
+        &(bridge::ExecuteMsg::Release {
+            cw721: cw721.to_string(),
+            token_id: native.clone(),
+            recipient: "holder".to_string(),
+        }),
+        &[]
+    ).unwrap();
+    assert_eq!(owner_of(&app, &cw721, &native), "holder");
+
+    // mint-in of a foreign token keeps its id
+    app.execute_contract(Addr::unchecked("relayer"), bridge.clone(), &bridge_mint("osmo/7"), &[])
+        .unwrap();
+    assert_eq!(owner_of(&app, &cw721, "osmo/7"), "holder");
+    assert_eq!(num_tokens(&app), 2);
+    let err = app
+        .execute_contract(Addr::unchecked("relayer"), bridge.clone(), &bridge_mint("1"), &[])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::TokenIdReserved {
+        token_id: "1".to_string(),
+    });
+    // the mint sequence carries on around it
+    assert_eq!(mint(&mut app, &cw721, "holder", &[]), "1");
+
+    // and leaves again: escrow, then burn
+    let burn = bridge::ExecuteMsg::Burn {
+        cw721: cw721.to_string(),
+        token_id: "osmo/7".to_string(),
+    };
+    let err = app
+        .execute_contract(Addr::unchecked("relayer"), bridge.clone(), &burn, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Ownership(cw_ownable::OwnershipError::NotOwner)
+    );
+    app.execute_contract(
+        Addr::unchecked("holder"),
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::SendNft {
+            contract: bridge.to_string(),
+            token_id: "osmo/7".to_string(),
+            msg: cosmwasm_std::to_json_binary("channel-1").unwrap(),
+        }),
+        &[]
+    ).unwrap();
+    app.execute_contract(Addr::unchecked("relayer"), bridge, &burn, &[]).unwrap();
+    assert_eq!(num_tokens(&app), 2);
+
+    // leaving over the bridge isn't a burn, the supply still adds up
+    let supply: crate::msg::SupplyResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::Supply {}))
+        .unwrap();
+    assert_eq!((supply.minted, supply.burned), (2, 0));
+    assert_eq!((supply.bridged_in, supply.bridged_out), (1, 1));
+    assert_eq!(supply.circulating, 2);
+    let activity: crate::msg::ActivityResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &cw721,
+            &(Cw721CustomQueryMsg::Activity { start_after: None, limit: None, order: None })
+        )
+        .unwrap();
+    let actions = activity.entries
+        .iter()
+        .filter(|entry| entry.token_id == "osmo/7")
+        .map(|entry| entry.action)
+        .collect::<Vec<_>>();
+    // newest first
+    assert_eq!(actions.first(), Some(&ActivityAction::BridgeBurn));
+    assert!(!actions.contains(&ActivityAction::Burn));
+    app.wrap()
+        .query_wasm_smart::<OwnerOfResponse>(&cw721, &(Cw721CustomQueryMsg::OwnerOf {
+            token_id: "osmo/7".to_string(),
+            include_expired: None,
+        }))
+        .unwrap_err();
+}
+
+#[test]
+fn gating_on_holdings() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        ..Default::default()
+    });
+    let gatekeeper = gatekeeper::instantiate_gatekeeper(&mut app, &cw721, 2);
+    let enter = |app: &mut App, sender: &str, msg: &gatekeeper::ExecuteMsg| {
+        app.execute_contract(Addr::unchecked(sender), gatekeeper.clone(), msg, &[])
+    };
+    let first = mint(&mut app, &cw721, "holder", &[]);
+
+    // one token is not enough for the collection-wide gate, but opens its own door
+    enter(&mut app, "holder", &gatekeeper::ExecuteMsg::Enter {}).unwrap_err();
+    let with_token = gatekeeper::ExecuteMsg::EnterWithToken { token_id: first.clone() };
+    enter(&mut app, "stranger", &with_token).unwrap_err();
+    enter(&mut app, "holder", &with_token).unwrap();
+
+    mint(&mut app, &cw721, "holder", &[]);
+    enter(&mut app, "holder", &gatekeeper::ExecuteMsg::Enter {}).unwrap();
+    let missing = gatekeeper::ExecuteMsg::EnterWithToken { token_id: "404".to_string() };
+    enter(&mut app, "holder", &missing).unwrap_err();
+    assert_eq!(gatekeeper::entered(&app, &gatekeeper), vec!["holder", "holder"]);
+
+    // the gate follows the tokens
+    app.execute_contract(
+        Addr::unchecked("holder"),
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::TransferNft {
+            recipient: "stranger".to_string(),
+            token_id: first.clone(),
+        }),
+        &[]
+    ).unwrap();
+    enter(&mut app, "holder", &with_token).unwrap_err();
+    enter(&mut app, "stranger", &with_token).unwrap();
+    let res: crate::msg::IsHolderResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::IsHolder {
+            address: "holder".to_string(),
+            min_count: Some(2),
+        }))
+        .unwrap();
+    assert_eq!(res, crate::msg::IsHolderResponse { is_holder: false, count: 1 });
+}
+
+#[test]
+fn wrapping_legacy_tokens() {
+    let mut app = App::default();
+    let legacy_code = app.store_code(
+        Box::new(
+            ContractWrapper::new(
+                cw721_base::entry::execute,
+                cw721_base::entry::instantiate,
+                cw721_base::entry::query
+            )
+        )
+    );
+    let instantiate_legacy = |app: &mut App, label: &str| {
+        let msg = cw721_base::InstantiateMsg {
+            name: "legacy".to_string(),
+            symbol: "old".to_string(),
+            minter: Some("legacy-minter".to_string()),
+            withdraw_address: None,
+        };
+        app.instantiate_contract(legacy_code, Addr::unchecked("deployer"), &msg, &[], label, None)
+            .unwrap()
+    };
+    let legacy = instantiate_legacy(&mut app, "legacy");
+    let impostor = instantiate_legacy(&mut app, "impostor");
+    let legacy_mint = |app: &mut App, collection: &Addr, token_id: &str| {
+        app.execute_contract(
+            Addr::unchecked("legacy-minter"),
+            collection.clone(),
+            &(cw721_base::ExecuteMsg::<cw721_base::Extension, Empty>::Mint {
+                token_id: token_id.to_string(),
+                owner: "holder".to_string(),
+                token_uri: Some(format!("ipfs://{token_id}")),
+                extension: None,
+            }),
+            &[]
+        ).unwrap();
+    };
+    legacy_mint(&mut app, &legacy, "7");
+    legacy_mint(&mut app, &legacy, "0");
+    legacy_mint(&mut app, &legacy, "sword");
+    legacy_mint(&mut app, &impostor, "7");
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        wrapped_collection: Some(legacy.to_string()),
+        ..Default::default()
+    });
+    let send_here = Cw721CustomExecuteMsg::SendNft {
+        contract: cw721.to_string(),
+        token_id: "7".to_string(),
+        msg: Binary::default(),
+    };
+    let legacy_owner = |app: &App| {
+        let res: OwnerOfResponse = app
+            .wrap()
+            .query_wasm_smart(&legacy, &(Cw721CustomQueryMsg::OwnerOf {
+                token_id: "7".to_string(),
+                include_expired: None,
+            }))
+            .unwrap();
+        res.owner
+    };
+
+    // only the configured collection gets wrapped
+    let err = app
+        .execute_contract(Addr::unchecked("holder"), impostor.clone(), &send_here, &[])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::UnknownCollection {});
+
+    app.execute_contract(Addr::unchecked("holder"), legacy.clone(), &send_here, &[]).unwrap();
+    assert_eq!(legacy_owner(&app), cw721.as_str());
+    // wrappers don't take ids the mint sequence will reach
+    assert_eq!(owner_of(&app, &cw721, "wrapped-7"), "holder");
+    let info: NftInfoResponse<crate::Extension> = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::NftInfo {
+            token_id: "wrapped-7".to_string(),
+        }))
+        .unwrap();
+    assert_eq!(info.token_uri.as_deref(), Some("ipfs://7"));
+
+    // burning the wrapper would strand the original, burning them all passes it over
+    let burn_msg = Cw721CustomExecuteMsg::Burn { token_id: "wrapped-7".to_string() };
+    let err = app
+        .execute_contract(Addr::unchecked("holder"), cw721.clone(), &burn_msg, &[])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::TokenWrapped {});
+    let burn_all = Cw721CustomExecuteMsg::BurnAll { start_after: None, limit: None };
+    app.execute_contract(Addr::unchecked("holder"), cw721.clone(), &burn_all, &[]).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "wrapped-7"), "holder");
+
+    // whoever holds the wrapper gets the original back
+    let unwrap = Cw721CustomExecuteMsg::Unwrap { token_id: "wrapped-7".to_string() };
+    app.execute_contract(
+        Addr::unchecked("holder"),
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::TransferNft {
+            recipient: "collector".to_string(),
+            token_id: "wrapped-7".to_string(),
+        }),
+        &[]
+    ).unwrap();
+    let err = app
+        .execute_contract(Addr::unchecked("holder"), cw721.clone(), &unwrap, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Ownership(cw_ownable::OwnershipError::NotOwner)
+    );
+    app.execute_contract(Addr::unchecked("collector"), cw721.clone(), &unwrap, &[]).unwrap();
+    assert_eq!(legacy_owner(&app), "collector");
+    let res: NumTokensResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::NumTokens {}))
+        .unwrap();
+    assert_eq!(res.count, 0);
+
+    // a wrapped "0" doesn't take the id of the first mint
+    let send_zero = Cw721CustomExecuteMsg::SendNft {
+        contract: cw721.to_string(),
+        token_id: "0".to_string(),
+        msg: Binary::default(),
+    };
+    app.execute_contract(Addr::unchecked("holder"), legacy.clone(), &send_zero, &[]).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "wrapped-0"), "holder");
+    let native = mint(&mut app, &cw721, "holder", &[]);
+    assert_eq!(native, "0");
+    assert_eq!(mint(&mut app, &cw721, "holder", &[]), "1");
+
+    // nor the id of a token that came in over the bridge
+    let bridge = bridge::instantiate_bridge(&mut app);
+    app.execute_contract(
+        owner,
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::SetBridge { bridge: Some(bridge.to_string()) }),
+        &[]
+    ).unwrap();
+    let bridge_mint = bridge::ExecuteMsg::Mint {
+        cw721: cw721.to_string(),
+        token_id: "sword".to_string(),
+        owner: "traveller".to_string(),
+    };
+    app.execute_contract(Addr::unchecked("relayer"), bridge, &bridge_mint, &[]).unwrap();
+    let send_sword = Cw721CustomExecuteMsg::SendNft {
+        contract: cw721.to_string(),
+        token_id: "sword".to_string(),
+        msg: Binary::default(),
+    };
+    app.execute_contract(Addr::unchecked("holder"), legacy.clone(), &send_sword, &[]).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "sword"), "traveller");
+    assert_eq!(owner_of(&app, &cw721, "wrapped-sword"), "holder");
+
+    // native tokens have nothing to unwrap
+    let err = app
+        .execute_contract(
+            Addr::unchecked("holder"),
+            cw721.clone(),
+            &(Cw721CustomExecuteMsg::Unwrap { token_id: native }),
+            &[]
+        )
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NotWrapped {});
+}
+
+#[test]
+fn burning_to_mint_across_collections() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let season_one = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "season one".to_string(),
+        symbol: "S1".to_string(),
+        ..Default::default()
+    });
+    let season_two = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "season two".to_string(),
+        symbol: "S2".to_string(),
+        max_supply: Some(2),
+        burn_to_mint: Some(BurnToMintConfig {
+            source_collection: season_one.clone(),
+            ratio: 2,
+            burn: true,
+            token_uri: Some("ipfs://season-two".to_string()),
+        }),
+        ..Default::default()
+    });
+    let old_tokens: Vec<String> = (0..4)
+        .map(|_| mint(&mut app, &season_one, "holder", &[]))
+        .collect();
+    let trade_in = |app: &mut App, token_id: &str| {
+        app.execute_contract(
+            Addr::unchecked("holder"),
+            season_one.clone(),
+            &(Cw721CustomExecuteMsg::SendNft {
+                contract: season_two.to_string(),
+                token_id: token_id.to_string(),
+                msg: Binary::default(),
+            }),
+            &[]
+        )
+    };
+    let num_tokens = |app: &App, cw721: &Addr| -> u64 {
+        let res: NumTokensResponse = app
+            .wrap()
+            .query_wasm_smart(cw721, &(Cw721CustomQueryMsg::NumTokens {}))
+            .unwrap();
+        res.count
+    };
+
+    // two old tokens buy a new one, both get burned
+    trade_in(&mut app, &old_tokens[0]).unwrap();
+    let credits: u64 = app
+        .wrap()
+        .query_wasm_smart(&season_two, &(Cw721CustomQueryMsg::BurnToMintCredits {
+            address: "holder".to_string(),
+        }))
+        .unwrap();
+    assert_eq!(credits, 1);
+    assert_eq!(num_tokens(&app, &season_two), 0);
+    trade_in(&mut app, &old_tokens[1]).unwrap();
+    assert_eq!(owner_of(&app, &season_two, "0"), "holder");
+    assert_eq!(num_tokens(&app, &season_one), 2);
+    let info: NftInfoResponse<crate::Extension> = app
+        .wrap()
+        .query_wasm_smart(&season_two, &(Cw721CustomQueryMsg::NftInfo {
+            token_id: "0".to_string(),
+        }))
+        .unwrap();
+    assert_eq!(info.token_uri.as_deref(), Some("ipfs://season-two"));
+
+    // escrowed one for one, up to the max supply. A send the collection can't honour
+    // leaves the token with its holder
+    app.execute_contract(
+        owner,
+        season_two.clone(),
+        &(Cw721CustomExecuteMsg::SetBurnToMint {
+            config: Some(BurnToMintConfig {
+                source_collection: season_one.clone(),
+                ratio: 1,
+                burn: false,
+                token_uri: None,
+            }),
+        }),
+        &[]
+    ).unwrap();
+    trade_in(&mut app, &old_tokens[2]).unwrap();
+    assert_eq!(owner_of(&app, &season_two, "1"), "holder");
+    assert_eq!(owner_of(&app, &season_one, &old_tokens[2]), season_two.as_str());
+    let res = trade_in(&mut app, &old_tokens[3]).unwrap();
+    let failure = res.events
+        .iter()
+        .find(|e| e.ty == "wasm-send_failed")
+        .and_then(|e| e.attributes.iter().find(|a| a.key == "error"))
+        .unwrap();
+    assert!(failure.value.contains(&ContractError::SupplyOverflow {}.to_string()));
+    assert_eq!(owner_of(&app, &season_one, &old_tokens[3]), "holder");
+}
+
+#[test]
+fn minting_straight_into_staking() {
+    let mut app = App::new(|router, _, storage| {
+        for buyer in ["buyer", "other_buyer"] {
+            router.bank
+                .init_balance(storage, &Addr::unchecked(buyer), vec![coin(1000, "usei")])
+                .unwrap();
+        }
+    });
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        price_per_nft: Some(coin(300, "usei")),
+        max_nfts_per_wallet: Some(1),
+        ..Default::default()
+    });
+    let staking = staking::instantiate_staking(&mut app);
+    let mint_and_send = |msg: &staking::ReceiveMsg| Cw721CustomExecuteMsg::MintAndSend {
+        contract: staking.to_string(),
+        msg: cosmwasm_std::to_json_binary(msg).unwrap(),
+        token_uri: None,
+        extension: None,
+    };
+    let stake = mint_and_send(&staking::ReceiveMsg::Stake {});
+
+    app.execute_contract(Addr::unchecked("buyer"), cw721.clone(), &stake, &[coin(300, "usei")])
+        .unwrap();
+    assert_eq!(owner_of(&app, &cw721, "0"), staking.to_string());
+    assert_eq!(staking::staker_of(&app, &staking, &cw721, "0"), Some("buyer".to_string()));
+    assert_eq!(app.wrap().query_balance(&cw721, "usei").unwrap(), coin(300, "usei"));
+
+    // the mint counts towards the payer's limit, not the staking contract's
+    let err = app
+        .execute_contract(Addr::unchecked("buyer"), cw721.clone(), &stake, &[coin(300, "usei")])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::MintPerWalletOverflow {});
+    let err = app
+        .execute_contract(Addr::unchecked("other_buyer"), cw721.clone(), &stake, &[])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NotEnoughFunds {});
+    app.execute_contract(
+        Addr::unchecked("other_buyer"),
+        cw721.clone(),
+        &stake,
+        &[coin(300, "usei")]
+    ).unwrap();
+    assert_eq!(staking::staker_of(&app, &staking, &cw721, "1"), Some("other_buyer".to_string()));
+}
+
+#[test]
+fn royalty_registry_gates_operators() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let registry = registry::instantiate_registry(&mut app, &["market"]);
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        royalty_registry: Some(registry.to_string()),
+        ..Default::default()
+    });
+    for _ in 0..4 {
+        mint(&mut app, &cw721, "holder", &[]);
+    }
+    for operator in ["market", "shady_market"] {
+        app.execute_contract(
+            Addr::unchecked("holder"),
+            cw721.clone(),
+            &(Cw721CustomExecuteMsg::ApproveAll {
+                operator: operator.to_string(),
+                expires: None,
+                max_uses: None,
+                permissions: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+    let transfer = |token_id: &str| Cw721CustomExecuteMsg::TransferNft {
+        recipient: "buyer".to_string(),
+        token_id: token_id.to_string(),
+    };
+
+    app.execute_contract(Addr::unchecked("market"), cw721.clone(), &transfer("0"), &[]).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "0"), "buyer");
+    let err = app
+        .execute_contract(Addr::unchecked("shady_market"), cw721.clone(), &transfer("1"), &[])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::OperatorNotCompliant {});
+    // the owner doesn't need the registry
+    app.execute_contract(Addr::unchecked("holder"), cw721.clone(), &transfer("1"), &[]).unwrap();
+
+    // answers are reused for the rest of the block
+    app.execute_contract(
+        Addr::unchecked("deployer"),
+        registry.clone(),
+        &(registry::ExecuteMsg::SetCompliant { operator: "market".to_string(), compliant: false }),
+        &[]
+    ).unwrap();
+    app.execute_contract(Addr::unchecked("market"), cw721.clone(), &transfer("2"), &[]).unwrap();
+    app.update_block(|block| {
+        block.height += 1;
+    });
+    let err = app
+        .execute_contract(Addr::unchecked("market"), cw721.clone(), &transfer("3"), &[])
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::OperatorNotCompliant {});
+
+    // without a registry any operator can transfer again
+    app.execute_contract(
+        owner.clone(),
+        cw721.clone(),
+        &(Cw721CustomExecuteMsg::SetRoyaltyRegistry { registry: None }),
+        &[]
+    ).unwrap();
+    app.execute_contract(Addr::unchecked("market"), cw721.clone(), &transfer("3"), &[]).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "3"), "buyer");
+}
+
+#[test]
+fn minting_through_partners() {
+    let mut app = App::new(|router, _, storage| {
+        router.bank
+            .init_balance(storage, &Addr::unchecked("buyer"), vec![coin(2000, "usei")])
+            .unwrap();
+    });
+    let owner = Addr::unchecked("owner");
+    let cw721 = instantiate_cw721(&mut app, &owner, InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "symbol".to_string(),
+        price_per_nft: Some(coin(400, "usei")),
+        max_nfts_per_wallet: Some(1),
+        ..Default::default()
+    });
+    let launchpad = partner::instantiate_partner(&mut app, &cw721);
+    let mint_for = |recipient: &str| partner::ExecuteMsg::MintFor {
+        recipient: recipient.to_string(),
+    };
+
+    // an unregistered contract mints like anyone else
+    app.execute_contract(
+        Addr::unchecked("buyer"),
+        launchpad.clone(),
+        &mint_for("buyer"),
+        &[coin(400, "usei")]
+    ).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "0"), "buyer");
+    assert_eq!(app.wrap().query_balance(&cw721, "usei").unwrap(), coin(400, "usei"));
+
+    let set_partner = |config: Option<PartnerConfig>| Cw721CustomExecuteMsg::SetPartnerMinter {
+        address: launchpad.to_string(),
+        config,
+    };
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            cw721.clone(),
+            &set_partner(Some(PartnerConfig { fee_bps: 10_001, counts_against_recipient: true })),
+            &[]
+        )
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidPartnerFee {});
+    app.execute_contract(
+        owner.clone(),
+        cw721.clone(),
+        &set_partner(Some(PartnerConfig { fee_bps: 500, counts_against_recipient: true })),
+        &[]
+    ).unwrap();
+    let res: crate::msg::PartnerMintersResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(Cw721CustomQueryMsg::PartnerMinters {
+            start_after: None,
+            limit: None,
+        }))
+        .unwrap();
+    assert_eq!(res.partners.len(), 1);
+    assert_eq!(res.partners[0].address, launchpad.to_string());
+
+    // the limit is still the recipient's, not the launchpad's
+    let err = app
+        .execute_contract(
+            Addr::unchecked("buyer"),
+            launchpad.clone(),
+            &mint_for("buyer"),
+            &[coin(400, "usei")]
+        )
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::MintPerWalletOverflow {});
+    let res = app
+        .execute_contract(
+            Addr::unchecked("buyer"),
+            launchpad.clone(),
+            &mint_for("friend"),
+            &[coin(400, "usei")]
+        )
+        .unwrap();
+    assert!(res.has_event(&cosmwasm_std::Event::new("wasm").add_attribute("partner", &launchpad)));
+    assert_eq!(owner_of(&app, &cw721, "1"), "friend");
+    // 5% of the price went to the launchpad
+    assert_eq!(app.wrap().query_balance(&launchpad, "usei").unwrap(), coin(20, "usei"));
+    assert_eq!(app.wrap().query_balance(&cw721, "usei").unwrap(), coin(780, "usei"));
+
+    // a partner trusted with its own limits can mint past them
+    app.execute_contract(
+        owner.clone(),
+        cw721.clone(),
+        &set_partner(Some(PartnerConfig { fee_bps: 0, counts_against_recipient: false })),
+        &[]
+    ).unwrap();
+    app.execute_contract(
+        Addr::unchecked("buyer"),
+        launchpad.clone(),
+        &mint_for("buyer"),
+        &[coin(400, "usei")]
+    ).unwrap();
+    assert_eq!(owner_of(&app, &cw721, "2"), "buyer");
+    assert_eq!(app.wrap().query_balance(&launchpad, "usei").unwrap(), coin(20, "usei"));
+
+    app.execute_contract(owner.clone(), cw721.clone(), &set_partner(None), &[]).unwrap();
+    let err = app
+        .execute_contract(
+            Addr::unchecked("buyer"),
+            launchpad.clone(),
+            &mint_for("buyer"),
+            &[coin(400, "usei")]
+        )
+        .unwrap_err();
+    assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::MintPerWalletOverflow {});
+}
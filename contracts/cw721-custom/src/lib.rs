@@ -10,9 +10,13 @@ pub mod upgrades;
 mod contract_tests;
 #[cfg(test)]
 mod multi_tests;
+#[cfg(test)]
+mod integration_tests;
+#[cfg(test)]
+mod testing;
 
 pub use crate::error::ContractError;
-pub use crate::msg::{ ExecuteMsg, InstantiateMsg, MinterResponse, QueryMsg };
+pub use crate::msg::{ ExecuteMsg, InstantiateMsg, MigrateMsg, MinterResponse, QueryMsg };
 pub use crate::state::Cw721Contract;
 
 // These types are re-exported so that contracts interacting with this
@@ -23,27 +27,50 @@ pub use crate::state::Cw721Contract;
 // `ContractError::Ownership`.
 pub use cw_ownable::{ Action, Ownership, OwnershipError };
 
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Empty;
 
-// This is a simple type to let us handle empty extensions
-pub type Extension = Option<Empty>;
+#[cw_serde]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+// see: https://docs.opensea.io/docs/metadata-standards
+#[cw_serde]
+#[derive(Default)]
+pub struct Metadata {
+    pub image: Option<String>,
+    pub image_data: Option<String>,
+    pub external_url: Option<String>,
+    pub description: Option<String>,
+    pub name: Option<String>,
+    pub attributes: Option<Vec<Trait>>,
+    pub background_color: Option<String>,
+    pub animation_url: Option<String>,
+    pub youtube_url: Option<String>,
+}
+
+// On-chain metadata of the deployed contract. Every field is optional, so tokens stored
+// with an empty extension by cw721-base still load
+pub type Extension = Option<Metadata>;
+
+// The generics as deployed, so schemas and clients don't have to spell them out
+pub type Cw721CustomContract<'a> = Cw721Contract<'a, Extension, Empty, Empty, Empty>;
+pub type Cw721CustomExecuteMsg = ExecuteMsg<Extension, Empty>;
+pub type Cw721CustomQueryMsg = QueryMsg<Empty>;
 
 // Version info for migration
 pub const CONTRACT_NAME: &str = "crates.io:cw721-base";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-// currently we only support migrating from 0.16.0. this is ok for now because
-// we have not released any 0.16.x where x != 0
-//
-// TODO: parse semvar so that any version 0.16.x can be migrated from
-pub const EXPECTED_FROM_VERSION: &str = "0.16.0";
-
 pub mod entry {
     use super::*;
 
     #[cfg(not(feature = "library"))]
     use cosmwasm_std::entry_point;
-    use cosmwasm_std::{ Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult };
+    use cosmwasm_std::{ Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult };
 
     // This makes a conscious choice on the various generics used by the contract
     #[cfg_attr(not(feature = "library"), entry_point)]
@@ -55,7 +82,7 @@ pub mod entry {
     ) -> Result<Response, ContractError> {
         cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-        let tract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+        let tract = Cw721CustomContract::default();
         tract.instantiate(deps, env, info, msg)
     }
 
@@ -64,29 +91,48 @@ pub mod entry {
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        msg: ExecuteMsg<Extension, Empty>
+        msg: Cw721CustomExecuteMsg
     ) -> Result<Response, ContractError> {
-        let tract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+        let tract = Cw721CustomContract::default();
         tract.execute(deps, env, info, msg)
     }
 
     #[cfg_attr(not(feature = "library"), entry_point)]
-    pub fn query(deps: Deps, env: Env, msg: QueryMsg<Empty>) -> StdResult<Binary> {
-        let tract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    pub fn query(deps: Deps, env: Env, msg: Cw721CustomQueryMsg) -> StdResult<Binary> {
+        let tract = Cw721CustomContract::default();
         tract.query(deps, env, msg)
     }
 
     #[cfg_attr(not(feature = "library"), entry_point)]
-    pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
-        // make sure the correct contract is being upgraded, and it's being
-        // upgraded from the correct version.
-        cw2::assert_contract_version(deps.as_ref().storage, CONTRACT_NAME, EXPECTED_FROM_VERSION)?;
-
-        // update contract version
-        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+        let tract = Cw721CustomContract::default();
+        tract.reply(deps, env, msg)
+    }
 
-        // perform the upgrade
-        upgrades::v0_17::migrate::<Extension, Empty, Empty, Empty>(deps)
+    #[cfg_attr(not(feature = "library"), entry_point)]
+    pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+        match msg {
+            MigrateMsg::Upgrade {} => upgrades::upgrade(deps),
+            MigrateMsg::FromCw721Base { max_supply, max_nfts_per_wallet, price_per_nft } =>
+                upgrades::from_cw721_base(deps, max_supply, max_nfts_per_wallet, price_per_nft),
+            MigrateMsg::UpdateMintSettings {
+                max_supply,
+                max_nfts_per_wallet,
+                price_per_nft,
+                withdraw_address,
+            } =>
+                upgrades::update_mint_settings(
+                    deps,
+                    max_supply,
+                    max_nfts_per_wallet,
+                    price_per_nft,
+                    withdraw_address
+                ),
+            MigrateMsg::ImportState { section, data } => {
+                let tract = Cw721CustomContract::default();
+                tract.import_state(deps, env, section, data)
+            }
+        }
     }
 }
 
@@ -145,4 +191,67 @@ mod tests {
             .owner.map(|a| a.into_string());
         assert_eq!(minter, Some("owner".to_string()));
     }
+
+    fn instantiate_collection(deps: cosmwasm_std::DepsMut) {
+        entry
+            ::instantiate(deps, mock_env(), mock_info("larry", &[]), InstantiateMsg {
+                name: "Sky".into(),
+                symbol: "SKY".into(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn migrating_from_older_version() {
+        let mut deps = mock_dependencies();
+        instantiate_collection(deps.as_mut());
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.17.0").unwrap();
+
+        let res = entry::migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+        assert!(res.attributes.contains(&cosmwasm_std::attr("from_version", "0.17.0")));
+        assert!(res.attributes.contains(&cosmwasm_std::attr("to_version", CONTRACT_VERSION)));
+
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // migrating again is refused, the stored version is no longer older
+        let err = entry::migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert_eq!(err, ContractError::MigrationNotNewer {
+            from: CONTRACT_VERSION.into(),
+            to: CONTRACT_VERSION.into(),
+        });
+    }
+
+    #[test]
+    fn migrating_rejects_other_contract() {
+        let mut deps = mock_dependencies();
+        instantiate_collection(deps.as_mut());
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.17.0").unwrap();
+
+        let err = entry::migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Version(cw2::VersionError::WrongContract {
+                expected: CONTRACT_NAME.into(),
+                found: "crates.io:cw20-base".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn querying_contract_version() {
+        let mut deps = mock_dependencies();
+        instantiate_collection(deps.as_mut());
+
+        let res = entry::query(deps.as_ref(), mock_env(), QueryMsg::ContractVersion {}).unwrap();
+        let version: msg::ContractVersionResponse = cosmwasm_std::from_json(res).unwrap();
+        assert_eq!(version, msg::ContractVersionResponse {
+            contract: Some(CONTRACT_NAME.to_string()),
+            version: Some(CONTRACT_VERSION.to_string()),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            features: version.features.clone(),
+        });
+        assert_eq!(version.features.contains(&"library".to_string()), cfg!(feature = "library"));
+    }
 }
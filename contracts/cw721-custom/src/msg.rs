@@ -1,10 +1,50 @@
 use cosmwasm_schema::{ cw_serde, QueryResponses };
-use cosmwasm_std::{ Binary, Coin };
-use cw721::Expiration;
+use cosmwasm_std::{
+    to_json_binary,
+    Binary,
+    Coin,
+    CosmosMsg,
+    Order,
+    StdResult,
+    Timestamp,
+    Uint128,
+    WasmMsg,
+};
+use cw20::Cw20ReceiveMsg;
+use cw721::{ Approval, Cw721ReceiveMsg, Expiration };
 use cw_ownable::{ cw_ownable_execute, cw_ownable_query };
 use schemars::JsonSchema;
 
+use crate::state::{
+    ActivityEntry,
+    BurnRecord,
+    Auction,
+    Bundle,
+    BurnToMintConfig,
+    CoSignConfig,
+    HistoryEntry,
+    Listing,
+    Offer,
+    OperatorGrant,
+    OperatorPermissions,
+    PaginationConfig,
+    PartnerConfig,
+    RafflePhase,
+    SubscriptionConfig,
+    Redemption,
+    RedemptionStatus,
+    RewardAsset,
+    Royalty,
+    SnapshotStrategy,
+    SaleRecord,
+    SaleStats,
+    SupplyCapBasis,
+    VestingConfig,
+    WithdrawRecord,
+};
+
 #[cw_serde]
+#[derive(Default)]
 pub struct InstantiateMsg {
     /// Name of the NFT contract
     pub name: String,
@@ -20,20 +60,82 @@ pub struct InstantiateMsg {
     pub max_nfts_per_wallet: Option<u64>,
     pub price_per_nft: Option<Coin>,
     pub max_supply: Option<u64>,
-}
-
-impl Default for InstantiateMsg {
-    fn default() -> Self {
-        Self {
-            name: Default::default(),
-            symbol: Default::default(),
-            minter: Default::default(),
-            withdraw_address: Default::default(),
-            max_nfts_per_wallet: Default::default(),
-            price_per_nft: Default::default(),
-            max_supply: Default::default(),
-        }
-    }
+    /// Whether `max_supply` caps every token ever minted or only those in circulation,
+    /// defaults to minted
+    pub supply_cap_basis: Option<SupplyCapBasis>,
+    /// Whether operators and approved spenders may burn tokens, defaults to true.
+    /// When false only the token owner can burn, while transfer permissions are unchanged.
+    pub operators_can_burn: Option<bool>,
+    /// If set, recipients of mints, transfers and sends must use this bech32 prefix
+    pub bech32_prefix: Option<String>,
+    /// Paid from the contract balance to the redeemer when a redemption is fulfilled
+    pub redemption_payout: Option<Coin>,
+    /// How many spenders can be approved on a single token, defaults to 32
+    pub max_approvals_per_token: Option<u64>,
+    /// If set, approvals and operator grants sent without an expiration expire after this many
+    /// seconds instead of never
+    pub default_approval_ttl: Option<u64>,
+    /// Whether an explicit `Expiration::Never` is accepted, defaults to true
+    pub allow_never_expiration: Option<bool>,
+    /// If true, operator grants only move tokens when the operator is on the allowlist
+    pub enforce_operator_allowlist: Option<bool>,
+    /// If true, every mint, transfer, send and burn is appended to the history of the token.
+    /// Off by default since it grows storage with every move
+    pub record_token_history: Option<bool>,
+    /// How many history entries are kept per token, older ones are pruned. Defaults to 500
+    pub max_token_history: Option<u64>,
+    /// How many of the newest mints, transfers, burns and approvals the activity feed keeps,
+    /// 0 disables it. Defaults to 1000
+    pub activity_buffer_size: Option<u64>,
+    /// If true, tokens are minted unrevealed with a placeholder token_uri, which the owner
+    /// replaces through `RevealTokens`
+    pub delayed_reveal: Option<bool>,
+    /// How past holder balances are kept for `HolderBalanceAt` and `NumHoldersAt`,
+    /// defaults to never. Can't be changed later
+    pub balance_snapshots: Option<SnapshotStrategy>,
+    /// If true, burning a token keeps a record of its last owner and the burn time.
+    /// Off by default since it grows storage with every burn
+    pub retain_burned_tokens: Option<bool>,
+    /// If true, tokens can be looked up by their token_uri through `TokensByUri`
+    pub index_token_uris: Option<bool>,
+    /// Default and maximum page size of the listing queries, defaults to 10 and 1000.
+    /// Some listings keep a tighter maximum of their own
+    pub pagination: Option<PaginationConfig>,
+    /// If true, state exported from another deployment can be written with
+    /// `MigrateMsg::ImportState` until the owner calls `FinalizeImport`
+    pub import_open: Option<bool>,
+    /// Cut of every sale through `Buy` paid to the collection
+    pub royalty: Option<Royalty>,
+    /// Bids this many seconds before the end of an auction push the end back to this many
+    /// seconds after the bid, defaults to 600
+    pub auction_extension: Option<u64>,
+    /// If true, the user set with `SetUser` keeps the token when it changes hands.
+    /// By default every transfer clears it
+    pub keep_user_on_transfer: Option<bool>,
+    /// Seconds a token must stay staked before it can be unstaked, none by default
+    pub min_stake_period: Option<u64>,
+    /// Legacy collection whose tokens are wrapped when sent here with `SendNft`. The wrapper
+    /// takes the token id prefixed with `wrapped-` and copies the token_uri and extension of
+    /// the original, so the original extension must decode as this one
+    pub wrapped_collection: Option<String>,
+    /// Lets holders of another collection trade its tokens in for new ones here
+    pub burn_to_mint: Option<BurnToMintConfig>,
+    /// Enables `FundRewards`, paying holders in the given asset
+    pub reward_asset: Option<RewardAsset>,
+    /// Registry contract answering `RoyaltyRegistryQueryMsg::IsCompliant`, operators it
+    /// doesn't vouch for can't transfer tokens
+    pub royalty_registry: Option<String>,
+    /// secp256k1 public key of the creator, enables `RedeemVoucher`
+    pub voucher_pubkey: Option<Binary>,
+    /// Makes tokens passes that expire unless renewed
+    pub subscription: Option<SubscriptionConfig>,
+    /// Requires every co-signer to approve mints, which then go through `ProposeMint`
+    pub co_signing: Option<CoSignConfig>,
+    /// Unlocks mint proceeds for withdrawal over time. Can't be changed later
+    pub proceeds_vesting: Option<VestingConfig>,
+    /// Start and end of an open edition. Mints are only allowed in between and aren't
+    /// capped, then `max_supply` is fixed to the tokens in circulation. Excludes `max_supply`
+    pub open_edition: Option<(Timestamp, Timestamp)>,
 }
 
 /// This is like Cw721ExecuteMsg but we add a Mint command for an owner
@@ -54,6 +156,27 @@ pub enum ExecuteMsg<T, E> {
         token_id: String,
         msg: Binary,
     },
+    /// Approve the receiving contract on the token, then send it there like SendNft.
+    /// If the receiver fails, the token comes back without the new approval
+    ApproveAndSend {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+        expires: Option<Expiration>,
+    },
+    /// Like TransferNft, but fails unless the token is currently owned by `from`
+    TransferFrom {
+        from: String,
+        recipient: String,
+        token_id: String,
+    },
+    /// Like SendNft, but fails unless the token is currently owned by `from`
+    SendFrom {
+        from: String,
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
     /// Allows operator to transfer / send the token from the owner's account.
     /// If expiration is set, then this allowance has a time/height limit
     Approve {
@@ -61,21 +184,46 @@ pub enum ExecuteMsg<T, E> {
         token_id: String,
         expires: Option<Expiration>,
     },
+    /// Approve several spenders on one token at once, fails as a whole if any entry is invalid
+    ApproveMultiple {
+        spenders: Vec<(String, Option<Expiration>)>,
+        token_id: String,
+    },
     /// Remove previously granted Approval
     Revoke {
         spender: String,
         token_id: String,
     },
+    /// Remove every Approval granted on a token
+    RevokeAllTokenApprovals {
+        token_id: String,
+    },
     /// Allows operator to transfer / send any token from the owner's account.
-    /// If expiration is set, then this allowance has a time/height limit
+    /// If expiration is set, then this allowance has a time/height limit.
+    /// If max_uses is set, the operator can only move that many tokens and can't approve spenders.
+    /// If permissions are unset, the operator can do everything the owner can
     ApproveAll {
         operator: String,
         expires: Option<Expiration>,
+        max_uses: Option<u64>,
+        permissions: Option<OperatorPermissions>,
+    },
+    /// Push back the expiration of an existing ApproveAll grant, keeping its other settings.
+    /// The new expiration must be later than the current one
+    ExtendOperator {
+        operator: String,
+        new_expiration: Expiration,
     },
     /// Remove previously granted ApproveAll permission
     RevokeAll {
         operator: String,
     },
+    /// Remove up to `limit` expired operator grants, optionally only those of `granter`.
+    /// Anyone can call this.
+    SweepExpiredOperators {
+        granter: Option<String>,
+        limit: Option<u32>,
+    },
 
     /// Mint a new NFT, can only be called by the contract minter
     Mint {
@@ -88,11 +236,73 @@ pub enum ExecuteMsg<T, E> {
         /// Any custom extension used by this contract
         extension: T,
     },
+    /// Mints a new NFT to the sender and sends it to `contract` right away, as with
+    /// `SendNft`. The sender pays the mint price and the token counts towards the sender's
+    /// wallet limit. If the receiver fails the token stays with the sender
+    MintAndSend {
+        contract: String,
+        msg: Binary,
+        token_uri: Option<String>,
+        extension: T,
+    },
 
     /// Burn an NFT the sender has access to
     Burn {
         token_id: String,
     },
+    /// Burn tokens owned by the sender, looking at up to `limit` of them after `start_after`.
+    /// Only the owner can call this, operators and approved spenders are ignored. Nested,
+    /// parent, staked, wrapped and for sale tokens are skipped but count against the limit.
+    /// Until the response reports `done`, call again with its `next_start_after`.
+    BurnAll {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Burn an NFT the sender has access to and notify `contract` with a
+    /// `Cw721BurnReceiveMsg`. If `rollback_on_error` is set, a failing receiver
+    /// reverts the burn, otherwise the burn stands and the failure is reported in an event.
+    BurnAndNotify {
+        token_id: String,
+        contract: String,
+        msg: Binary,
+        rollback_on_error: bool,
+    },
+
+    /// Redeem a token for its physical or utility counterpart. Only the token owner can
+    /// call this. The token is kept but can't be transferred anymore.
+    Redeem {
+        token_id: String,
+        redemption_data: Option<String>,
+    },
+    /// Mark a redemption as fulfilled and pay out the configured redemption payout.
+    /// Only owner can call this.
+    FulfillRedemption {
+        token_id: String,
+    },
+
+    /// Nest a token into another one. Both must be controlled by the sender.
+    /// A nested token can't be moved on its own, it follows the owner of its parent.
+    NestToken {
+        child_id: String,
+        parent_id: String,
+    },
+    /// Detach a nested token, it becomes owned by the current owner of its parent
+    UnnestToken {
+        child_id: String,
+    },
+    /// Equip a token with an accessory from the collection. The accessory is nested into
+    /// the token and `NftInfo` of the token merges its extension in, if the contract was
+    /// built with `with_accessory_merge`. The sender must own both directly.
+    Attach {
+        parent_id: String,
+        accessory_id: String,
+    },
+    /// Take an accessory off, it becomes owned by the current owner of the token.
+    /// Only the owner of the token can call this.
+    Detach {
+        parent_id: String,
+        accessory_id: String,
+    },
 
     /// Extension msg
     Extension {
@@ -110,6 +320,358 @@ pub enum ExecuteMsg<T, E> {
     WithdrawFunds {
         amount: Coin,
     },
+    /// Adds a marketplace to the operator allowlist. Only owner can call this.
+    AddAllowedOperator {
+        operator: String,
+    },
+    /// Removes a marketplace from the operator allowlist. Only owner can call this.
+    RemoveAllowedOperator {
+        operator: String,
+    },
+    /// Sets the final token_uri of unrevealed tokens, given as (token_id, token_uri) pairs.
+    /// Tokens can be revealed in any order and batches. Only owner can call this.
+    RevealTokens {
+        tokens: Vec<(String, String)>,
+    },
+    /// Makes holder balances queryable at the current height.
+    /// Only with the selected snapshot strategy, only owner can call this.
+    CheckpointBalances {},
+    /// Approve on behalf of the owner with a permit they signed off-chain.
+    /// Anyone can submit it, e.g. the marketplace being approved.
+    ApproveWithPermit {
+        permit: ApprovalPermit,
+    },
+    /// Closes `MigrateMsg::ImportState` for good. Only owner can call this.
+    FinalizeImport {},
+    /// Rewrites up to `limit` tokens still stored in an older shape after a migration.
+    /// Tokens read fine in either shape meanwhile. Anyone can call this until the
+    /// response reports `done`.
+    MigrateBatch {
+        limit: Option<u32>,
+    },
+
+    /// Sets or clears the ICS-721 bridge. Only owner can call this.
+    SetBridge {
+        bridge: Option<String>,
+    },
+    /// Mints a token coming back over IBC under the id it left with. Numeric ids are
+    /// reserved for `Mint`. Only the bridge can call this.
+    BridgeMint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: T,
+    },
+    /// Burns a token the bridge holds once it has been sent to another chain.
+    /// Only the bridge can call this.
+    BridgeBurn {
+        token_id: String,
+    },
+    /// Sets or clears the operator of a lock/release bridge. Only owner can call this.
+    SetBridgeOperator {
+        operator: Option<String>,
+    },
+    /// Locks a token with the contract until the bridge operator releases it, keeping its
+    /// history. Only the token owner can call this.
+    BridgeLock {
+        token_id: String,
+    },
+    /// Hands a locked token to `recipient`. Only the bridge operator can call this.
+    BridgeRelease {
+        token_id: String,
+        recipient: String,
+    },
+
+    /// Puts a token up for sale at a fixed price. The token can't move until it is
+    /// sold, delisted or the listing expires. Listing again replaces the listing.
+    /// Only the token owner can call this.
+    ListToken {
+        token_id: String,
+        price: Coin,
+        expires: Option<Expiration>,
+    },
+    /// Takes a token off sale. Only the token owner can call this.
+    Delist {
+        token_id: String,
+    },
+    /// Buys a listed token with at least the listing price attached. The seller gets the
+    /// price minus the royalty and anything paid on top is refunded.
+    Buy {
+        token_id: String,
+    },
+    /// Puts several tokens up for sale together at one price. None of them can move until
+    /// the bundle is sold, delisted or expires. The sender must own every token.
+    ListBundle {
+        token_ids: Vec<String>,
+        price: Coin,
+        expires: Option<Expiration>,
+    },
+    /// Takes a bundle off sale, also once some of its tokens are gone.
+    /// Only the seller can call this.
+    DelistBundle {
+        bundle_id: u64,
+    },
+    /// Buys every token of a bundle with at least the bundle price attached. The seller
+    /// gets the price minus the royalty and anything paid on top is refunded.
+    BuyBundle {
+        bundle_id: u64,
+    },
+    /// Sets or clears the royalty taken from sales. Only owner can call this.
+    SetRoyalty {
+        royalty: Option<Royalty>,
+    },
+
+    /// Auctions a token for `duration` seconds. The token can't move until the auction is
+    /// settled. Only the token owner can call this.
+    CreateAuction {
+        token_id: String,
+        min_bid: Coin,
+        duration: u64,
+    },
+    /// Bids the attached funds, which must beat the highest bid. The bid it beats is
+    /// refunded. A bid close to the end extends the auction.
+    PlaceBid {
+        token_id: String,
+    },
+    /// Ends an auction past its end time, anyone can call this. The highest bidder gets the
+    /// token and the seller the bid minus the royalty. Without bids the token stays with
+    /// the seller.
+    SettleAuction {
+        token_id: String,
+    },
+
+    /// Offers the attached funds for a token, held by the contract until the offer is
+    /// accepted or withdrawn. Offering again replaces the offer and refunds the old one.
+    MakeOffer {
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Returns the funds of an offer to its bidder. Only the bidder can call this, unless
+    /// the offer has expired, then anyone can on behalf of `bidder`.
+    WithdrawOffer {
+        token_id: String,
+        bidder: Option<String>,
+    },
+    /// Sells the token to `bidder` for their offer minus the royalty.
+    /// Only the token owner can call this.
+    AcceptOffer {
+        token_id: String,
+        bidder: String,
+    },
+
+    /// Lets `user` use the token until `expires` without owning it, or clears the user.
+    /// The user can't move the token. Only the owner or an approved spender can call this.
+    SetUser {
+        token_id: String,
+        user: Option<String>,
+        expires: Expiration,
+    },
+
+    /// Locks the token with its owner, it can't be transferred, sent, sold or burned until
+    /// unstaked. Only the token owner can call this.
+    Stake {
+        token_id: String,
+    },
+    /// Unlocks a staked token once the minimum stake period has passed.
+    /// Only the token owner can call this.
+    Unstake {
+        token_id: String,
+    },
+    /// Sets how many seconds tokens stay staked before they can be unstaked, also for
+    /// tokens already staked. Only owner can call this.
+    SetMinStakePeriod {
+        period: u64,
+    },
+
+    /// Takes a token sent here with `SendNft`. A token of the wrapped collection is wrapped:
+    /// the original stays in escrow and the sender gets a wrapper with the same token id.
+    /// A token of the burn-to-mint source counts towards a new token for the sender.
+    ReceiveNft(Cw721ReceiveMsg),
+    /// Burns a wrapper and sends the escrowed original to the sender.
+    /// Only the wrapper owner can call this.
+    Unwrap {
+        token_id: String,
+    },
+    /// Sets or stops burn-to-mint. Credits towards the next token are kept.
+    /// Only owner can call this.
+    SetBurnToMint {
+        config: Option<BurnToMintConfig>,
+    },
+
+    /// Lends the use rights of the token to `delegate`, replacing any previous delegate.
+    /// The delegate can't move the token and loses the rights when it changes hands.
+    /// Only the token owner can call this.
+    Delegate {
+        token_id: String,
+        delegate: String,
+        expires: Option<Expiration>,
+    },
+    /// Takes the use rights back from the delegate. Only the token owner can call this.
+    Undelegate {
+        token_id: String,
+    },
+
+    /// Splits the attached funds evenly over the tokens in circulation, except bridge-locked
+    /// ones, holders claim their share with `ClaimRewards`. Only with a native reward asset
+    FundRewards {},
+    /// Takes CW20 tokens sent here, a `Cw20HookMsg::FundRewards` hook funds the rewards
+    /// like `FundRewards` does. Only from the CW20 reward asset
+    Receive(Cw20ReceiveMsg),
+    /// Pays out the rewards the sender's tokens earned while they held them
+    ClaimRewards {},
+    /// Sets what rewards are paid in, only before any rewards were funded.
+    /// Only owner can call this.
+    SetRewardAsset {
+        asset: Option<RewardAsset>,
+    },
+    /// Sets or removes the royalty registry operators are checked against.
+    /// Only owner can call this.
+    SetRoyaltyRegistry {
+        registry: Option<String>,
+    },
+
+    /// Mints the token described by a voucher the creator signed off-chain to the sender,
+    /// who pays the voucher price instead of the mint price. Each nonce can be redeemed once
+    RedeemVoucher {
+        voucher: MintVoucher<T>,
+        /// secp256k1 signature over the sha256 hash of the JSON encoded `voucher`,
+        /// made with the key set as `voucher_pubkey`
+        signature: Binary,
+    },
+    /// Sets or removes the public key vouchers are checked against.
+    /// Only owner can call this.
+    SetVoucherPubkey {
+        pubkey: Option<Binary>,
+    },
+    /// Registers a contract minting on behalf of its users, or removes it with `None`.
+    /// Only owner can call this.
+    SetPartnerMinter {
+        address: String,
+        config: Option<PartnerConfig>,
+    },
+
+    /// Opens a raffle for the remaining supply, taking entries until `ends_at`. Other mints
+    /// wait until every winner claimed. Unless the seed is revealed within
+    /// `RAFFLE_REVEAL_PERIOD` of `ends_at`, the raffle is called off. Only owner can call this.
+    StartRaffle {
+        ends_at: Timestamp,
+    },
+    /// Enters the sender with the mint price, which stays in escrow. One entry per wallet
+    EnterRaffle {},
+    /// Closes entries and commits to a seed by its sha256 hash. Only owner can call this.
+    CommitRaffleSeed {
+        hash: Binary,
+    },
+    /// Reveals the committed seed, which draws the winners. Only owner can call this.
+    RevealRaffleSeed {
+        seed: Binary,
+    },
+    /// Mints the token of a winning entry, if the wallet is still under `max_nfts_per_wallet`
+    ClaimMint {},
+    /// Returns the escrowed price of a losing entry, of any entry of a called off raffle, or
+    /// of a winning entry whose wallet reached `max_nfts_per_wallet` since entering
+    ClaimRefund {},
+
+    /// Sets or removes the subscription terms. Passes minted before keep their expiry.
+    /// Only owner can call this.
+    SetSubscription {
+        config: Option<SubscriptionConfig>,
+    },
+    /// Extends a pass by a period, counting from its expiry or from now if it already
+    /// expired. Anyone can pay for a renewal
+    Renew {
+        token_id: String,
+    },
+    /// Burns passes expired for longer than `reap_after`, oldest first, looking at up to
+    /// `limit` of them after where the previous call stopped. Passes that can't be burned
+    /// right now are skipped but count against the limit, once the response reports `done`
+    /// the next call starts over. Only owner can call this.
+    ReapExpired {
+        limit: Option<u32>,
+    },
+
+    /// Sets or removes the co-signers mints need. Pending proposals need the approval of
+    /// whoever is a co-signer when they complete. Only owner can call this.
+    SetCoSigning {
+        config: Option<CoSignConfig>,
+    },
+    /// Proposes a mint, approved by the sender. Only co-signers can call this.
+    ProposeMint {
+        owner: String,
+        token_uri: Option<String>,
+        extension: T,
+    },
+    /// Approves a proposed mint, the last approval mints the token.
+    /// Only co-signers can call this.
+    ApproveMint {
+        proposal_id: u64,
+    },
+    /// Withdraws a proposed mint. Only the proposer can call this.
+    CancelMint {
+        proposal_id: u64,
+    },
+    /// Records a sale settled by a marketplace outside this contract, for the token's current
+    /// owner as buyer. Only operators on the allowlist can call this.
+    ReportSale {
+        token_id: String,
+        price: Coin,
+    },
+}
+
+/// A mint the creator signed off-chain, nothing is stored until it is redeemed
+#[cw_serde]
+pub struct MintVoucher<T> {
+    /// The collection the voucher is for, so it can't be redeemed elsewhere
+    pub collection: String,
+    /// A specific token id, or None for the next one of the sequence. Like with
+    /// `BridgeMint`, numeric ids are reserved for the sequence
+    pub token_id: Option<String>,
+    pub token_uri: Option<String>,
+    pub extension: T,
+    /// What the buyer pays, None for a free mint
+    pub price: Option<Coin>,
+    pub expires: Expiration,
+    /// Any number, but each one can only be redeemed once
+    pub nonce: u64,
+}
+
+/// Queries a royalty registry has to answer
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum RoyaltyRegistryQueryMsg {
+    /// Whether the operator is a marketplace that pays royalties
+    #[returns(bool)]
+    IsCompliant {
+        operator: String,
+    },
+}
+
+/// Hook messages for CW20 tokens sent here
+#[cw_serde]
+pub enum Cw20HookMsg {
+    FundRewards {},
+}
+
+/// The fields the owner signs to grant an approval off-chain
+#[cw_serde]
+pub struct PermitParams {
+    pub owner: String,
+    pub spender: String,
+    /// The token to approve, or None to make the spender an operator of all the owner's tokens
+    pub token_id: Option<String>,
+    /// When the approval expires, the permit can't be used after this either
+    pub expires: Expiration,
+    /// Any number, but each one can only be used once per owner
+    pub nonce: u64,
+}
+
+#[cw_serde]
+pub struct ApprovalPermit {
+    pub params: PermitParams,
+    /// secp256k1 public key of the owner, the owner address is derived from it
+    pub pubkey: Binary,
+    /// secp256k1 signature over the sha256 hash of the JSON encoded `params`
+    pub signature: Binary,
 }
 
 #[cw_ownable_query]
@@ -123,6 +685,18 @@ pub enum QueryMsg<Q: JsonSchema> {
         /// unset or false will filter out expired approvals, you must set to true to see them
         include_expired: Option<bool>,
     },
+    /// Return the owners of up to 100 tokens in the order asked for, None for missing tokens
+    #[returns(Vec<Option<cw721::OwnerOfResponse>>)]
+    BatchOwnerOf {
+        token_ids: Vec<String>,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: Option<bool>,
+    },
+    /// Return whether each of up to 500 tokens exists, in the order asked for
+    #[returns(Vec<bool>)]
+    TokensExist {
+        token_ids: Vec<String>,
+    },
     /// Return operator that can access all of the owner's tokens.
     #[returns(cw721::ApprovalResponse)]
     Approval {
@@ -136,6 +710,15 @@ pub enum QueryMsg<Q: JsonSchema> {
         token_id: String,
         include_expired: Option<bool>,
     },
+    /// List the tokens the given spender has a per-token approval on
+    #[returns(ApprovalsBySpenderResponse)]
+    ApprovalsBySpender {
+        spender: String,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Return approval of a given operator for all tokens of an owner, error if not set
     #[returns(cw721::OperatorResponse)]
     Operator {
@@ -152,9 +735,94 @@ pub enum QueryMsg<Q: JsonSchema> {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Count the operators an owner has granted rights over all their tokens.
+    /// Counting stops at 100, `at_least` tells whether there may be more
+    #[returns(OperatorCountResponse)]
+    OperatorCount {
+        owner: String,
+        /// unset or false will not count expired grants
+        include_expired: Option<bool>,
+    },
+    /// List the owners that granted the given address operator rights over all their tokens
+    #[returns(GrantersResponse)]
+    GrantersOfOperator {
+        operator: String,
+        /// unset or false will filter out expired items, you must set to true to see them
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// List every operator grant in the collection in storage order, which keeps the grants
+    /// of each granter together. Meant for audits, pages hold at most 100 grants
+    #[returns(OperatorGrantsResponse)]
+    AllOperatorGrants {
+        /// (granter, operator) of the last grant on the previous page
+        start_after: Option<(String, String)>,
+        /// unset or false will filter out expired items, you must set to true to see them
+        include_expired: Option<bool>,
+        limit: Option<u32>,
+    },
+    /// List the marketplaces on the operator allowlist
+    #[returns(AllowedOperatorsResponse)]
+    AllowedOperators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// List the tokens locked with the contract for the bridge, with their original owners
+    #[returns(BridgeLocksResponse)]
+    BridgeLocks {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Mint proceeds per denom with how much of them unlocked and was withdrawn so far
+    #[returns(VestedProceedsResponse)]
+    VestedProceeds {},
+    /// List the mint proposals that are still open for approvals
+    #[returns(MintProposalsResponse<Q>)]
+    MintProposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Whether the token is an active pass, tokens that aren't passes always are
+    #[returns(IsActiveResponse)]
+    IsActive {
+        token_id: String,
+    },
+    /// Returns the last sale of a token, None if it was never sold
+    #[returns(Option<SaleRecord>)]
+    LastSale {
+        token_id: String,
+    },
+    /// Number of sales and volume per denom over the whole collection
+    #[returns(SaleStats)]
+    SaleStats {},
+    /// Current or last raffle
+    #[returns(RaffleResponse)]
+    Raffle {},
+    /// Unclaimed raffle entry of the address, if any
+    #[returns(Option<RaffleEntryResponse>)]
+    RaffleEntry {
+        address: String,
+    },
+    /// List the contracts allowed to mint on behalf of their users
+    #[returns(PartnerMintersResponse)]
+    PartnerMinters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Mint counts per wallet in address order, with the number of wallets to page through.
+    /// `start_after` must be a valid address
+    #[returns(MintLedgerResponse)]
+    MintLedger {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Total number of tokens issued
     #[returns(cw721::NumTokensResponse)]
     NumTokens {},
+    /// Tokens in circulation along with how many were ever minted and burned
+    #[returns(SupplyResponse)]
+    Supply {},
 
     /// With MetaData Extension.
     /// Returns top-level metadata about the contract
@@ -179,11 +847,43 @@ pub enum QueryMsg<Q: JsonSchema> {
 
     /// With Enumerable extension.
     /// Returns all tokens owned by the given address, [] if unset.
-    #[returns(cw721::TokensResponse)]
+    #[returns(OwnerTokensResponse)]
     Tokens {
         owner: String,
         start_after: Option<String>,
         limit: Option<u32>,
+        /// unset lists ascending, when descending `start_after` is an exclusive upper bound
+        order: Option<RangeOrder>,
+        /// if true, the response also holds how many tokens the owner has in total
+        include_total: Option<bool>,
+    },
+    /// Like `Tokens`, but returns the full record of every token so wallets can render an
+    /// inventory in one query. Pages hold at most 30 tokens.
+    #[returns(TokensDetailedResponse<Q>)]
+    TokensDetailed {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: Option<bool>,
+    },
+    /// Lists the tokens with ids from `start` to `end` inclusive along with their owners,
+    /// skipping burned ids. The range may span at most 10000 ids, continue a page by
+    /// passing the last id + 1 as `start`.
+    #[returns(TokenRangeResponse)]
+    TokenRange {
+        start: u64,
+        end: u64,
+        limit: Option<u32>,
+    },
+    /// Lists tokens minted from `start` up to but excluding `end`, in mint order.
+    /// Tokens minted before mint times were recorded are never listed.
+    #[returns(cw721::TokensResponse)]
+    TokensMintedBetween {
+        start: Timestamp,
+        end: Timestamp,
+        start_after: Option<String>,
+        limit: Option<u32>,
     },
     /// With Enumerable extension.
     /// Requires pagination. Lists all token_ids controlled by the contract.
@@ -191,6 +891,14 @@ pub enum QueryMsg<Q: JsonSchema> {
     AllTokens {
         start_after: Option<String>,
         limit: Option<u32>,
+        /// unset lists ascending, when descending `start_after` is an exclusive upper bound
+        order: Option<RangeOrder>,
+    },
+    /// Like `AllTokens`, but each token comes with its owner. At most 200 tokens per page
+    #[returns(TokensWithOwnerResponse)]
+    AllTokensWithOwner {
+        start_after: Option<String>,
+        limit: Option<u32>,
     },
 
     /// Return the minter
@@ -204,10 +912,1004 @@ pub enum QueryMsg<Q: JsonSchema> {
     },
 
     #[returns(Option<String>)] GetWithdrawAddress {},
-}
 
-/// Shows who can mint these tokens
-#[cw_serde]
-pub struct MinterResponse {
-    pub minter: Option<String>,
+    /// Returns the contract balance per denom and how much of it can be withdrawn,
+    /// payouts owed to pending redemptions are reserved
+    #[returns(TreasuryResponse)]
+    Treasury {},
+
+    /// Returns past withdrawals, oldest first
+    #[returns(WithdrawHistoryResponse)]
+    WithdrawHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the sum of all withdrawals per denom
+    #[returns(TotalWithdrawnResponse)]
+    TotalWithdrawn {},
+
+    /// Returns whether operators and approved spenders are allowed to burn tokens
+    #[returns(bool)]
+    OperatorsCanBurn {},
+
+    /// Returns the redemption of a token, None if it was never redeemed
+    #[returns(Option<RedemptionResponse>)]
+    RedemptionStatus {
+        token_id: String,
+    },
+    /// Lists redemptions, optionally only those with the given status
+    #[returns(RedemptionsResponse)]
+    Redemptions {
+        status: Option<RedemptionStatus>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the token the given token is nested into, if any
+    #[returns(Option<String>)]
+    Parent {
+        token_id: String,
+    },
+    /// Lists the tokens directly nested into the given token
+    #[returns(cw721::TokensResponse)]
+    Children {
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns whether the token can be transferred right now, by `by` if given.
+    /// Runs the same checks as a transfer and reports why it would fail
+    #[returns(TransferableResponse)]
+    IsTransferable {
+        token_id: String,
+        by: Option<String>,
+    },
+
+    /// Returns the user of a token, None if there is none or their role has expired
+    #[returns(UserOfResponse)]
+    UserOf {
+        token_id: String,
+    },
+
+    /// Returns the delegate of a token, None if there is none or the delegation has expired
+    #[returns(DelegateOfResponse)]
+    DelegateOf {
+        token_id: String,
+    },
+
+    /// Lists the tokens delegated to an address by token id, leaving out expired delegations
+    #[returns(cw721::TokensResponse)]
+    TokensDelegatedTo {
+        delegate: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns since when and for how long a token has been staked, None if it isn't
+    #[returns(Option<StakedTokenResponse>)]
+    StakedToken {
+        token_id: String,
+    },
+
+    /// Lists the staked tokens of an owner by token id
+    #[returns(StakedTokensResponse)]
+    StakedTokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the operational state of the contract in one query
+    #[returns(ContractStatusResponse)]
+    ContractStatus {},
+
+    /// What this contract is: its cw2 info and how the code was built
+    #[returns(ContractVersionResponse)]
+    ContractVersion {},
+
+    /// Returns the whole configuration of the contract in one query.
+    /// The individual queries like `Minter` and `GetWithdrawAddress` return the same values
+    #[returns(ConfigResponse)]
+    Config {},
+
+    /// Returns whether `address` could mint `quantity` tokens (default 1) right now.
+    /// Runs the same checks as a mint and reports why it would fail
+    #[returns(CanMintResponse)]
+    CanMint {
+        address: String,
+        quantity: Option<u64>,
+    },
+
+    /// Returns the recorded chain of custody of a token, oldest first by default.
+    /// History of burned tokens stays available
+    #[returns(TokenHistoryResponse)]
+    TokenHistory {
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order: Option<RangeOrder>,
+    },
+
+    /// Returns the most recent mints, transfers, burns and approvals across the collection,
+    /// newest first by default. `start_after` is an event id
+    #[returns(ActivityResponse)]
+    Activity {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order: Option<RangeOrder>,
+    },
+
+    /// Returns how often a token was transferred and when it last moved
+    #[returns(TokenStatsResponse)]
+    TokenStats {
+        token_id: String,
+    },
+
+    /// Returns the token at `index` among all live tokens, like ERC721Enumerable.
+    /// Positions are not stable, burning a token moves the last one into its place
+    #[returns(TokenByIndexResponse)]
+    TokenByIndex {
+        index: u64,
+    },
+
+    /// Returns the token at `index` among the tokens of `owner`.
+    /// Positions are not stable, any token leaving the owner moves their last one into its place
+    #[returns(TokenByIndexResponse)]
+    TokenOfOwnerByIndex {
+        owner: String,
+        index: u64,
+    },
+
+    /// Number of tokens held by `owner`, without paging through them
+    #[returns(cw721::NumTokensResponse)]
+    NumTokensOfOwner {
+        owner: String,
+    },
+
+    /// Lists the tokens whose extension is indexed under `key` with exactly `value`.
+    /// Only available if the contract was built with an extension index
+    #[returns(cw721::TokensResponse)]
+    TokensByExtensionKey {
+        key: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Lists the tokens pointing at `token_uri`.
+    /// Only available if the contract was instantiated with `index_token_uris`
+    #[returns(cw721::TokensResponse)]
+    TokensByUri {
+        token_uri: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns who owned a burned token and when it was burned, None if the token was never
+    /// burned or burned tokens are not retained
+    #[returns(Option<BurnRecord>)]
+    BurnRecord {
+        token_id: String,
+    },
+
+    /// Lists the retained burned tokens by token id
+    #[returns(BurnedTokensResponse)]
+    BurnedTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the listing of a token, None if it isn't listed. Expired listings are returned
+    /// until they are replaced or the token moves
+    #[returns(Option<Listing>)]
+    Listing {
+        token_id: String,
+    },
+
+    /// Lists the open sales by token id
+    #[returns(ListingsResponse)]
+    Listings {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns a bundle with the tokens in it, None if it was sold or delisted
+    #[returns(Option<Bundle>)]
+    Bundle {
+        bundle_id: u64,
+    },
+
+    /// Lists the bundles for sale by id, including expired ones until they are delisted
+    #[returns(BundlesResponse)]
+    Bundles {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the auction of a token, None if there is none to settle
+    #[returns(Option<Auction>)]
+    Auction {
+        token_id: String,
+    },
+
+    /// Lists the auctions still taking bids by token id, or also those waiting to be
+    /// settled with `include_ended`
+    #[returns(AuctionsResponse)]
+    Auctions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_ended: Option<bool>,
+    },
+
+    /// Lists the offers on a token by bidder
+    #[returns(OffersResponse)]
+    OffersByToken {
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Lists the offers of a bidder by token id
+    #[returns(OffersResponse)]
+    OffersByBidder {
+        bidder: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Lists the tokens whose token_uri is still a placeholder, along with how many there are
+    #[returns(UnrevealedTokensResponse)]
+    UnrevealedTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Number of addresses holding at least one token
+    #[returns(NumHoldersResponse)]
+    NumHolders {},
+
+    /// How many tokens the given address holds
+    #[returns(HolderBalanceResponse)]
+    HolderBalance {
+        address: String,
+    },
+
+    /// Recounts the tokens of `address` from the owner index and compares them to the
+    /// stored balance. For debugging, it reads every token of the address
+    #[returns(VerifyHoldingsResponse)]
+    VerifyHoldings {
+        address: String,
+    },
+
+    /// Whether the given address holds at least `min_count` tokens, 1 by default.
+    /// Only active passes count if tokens are subscriptions.
+    /// Meant for token-gating in other contracts
+    #[returns(IsHolderResponse)]
+    IsHolder {
+        address: String,
+        min_count: Option<u64>,
+    },
+
+    /// How many tokens the given address has traded in towards its next burn-to-mint
+    #[returns(u64)]
+    BurnToMintCredits {
+        address: String,
+    },
+
+    /// Rewards the given address can claim with `ClaimRewards`
+    #[returns(Uint128)]
+    PendingRewards {
+        address: String,
+    },
+
+    /// Whether the given address owns the token, false if the token doesn't exist
+    #[returns(bool)]
+    HoldsToken {
+        address: String,
+        token_id: String,
+    },
+
+    /// How many tokens the given address held at the start of block `height`.
+    /// Needs balance snapshots, with the selected strategy only at checkpointed heights
+    #[returns(HolderBalanceResponse)]
+    HolderBalanceAt {
+        address: String,
+        height: u64,
+    },
+
+    /// Number of holders at the start of block `height`, with the same limits as
+    /// `HolderBalanceAt`
+    #[returns(NumHoldersResponse)]
+    NumHoldersAt {
+        height: u64,
+    },
+
+    /// Voting power of the given address at the start of block `height`, one per token held.
+    /// Shaped for DAO tooling, with the same limits as `HolderBalanceAt`
+    #[returns(PowerResponse)]
+    VotingPowerAt {
+        address: String,
+        height: u64,
+    },
+
+    /// Number of tokens in circulation at the start of block `height`, with the same limits
+    /// as `HolderBalanceAt`
+    #[returns(cw721::NumTokensResponse)]
+    NumTokensAt {
+        height: u64,
+    },
+
+    /// Total voting power at the start of block `height`, the number of tokens in circulation.
+    /// With the same limits as `HolderBalanceAt`
+    #[returns(PowerResponse)]
+    TotalPowerAt {
+        height: u64,
+    },
+
+    /// Lists every holder with the number of tokens they hold, sorted by address.
+    /// At most 100 holders are returned per page.
+    #[returns(HoldersResponse)]
+    Holders {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// The holders with the most tokens, largest first. At most 50 holders are returned.
+    #[returns(HoldersResponse)]
+    TopHolders {
+        limit: Option<u32>,
+    },
+
+    /// Like `Holders`, but only holders with at least `min_count` tokens
+    #[returns(HoldersResponse)]
+    HoldersByCount {
+        min_count: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// A page of raw state to move the collection elsewhere with `MigrateMsg::ImportState`.
+    /// `start_after` is the last token id or wallet of the previous page, for operators
+    /// "granter/operator". Config is returned whole
+    #[returns(StateExport<Q>)]
+    ExportState {
+        section: ExportSection,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub enum MigrateMsg {
+    /// Upgrade a deployment of an older version of this contract, running every
+    /// state transition between the stored cw2 version and this code
+    Upgrade {},
+    /// Adopt a collection deployed with stock cw721-base, converting its storage and
+    /// setting the mint rules it never had. Per-wallet mint counts start out empty, so
+    /// `max_nfts_per_wallet` only counts tokens minted after the migration.
+    FromCw721Base {
+        max_supply: Option<u64>,
+        max_nfts_per_wallet: Option<u64>,
+        price_per_nft: Option<Coin>,
+    },
+    /// Set or change the mint rules of a deployment, gated by the chain's migrate
+    /// permission. Emits an attribute for every field that changed
+    UpdateMintSettings {
+        /// Can't be lower than the tokens currently in circulation
+        max_supply: Option<u64>,
+        max_nfts_per_wallet: Option<u64>,
+        price_per_nft: Option<Coin>,
+        withdraw_address: Option<String>,
+    },
+    /// Write a page of `ExportState` into this contract, only while `import_open` is set
+    ImportState {
+        section: ExportSection,
+        data: StateExport<crate::Extension>,
+    },
+}
+
+/// Direction in which token listings are paginated
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum RangeOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<RangeOrder> for Order {
+    fn from(order: RangeOrder) -> Self {
+        match order {
+            RangeOrder::Asc => Order::Ascending,
+            RangeOrder::Desc => Order::Descending,
+        }
+    }
+}
+
+/// Shows who can mint these tokens
+#[cw_serde]
+pub struct MinterResponse {
+    pub minter: Option<String>,
+}
+
+#[cw_serde]
+pub struct TokenApproval {
+    pub token_id: String,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct ApprovalsBySpenderResponse {
+    pub approvals: Vec<TokenApproval>,
+}
+
+#[cw_serde]
+pub struct OperatorCountResponse {
+    pub count: u32,
+    /// true if counting stopped at the cap
+    pub at_least: bool,
+}
+
+#[cw_serde]
+pub struct AllowedOperatorsResponse {
+    pub operators: Vec<String>,
+}
+
+#[cw_serde]
+pub struct BridgeLockResponse {
+    pub token_id: String,
+    pub original_owner: String,
+}
+
+#[cw_serde]
+pub struct BridgeLocksResponse {
+    pub locks: Vec<BridgeLockResponse>,
+}
+
+#[cw_serde]
+pub struct VestedProceeds {
+    pub denom: String,
+    pub total: Uint128,
+    pub vested: Uint128,
+    pub withdrawn: Uint128,
+}
+
+#[cw_serde]
+pub struct VestedProceedsResponse {
+    pub proceeds: Vec<VestedProceeds>,
+}
+
+#[cw_serde]
+pub struct MintProposalResponse<T> {
+    pub id: u64,
+    pub proposer: String,
+    pub owner: String,
+    pub token_uri: Option<String>,
+    pub extension: T,
+    pub approvals: Vec<String>,
+    pub expires: Timestamp,
+}
+
+#[cw_serde]
+pub struct MintProposalsResponse<T> {
+    pub proposals: Vec<MintProposalResponse<T>>,
+}
+
+#[cw_serde]
+pub struct IsActiveResponse {
+    pub active: bool,
+    pub valid_until: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct RaffleResponse {
+    pub phase: Option<RafflePhase>,
+    pub entries: u64,
+    pub claimed: u64,
+}
+
+#[cw_serde]
+pub struct RaffleEntryResponse {
+    pub index: u64,
+    pub paid: Option<Coin>,
+    /// Known once the seed is revealed
+    pub won: Option<bool>,
+}
+
+#[cw_serde]
+pub struct PartnerMinterResponse {
+    pub address: String,
+    pub config: PartnerConfig,
+}
+
+#[cw_serde]
+pub struct PartnerMintersResponse {
+    pub partners: Vec<PartnerMinterResponse>,
+}
+
+#[cw_serde]
+pub struct MintLedgerResponse {
+    pub entries: Vec<WalletMints>,
+    /// Number of wallets over all pages
+    pub total_entries: u64,
+}
+
+#[cw_serde]
+pub struct Granter {
+    pub granter: String,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct GrantersResponse {
+    pub granters: Vec<Granter>,
+}
+
+#[cw_serde]
+pub struct OperatorGrantInfo {
+    pub granter: String,
+    pub operator: String,
+    pub grant: OperatorGrant,
+}
+
+#[cw_serde]
+pub struct OperatorGrantsResponse {
+    pub grants: Vec<OperatorGrantInfo>,
+}
+
+#[cw_serde]
+pub struct RedemptionResponse {
+    pub token_id: String,
+    pub redemption: Redemption,
+}
+
+#[cw_serde]
+pub struct RedemptionsResponse {
+    pub redemptions: Vec<RedemptionResponse>,
+}
+
+#[cw_serde]
+pub struct BurnedTokenResponse {
+    pub token_id: String,
+    pub record: BurnRecord,
+}
+
+#[cw_serde]
+pub struct BurnedTokensResponse {
+    pub tokens: Vec<BurnedTokenResponse>,
+}
+
+#[cw_serde]
+pub struct ListingResponse {
+    pub token_id: String,
+    pub listing: Listing,
+}
+
+#[cw_serde]
+pub struct ListingsResponse {
+    pub listings: Vec<ListingResponse>,
+}
+
+#[cw_serde]
+pub struct BundleResponse {
+    pub bundle_id: u64,
+    pub bundle: Bundle,
+}
+
+#[cw_serde]
+pub struct BundlesResponse {
+    pub bundles: Vec<BundleResponse>,
+}
+
+#[cw_serde]
+pub struct OfferResponse {
+    pub token_id: String,
+    pub bidder: String,
+    pub offer: Offer,
+}
+
+#[cw_serde]
+pub struct OffersResponse {
+    pub offers: Vec<OfferResponse>,
+}
+
+#[cw_serde]
+pub struct AuctionResponse {
+    pub token_id: String,
+    pub auction: Auction,
+}
+
+#[cw_serde]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionResponse>,
+}
+
+#[cw_serde]
+pub struct WithdrawalResponse {
+    pub id: u64,
+    pub withdrawal: WithdrawRecord,
+}
+
+#[cw_serde]
+pub struct WithdrawHistoryResponse {
+    pub withdrawals: Vec<WithdrawalResponse>,
+}
+
+#[cw_serde]
+pub struct TotalWithdrawnResponse {
+    pub amounts: Vec<Coin>,
+}
+
+/// `cw721::TokensResponse` with the owner's total token count when it was asked for
+#[cw_serde]
+pub struct OwnerTokensResponse {
+    pub tokens: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+#[cw_serde]
+pub struct UnrevealedTokensResponse {
+    pub tokens: Vec<String>,
+    /// Unrevealed tokens across all pages
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct TokenByIndexResponse {
+    pub token_id: String,
+}
+
+#[cw_serde]
+pub struct NumHoldersResponse {
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct PowerResponse {
+    pub power: Uint128,
+}
+
+#[cw_serde]
+pub struct HolderBalanceResponse {
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct VerifyHoldingsResponse {
+    /// The balance kept in `holdings`
+    pub recorded: u64,
+    /// The tokens found in the owner index
+    pub indexed: u64,
+    pub consistent: bool,
+}
+
+#[cw_serde]
+pub struct IsHolderResponse {
+    pub is_holder: bool,
+    /// How many tokens the address holds
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct TokenDetail<T> {
+    pub token_id: String,
+    pub token_uri: Option<String>,
+    pub extension: T,
+    pub approvals: Vec<cw721::Approval>,
+    pub staked_since: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct TokensDetailedResponse<T> {
+    pub tokens: Vec<TokenDetail<T>>,
+}
+
+#[cw_serde]
+pub struct SupplyResponse {
+    /// minted + bridged_in - burned - bridged_out
+    pub circulating: u64,
+    pub minted: u64,
+    pub burned: u64,
+    /// Tokens that arrived with `BridgeMint`
+    pub bridged_in: u64,
+    /// Tokens that left with `BridgeBurn`
+    pub bridged_out: u64,
+    pub max_supply: Option<u64>,
+    pub cap_basis: SupplyCapBasis,
+    /// true once `max_supply` is reached and minting fails
+    pub sold_out: bool,
+}
+
+#[cw_serde]
+pub struct TreasuryBalance {
+    pub denom: String,
+    pub balance: Uint128,
+    pub reserved: Uint128,
+    pub withdrawable: Uint128,
+}
+
+#[cw_serde]
+pub struct TreasuryResponse {
+    pub withdraw_address: Option<String>,
+    pub balances: Vec<TreasuryBalance>,
+}
+
+#[cw_serde]
+pub struct TokenOwner {
+    pub token_id: String,
+    pub owner: String,
+}
+
+#[cw_serde]
+pub struct TokenRangeResponse {
+    pub tokens: Vec<TokenOwner>,
+}
+
+#[cw_serde]
+pub struct TokensWithOwnerResponse {
+    pub tokens: Vec<TokenOwner>,
+}
+
+#[cw_serde]
+pub struct Holder {
+    pub address: String,
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct HoldersResponse {
+    pub holders: Vec<Holder>,
+}
+
+#[cw_serde]
+pub struct ContractStatusResponse {
+    /// true once `max_supply` is reached and minting fails
+    pub sold_out: bool,
+    pub owner: Option<String>,
+    pub pending_owner: Option<String>,
+    /// true if ownership was renounced, nobody can change the configuration anymore
+    pub ownership_renounced: bool,
+    pub operators_can_burn: bool,
+    pub enforce_operator_allowlist: bool,
+    pub version: ContractVersionResponse,
+}
+
+#[cw_serde]
+pub struct ContractVersionResponse {
+    /// The cw2 contract name, None if it was never set, e.g. when embedded as a library
+    pub contract: Option<String>,
+    /// The cw2 contract version
+    pub version: Option<String>,
+    /// Version of the crate the code was compiled from
+    pub crate_version: String,
+    /// Cargo features the code was compiled with
+    pub features: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub name: String,
+    pub symbol: String,
+    /// Current owner, which is also the only address allowed to mint
+    pub owner: Option<String>,
+    pub pending_owner: Option<String>,
+    pub withdraw_address: Option<String>,
+    pub max_supply: Option<u64>,
+    pub supply_cap_basis: SupplyCapBasis,
+    pub max_nfts_per_wallet: Option<u64>,
+    pub mint_price_per_nft: Option<Coin>,
+    pub redemption_payout: Option<Coin>,
+    pub max_approvals_per_token: u64,
+    /// Lifetime in seconds given to approvals created without an expiration
+    pub default_approval_ttl: Option<u64>,
+    pub allow_never_expiration: bool,
+    pub operators_can_burn: bool,
+    pub enforce_operator_allowlist: bool,
+    pub record_token_history: bool,
+    pub max_token_history: u64,
+    pub activity_buffer_size: u64,
+    pub delayed_reveal: bool,
+    pub balance_snapshots: SnapshotStrategy,
+    pub retain_burned_tokens: bool,
+    pub index_token_uris: bool,
+    pub pagination: PaginationConfig,
+    pub import_open: bool,
+    pub bridge: Option<String>,
+    pub royalty: Option<Royalty>,
+    pub auction_extension: u64,
+    pub keep_user_on_transfer: bool,
+    pub min_stake_period: u64,
+    pub wrapped_collection: Option<String>,
+    pub burn_to_mint: Option<BurnToMintConfig>,
+    pub reward_asset: Option<RewardAsset>,
+    pub royalty_registry: Option<String>,
+    pub voucher_pubkey: Option<Binary>,
+    pub bridge_operator: Option<String>,
+    pub subscription: Option<SubscriptionConfig>,
+    pub co_signing: Option<CoSignConfig>,
+    pub proceeds_vesting: Option<VestingConfig>,
+    pub open_edition: Option<(Timestamp, Timestamp)>,
+}
+
+#[cw_serde]
+pub struct CanMintResponse {
+    pub can_mint: bool,
+    /// The error a mint would fail with
+    pub reason: Option<String>,
+    /// Total price of `quantity` tokens, if minting costs anything
+    pub price: Option<Coin>,
+    /// How many more tokens the address may mint, if there is a per-wallet limit
+    pub remaining_allocation: Option<u64>,
+}
+
+#[cw_serde]
+pub struct TransferableResponse {
+    pub transferable: bool,
+    /// The error a transfer would fail with
+    pub reason: Option<String>,
+}
+
+#[cw_serde]
+pub struct StakedTokenResponse {
+    pub token_id: String,
+    pub staked_since: Timestamp,
+    /// Seconds the token has been staked for
+    pub staked_for: u64,
+}
+
+#[cw_serde]
+pub struct StakedTokensResponse {
+    pub tokens: Vec<StakedTokenResponse>,
+}
+
+#[cw_serde]
+pub struct DelegateOfResponse {
+    pub delegate: Option<String>,
+    pub expires: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct UserOfResponse {
+    pub user: Option<String>,
+    pub expires: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct TokenHistoryEntry {
+    pub sequence: u64,
+    pub entry: HistoryEntry,
+}
+
+#[cw_serde]
+pub struct TokenHistoryResponse {
+    pub entries: Vec<TokenHistoryEntry>,
+}
+
+#[cw_serde]
+pub struct ActivityResponse {
+    pub entries: Vec<ActivityEntry>,
+}
+
+#[cw_serde]
+pub struct TokenStatsResponse {
+    pub transfer_count: u64,
+    pub last_transferred_at: Option<Timestamp>,
+}
+
+/// Cw721BurnReceiveMsg should be de/serialized under `BurnReceiveNft()` variant in a ExecuteMsg
+#[cw_serde]
+pub struct Cw721BurnReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+impl Cw721BurnReceiveMsg {
+    /// serializes the message
+    pub fn into_json_binary(self) -> StdResult<Binary> {
+        let msg = BurnReceiverExecuteMsg::BurnReceiveNft(self);
+        to_json_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>, C>(self, contract_addr: T) -> StdResult<CosmosMsg<C>> {
+        let msg = self.into_json_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// This is just a helper to properly serialize the above message.
+/// The actual receiver should include this variant in the larger ExecuteMsg enum
+#[cw_serde]
+enum BurnReceiverExecuteMsg {
+    BurnReceiveNft(Cw721BurnReceiveMsg),
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum ExportSection {
+    Tokens,
+    Operators,
+    WalletMints,
+    Config,
+}
+
+#[cw_serde]
+pub struct StateExport<T> {
+    /// `STATE_EXPORT_VERSION` of the exporting contract
+    pub version: u32,
+    pub data: ExportData<T>,
+}
+
+#[cw_serde]
+pub enum ExportData<T> {
+    Tokens(Vec<ExportedToken<T>>),
+    Operators(Vec<ExportedOperator>),
+    WalletMints(Vec<WalletMints>),
+    Config(ExportedConfig),
+}
+
+impl<T> ExportData<T> {
+    pub fn section(&self) -> ExportSection {
+        match self {
+            ExportData::Tokens(_) => ExportSection::Tokens,
+            ExportData::Operators(_) => ExportSection::Operators,
+            ExportData::WalletMints(_) => ExportSection::WalletMints,
+            ExportData::Config(_) => ExportSection::Config,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct ExportedToken<T> {
+    pub token_id: String,
+    pub owner: String,
+    pub token_uri: Option<String>,
+    pub extension: T,
+    pub approvals: Vec<Approval>,
+    pub parent: Option<String>,
+    pub redeemed: bool,
+    pub revealed: bool,
+    #[serde(default)]
+    pub accessories: Vec<String>,
+    #[serde(default)]
+    pub valid_until: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct ExportedOperator {
+    pub granter: String,
+    pub operator: String,
+    pub grant: OperatorGrant,
+}
+
+#[cw_serde]
+pub struct WalletMints {
+    pub wallet: String,
+    pub minted: u64,
+}
+
+#[cw_serde]
+pub struct ExportedConfig {
+    pub name: String,
+    pub symbol: String,
+    pub withdraw_address: Option<String>,
+    pub max_supply: Option<u64>,
+    pub max_nfts_per_wallet: Option<u64>,
+    pub price_per_nft: Option<Coin>,
+    /// Carried over so ids of burned tokens are not handed out again
+    pub minted_total: u64,
+    pub burned_total: u64,
+    #[serde(default)]
+    pub bridged_in_total: u64,
+    #[serde(default)]
+    pub bridged_out_total: u64,
 }
@@ -1,15 +1,151 @@
-use cosmwasm_std::{ Addr, Empty };
-use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+use std::marker::PhantomData;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin,
+    from_json,
+    to_json_binary,
+    Addr,
+    BankMsg,
+    Binary,
+    Deps,
+    DepsMut,
+    Empty,
+    Env,
+    MessageInfo,
+    Response,
+    StdError,
+    StdResult,
+    Uint128,
+};
+use cw721::{ ApprovalResponse, ApprovalsResponse, Cw721ReceiveMsg, OwnerOfResponse };
+use cw_multi_test::{ next_block, App, Contract, ContractWrapper, Executor };
+
+use crate::helpers;
+use crate::msg::{
+    Cw721BurnReceiveMsg,
+    TotalWithdrawnResponse,
+    TreasuryBalance,
+    TreasuryResponse,
+    WithdrawHistoryResponse,
+};
 
 fn cw721_base_latest_contract() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         crate::entry::execute,
         crate::entry::instantiate,
         crate::entry::query
-    ).with_migrate(crate::entry::migrate);
+    )
+        .with_reply(crate::entry::reply)
+        .with_migrate(crate::entry::migrate);
     Box::new(contract)
 }
 
+#[cw_serde]
+enum ReceiverExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+    BurnReceiveNft(Cw721BurnReceiveMsg),
+}
+
+/// Accepts every notification unless the attached msg is the string "reject".
+/// With "check_approval", it only accepts tokens it is approved on
+fn receiver_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ReceiverExecuteMsg
+) -> StdResult<Response> {
+    let (token_id, msg) = match msg {
+        ReceiverExecuteMsg::ReceiveNft(receive) => (receive.token_id, receive.msg),
+        ReceiverExecuteMsg::BurnReceiveNft(receive) => (receive.token_id, receive.msg),
+    };
+    let instruction: String = from_json(&msg)?;
+    if instruction == "reject" {
+        return Err(StdError::generic_err("receiver rejected token"));
+    }
+    if instruction == "check_approval" {
+        let _: ApprovalResponse = deps.querier.query_wasm_smart(
+            info.sender,
+            &(crate::QueryMsg::<Empty>::Approval {
+                token_id: token_id.clone(),
+                spender: env.contract.address.to_string(),
+                include_expired: None,
+            })
+        )?;
+    }
+    Ok(Response::new().add_attribute("received", token_id))
+}
+
+fn receiver_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty
+) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn receiver_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Ok(Binary::default())
+}
+
+fn receiver_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(receiver_execute, receiver_instantiate, receiver_query))
+}
+
+#[cw_serde]
+enum ForwarderExecuteMsg {
+    Forward {
+        cw721: String,
+        token_id: String,
+        recipient: String,
+    },
+}
+
+/// Moves tokens it is approved on through the typed helper, checking first that it may
+fn forwarder_execute(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: ForwarderExecuteMsg
+) -> StdResult<Response> {
+    let ForwarderExecuteMsg::Forward { cw721, token_id, recipient } = msg;
+    let cw721 = helpers::Cw721Contract::<Empty, Empty>(
+        deps.api.addr_validate(&cw721)?,
+        PhantomData,
+        PhantomData
+    );
+    let check = cw721.is_transferable(
+        &deps.querier,
+        token_id.clone(),
+        Some(env.contract.address.to_string())
+    )?;
+    if !check.transferable {
+        return Err(StdError::generic_err(check.reason.unwrap_or_default()));
+    }
+    let holding = cw721.num_tokens_of_owner(&deps.querier, recipient.clone())?;
+    let msg = cw721.call(crate::ExecuteMsg::<crate::Extension, Empty>::TransferNft {
+        recipient,
+        token_id,
+    })?;
+    Ok(Response::new().add_message(msg).add_attribute("recipient_holding", holding.to_string()))
+}
+
+fn forwarder_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(forwarder_execute, receiver_instantiate, receiver_query))
+}
+
+fn query_owner(app: &App, cw721: &Addr, token_id: &str) -> String {
+    let resp: OwnerOfResponse = app
+        .wrap()
+        .query_wasm_smart(cw721, &(crate::QueryMsg::<Empty>::OwnerOf {
+            token_id: token_id.to_string(),
+            include_expired: None,
+        }))
+        .unwrap();
+    resp.owner
+}
+
 /// Test backward compatibility using instantiate msg from a 0.16 version on latest contract.
 /// This ensures existing 3rd party contracts doesnt need to updated as well.
 #[test]
@@ -42,3 +178,945 @@ fn test_instantiate_016_msg() {
         .unwrap();
     assert!(withdraw_addr.is_none());
 }
+
+#[test]
+fn test_safe_send() {
+    let mut app = App::default();
+    let admin = Addr::unchecked("admin");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            None
+        )
+        .unwrap();
+    let receiver_id = app.store_code(receiver_contract());
+    let receiver = app
+        .instantiate_contract(receiver_id, admin.clone(), &Empty {}, &[], "receiver", None)
+        .unwrap();
+
+    for _ in 0..2 {
+        app.execute_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+
+    // rejected send hands the token back to the owner
+    let res = app
+        .execute_contract(
+            Addr::unchecked("owner"),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::SendNft {
+                contract: receiver.to_string(),
+                token_id: "0".to_string(),
+                msg: to_json_binary("reject").unwrap(),
+            }),
+            &[]
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|e| e.ty == "wasm-send_failed"));
+    assert_eq!(query_owner(&app, &cw721, "0"), "owner");
+
+    // the owner can still move it afterwards
+    app.execute_contract(
+        Addr::unchecked("owner"),
+        cw721.clone(),
+        &(crate::ExecuteMsg::<crate::Extension, Empty>::TransferNft {
+            recipient: "buyer".to_string(),
+            token_id: "0".to_string(),
+        }),
+        &[]
+    ).unwrap();
+    assert_eq!(query_owner(&app, &cw721, "0"), "buyer");
+
+    // accepted send keeps the receiver as owner
+    let res = app
+        .execute_contract(
+            Addr::unchecked("owner"),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::SendNft {
+                contract: receiver.to_string(),
+                token_id: "1".to_string(),
+                msg: to_json_binary("accept").unwrap(),
+            }),
+            &[]
+        )
+        .unwrap();
+    assert!(!res.events.iter().any(|e| e.ty == "wasm-send_failed"));
+    assert_eq!(query_owner(&app, &cw721, "1"), receiver.to_string());
+}
+
+#[test]
+fn test_burn_and_notify() {
+    let mut app = App::default();
+    let admin = Addr::unchecked("admin");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            None
+        )
+        .unwrap();
+    let receiver_id = app.store_code(receiver_contract());
+    let receiver = app
+        .instantiate_contract(receiver_id, admin.clone(), &Empty {}, &[], "receiver", None)
+        .unwrap();
+
+    for _ in 0..3 {
+        app.execute_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+    let burn_and_notify = |token_id: &str, instruction: &str, rollback_on_error: bool| {
+        crate::ExecuteMsg::<crate::Extension, Empty>::BurnAndNotify {
+            token_id: token_id.to_string(),
+            contract: receiver.to_string(),
+            msg: to_json_binary(instruction).unwrap(),
+            rollback_on_error,
+        }
+    };
+    let exists = |app: &App, token_id: &str| {
+        app.wrap()
+            .query_wasm_smart::<OwnerOfResponse>(&cw721, &(crate::QueryMsg::<Empty>::OwnerOf {
+                token_id: token_id.to_string(),
+                include_expired: None,
+            }))
+            .is_ok()
+    };
+
+    // accepted notification burns the token
+    let res = app
+        .execute_contract(
+            Addr::unchecked("owner"),
+            cw721.clone(),
+            &burn_and_notify("0", "accept", true),
+            &[]
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|e| e.attributes.iter().any(|a| a.key == "received")));
+    assert!(!exists(&app, "0"));
+
+    // rejected notification with rollback keeps the token
+    app.execute_contract(
+        Addr::unchecked("owner"),
+        cw721.clone(),
+        &burn_and_notify("1", "reject", true),
+        &[]
+    ).unwrap_err();
+    assert!(exists(&app, "1"));
+
+    // rejected notification without rollback still burns
+    let res = app
+        .execute_contract(
+            Addr::unchecked("owner"),
+            cw721.clone(),
+            &burn_and_notify("2", "reject", false),
+            &[]
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|e| e.ty == "wasm-burn_notify_failed"));
+    assert!(!exists(&app, "2"));
+}
+
+#[test]
+fn test_approve_and_send() {
+    let mut app = App::default();
+    let admin = Addr::unchecked("admin");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            None
+        )
+        .unwrap();
+    let receiver_id = app.store_code(receiver_contract());
+    let receiver = app
+        .instantiate_contract(receiver_id, admin.clone(), &Empty {}, &[], "receiver", None)
+        .unwrap();
+
+    for _ in 0..2 {
+        app.execute_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+    let owner = Addr::unchecked("owner");
+    let approve_and_send = |token_id: &str, instruction: &str| {
+        crate::ExecuteMsg::<crate::Extension, Empty>::ApproveAndSend {
+            contract: receiver.to_string(),
+            token_id: token_id.to_string(),
+            msg: to_json_binary(instruction).unwrap(),
+            expires: None,
+        }
+    };
+
+    // the receiver finds itself approved when it checks on receipt
+    app.execute_contract(
+        owner.clone(),
+        cw721.clone(),
+        &approve_and_send("0", "check_approval"),
+        &[]
+    ).unwrap();
+    assert_eq!(query_owner(&app, &cw721, "0"), receiver.to_string());
+
+    // a rejected send gives the token back with only the approvals it had before
+    app.execute_contract(
+        owner.clone(),
+        cw721.clone(),
+        &(crate::ExecuteMsg::<crate::Extension, Empty>::Approve {
+            spender: "market".to_string(),
+            token_id: "1".to_string(),
+            expires: None,
+        }),
+        &[]
+    ).unwrap();
+    let res = app
+        .execute_contract(owner, cw721.clone(), &approve_and_send("1", "reject"), &[])
+        .unwrap();
+    assert!(res.events.iter().any(|e| e.ty == "wasm-send_failed"));
+    assert_eq!(query_owner(&app, &cw721, "1"), "owner");
+    let approvals: ApprovalsResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(crate::QueryMsg::<Empty>::Approvals {
+            token_id: "1".to_string(),
+            include_expired: None,
+        }))
+        .unwrap();
+    let spenders: Vec<_> = approvals.approvals
+        .into_iter()
+        .map(|apr| apr.spender)
+        .collect();
+    assert_eq!(spenders, vec!["market"]);
+}
+
+#[test]
+fn test_treasury() {
+    let funder = Addr::unchecked("funder");
+    let mut app = App::new(|router, _, storage| {
+        router.bank
+            .init_balance(storage, &funder, vec![coin(1000, "usei"), coin(50, "uatom")])
+            .unwrap();
+    });
+    let admin = Addr::unchecked("admin");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                withdraw_address: Some("treasurer".to_string()),
+                redemption_payout: Some(coin(100, "usei")),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            None
+        )
+        .unwrap();
+    let treasury = |app: &App| -> TreasuryResponse {
+        app.wrap()
+            .query_wasm_smart(&cw721, &crate::QueryMsg::<Empty>::Treasury {})
+            .unwrap()
+    };
+    let balance = |denom: &str, balance: u128, reserved: u128| TreasuryBalance {
+        denom: denom.to_string(),
+        balance: Uint128::new(balance),
+        reserved: Uint128::new(reserved),
+        withdrawable: Uint128::new(balance - reserved),
+    };
+
+    let empty = treasury(&app);
+    assert_eq!(empty.withdraw_address, Some("treasurer".to_string()));
+    assert!(empty.balances.is_empty());
+
+    app.execute(
+        funder.clone(),
+        (BankMsg::Send {
+            to_address: cw721.to_string(),
+            amount: vec![coin(1000, "usei"), coin(50, "uatom")],
+        }).into()
+    ).unwrap();
+    assert_eq!(treasury(&app).balances, vec![balance("uatom", 50, 0), balance("usei", 1000, 0)]);
+
+    // both tokens are redeemed, each redemption is owed the payout
+    for _ in 0..2 {
+        app.execute_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+    for token_id in ["0", "1"] {
+        app.execute_contract(
+            Addr::unchecked("owner"),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Redeem {
+                token_id: token_id.to_string(),
+                redemption_data: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+    assert_eq!(treasury(&app).balances, vec![balance("uatom", 50, 0), balance("usei", 1000, 200)]);
+
+    // fulfilling pays out one of them
+    app.execute_contract(
+        admin,
+        cw721.clone(),
+        &(crate::ExecuteMsg::<crate::Extension, Empty>::FulfillRedemption {
+            token_id: "0".to_string(),
+        }),
+        &[]
+    ).unwrap();
+    assert_eq!(treasury(&app).balances, vec![balance("uatom", 50, 0), balance("usei", 900, 100)]);
+    assert_eq!(app.wrap().query_balance("owner", "usei").unwrap().amount, Uint128::new(100));
+}
+
+#[test]
+fn test_withdraw_history() {
+    let funder = Addr::unchecked("funder");
+    let mut app = App::new(|router, _, storage| {
+        router.bank
+            .init_balance(storage, &funder, vec![coin(1000, "usei"), coin(50, "uatom")])
+            .unwrap();
+    });
+    let admin = Addr::unchecked("admin");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin,
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                withdraw_address: Some("treasurer".to_string()),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            None
+        )
+        .unwrap();
+    app.execute(
+        funder.clone(),
+        (BankMsg::Send {
+            to_address: cw721.to_string(),
+            amount: vec![coin(1000, "usei"), coin(50, "uatom")],
+        }).into()
+    ).unwrap();
+
+    // anyone may withdraw, the funds always go to the withdraw address
+    for amount in [coin(300, "usei"), coin(20, "uatom"), coin(200, "usei")] {
+        app.update_block(next_block);
+        app.execute_contract(
+            funder.clone(),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::WithdrawFunds { amount }),
+            &[]
+        ).unwrap();
+    }
+    assert_eq!(app.wrap().query_balance("treasurer", "usei").unwrap(), coin(500, "usei"));
+
+    let history = |app: &App, start_after, limit| -> WithdrawHistoryResponse {
+        app.wrap()
+            .query_wasm_smart(&cw721, &crate::QueryMsg::<Empty>::WithdrawHistory {
+                start_after,
+                limit,
+            })
+            .unwrap()
+    };
+    let all = history(&app, None, None).withdrawals;
+    assert_eq!(
+        all
+            .iter()
+            .map(|w| (w.id, w.withdrawal.amount.u128(), w.withdrawal.denom.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(1, 300, "usei"), (2, 20, "uatom"), (3, 200, "usei")]
+    );
+    assert!(all.iter().all(|w| w.withdrawal.to == "treasurer"));
+    assert!(all[0].withdrawal.at_height < all[1].withdrawal.at_height);
+    assert!(all[1].withdrawal.at_time < all[2].withdrawal.at_time);
+
+    let page = history(&app, Some(1), Some(1)).withdrawals;
+    assert_eq!(page, all[1..2].to_vec());
+    assert!(history(&app, Some(3), None).withdrawals.is_empty());
+
+    let totals: TotalWithdrawnResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &crate::QueryMsg::<Empty>::TotalWithdrawn {})
+        .unwrap();
+    assert_eq!(totals.amounts, vec![coin(20, "uatom"), coin(500, "usei")]);
+
+    // a failed withdrawal is rolled back and not recorded
+    app.execute_contract(
+        funder,
+        cw721.clone(),
+        &(crate::ExecuteMsg::<crate::Extension, Empty>::WithdrawFunds {
+            amount: coin(1000, "usei"),
+        }),
+        &[]
+    ).unwrap_err();
+    assert_eq!(history(&app, None, None).withdrawals.len(), 3);
+}
+
+#[test]
+fn test_helper_client() {
+    let mut app = App::default();
+    let admin = Addr::unchecked("admin");
+    let owner = Addr::unchecked("owner");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            None
+        )
+        .unwrap();
+    let forwarder_id = app.store_code(forwarder_contract());
+    let forwarder = app
+        .instantiate_contract(forwarder_id, admin.clone(), &Empty {}, &[], "forwarder", None)
+        .unwrap();
+
+    for _ in 0..2 {
+        app.execute_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: owner.to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+    app.execute_contract(
+        owner.clone(),
+        cw721.clone(),
+        &(crate::ExecuteMsg::<crate::Extension, Empty>::Approve {
+            spender: forwarder.to_string(),
+            token_id: "0".to_string(),
+            expires: None,
+        }),
+        &[]
+    ).unwrap();
+
+    let forward = |app: &mut App, token_id: &str| {
+        app.execute_contract(
+            owner.clone(),
+            forwarder.clone(),
+            &(ForwarderExecuteMsg::Forward {
+                cw721: cw721.to_string(),
+                token_id: token_id.to_string(),
+                recipient: "buyer".to_string(),
+            }),
+            &[]
+        )
+    };
+
+    // the approved token goes through
+    let res = forward(&mut app, "0").unwrap();
+    assert!(
+        res.events
+            .iter()
+            .any(|e| e.attributes.iter().any(|a| a.key == "recipient_holding" && a.value == "0"))
+    );
+    assert_eq!(query_owner(&app, &cw721, "0"), "buyer");
+
+    // the helper reports why the other one can't be moved
+    let err = forward(&mut app, "1").unwrap_err();
+    assert!(err.root_cause().to_string().contains("Caller is not the contract's current owner"));
+    assert_eq!(query_owner(&app, &cw721, "1"), owner.to_string());
+
+    let helper = helpers::Cw721Contract::<Empty, Empty>(cw721, PhantomData, PhantomData);
+    assert_eq!(helper.num_tokens_of_owner(&app.wrap(), "buyer").unwrap(), 1);
+    assert_eq!(helper.supply(&app.wrap()).unwrap().circulating, 2);
+    assert!(helper.can_mint(&app.wrap(), "buyer", None).unwrap().can_mint);
+}
+
+fn cw721_base_stock_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query
+    );
+    Box::new(contract)
+}
+
+#[test]
+fn test_migrate_from_cw721_base() {
+    let mut app = App::new(|router, _, storage| {
+        for fan in ["buyer", "fan", "late"] {
+            router.bank
+                .init_balance(storage, &Addr::unchecked(fan), vec![coin(100, "usei")])
+                .unwrap();
+        }
+    });
+    let admin = Addr::unchecked("admin");
+    let holder = Addr::unchecked("holder");
+
+    let stock_id = app.store_code(cw721_base_stock_contract());
+    let cw721 = app
+        .instantiate_contract(
+            stock_id,
+            admin.clone(),
+            &(cw721_base::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                minter: None,
+                withdraw_address: None,
+            }),
+            &[],
+            "cw721-base",
+            Some(admin.to_string())
+        )
+        .unwrap();
+    for token_id in ["0", "1"] {
+        app.execute_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(cw721_base::ExecuteMsg::<cw721_base::Extension, Empty>::Mint {
+                token_id: token_id.to_string(),
+                owner: holder.to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[]
+        ).unwrap();
+    }
+    app.execute_contract(
+        holder.clone(),
+        cw721.clone(),
+        &(cw721_base::ExecuteMsg::<cw721_base::Extension, Empty>::Approve {
+            spender: "market".to_string(),
+            token_id: "0".to_string(),
+            expires: None,
+        }),
+        &[]
+    ).unwrap();
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let res = app
+        .migrate_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(crate::MigrateMsg::FromCw721Base {
+                max_supply: Some(4),
+                max_nfts_per_wallet: Some(1),
+                price_per_nft: Some(coin(10, "usei")),
+            }),
+            code_id
+        )
+        .unwrap();
+    assert!(
+        res.events
+            .iter()
+            .any(|e| e.attributes.iter().any(|a| a.key == "validated_tokens" && a.value == "2"))
+    );
+
+    // the old tokens and their approvals carried over
+    assert_eq!(query_owner(&app, &cw721, "0"), holder.to_string());
+    assert_eq!(query_owner(&app, &cw721, "1"), holder.to_string());
+    let approval: ApprovalResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &crate::QueryMsg::<Empty>::Approval {
+            token_id: "0".to_string(),
+            spender: "market".to_string(),
+            include_expired: None,
+        })
+        .unwrap();
+    assert_eq!(approval.approval.spender, "market");
+
+    // new mints follow the mint rules and continue after the stock ids
+    let mint = |app: &mut App, minter: &str, funds: &[cosmwasm_std::Coin]| {
+        app.execute_contract(
+            Addr::unchecked(minter),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: minter.to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            funds
+        )
+    };
+    let err = mint(&mut app, "buyer", &[]).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Not enough funds");
+    mint(&mut app, "buyer", &[coin(10, "usei")]).unwrap();
+    assert_eq!(query_owner(&app, &cw721, "2"), "buyer");
+    let err = mint(&mut app, "buyer", &[coin(10, "usei")]).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "User already minted max amount");
+    mint(&mut app, "fan", &[coin(10, "usei")]).unwrap();
+    let err = mint(&mut app, "late", &[coin(10, "usei")]).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Supply overflow");
+}
+
+#[test]
+fn test_update_mint_settings() {
+    let mut app = App::new(|router, _, storage| {
+        router.bank
+            .init_balance(storage, &Addr::unchecked("buyer"), vec![coin(100, "usei")])
+            .unwrap();
+    });
+    let admin = Addr::unchecked("admin");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                max_nfts_per_wallet: Some(5),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            Some(admin.to_string())
+        )
+        .unwrap();
+    let mint = |app: &mut App, funds: &[cosmwasm_std::Coin]| {
+        app.execute_contract(
+            Addr::unchecked("buyer"),
+            cw721.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: "buyer".to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            funds
+        )
+    };
+    for _ in 0..2 {
+        mint(&mut app, &[]).unwrap();
+    }
+    let update = |app: &mut App, max_supply: Option<u64>| {
+        app.migrate_contract(
+            admin.clone(),
+            cw721.clone(),
+            &(crate::MigrateMsg::UpdateMintSettings {
+                max_supply,
+                max_nfts_per_wallet: Some(5),
+                price_per_nft: Some(coin(10, "usei")),
+                withdraw_address: Some("treasurer".to_string()),
+            }),
+            code_id
+        )
+    };
+
+    // the cap can't go below the tokens already minted
+    let err = update(&mut app, Some(1)).unwrap_err();
+    assert_eq!(
+        err.downcast::<crate::ContractError>().unwrap(),
+        crate::ContractError::MaxSupplyBelowTokenCount { max_supply: 1, token_count: 2 }
+    );
+
+    // only the fields that changed are reported
+    let res = update(&mut app, Some(3)).unwrap();
+    let attributes = res.events
+        .iter()
+        .filter(|e| e.ty == "wasm")
+        .flat_map(|e| e.attributes.iter().map(|a| a.key.as_str()))
+        .collect::<Vec<_>>();
+    assert!(attributes.contains(&"max_supply"));
+    assert!(attributes.contains(&"price_per_nft"));
+    assert!(attributes.contains(&"withdraw_address"));
+    assert!(!attributes.contains(&"max_nfts_per_wallet"));
+
+    let config: crate::msg::ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&cw721, &crate::QueryMsg::<Empty>::Config {})
+        .unwrap();
+    assert_eq!(config.max_supply, Some(3));
+    assert_eq!(config.mint_price_per_nft, Some(coin(10, "usei")));
+    assert_eq!(config.withdraw_address, Some("treasurer".to_string()));
+
+    // minting now has to be paid for and stops at the new cap
+    let err = mint(&mut app, &[]).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Not enough funds");
+    mint(&mut app, &[coin(10, "usei")]).unwrap();
+    let err = mint(&mut app, &[coin(10, "usei")]).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Supply overflow");
+}
+
+#[test]
+fn test_export_and_import_state() {
+    let mut app = App::default();
+    let admin = Addr::unchecked("admin");
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let instantiate = |app: &mut App, import_open: Option<bool>| {
+        app.instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                max_supply: Some(10),
+                max_nfts_per_wallet: Some(3),
+                import_open,
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            Some(admin.to_string())
+        ).unwrap()
+    };
+    let source = instantiate(&mut app, None);
+    let target = instantiate(&mut app, Some(true));
+
+    let execute = |app: &mut App, sender: &str, msg: crate::Cw721CustomExecuteMsg| {
+        app.execute_contract(Addr::unchecked(sender), source.clone(), &msg, &[]).unwrap();
+    };
+    for owner in ["alice", "alice", "bob"] {
+        execute(&mut app, owner, crate::ExecuteMsg::Mint {
+            owner: owner.to_string(),
+            token_uri: Some(format!("ipfs://{owner}")),
+            extension: None,
+        });
+    }
+    execute(&mut app, "bob", crate::ExecuteMsg::Burn { token_id: "2".to_string() });
+    execute(&mut app, "alice", crate::ExecuteMsg::Approve {
+        spender: "market".to_string(),
+        token_id: "0".to_string(),
+        expires: None,
+    });
+    execute(&mut app, "alice", crate::ExecuteMsg::ApproveAll {
+        operator: "broker".to_string(),
+        expires: None,
+        max_uses: Some(2),
+        permissions: None,
+    });
+    execute(&mut app, admin.as_str(), crate::ExecuteMsg::SetWithdrawAddress {
+        address: "treasurer".to_string(),
+    });
+
+    let export = |app: &App, cw721: &Addr, section, start_after: Option<String>, limit| {
+        app.wrap()
+            .query_wasm_smart::<crate::msg::StateExport<crate::Extension>>(
+                cw721,
+                &(crate::QueryMsg::<Empty>::ExportState { section, start_after, limit })
+            )
+            .unwrap()
+    };
+    let cursor = |data: &crate::msg::ExportData<crate::Extension>| {
+        match data {
+            crate::msg::ExportData::Tokens(tokens) => tokens.last().map(|t| t.token_id.clone()),
+            crate::msg::ExportData::Operators(operators) =>
+                operators.last().map(|o| format!("{}/{}", o.granter, o.operator)),
+            crate::msg::ExportData::WalletMints(wallets) =>
+                wallets.last().map(|w| w.wallet.clone()),
+            crate::msg::ExportData::Config(_) => None,
+        }
+    };
+    let import = |app: &mut App, section, data| {
+        app.migrate_contract(
+            admin.clone(),
+            target.clone(),
+            &(crate::MigrateMsg::ImportState { section, data }),
+            code_id
+        )
+    };
+
+    // copy every section a page at a time
+    use crate::msg::ExportSection;
+    let sections = [
+        ExportSection::Config,
+        ExportSection::Tokens,
+        ExportSection::Operators,
+        ExportSection::WalletMints,
+    ];
+    for section in sections {
+        let mut start_after = None;
+        loop {
+            let page = export(&app, &source, section, start_after, Some(1));
+            start_after = cursor(&page.data);
+            assert_eq!(page.version, crate::state::STATE_EXPORT_VERSION);
+            import(&mut app, section, page).unwrap();
+            if start_after.is_none() {
+                break;
+            }
+        }
+    }
+
+    // data has to match the section it is imported as
+    let tokens = export(&app, &source, ExportSection::Tokens, None, None);
+    let err = import(&mut app, ExportSection::Operators, tokens.clone()).unwrap_err();
+    assert_eq!(
+        err.downcast::<crate::ContractError>().unwrap(),
+        crate::ContractError::ImportSectionMismatch {}
+    );
+
+    app.execute_contract(
+        admin.clone(),
+        target.clone(),
+        &crate::Cw721CustomExecuteMsg::FinalizeImport {},
+        &[]
+    ).unwrap();
+    let err = import(&mut app, ExportSection::Tokens, tokens).unwrap_err();
+    assert_eq!(
+        err.downcast::<crate::ContractError>().unwrap(),
+        crate::ContractError::ImportClosed {}
+    );
+
+    // both contracts answer the same
+    for section in sections {
+        assert_eq!(
+            export(&app, &source, section, None, None),
+            export(&app, &target, section, None, None)
+        );
+    }
+    let same = |msg: crate::Cw721CustomQueryMsg| {
+        let source: serde_json::Value = app.wrap().query_wasm_smart(&source, &msg).unwrap();
+        let target: serde_json::Value = app.wrap().query_wasm_smart(&target, &msg).unwrap();
+        assert_eq!(source, target);
+    };
+    same(crate::QueryMsg::Config {});
+    same(crate::QueryMsg::Supply {});
+    same(crate::QueryMsg::NumHolders {});
+    same(crate::QueryMsg::AllTokens { start_after: None, limit: None, order: None });
+    same(crate::QueryMsg::Tokens {
+        owner: "alice".to_string(),
+        start_after: None,
+        limit: None,
+        order: None,
+        include_total: None,
+    });
+    same(crate::QueryMsg::Approvals { token_id: "0".to_string(), include_expired: None });
+    same(crate::QueryMsg::NftInfo { token_id: "1".to_string() });
+
+    // burned ids are not handed out again on the new contract
+    let res = app
+        .execute_contract(
+            Addr::unchecked("carol"),
+            target.clone(),
+            &(crate::ExecuteMsg::<crate::Extension, Empty>::Mint {
+                owner: "carol".to_string(),
+                token_uri: None,
+                extension: None,
+            }),
+            &[]
+        )
+        .unwrap();
+    assert!(
+        res.events
+            .iter()
+            .any(|e| e.attributes.iter().any(|a| a.key == "token_id" && a.value == "3"))
+    );
+}
+
+/// The entry points run the contract with on-chain metadata, whether built as a library or not
+#[test]
+fn test_entry_points_with_metadata() {
+    let mut app = App::default();
+    let admin = Addr::unchecked("admin");
+
+    let code_id = app.store_code(cw721_base_latest_contract());
+    let cw721 = app
+        .instantiate_contract(
+            code_id,
+            admin.clone(),
+            &crate::InstantiateMsg {
+                name: "collection".to_string(),
+                symbol: "symbol".to_string(),
+                ..Default::default()
+            },
+            &[],
+            "cw721-custom",
+            None
+        )
+        .unwrap();
+
+    let metadata = crate::Metadata {
+        name: Some("Starship".to_string()),
+        attributes: Some(
+            vec![crate::Trait {
+                display_type: None,
+                trait_type: "engines".to_string(),
+                value: "3".to_string(),
+            }]
+        ),
+        ..Default::default()
+    };
+    app.execute_contract(
+        admin,
+        cw721.clone(),
+        &(crate::Cw721CustomExecuteMsg::Mint {
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: Some(metadata.clone()),
+        }),
+        &[]
+    ).unwrap();
+
+    let info: cw721::NftInfoResponse<crate::Extension> = app
+        .wrap()
+        .query_wasm_smart(&cw721, &(crate::Cw721CustomQueryMsg::NftInfo {
+            token_id: "0".to_string(),
+        }))
+        .unwrap();
+    assert_eq!(info.extension, Some(metadata));
+}
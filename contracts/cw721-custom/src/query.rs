@@ -2,7 +2,8 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, BlockInfo, CustomMsg, Deps, Env, Order, StdError, StdResult,
+    to_json_binary, Addr, Binary, BlockInfo, Coin, CustomMsg, Deps, Env, Order, StdError,
+    StdResult, Timestamp, Uint128,
 };
 
 use cw721::{
@@ -10,14 +11,49 @@ use cw721::{
     Expiration, NftInfoResponse, NumTokensResponse, OperatorResponse, OperatorsResponse,
     OwnerOfResponse, TokensResponse,
 };
-use cw_storage_plus::Bound;
+use cw_storage_plus::{Bound, PrimaryKey};
 use cw_utils::maybe_addr;
 
-use crate::msg::{MinterResponse, QueryMsg};
-use crate::state::{Approval, Cw721Contract, TokenInfo};
+use crate::msg::{
+    ActivityResponse, AllowedOperatorsResponse, ApprovalsBySpenderResponse, AuctionResponse,
+    AuctionsResponse, BridgeLockResponse, BridgeLocksResponse, BundleResponse, BundlesResponse,
+    BurnedTokenResponse, BurnedTokensResponse, CanMintResponse, ConfigResponse,
+    ContractStatusResponse, ContractVersionResponse, DelegateOfResponse, ExportData, ExportSection,
+    ExportedConfig, ExportedOperator, ExportedToken, Granter, GrantersResponse, Holder,
+    HolderBalanceResponse, HoldersResponse, IsActiveResponse, IsHolderResponse, ListingResponse,
+    ListingsResponse, MintLedgerResponse, MintProposalResponse, MintProposalsResponse,
+    MinterResponse, NumHoldersResponse, OfferResponse, OffersResponse, OperatorCountResponse,
+    OperatorGrantInfo, OperatorGrantsResponse, OwnerTokensResponse, PartnerMinterResponse,
+    PartnerMintersResponse, PowerResponse, QueryMsg, RaffleEntryResponse, RaffleResponse,
+    RangeOrder, RedemptionResponse, RedemptionsResponse, StakedTokenResponse, StakedTokensResponse,
+    StateExport, SupplyResponse, TokenApproval, TokenByIndexResponse, TokenDetail,
+    TokenHistoryEntry, TokenHistoryResponse, TokenOwner, TokenRangeResponse, TokenStatsResponse,
+    TokensDetailedResponse, TokensWithOwnerResponse, TotalWithdrawnResponse, TransferableResponse,
+    TreasuryBalance, TreasuryResponse, UnrevealedTokensResponse, UserOfResponse,
+    VerifyHoldingsResponse, VestedProceeds, VestedProceedsResponse, WalletMints,
+    WithdrawHistoryResponse, WithdrawalResponse,
+};
+use crate::state::{
+    token_id_from_key, token_key, Approval, Cw721Contract, RafflePhase, RedemptionStatus,
+    TokenInfo, STATE_EXPORT_VERSION,
+};
 
-const DEFAULT_LIMIT: u32 = 10;
-const MAX_LIMIT: u32 = 1000;
+const MAX_OPERATOR_COUNT: usize = 100;
+const MAX_HOLDERS_LIMIT: u32 = 100;
+const MAX_TOP_HOLDERS_LIMIT: u32 = 50;
+const MAX_DETAILED_LIMIT: u32 = 30;
+const MAX_BATCH_OWNER_OF: usize = 100;
+const MAX_TOKENS_EXIST: usize = 500;
+const MAX_TOKEN_RANGE_SPAN: u64 = 10_000;
+const MAX_WITHDRAW_HISTORY_LIMIT: u32 = 100;
+const MAX_TOKEN_HISTORY_LIMIT: u32 = 100;
+const MAX_ACTIVITY_LIMIT: u32 = 100;
+const MAX_OPERATOR_GRANTS_LIMIT: u32 = 100;
+const MAX_TOKENS_WITH_OWNER_LIMIT: u32 = 200;
+const FEATURES: &[(&str, bool)] = &[
+    ("library", cfg!(feature = "library")),
+    ("backtraces", cfg!(feature = "backtraces")),
+];
 
 impl<'a, T, C, E, Q> Cw721Query<T> for Cw721Contract<'a, T, C, E, Q>
 where
@@ -36,10 +72,11 @@ where
     }
 
     fn nft_info(&self, deps: Deps, token_id: String) -> StdResult<NftInfoResponse<T>> {
-        let info = self.tokens.load(deps.storage, &token_id)?;
+        let info = self.load_token(deps.storage, &token_id)?;
+        let extension = self.merged_extension(deps.storage, &info)?;
         Ok(NftInfoResponse {
             token_uri: info.token_uri,
-            extension: info.extension,
+            extension,
         })
     }
 
@@ -50,11 +87,8 @@ where
         token_id: String,
         include_expired: bool,
     ) -> StdResult<OwnerOfResponse> {
-        let info = self.tokens.load(deps.storage, &token_id)?;
-        Ok(OwnerOfResponse {
-            owner: info.owner.to_string(),
-            approvals: humanize_approvals(&env.block, &info, include_expired),
-        })
+        let info = self.load_token(deps.storage, &token_id)?;
+        self.owner_of_token(deps, &env.block, &token_id, info, include_expired)
     }
 
     /// operator returns the approval status of an operator for a given owner if exists
@@ -73,15 +107,15 @@ where
             .operators
             .may_load(deps.storage, (&owner_addr, &operator_addr))?;
 
-        if let Some(expires) = info {
-            if !include_expired && expires.is_expired(&env.block) {
+        if let Some(grant) = info {
+            if !include_expired && grant.is_expired(&env.block) {
                 return Err(StdError::not_found("Approval not found"));
             }
 
             return Ok(OperatorResponse {
                 approval: cw721::Approval {
                     spender: operator,
-                    expires,
+                    expires: grant.expires,
                 },
             });
         }
@@ -99,7 +133,7 @@ where
         start_after: Option<String>,
         limit: Option<u32>,
     ) -> StdResult<OperatorsResponse> {
-        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let limit = self.page_limit(deps.storage, limit, None)?;
         let start_addr = maybe_addr(deps.api, start_after)?;
         let start = start_addr.as_ref().map(Bound::exclusive);
 
@@ -112,6 +146,7 @@ where
                 include_expired || r.is_err() || !r.as_ref().unwrap().1.is_expired(&env.block)
             })
             .take(limit)
+            .map(|r| r.map(|(operator, grant)| (operator, grant.expires)))
             .map(parse_approval)
             .collect();
         Ok(OperatorsResponse { operators: res? })
@@ -125,7 +160,7 @@ where
         spender: String,
         include_expired: bool,
     ) -> StdResult<ApprovalResponse> {
-        let token = self.tokens.load(deps.storage, &token_id)?;
+        let token = self.load_token(deps.storage, &token_id)?;
 
         // token owner has absolute approval
         if token.owner == spender {
@@ -136,8 +171,8 @@ where
             return Ok(ApprovalResponse { approval });
         }
 
-        let filtered: Vec<_> = token
-            .approvals
+        let filtered: Vec<_> = self
+            .approvals_of(deps.storage, &token_id)?
             .into_iter()
             .filter(|t| t.spender == spender)
             .filter(|t| include_expired || !t.is_expired(&env.block))
@@ -164,9 +199,10 @@ where
         token_id: String,
         include_expired: bool,
     ) -> StdResult<ApprovalsResponse> {
-        let token = self.tokens.load(deps.storage, &token_id)?;
-        let approvals: Vec<_> = token
-            .approvals
+        // make sure the token exists
+        self.load_token(deps.storage, &token_id)?;
+        let approvals: Vec<_> = self
+            .approvals_of(deps.storage, &token_id)?
             .into_iter()
             .filter(|t| include_expired || !t.is_expired(&env.block))
             .map(|a| cw721::Approval {
@@ -185,20 +221,7 @@ where
         start_after: Option<String>,
         limit: Option<u32>,
     ) -> StdResult<TokensResponse> {
-        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-        let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
-
-        let owner_addr = deps.api.addr_validate(&owner)?;
-        let tokens: Vec<String> = self
-            .tokens
-            .idx
-            .owner
-            .prefix(owner_addr)
-            .keys(deps.storage, start, None, Order::Ascending)
-            .take(limit)
-            .collect::<StdResult<Vec<_>>>()?;
-
-        Ok(TokensResponse { tokens })
+        self.tokens_in_order(deps, owner, start_after, limit, RangeOrder::Asc)
     }
 
     fn all_tokens(
@@ -207,17 +230,7 @@ where
         start_after: Option<String>,
         limit: Option<u32>,
     ) -> StdResult<TokensResponse> {
-        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-        let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
-
-        let tokens: StdResult<Vec<String>> = self
-            .tokens
-            .range(deps.storage, start, None, Order::Ascending)
-            .take(limit)
-            .map(|item| item.map(|(k, _)| k))
-            .collect();
-
-        Ok(TokensResponse { tokens: tokens? })
+        self.all_tokens_in_order(deps, start_after, limit, RangeOrder::Asc)
     }
 
     fn all_nft_info(
@@ -227,15 +240,18 @@ where
         token_id: String,
         include_expired: bool,
     ) -> StdResult<AllNftInfoResponse<T>> {
-        let info = self.tokens.load(deps.storage, &token_id)?;
+        let info = self.load_token(deps.storage, &token_id)?;
+        let (_, root) = self.root_token(deps.storage, &token_id, info.clone())?;
+        let approvals = self.approvals_of(deps.storage, &token_id)?;
+        let extension = self.merged_extension(deps.storage, &info)?;
         Ok(AllNftInfoResponse {
             access: OwnerOfResponse {
-                owner: info.owner.to_string(),
-                approvals: humanize_approvals(&env.block, &info, include_expired),
+                owner: root.owner.to_string(),
+                approvals: humanize_approvals(&env.block, &approvals, include_expired),
             },
             info: NftInfoResponse {
                 token_uri: info.token_uri,
-                extension: info.extension,
+                extension,
             },
         })
     }
@@ -262,6 +278,18 @@ where
                 token_id,
                 include_expired.unwrap_or(false),
             )?),
+            QueryMsg::BatchOwnerOf {
+                token_ids,
+                include_expired,
+            } => to_json_binary(&self.batch_owner_of(
+                deps,
+                env,
+                token_ids,
+                include_expired.unwrap_or(false),
+            )?),
+            QueryMsg::TokensExist { token_ids } => {
+                to_json_binary(&self.tokens_exist(deps, token_ids)?)
+            }
             QueryMsg::AllNftInfo {
                 token_id,
                 include_expired,
@@ -295,15 +323,117 @@ where
                 start_after,
                 limit,
             )?),
+            QueryMsg::OperatorCount {
+                owner,
+                include_expired,
+            } => to_json_binary(&self.operator_count(
+                deps,
+                env,
+                owner,
+                include_expired.unwrap_or(false),
+            )?),
+            QueryMsg::AllowedOperators { start_after, limit } => {
+                to_json_binary(&self.allowed_operators(deps, start_after, limit)?)
+            }
+            QueryMsg::BridgeLocks { start_after, limit } => {
+                to_json_binary(&self.bridge_locks(deps, start_after, limit)?)
+            }
+            QueryMsg::VestedProceeds {} => to_json_binary(&self.vested_proceeds(deps, env)?),
+            QueryMsg::MintProposals { start_after, limit } => {
+                to_json_binary(&self.mint_proposals(deps, env, start_after, limit)?)
+            }
+            QueryMsg::IsActive { token_id } => {
+                to_json_binary(&self.is_active(deps, env, token_id)?)
+            }
+            QueryMsg::Raffle {} => to_json_binary(&self.raffle(deps)?),
+            QueryMsg::RaffleEntry { address } => to_json_binary(&self.raffle_entry(deps, address)?),
+            QueryMsg::MintLedger { start_after, limit } => {
+                to_json_binary(&self.mint_ledger(deps, start_after, limit)?)
+            }
+            QueryMsg::PartnerMinters { start_after, limit } => {
+                to_json_binary(&self.partner_minters(deps, start_after, limit)?)
+            }
+            QueryMsg::AllOperatorGrants {
+                start_after,
+                include_expired,
+                limit,
+            } => to_json_binary(&self.all_operator_grants(
+                deps,
+                env,
+                start_after,
+                include_expired.unwrap_or(false),
+                limit,
+            )?),
+            QueryMsg::GrantersOfOperator {
+                operator,
+                include_expired,
+                start_after,
+                limit,
+            } => to_json_binary(&self.granters_of_operator(
+                deps,
+                env,
+                operator,
+                include_expired.unwrap_or(false),
+                start_after,
+                limit,
+            )?),
             QueryMsg::NumTokens {} => to_json_binary(&self.num_tokens(deps)?),
+            QueryMsg::Supply {} => to_json_binary(&self.supply(deps)?),
             QueryMsg::Tokens {
                 owner,
                 start_after,
                 limit,
-            } => to_json_binary(&self.tokens(deps, owner, start_after, limit)?),
-            QueryMsg::AllTokens { start_after, limit } => {
-                to_json_binary(&self.all_tokens(deps, start_after, limit)?)
+                order,
+                include_total,
+            } => {
+                let total = match include_total {
+                    Some(true) => Some(self.num_tokens_of_owner(deps, owner.clone())?.count),
+                    _ => None,
+                };
+                let order = order.unwrap_or_default();
+                let tokens = self.tokens_in_order(deps, owner, start_after, limit, order)?;
+                to_json_binary(&OwnerTokensResponse {
+                    tokens: tokens.tokens,
+                    total,
+                })
             }
+            QueryMsg::TokensDetailed {
+                owner,
+                start_after,
+                limit,
+                include_expired,
+            } => to_json_binary(&self.tokens_detailed(
+                deps,
+                env,
+                owner,
+                start_after,
+                limit,
+                include_expired.unwrap_or(false),
+            )?),
+            QueryMsg::TokenRange { start, end, limit } => {
+                to_json_binary(&self.token_range(deps, start, end, limit)?)
+            }
+            QueryMsg::TokensMintedBetween {
+                start,
+                end,
+                start_after,
+                limit,
+            } => {
+                to_json_binary(&self.tokens_minted_between(deps, start, end, start_after, limit)?)
+            }
+            QueryMsg::AllTokensWithOwner { start_after, limit } => {
+                to_json_binary(&self.all_tokens_with_owner(deps, start_after, limit)?)
+            }
+            QueryMsg::AllTokens {
+                start_after,
+                limit,
+                order,
+            } => to_json_binary(&self.all_tokens_in_order(
+                deps,
+                start_after,
+                limit,
+                order.unwrap_or_default(),
+            )?),
             QueryMsg::Approval {
                 token_id,
                 spender,
@@ -324,11 +454,242 @@ where
                 token_id,
                 include_expired.unwrap_or(false),
             )?),
+            QueryMsg::ApprovalsBySpender {
+                spender,
+                include_expired,
+                start_after,
+                limit,
+            } => to_json_binary(&self.approvals_by_spender(
+                deps,
+                env,
+                spender,
+                include_expired.unwrap_or(false),
+                start_after,
+                limit,
+            )?),
             QueryMsg::Ownership {} => to_json_binary(&Self::ownership(deps)?),
             QueryMsg::Extension { msg: _ } => Ok(Binary::default()),
             QueryMsg::GetWithdrawAddress {} => {
                 to_json_binary(&self.withdraw_address.may_load(deps.storage)?)
             }
+            QueryMsg::Treasury {} => to_json_binary(&self.treasury(deps, env)?),
+            QueryMsg::WithdrawHistory { start_after, limit } => {
+                to_json_binary(&self.withdraw_history(deps, start_after, limit)?)
+            }
+            QueryMsg::TotalWithdrawn {} => to_json_binary(&self.total_withdrawn(deps)?),
+            QueryMsg::OperatorsCanBurn {} => {
+                to_json_binary(&self.operators_can_burn(deps.storage)?)
+            }
+            QueryMsg::RedemptionStatus { token_id } => {
+                to_json_binary(&self.redemption_status(deps, token_id)?)
+            }
+            QueryMsg::Redemptions {
+                status,
+                start_after,
+                limit,
+            } => to_json_binary(&self.redemptions(deps, status, start_after, limit)?),
+            QueryMsg::Parent { token_id } => to_json_binary(
+                &self
+                    .tokens
+                    .load(deps.storage, &token_key(&token_id))?
+                    .parent,
+            ),
+            QueryMsg::Children {
+                token_id,
+                start_after,
+                limit,
+            } => to_json_binary(&self.children(deps, token_id, start_after, limit)?),
+            QueryMsg::ContractStatus {} => to_json_binary(&self.contract_status(deps)?),
+            QueryMsg::ContractVersion {} => to_json_binary(&self.contract_version(deps)?),
+            QueryMsg::Config {} => to_json_binary(&self.config(deps)?),
+            QueryMsg::CanMint { address, quantity } => {
+                to_json_binary(&self.can_mint(deps, env, address, quantity)?)
+            }
+            QueryMsg::IsTransferable { token_id, by } => {
+                to_json_binary(&self.is_transferable(deps, env, token_id, by)?)
+            }
+            QueryMsg::UserOf { token_id } => to_json_binary(&self.user_of(deps, env, token_id)?),
+            QueryMsg::DelegateOf { token_id } => {
+                to_json_binary(&self.delegate_of(deps, env, token_id)?)
+            }
+            QueryMsg::TokensDelegatedTo {
+                delegate,
+                start_after,
+                limit,
+            } => to_json_binary(&self.tokens_delegated_to(
+                deps,
+                env,
+                delegate,
+                start_after,
+                limit,
+            )?),
+            QueryMsg::StakedToken { token_id } => {
+                to_json_binary(&self.staked_token(deps, env, token_id)?)
+            }
+            QueryMsg::StakedTokens {
+                owner,
+                start_after,
+                limit,
+            } => to_json_binary(&self.staked_tokens(deps, env, owner, start_after, limit)?),
+            QueryMsg::TokenHistory {
+                token_id,
+                start_after,
+                limit,
+                order,
+            } => to_json_binary(&self.token_history(deps, token_id, start_after, limit, order)?),
+            QueryMsg::Activity {
+                start_after,
+                limit,
+                order,
+            } => to_json_binary(&self.activity(deps, start_after, limit, order)?),
+            QueryMsg::TokenStats { token_id } => to_json_binary(&self.token_stats(deps, token_id)?),
+            QueryMsg::TokensByExtensionKey {
+                key,
+                value,
+                start_after,
+                limit,
+            } => to_json_binary(&self.tokens_by_extension_key(
+                deps,
+                key,
+                value,
+                start_after,
+                limit,
+            )?),
+            QueryMsg::TokensByUri {
+                token_uri,
+                start_after,
+                limit,
+            } => to_json_binary(&self.tokens_by_uri(deps, token_uri, start_after, limit)?),
+            QueryMsg::BurnRecord { token_id } => to_json_binary(
+                &self
+                    .burned_tokens
+                    .may_load(deps.storage, &token_key(&token_id))?,
+            ),
+            QueryMsg::BurnedTokens { start_after, limit } => {
+                to_json_binary(&self.burned_tokens(deps, start_after, limit)?)
+            }
+            QueryMsg::Listing { token_id } => to_json_binary(
+                &self
+                    .listings
+                    .may_load(deps.storage, &token_key(&token_id))?,
+            ),
+            QueryMsg::Listings { start_after, limit } => {
+                to_json_binary(&self.listings(deps, start_after, limit)?)
+            }
+            QueryMsg::Bundle { bundle_id } => {
+                to_json_binary(&self.bundles.may_load(deps.storage, bundle_id)?)
+            }
+            QueryMsg::Bundles { start_after, limit } => {
+                to_json_binary(&self.bundles(deps, start_after, limit)?)
+            }
+            QueryMsg::LastSale { token_id } => to_json_binary(
+                &self
+                    .last_sale
+                    .may_load(deps.storage, &token_key(&token_id))?,
+            ),
+            QueryMsg::SaleStats {} => {
+                to_json_binary(&self.sale_stats.may_load(deps.storage)?.unwrap_or_default())
+            }
+            QueryMsg::Auction { token_id } => to_json_binary(
+                &self
+                    .auctions
+                    .may_load(deps.storage, &token_key(&token_id))?,
+            ),
+            QueryMsg::Auctions {
+                start_after,
+                limit,
+                include_ended,
+            } => to_json_binary(&self.auctions(
+                deps,
+                env,
+                start_after,
+                limit,
+                include_ended.unwrap_or(false),
+            )?),
+            QueryMsg::OffersByToken {
+                token_id,
+                start_after,
+                limit,
+            } => to_json_binary(&self.offers_by_token(deps, token_id, start_after, limit)?),
+            QueryMsg::OffersByBidder {
+                bidder,
+                start_after,
+                limit,
+            } => to_json_binary(&self.offers_by_bidder(deps, bidder, start_after, limit)?),
+            QueryMsg::UnrevealedTokens { start_after, limit } => {
+                to_json_binary(&self.unrevealed_tokens(deps, start_after, limit)?)
+            }
+            QueryMsg::NumTokensOfOwner { owner } => {
+                to_json_binary(&self.num_tokens_of_owner(deps, owner)?)
+            }
+            QueryMsg::TokenByIndex { index } => to_json_binary(&self.token_by_index(deps, index)?),
+            QueryMsg::TokenOfOwnerByIndex { owner, index } => {
+                to_json_binary(&self.token_of_owner_by_index(deps, owner, index)?)
+            }
+            QueryMsg::NumHolders {} => to_json_binary(&NumHoldersResponse {
+                count: self.holder_count(deps.storage)?,
+            }),
+            QueryMsg::HolderBalance { address } => {
+                to_json_binary(&self.holder_balance(deps, address)?)
+            }
+            QueryMsg::VerifyHoldings { address } => {
+                to_json_binary(&self.verify_holdings(deps, address)?)
+            }
+            QueryMsg::IsHolder { address, min_count } => {
+                to_json_binary(&self.is_holder(deps, env, address, min_count)?)
+            }
+            QueryMsg::BurnToMintCredits { address } => {
+                let address = deps.api.addr_validate(&address)?;
+                to_json_binary(
+                    &self
+                        .burn_to_mint_credits
+                        .may_load(deps.storage, &address)?
+                        .unwrap_or_default(),
+                )
+            }
+            QueryMsg::PendingRewards { address } => {
+                let address = deps.api.addr_validate(&address)?;
+                to_json_binary(&self.pending_rewards(deps.storage, &address)?)
+            }
+            QueryMsg::HoldsToken { address, token_id } => {
+                to_json_binary(&self.holds_token(deps, address, token_id)?)
+            }
+            QueryMsg::HolderBalanceAt { address, height } => {
+                let address = deps.api.addr_validate(&address)?;
+                to_json_binary(&HolderBalanceResponse {
+                    count: self.holder_balance_at(deps.storage, &address, height)?,
+                })
+            }
+            QueryMsg::NumHoldersAt { height } => to_json_binary(&NumHoldersResponse {
+                count: self.holder_count_at(deps.storage, height)?,
+            }),
+            QueryMsg::VotingPowerAt { address, height } => {
+                let address = deps.api.addr_validate(&address)?;
+                let balance = self.holder_balance_at(deps.storage, &address, height)?;
+                to_json_binary(&PowerResponse {
+                    power: Uint128::from(balance),
+                })
+            }
+            QueryMsg::NumTokensAt { height } => to_json_binary(&NumTokensResponse {
+                count: self.token_count_at(deps.storage, height)?,
+            }),
+            QueryMsg::TotalPowerAt { height } => to_json_binary(&PowerResponse {
+                power: Uint128::from(self.token_count_at(deps.storage, height)?),
+            }),
+            QueryMsg::Holders { start_after, limit } => {
+                to_json_binary(&self.holders(deps, 1, start_after, limit)?)
+            }
+            QueryMsg::TopHolders { limit } => to_json_binary(&self.top_holders(deps, limit)?),
+            QueryMsg::HoldersByCount {
+                min_count,
+                start_after,
+                limit,
+            } => to_json_binary(&self.holders(deps, min_count, start_after, limit)?),
+            QueryMsg::ExportState {
+                section,
+                start_after,
+                limit,
+            } => to_json_binary(&self.export_state(deps, section, start_after, limit)?),
         }
     }
 
@@ -340,33 +701,1609 @@ where
         Ok(MinterResponse { minter })
     }
 
-    pub fn ownership(deps: Deps) -> StdResult<cw_ownable::Ownership<Addr>> {
-        cw_ownable::get_ownership(deps.storage)
+    pub fn approvals_by_spender(
+        &self,
+        deps: Deps,
+        env: Env,
+        spender: String,
+        include_expired: bool,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ApprovalsBySpenderResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start = start_after.as_deref().map(Bound::exclusive);
+
+        let spender_addr = deps.api.addr_validate(&spender)?;
+        let approvals = self
+            .spender_approvals
+            .prefix(&spender_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|r| {
+                include_expired || r.is_err() || !r.as_ref().unwrap().1.is_expired(&env.block)
+            })
+            .take(limit)
+            .map(|r| r.map(|(token_id, expires)| TokenApproval { token_id, expires }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ApprovalsBySpenderResponse { approvals })
     }
-}
 
-fn parse_approval(item: StdResult<(Addr, Expiration)>) -> StdResult<cw721::Approval> {
-    item.map(|(spender, expires)| cw721::Approval {
-        spender: spender.to_string(),
-        expires,
-    })
-}
+    pub fn operator_count(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+        include_expired: bool,
+    ) -> StdResult<OperatorCountResponse> {
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        let count = self
+            .operators
+            .prefix(&owner_addr)
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|r| {
+                include_expired || r.is_err() || !r.as_ref().unwrap().1.is_expired(&env.block)
+            })
+            .take(MAX_OPERATOR_COUNT)
+            .collect::<StdResult<Vec<_>>>()?
+            .len();
 
-fn humanize_approvals<T>(
-    block: &BlockInfo,
-    info: &TokenInfo<T>,
-    include_expired: bool,
-) -> Vec<cw721::Approval> {
-    info.approvals
-        .iter()
-        .filter(|apr| include_expired || !apr.is_expired(block))
-        .map(humanize_approval)
-        .collect()
-}
+        Ok(OperatorCountResponse {
+            count: count as u32,
+            at_least: count == MAX_OPERATOR_COUNT,
+        })
+    }
 
-fn humanize_approval(approval: &Approval) -> cw721::Approval {
-    cw721::Approval {
-        spender: approval.spender.to_string(),
-        expires: approval.expires,
+    pub fn granters_of_operator(
+        &self,
+        deps: Deps,
+        env: Env,
+        operator: String,
+        include_expired: bool,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<GrantersResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.as_ref().map(Bound::exclusive);
+
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        let granters = self
+            .operator_granters
+            .prefix(&operator_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|r| {
+                include_expired || r.is_err() || !r.as_ref().unwrap().1.is_expired(&env.block)
+            })
+            .take(limit)
+            .map(|r| {
+                r.map(|(granter, expires)| Granter {
+                    granter: granter.into_string(),
+                    expires,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(GrantersResponse { granters })
+    }
+
+    pub fn all_operator_grants(
+        &self,
+        deps: Deps,
+        env: Env,
+        start_after: Option<(String, String)>,
+        include_expired: bool,
+        limit: Option<u32>,
+    ) -> StdResult<OperatorGrantsResponse> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_OPERATOR_GRANTS_LIMIT))?;
+        let cursor = start_after
+            .map(|(granter, operator)| {
+                Ok::<_, StdError>((
+                    deps.api.addr_validate(&granter)?,
+                    deps.api.addr_validate(&operator)?,
+                ))
+            })
+            .transpose()?;
+        let start = cursor
+            .as_ref()
+            .map(|(granter, operator)| Bound::exclusive((granter, operator)));
+
+        let grants = self
+            .operators
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|r| {
+                include_expired || r.is_err() || !r.as_ref().unwrap().1.is_expired(&env.block)
+            })
+            .take(limit)
+            .map(|r| {
+                r.map(|((granter, operator), grant)| OperatorGrantInfo {
+                    granter: granter.into_string(),
+                    operator: operator.into_string(),
+                    grant,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(OperatorGrantsResponse { grants })
+    }
+
+    /// Owners of the given tokens in the same order, `None` for tokens that don't exist
+    pub fn tokens_exist(&self, deps: Deps, token_ids: Vec<String>) -> StdResult<Vec<bool>> {
+        if token_ids.len() > MAX_TOKENS_EXIST {
+            return Err(StdError::generic_err(format!(
+                "At most {} token ids can be queried at once",
+                MAX_TOKENS_EXIST
+            )));
+        }
+        Ok(token_ids
+            .iter()
+            .map(|token_id| self.tokens.has(deps.storage, &token_key(token_id)))
+            .collect())
+    }
+
+    pub fn batch_owner_of(
+        &self,
+        deps: Deps,
+        env: Env,
+        token_ids: Vec<String>,
+        include_expired: bool,
+    ) -> StdResult<Vec<Option<OwnerOfResponse>>> {
+        if token_ids.len() > MAX_BATCH_OWNER_OF {
+            return Err(StdError::generic_err(format!(
+                "At most {} token ids can be queried at once",
+                MAX_BATCH_OWNER_OF
+            )));
+        }
+        token_ids
+            .iter()
+            .map(
+                |token_id| match self.may_load_token(deps.storage, token_id)? {
+                    Some(info) => self
+                        .owner_of_token(deps, &env.block, token_id, info, include_expired)
+                        .map(Some),
+                    None => Ok(None),
+                },
+            )
+            .collect()
+    }
+
+    fn owner_of_token(
+        &self,
+        deps: Deps,
+        block: &BlockInfo,
+        token_id: &str,
+        info: TokenInfo<T>,
+        include_expired: bool,
+    ) -> StdResult<OwnerOfResponse> {
+        let (_, root) = self.root_token(deps.storage, token_id, info)?;
+        let approvals = self.approvals_of(deps.storage, token_id)?;
+        Ok(OwnerOfResponse {
+            owner: root.owner.to_string(),
+            approvals: humanize_approvals(block, &approvals, include_expired),
+        })
+    }
+
+    pub fn tokens_in_order(
+        &self,
+        deps: Deps,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: RangeOrder,
+    ) -> StdResult<TokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, order);
+
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        let tokens: Vec<String> = self
+            .tokens
+            .idx
+            .owner
+            .prefix(owner_addr)
+            .keys(deps.storage, min, max, order.into())
+            .take(limit)
+            .map(|key| key.map(token_id_from_key))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensResponse { tokens })
+    }
+
+    pub fn tokens_detailed(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_expired: bool,
+    ) -> StdResult<TokensDetailedResponse<T>> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_DETAILED_LIMIT))?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        let tokens = self
+            .tokens
+            .idx
+            .owner
+            .prefix(owner_addr)
+            .range(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (key, info) = item?;
+                let token_id = token_id_from_key(key);
+                let approvals = self.approvals_of(deps.storage, &token_id)?;
+                Ok(TokenDetail {
+                    approvals: humanize_approvals(&env.block, &approvals, include_expired),
+                    token_id,
+                    token_uri: info.token_uri,
+                    extension: info.extension,
+                    staked_since: info.staked_since,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensDetailedResponse { tokens })
+    }
+
+    pub fn token_range(
+        &self,
+        deps: Deps,
+        start: u64,
+        end: u64,
+        limit: Option<u32>,
+    ) -> StdResult<TokenRangeResponse> {
+        if start > end {
+            return Err(StdError::generic_err(
+                "Range start must not be after its end",
+            ));
+        }
+        if end - start >= MAX_TOKEN_RANGE_SPAN {
+            return Err(StdError::generic_err(format!(
+                "Range may span at most {} token ids",
+                MAX_TOKEN_RANGE_SPAN
+            )));
+        }
+        let limit = self.page_limit(deps.storage, limit, None)?;
+
+        // numeric ids are zero-padded keys, so the range is a plain key range
+        let min = token_key(&start.to_string());
+        let max = token_key(&end.to_string());
+        let tokens = self
+            .tokens
+            .range(
+                deps.storage,
+                Some(Bound::inclusive(min.as_str())),
+                Some(Bound::inclusive(max.as_str())),
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                let (key, info) = item?;
+                let token_id = token_id_from_key(key);
+                let (_, root) = self.root_token(deps.storage, &token_id, info)?;
+                Ok(TokenOwner {
+                    token_id,
+                    owner: root.owner.into_string(),
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokenRangeResponse { tokens })
+    }
+
+    pub fn tokens_minted_between(
+        &self,
+        deps: Deps,
+        start: Timestamp,
+        end: Timestamp,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+
+        // the cursor is a token id, continue right after its mint time entry
+        let cursor = match start_after {
+            Some(token_id) => {
+                let key = token_key(&token_id);
+                let info = self.tokens.load(deps.storage, &key)?.from_legacy();
+                info.minted_at.map(|minted_at| (minted_at.seconds(), key))
+            }
+            None => None,
+        };
+        let min = match cursor {
+            Some((seconds, ref key)) if seconds >= start.seconds() => {
+                Bound::exclusive((seconds, key.as_str()))
+            }
+            _ => Bound::inclusive((start.seconds(), "")),
+        };
+        let max = Bound::exclusive((end.seconds(), ""));
+
+        let tokens = self
+            .mint_times
+            .keys(deps.storage, Some(min), Some(max), Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, key)| token_id_from_key(key)))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensResponse { tokens })
+    }
+
+    pub fn all_tokens_in_order(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: RangeOrder,
+    ) -> StdResult<TokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, order);
+
+        let tokens: StdResult<Vec<String>> = self
+            .tokens
+            .keys(deps.storage, min, max, order.into())
+            .take(limit)
+            .map(|key| key.map(token_id_from_key))
+            .collect();
+
+        Ok(TokensResponse { tokens: tokens? })
+    }
+
+    pub fn all_tokens_with_owner(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensWithOwnerResponse> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_TOKENS_WITH_OWNER_LIMIT))?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let tokens = self
+            .tokens
+            .range(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (key, info) = item?;
+                let token_id = token_id_from_key(key);
+                // nested tokens belong to the owner of their root, as in `OwnerOf`
+                let (_, root) = self.root_token(deps.storage, &token_id, info)?;
+                Ok(TokenOwner {
+                    token_id,
+                    owner: root.owner.into_string(),
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensWithOwnerResponse { tokens })
+    }
+
+    pub fn allowed_operators(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AllowedOperatorsResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.as_ref().map(Bound::exclusive);
+
+        let operators = self
+            .operator_allowlist
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|r| r.map(Addr::into_string))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(AllowedOperatorsResponse { operators })
+    }
+
+    pub fn bridge_locks(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BridgeLocksResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start_key = start_after.map(|token_id| token_key(&token_id));
+        let start = start_key.as_deref().map(Bound::exclusive);
+
+        let locks = self
+            .bridge_locks
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|r| {
+                r.map(|(key, original_owner)| BridgeLockResponse {
+                    token_id: token_id_from_key(key),
+                    original_owner: original_owner.into_string(),
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(BridgeLocksResponse { locks })
+    }
+
+    pub fn mint_proposals(
+        &self,
+        deps: Deps,
+        env: Env,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<MintProposalsResponse<T>> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start = start_after.map(Bound::exclusive);
+
+        let proposals = self
+            .mint_proposals
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|r| {
+                r.as_ref()
+                    .map_or(true, |(_, proposal)| env.block.time < proposal.expires)
+            })
+            .take(limit)
+            .map(|r| {
+                r.map(|(id, proposal)| MintProposalResponse {
+                    id,
+                    proposer: proposal.proposer.into_string(),
+                    owner: proposal.owner.into_string(),
+                    token_uri: proposal.token_uri,
+                    extension: proposal.extension,
+                    approvals: proposal
+                        .approvals
+                        .into_iter()
+                        .map(Addr::into_string)
+                        .collect(),
+                    expires: proposal.expires,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(MintProposalsResponse { proposals })
+    }
+
+    pub fn is_active(&self, deps: Deps, env: Env, token_id: String) -> StdResult<IsActiveResponse> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        Ok(IsActiveResponse {
+            active: token.is_active(&env.block),
+            valid_until: token.valid_until,
+        })
+    }
+
+    pub fn raffle(&self, deps: Deps) -> StdResult<RaffleResponse> {
+        Ok(RaffleResponse {
+            phase: self.raffle_phase.may_load(deps.storage)?,
+            entries: self
+                .raffle_entry_count
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+            claimed: self
+                .raffle_claimed
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn raffle_entry(
+        &self,
+        deps: Deps,
+        address: String,
+    ) -> StdResult<Option<RaffleEntryResponse>> {
+        let address = deps.api.addr_validate(&address)?;
+        let Some(entry) = self.raffle_entries.may_load(deps.storage, &address)? else {
+            return Ok(None);
+        };
+        let revealed = matches!(
+            self.raffle_phase.may_load(deps.storage)?,
+            Some(RafflePhase::Revealed { .. })
+        );
+        let won = match revealed {
+            true => Some(self.raffle_won(deps.storage, &entry)?),
+            false => None,
+        };
+        Ok(Some(RaffleEntryResponse {
+            index: entry.index,
+            paid: entry.paid,
+            won,
+        }))
+    }
+
+    pub fn partner_minters(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<PartnerMintersResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.as_ref().map(Bound::exclusive);
+
+        let partners = self
+            .partner_minters
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|r| {
+                r.map(|(address, config)| PartnerMinterResponse {
+                    address: address.into_string(),
+                    config,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(PartnerMintersResponse { partners })
+    }
+
+    pub fn mint_ledger(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<MintLedgerResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.as_ref().map(Bound::exclusive);
+
+        let entries = self
+            .wallets_minted_amount
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|r| {
+                r.map(|(wallet, minted)| WalletMints {
+                    wallet: wallet.into_string(),
+                    minted,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(MintLedgerResponse {
+            entries,
+            total_entries: self
+                .wallet_mint_count
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn redemption_status(
+        &self,
+        deps: Deps,
+        token_id: String,
+    ) -> StdResult<Option<RedemptionResponse>> {
+        let redemption = self.redemptions.may_load(deps.storage, &token_id)?;
+        Ok(redemption.map(|redemption| RedemptionResponse {
+            token_id,
+            redemption,
+        }))
+    }
+
+    pub fn redemptions(
+        &self,
+        deps: Deps,
+        status: Option<RedemptionStatus>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RedemptionsResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start = start_after.as_deref().map(Bound::exclusive);
+
+        let redemptions = self
+            .redemptions
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|r| match (r, status) {
+                (Ok((_, redemption)), Some(status)) => redemption.status == status,
+                _ => true,
+            })
+            .take(limit)
+            .map(|r| {
+                r.map(|(token_id, redemption)| RedemptionResponse {
+                    token_id,
+                    redemption,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(RedemptionsResponse { redemptions })
+    }
+
+    pub fn children(
+        &self,
+        deps: Deps,
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start = start_after.as_deref().map(Bound::exclusive);
+
+        let tokens = self
+            .token_children
+            .prefix(&token_id)
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensResponse { tokens })
+    }
+
+    pub fn contract_status(&self, deps: Deps) -> StdResult<ContractStatusResponse> {
+        let ownership = cw_ownable::get_ownership(deps.storage)?;
+        Ok(ContractStatusResponse {
+            sold_out: self.sold_out(deps.storage)?,
+            ownership_renounced: ownership.owner.is_none() && ownership.pending_owner.is_none(),
+            owner: ownership.owner.map(Addr::into_string),
+            pending_owner: ownership.pending_owner.map(Addr::into_string),
+            operators_can_burn: self.operators_can_burn(deps.storage)?,
+            enforce_operator_allowlist: self.enforce_operator_allowlist(deps.storage)?,
+            version: self.contract_version(deps)?,
+        })
+    }
+
+    pub fn contract_version(&self, deps: Deps) -> StdResult<ContractVersionResponse> {
+        let cw2 = cw2::CONTRACT.may_load(deps.storage)?;
+        Ok(ContractVersionResponse {
+            contract: cw2.as_ref().map(|info| info.contract.clone()),
+            version: cw2.map(|info| info.version),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            features: FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(feature, _)| feature.to_string())
+                .collect(),
+        })
+    }
+
+    pub fn config(&self, deps: Deps) -> StdResult<ConfigResponse> {
+        let info = self.contract_info.load(deps.storage)?;
+        let ownership = cw_ownable::get_ownership(deps.storage)?;
+        Ok(ConfigResponse {
+            name: info.name,
+            symbol: info.symbol,
+            owner: ownership.owner.map(Addr::into_string),
+            pending_owner: ownership.pending_owner.map(Addr::into_string),
+            withdraw_address: self.withdraw_address.may_load(deps.storage)?,
+            max_supply: self.max_supply.may_load(deps.storage)?,
+            supply_cap_basis: self.supply_cap_basis(deps.storage)?,
+            max_nfts_per_wallet: self.max_nfts_per_wallet.may_load(deps.storage)?,
+            mint_price_per_nft: self.mint_price_per_nft.may_load(deps.storage)?,
+            redemption_payout: self.redemption_payout.may_load(deps.storage)?,
+            max_approvals_per_token: self.max_approvals_per_token(deps.storage)?,
+            default_approval_ttl: self.default_approval_ttl.may_load(deps.storage)?,
+            allow_never_expiration: self.allow_never_expiration(deps.storage)?,
+            operators_can_burn: self.operators_can_burn(deps.storage)?,
+            enforce_operator_allowlist: self.enforce_operator_allowlist(deps.storage)?,
+            record_token_history: self.record_token_history(deps.storage)?,
+            max_token_history: self.max_token_history(deps.storage)?,
+            activity_buffer_size: self.activity_buffer_size(deps.storage)?,
+            delayed_reveal: self.delayed_reveal(deps.storage)?,
+            balance_snapshots: self.balance_snapshots(deps.storage)?,
+            retain_burned_tokens: self.retain_burned_tokens(deps.storage)?,
+            index_token_uris: self.index_token_uris(deps.storage)?,
+            pagination: self.pagination(deps.storage)?,
+            import_open: self.import_open(deps.storage)?,
+            bridge: self.bridge(deps.storage)?.map(String::from),
+            royalty: self.royalty.may_load(deps.storage)?,
+            auction_extension: self.auction_extension(deps.storage)?,
+            keep_user_on_transfer: self.keep_user_on_transfer(deps.storage)?,
+            min_stake_period: self.min_stake_period(deps.storage)?,
+            wrapped_collection: self
+                .wrapped_collection
+                .may_load(deps.storage)?
+                .map(String::from),
+            burn_to_mint: self.burn_to_mint.may_load(deps.storage)?,
+            reward_asset: self.reward_asset.may_load(deps.storage)?,
+            royalty_registry: self
+                .royalty_registry
+                .may_load(deps.storage)?
+                .map(String::from),
+            voucher_pubkey: self.voucher_pubkey.may_load(deps.storage)?,
+            bridge_operator: self
+                .bridge_operator
+                .may_load(deps.storage)?
+                .map(String::from),
+            subscription: self.subscription.may_load(deps.storage)?,
+            co_signing: self.co_signing.may_load(deps.storage)?,
+            proceeds_vesting: self.proceeds_vesting.may_load(deps.storage)?,
+            open_edition: self.open_edition.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn export_state(
+        &self,
+        deps: Deps,
+        section: ExportSection,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<StateExport<T>> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let data = match section {
+            ExportSection::Tokens => {
+                let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+                let tokens = self
+                    .tokens
+                    .range(deps.storage, min, max, Order::Ascending)
+                    .take(limit)
+                    .map(|item| {
+                        let (key, token) = item?;
+                        let token_id = token_id_from_key(key);
+                        // expired approvals are exported too, the state is copied as is
+                        let approvals = self
+                            .token_approvals
+                            .prefix(&token_id)
+                            .range(deps.storage, None, None, Order::Ascending)
+                            .map(|item| {
+                                item.map(|(spender, expires)| cw721::Approval {
+                                    spender: spender.into_string(),
+                                    expires,
+                                })
+                            })
+                            .collect::<StdResult<Vec<_>>>()?;
+                        Ok(ExportedToken {
+                            token_id,
+                            owner: token.owner.into_string(),
+                            token_uri: token.token_uri,
+                            extension: token.extension,
+                            approvals,
+                            parent: token.parent,
+                            redeemed: token.redeemed,
+                            revealed: token.revealed,
+                            accessories: token.accessories,
+                            valid_until: token.valid_until,
+                        })
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
+                ExportData::Tokens(tokens)
+            }
+            ExportSection::Operators => {
+                let cursor = start_after
+                    .map(|cursor| match cursor.split_once('/') {
+                        Some((granter, operator)) => {
+                            Ok((Addr::unchecked(granter), Addr::unchecked(operator)))
+                        }
+                        None => Err(StdError::generic_err(
+                            "operators are paged by \"granter/operator\"",
+                        )),
+                    })
+                    .transpose()?;
+                let min = cursor
+                    .as_ref()
+                    .map(|(granter, operator)| Bound::exclusive((granter, operator)));
+                let operators = self
+                    .operators
+                    .range(deps.storage, min, None, Order::Ascending)
+                    .take(limit)
+                    .map(|item| {
+                        item.map(|((granter, operator), grant)| ExportedOperator {
+                            granter: granter.into_string(),
+                            operator: operator.into_string(),
+                            grant,
+                        })
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
+                ExportData::Operators(operators)
+            }
+            ExportSection::WalletMints => {
+                let start_after = start_after.map(Addr::unchecked);
+                let wallets = self
+                    .wallets_minted_amount
+                    .range(
+                        deps.storage,
+                        start_after.as_ref().map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .map(|item| {
+                        item.map(|(wallet, minted)| WalletMints {
+                            wallet: wallet.into_string(),
+                            minted,
+                        })
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
+                ExportData::WalletMints(wallets)
+            }
+            ExportSection::Config => {
+                let info = self.contract_info.load(deps.storage)?;
+                ExportData::Config(ExportedConfig {
+                    name: info.name,
+                    symbol: info.symbol,
+                    withdraw_address: self.withdraw_address.may_load(deps.storage)?,
+                    max_supply: self.max_supply.may_load(deps.storage)?,
+                    max_nfts_per_wallet: self.max_nfts_per_wallet.may_load(deps.storage)?,
+                    price_per_nft: self.mint_price_per_nft.may_load(deps.storage)?,
+                    minted_total: self.minted_total(deps.storage)?,
+                    burned_total: self.burned_total(deps.storage)?,
+                    bridged_in_total: self.bridged_in_total(deps.storage)?,
+                    bridged_out_total: self.bridged_out_total(deps.storage)?,
+                })
+            }
+        };
+
+        Ok(StateExport {
+            version: STATE_EXPORT_VERSION,
+            data,
+        })
+    }
+
+    pub fn is_transferable(
+        &self,
+        deps: Deps,
+        env: Env,
+        token_id: String,
+        by: Option<String>,
+    ) -> StdResult<TransferableResponse> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        let by = by.map(|by| deps.api.addr_validate(&by)).transpose()?;
+
+        let res = self
+            .assert_transferable(deps.storage, &env.block, &token_id, &token)
+            .and_then(|_| match by {
+                Some(by) => self
+                    .can_move(deps.storage, &env.block, &by, &token_id, &token, false)
+                    .and_then(|grant| match grant {
+                        Some(_) => self.assert_royalty_compliant(deps, &env.block, &by),
+                        None => Ok(()),
+                    }),
+                None => Ok(()),
+            });
+        Ok(TransferableResponse {
+            transferable: res.is_ok(),
+            reason: res.err().map(|err| err.to_string()),
+        })
+    }
+
+    pub fn user_of(&self, deps: Deps, env: Env, token_id: String) -> StdResult<UserOfResponse> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        match (token.user, token.user_expires) {
+            (Some(user), Some(expires)) if !expires.is_expired(&env.block) => Ok(UserOfResponse {
+                user: Some(user.into_string()),
+                expires: Some(expires),
+            }),
+            _ => Ok(UserOfResponse {
+                user: None,
+                expires: None,
+            }),
+        }
+    }
+
+    pub fn delegate_of(
+        &self,
+        deps: Deps,
+        env: Env,
+        token_id: String,
+    ) -> StdResult<DelegateOfResponse> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        match (token.delegate, token.delegate_expires) {
+            (Some(delegate), Some(expires)) if !expires.is_expired(&env.block) => {
+                Ok(DelegateOfResponse {
+                    delegate: Some(delegate.into_string()),
+                    expires: Some(expires),
+                })
+            }
+            _ => Ok(DelegateOfResponse {
+                delegate: None,
+                expires: None,
+            }),
+        }
+    }
+
+    pub fn tokens_delegated_to(
+        &self,
+        deps: Deps,
+        env: Env,
+        delegate: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let delegate = deps.api.addr_validate(&delegate)?;
+        let tokens = self
+            .delegated_tokens
+            .prefix(&delegate)
+            .range(deps.storage, min, max, Order::Ascending)
+            .filter(|r| {
+                r.as_ref()
+                    .map_or(true, |(_, expires)| !expires.is_expired(&env.block))
+            })
+            .take(limit)
+            .map(|r| r.map(|(key, _)| token_id_from_key(key)))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensResponse { tokens })
+    }
+
+    pub fn staked_token(
+        &self,
+        deps: Deps,
+        env: Env,
+        token_id: String,
+    ) -> StdResult<Option<StakedTokenResponse>> {
+        let token = self.load_token(deps.storage, &token_id)?;
+        Ok(token.staked_since.map(|staked_since| StakedTokenResponse {
+            token_id,
+            staked_since,
+            staked_for: env.block.time.seconds() - staked_since.seconds(),
+        }))
+    }
+
+    pub fn staked_tokens(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<StakedTokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        let tokens = self
+            .staked_tokens
+            .prefix(&owner_addr)
+            .range(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(key, staked_since)| StakedTokenResponse {
+                    token_id: token_id_from_key(key),
+                    staked_since,
+                    staked_for: env.block.time.seconds() - staked_since.seconds(),
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(StakedTokensResponse { tokens })
+    }
+
+    pub fn can_mint(
+        &self,
+        deps: Deps,
+        env: Env,
+        address: String,
+        quantity: Option<u64>,
+    ) -> StdResult<CanMintResponse> {
+        let quantity = quantity.unwrap_or(1);
+        let price = self.mint_price(deps.storage, quantity)?;
+
+        let owner = self.validate_mint_owner(deps, &address);
+        // an address that can't mint has no allocation to report
+        let remaining_allocation = match &owner {
+            Ok(owner) => self.remaining_allocation(deps.storage, owner)?,
+            Err(_) => None,
+        };
+        let res = owner.and_then(|owner| {
+            self.assert_supply_available(deps.storage, &env.block, quantity)?;
+            self.assert_wallet_allocation(deps.storage, &owner, quantity)
+        });
+        Ok(CanMintResponse {
+            can_mint: res.is_ok(),
+            reason: res.err().map(|err| err.to_string()),
+            price,
+            remaining_allocation,
+        })
+    }
+
+    pub fn token_history(
+        &self,
+        deps: Deps,
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order: Option<RangeOrder>,
+    ) -> StdResult<TokenHistoryResponse> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_TOKEN_HISTORY_LIMIT))?;
+        let order = order.unwrap_or_default();
+        let cursor = start_after.map(Bound::exclusive);
+        let (min, max) = match order {
+            RangeOrder::Asc => (cursor, None),
+            RangeOrder::Desc => (None, cursor),
+        };
+
+        let entries = self
+            .token_history
+            .prefix(&token_id)
+            .range(deps.storage, min, max, order.into())
+            .take(limit)
+            .map(|r| r.map(|(sequence, entry)| TokenHistoryEntry { sequence, entry }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokenHistoryResponse { entries })
+    }
+
+    pub fn activity(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order: Option<RangeOrder>,
+    ) -> StdResult<ActivityResponse> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_ACTIVITY_LIMIT))?;
+        let size = self.activity_buffer_size(deps.storage)?;
+        let head = self.activity_head(deps.storage)?;
+        if size == 0 {
+            return Ok(ActivityResponse { entries: vec![] });
+        }
+
+        // only the newest `size` events are still in the buffer
+        let oldest = head.saturating_sub(size);
+        let ids: Vec<u64> = match order.unwrap_or(RangeOrder::Desc) {
+            RangeOrder::Asc => {
+                let from = start_after.map_or(oldest, |id| id.saturating_add(1).max(oldest));
+                (from..head).take(limit).collect()
+            }
+            RangeOrder::Desc => {
+                let to = start_after.map_or(head, |id| id.min(head));
+                (oldest..to).rev().take(limit).collect()
+            }
+        };
+        let entries = ids
+            .into_iter()
+            .map(|id| self.activity.load(deps.storage, id % size))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ActivityResponse { entries })
+    }
+
+    pub fn tokens_by_extension_key(
+        &self,
+        deps: Deps,
+        key: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        if self.index_extension.is_none() {
+            return Err(StdError::generic_err("Token extensions are not indexed"));
+        }
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let tokens = self
+            .extension_index
+            .prefix((&key, &value))
+            .keys(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|key| key.map(token_id_from_key))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensResponse { tokens })
+    }
+
+    pub fn tokens_by_uri(
+        &self,
+        deps: Deps,
+        token_uri: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        if !self.index_token_uris(deps.storage)? {
+            return Err(StdError::generic_err("Token uris are not indexed"));
+        }
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let tokens = self
+            .uri_tokens
+            .prefix(&token_uri)
+            .keys(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|key| key.map(token_id_from_key))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TokensResponse { tokens })
+    }
+
+    pub fn burned_tokens(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BurnedTokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let tokens = self
+            .burned_tokens
+            .range(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(key, record)| BurnedTokenResponse {
+                    token_id: token_id_from_key(key),
+                    record,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(BurnedTokensResponse { tokens })
+    }
+
+    pub fn listings(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let listings = self
+            .listings
+            .range(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(key, listing)| ListingResponse {
+                    token_id: token_id_from_key(key),
+                    listing,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ListingsResponse { listings })
+    }
+
+    pub fn bundles(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<BundlesResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start = start_after.map(Bound::exclusive);
+
+        let bundles = self
+            .bundles
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(bundle_id, bundle)| BundleResponse { bundle_id, bundle }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(BundlesResponse { bundles })
+    }
+
+    pub fn offers_by_token(
+        &self,
+        deps: Deps,
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.as_ref().map(Bound::exclusive);
+
+        let offers = self
+            .offers
+            .prefix(&token_id)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(bidder, offer)| OfferResponse {
+                    token_id: token_id.clone(),
+                    bidder: bidder.into_string(),
+                    offer,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(OffersResponse { offers })
+    }
+
+    pub fn offers_by_bidder(
+        &self,
+        deps: Deps,
+        bidder: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let start = start_after.as_deref().map(Bound::exclusive);
+
+        let bidder_addr = deps.api.addr_validate(&bidder)?;
+        let offers = self
+            .bidder_offers
+            .prefix(&bidder_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(token_id, offer)| OfferResponse {
+                    token_id,
+                    bidder: bidder.clone(),
+                    offer,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(OffersResponse { offers })
+    }
+
+    pub fn auctions(
+        &self,
+        deps: Deps,
+        env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_ended: bool,
+    ) -> StdResult<AuctionsResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let auctions = self
+            .auctions
+            .range(deps.storage, min, max, Order::Ascending)
+            .filter(|item| {
+                item.as_ref().map_or(true, |(_, auction)| {
+                    include_ended || !auction.is_ended(&env.block)
+                })
+            })
+            .take(limit)
+            .map(|item| {
+                item.map(|(key, auction)| AuctionResponse {
+                    token_id: token_id_from_key(key),
+                    auction,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(AuctionsResponse { auctions })
+    }
+
+    pub fn unrevealed_tokens(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<UnrevealedTokensResponse> {
+        let limit = self.page_limit(deps.storage, limit, None)?;
+        let (min, max) = page_bounds(start_after, RangeOrder::Asc);
+
+        let tokens = self
+            .unrevealed_tokens
+            .keys(deps.storage, min, max, Order::Ascending)
+            .take(limit)
+            .map(|key| key.map(token_id_from_key))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(UnrevealedTokensResponse {
+            tokens,
+            count: self.unrevealed_count(deps.storage)?,
+        })
+    }
+
+    pub fn num_tokens_of_owner(&self, deps: Deps, owner: String) -> StdResult<NumTokensResponse> {
+        let owner = deps.api.addr_validate(&owner)?;
+        let count = self
+            .holdings
+            .may_load(deps.storage, &owner)?
+            .unwrap_or_default();
+        Ok(NumTokensResponse { count })
+    }
+
+    pub fn verify_holdings(
+        &self,
+        deps: Deps,
+        address: String,
+    ) -> StdResult<VerifyHoldingsResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        let recorded = self
+            .holdings
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        let indexed = self
+            .tokens
+            .idx
+            .owner
+            .prefix(address)
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .count() as u64;
+        Ok(VerifyHoldingsResponse {
+            recorded,
+            indexed,
+            consistent: recorded == indexed,
+        })
+    }
+
+    pub fn token_by_index(&self, deps: Deps, index: u64) -> StdResult<TokenByIndexResponse> {
+        let token_id = self
+            .token_positions
+            .may_load(deps.storage, index)?
+            .ok_or_else(|| StdError::generic_err(format!("Index {} out of bounds", index)))?;
+        Ok(TokenByIndexResponse { token_id })
+    }
+
+    pub fn token_of_owner_by_index(
+        &self,
+        deps: Deps,
+        owner: String,
+        index: u64,
+    ) -> StdResult<TokenByIndexResponse> {
+        let owner = deps.api.addr_validate(&owner)?;
+        let token_id = self
+            .owner_token_positions
+            .may_load(deps.storage, (&owner, index))?
+            .ok_or_else(|| StdError::generic_err(format!("Index {} out of bounds", index)))?;
+        Ok(TokenByIndexResponse { token_id })
+    }
+
+    pub fn token_stats(&self, deps: Deps, token_id: String) -> StdResult<TokenStatsResponse> {
+        let info = self.load_token(deps.storage, &token_id)?;
+        Ok(TokenStatsResponse {
+            transfer_count: info.transfer_count,
+            last_transferred_at: info.last_transferred_at,
+        })
+    }
+
+    pub fn supply(&self, deps: Deps) -> StdResult<SupplyResponse> {
+        Ok(SupplyResponse {
+            circulating: self.token_count(deps.storage)?,
+            minted: self.minted_total(deps.storage)?,
+            burned: self.burned_total(deps.storage)?,
+            bridged_in: self.bridged_in_total(deps.storage)?,
+            bridged_out: self.bridged_out_total(deps.storage)?,
+            max_supply: self.max_supply.may_load(deps.storage)?,
+            cap_basis: self.supply_cap_basis(deps.storage)?,
+            sold_out: self.sold_out(deps.storage)?,
+        })
+    }
+
+    pub fn withdraw_history(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<WithdrawHistoryResponse> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_WITHDRAW_HISTORY_LIMIT))?;
+        let start = start_after.map(Bound::exclusive);
+
+        let withdrawals = self
+            .withdraw_history
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|r| r.map(|(id, withdrawal)| WithdrawalResponse { id, withdrawal }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(WithdrawHistoryResponse { withdrawals })
+    }
+
+    pub fn total_withdrawn(&self, deps: Deps) -> StdResult<TotalWithdrawnResponse> {
+        let amounts = self
+            .total_withdrawn
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|r| r.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(TotalWithdrawnResponse { amounts })
+    }
+
+    pub fn vested_proceeds(&self, deps: Deps, env: Env) -> StdResult<VestedProceedsResponse> {
+        let Some(vesting) = self.proceeds_vesting.may_load(deps.storage)? else {
+            return Ok(VestedProceedsResponse { proceeds: vec![] });
+        };
+        let proceeds = self
+            .vesting_proceeds
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|r| {
+                let (denom, total) = r?;
+                let withdrawn = self
+                    .total_withdrawn
+                    .may_load(deps.storage, &denom)?
+                    .unwrap_or_default();
+                Ok(VestedProceeds {
+                    vested: vesting.vested(total, env.block.time),
+                    denom,
+                    total,
+                    withdrawn,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(VestedProceedsResponse { proceeds })
+    }
+
+    pub fn treasury(&self, deps: Deps, env: Env) -> StdResult<TreasuryResponse> {
+        let mut balances: Vec<TreasuryBalance> = deps
+            .querier
+            .query_all_balances(env.contract.address)?
+            .into_iter()
+            .map(|coin| TreasuryBalance {
+                denom: coin.denom,
+                balance: coin.amount,
+                reserved: Uint128::zero(),
+                withdrawable: Uint128::zero(),
+            })
+            .collect();
+
+        // every pending redemption is owed the payout once fulfilled
+        if let Some(payout) = self.redemption_payout.may_load(deps.storage)? {
+            let mut pending = 0u64;
+            for item in self
+                .redemptions
+                .range(deps.storage, None, None, Order::Ascending)
+            {
+                if item?.1.status == RedemptionStatus::Pending {
+                    pending += 1;
+                }
+            }
+            let reserved = payout.amount * Uint128::from(pending);
+            match balances.iter_mut().find(|b| b.denom == payout.denom) {
+                Some(balance) => balance.reserved = reserved,
+                None if !reserved.is_zero() => balances.push(TreasuryBalance {
+                    denom: payout.denom,
+                    balance: Uint128::zero(),
+                    reserved,
+                    withdrawable: Uint128::zero(),
+                }),
+                None => {}
+            }
+        }
+        // the highest bids go back to their bidders or to the sellers
+        for item in self
+            .escrowed_bids
+            .range(deps.storage, None, None, Order::Ascending)
+        {
+            let (denom, escrowed) = item?;
+            match balances.iter_mut().find(|b| b.denom == denom) {
+                Some(balance) => balance.reserved += escrowed,
+                None => balances.push(TreasuryBalance {
+                    denom,
+                    balance: Uint128::zero(),
+                    reserved: escrowed,
+                    withdrawable: Uint128::zero(),
+                }),
+            }
+        }
+        for balance in balances.iter_mut() {
+            balance.withdrawable = balance.balance.saturating_sub(balance.reserved);
+        }
+
+        Ok(TreasuryResponse {
+            withdraw_address: self.withdraw_address.may_load(deps.storage)?,
+            balances,
+        })
+    }
+
+    pub fn holder_balance(&self, deps: Deps, address: String) -> StdResult<HolderBalanceResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        let count = self.holdings.may_load(deps.storage, &address)?;
+        Ok(HolderBalanceResponse {
+            count: count.unwrap_or_default(),
+        })
+    }
+
+    pub fn is_holder(
+        &self,
+        deps: Deps,
+        env: Env,
+        address: String,
+        min_count: Option<u64>,
+    ) -> StdResult<IsHolderResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        let count = self
+            .holdings
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        let min_count = min_count.unwrap_or(1);
+        if count < min_count || !self.subscription.exists(deps.storage) {
+            return Ok(IsHolderResponse {
+                is_holder: count >= min_count,
+                count,
+            });
+        }
+
+        // expired passes don't count, stop looking once enough active ones turned up
+        let mut active = 0;
+        for token_id in self.owner_token_positions.prefix(&address).range(
+            deps.storage,
+            None,
+            None,
+            Order::Ascending,
+        ) {
+            let (_, token_id) = token_id?;
+            if self
+                .load_token(deps.storage, &token_id)?
+                .is_active(&env.block)
+            {
+                active += 1;
+                if active >= min_count {
+                    break;
+                }
+            }
+        }
+        Ok(IsHolderResponse {
+            is_holder: active >= min_count,
+            count,
+        })
+    }
+
+    pub fn holds_token(&self, deps: Deps, address: String, token_id: String) -> StdResult<bool> {
+        let address = deps.api.addr_validate(&address)?;
+        let token = self.may_load_token(deps.storage, &token_id)?;
+        Ok(token.map_or(false, |token| token.owner == address))
+    }
+
+    /// Holders with at least `min_count` tokens, sorted by address
+    pub fn holders(
+        &self,
+        deps: Deps,
+        min_count: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<HoldersResponse> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_HOLDERS_LIMIT))?;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.as_ref().map(Bound::exclusive);
+
+        let holders = self
+            .holdings
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|r| r.as_ref().map_or(true, |(_, count)| *count >= min_count))
+            .take(limit)
+            .map(|r| {
+                r.map(|(address, count)| Holder {
+                    address: address.into_string(),
+                    count,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(HoldersResponse { holders })
+    }
+
+    pub fn top_holders(&self, deps: Deps, limit: Option<u32>) -> StdResult<HoldersResponse> {
+        let limit = self.page_limit(deps.storage, limit, Some(MAX_TOP_HOLDERS_LIMIT))?;
+
+        let holders = self
+            .holdings
+            .idx
+            .count
+            .range(deps.storage, None, None, Order::Descending)
+            .take(limit)
+            .map(|r| {
+                r.map(|(address, count)| Holder {
+                    address: address.into_string(),
+                    count,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(HoldersResponse { holders })
+    }
+
+    pub fn ownership(deps: Deps) -> StdResult<cw_ownable::Ownership<Addr>> {
+        cw_ownable::get_ownership(deps.storage)
+    }
+}
+
+fn parse_approval(item: StdResult<(Addr, Expiration)>) -> StdResult<cw721::Approval> {
+    item.map(|(spender, expires)| cw721::Approval {
+        spender: spender.to_string(),
+        expires,
+    })
+}
+
+fn humanize_approvals(
+    block: &BlockInfo,
+    approvals: &[Approval],
+    include_expired: bool,
+) -> Vec<cw721::Approval> {
+    approvals
+        .iter()
+        .filter(|apr| include_expired || !apr.is_expired(block))
+        .map(humanize_approval)
+        .collect()
+}
+
+fn humanize_approval(approval: &Approval) -> cw721::Approval {
+    cw721::Approval {
+        spender: approval.spender.to_string(),
+        expires: approval.expires,
+    }
+}
+
+/// Turns a `start_after` token id into bounds over token keys, capping from above when descending
+fn page_bounds<'a, K: PrimaryKey<'a>>(
+    start_after: Option<String>,
+    order: RangeOrder,
+) -> (Option<Bound<'a, K>>, Option<Bound<'a, K>>) {
+    let cursor = start_after.map(|token_id| Bound::ExclusiveRaw(token_key(&token_id).into()));
+    match order {
+        RangeOrder::Asc => (cursor, None),
+        RangeOrder::Desc => (None, cursor),
     }
 }
@@ -3,23 +3,265 @@ use serde::de::DeserializeOwned;
 use serde::{ Deserialize, Serialize };
 use std::marker::PhantomData;
 
-use cosmwasm_std::{ Addr, BlockInfo, Coin, CustomMsg, StdResult, Storage };
+use cosmwasm_std::{
+    Addr,
+    Binary,
+    BlockInfo,
+    Coin,
+    CustomMsg,
+    Decimal,
+    Decimal256,
+    Empty,
+    Order,
+    StdError,
+    StdResult,
+    Storage,
+    Timestamp,
+    Uint128,
+    Uint256,
+};
 
 use cw721::{ ContractInfoResponse, Cw721, Expiration };
-use cw_storage_plus::{ Index, IndexList, IndexedMap, Item, Map, MultiIndex };
+use cw_storage_plus::{
+    Bound,
+    Index,
+    IndexList,
+    IndexedMap,
+    Item,
+    Map,
+    MultiIndex,
+    SnapshotItem,
+    SnapshotMap,
+    Strategy,
+};
+
+/// How many levels of parents a token may have
+pub const MAX_NEST_DEPTH: u32 = 8;
+pub const DEFAULT_MAX_APPROVALS_PER_TOKEN: u64 = 32;
+pub const DEFAULT_MAX_TOKEN_HISTORY: u64 = 500;
+pub const DEFAULT_ACTIVITY_BUFFER_SIZE: u64 = 1000;
+pub const DEFAULT_PAGE_LIMIT: u32 = 10;
+pub const MAX_PAGE_LIMIT: u32 = 1000;
+pub const DEFAULT_AUCTION_EXTENSION: u64 = 600;
+/// Seconds the owner has after a raffle closes to reveal its seed. Past that the raffle is
+/// called off and every entry is refunded
+pub const RAFFLE_REVEAL_PERIOD: u64 = 7 * 24 * 60 * 60;
+/// Shape of `TokenInfo` this code writes. Bump it together with `TokenInfo::from_legacy`
+/// when stored tokens need more than serde defaults to load in the new shape
+pub const TOKEN_STORAGE_VERSION: u32 = 1;
+/// Bumped whenever an `ExportState` structure changes, imports only accept the same version
+pub const STATE_EXPORT_VERSION: u32 = 1;
+
+/// Derives the (key, value) pairs a token is indexed by from its extension
+pub type ExtensionIndexFn<T> = fn(&T) -> Vec<(String, String)>;
+/// Folds the extension of an attached accessory into the extension of its parent
+pub type AccessoryMergeFn<T> = fn(T, &T) -> T;
 
 pub struct Cw721Contract<'a, T, C, E, Q>
     where T: Serialize + DeserializeOwned + Clone, Q: CustomMsg, E: CustomMsg {
     pub contract_info: Item<'a, ContractInfoResponse>,
     pub token_count: Item<'a, u64>,
     /// Stored as (granter, operator) giving operator full control over granter's account
-    pub operators: Map<'a, (&'a Addr, &'a Addr), Expiration>,
+    pub operators: Map<'a, (&'a Addr, &'a Addr), OperatorGrant>,
+    /// Stored as (operator, granter), mirrors the expirations held in `operators`
+    pub operator_granters: Map<'a, (&'a Addr, &'a Addr), Expiration>,
     pub tokens: IndexedMap<'a, &'a str, TokenInfo<T>, TokenIndexes<'a, T>>,
+    /// Stored as (token_id, spender), kept apart from TokenInfo so approving doesn't rewrite it
+    pub token_approvals: Map<'a, (&'a str, &'a Addr), Expiration>,
+    /// Stored as (spender, token_id), mirrors `token_approvals`
+    pub spender_approvals: Map<'a, (&'a Addr, &'a str), Expiration>,
+    pub max_approvals_per_token: Item<'a, u64>,
+    /// Seconds until an approval or operator grant expires when the caller does not pick one
+    pub default_approval_ttl: Item<'a, u64>,
+    pub allow_never_expiration: Item<'a, bool>,
+    /// Permit nonces already consumed, per owner
+    pub used_permit_nonces: Map<'a, (&'a Addr, u64), Empty>,
     pub withdraw_address: Item<'a, String>,
     pub max_nfts_per_wallet: Item<'a, u64>,
     pub max_supply: Item<'a, u64>,
     pub mint_price_per_nft: Item<'a, Coin>,
-    pub wallets_minted_amount: Map<'a, String, u64>,
+    pub wallets_minted_amount: Map<'a, &'a Addr, u64>,
+    pub operators_can_burn: Item<'a, bool>,
+    pub bech32_prefix: Item<'a, String>,
+    pub redemptions: Map<'a, &'a str, Redemption>,
+    /// Paid from the contract balance to the redeemer when a redemption is fulfilled
+    pub redemption_payout: Item<'a, Coin>,
+    /// Tokens handed to a receiver contract that may still be reverted in `reply`
+    pub pending_sends: Map<'a, u64, PendingSend<T>>,
+    pub next_reply_id: Item<'a, u64>,
+    /// Stored as (parent, child) for every nested token
+    pub token_children: Map<'a, (&'a str, &'a str), Empty>,
+    /// Marketplaces allowed to move tokens through an operator grant
+    pub operator_allowlist: Map<'a, &'a Addr, Empty>,
+    pub enforce_operator_allowlist: Item<'a, bool>,
+    /// Number of addresses holding at least one token
+    pub holder_count: Item<'a, u64>,
+    /// How many tokens each address holds, addresses without tokens are removed
+    pub holdings: IndexedMap<'a, &'a Addr, u64, HoldingIndexes<'a>>,
+    /// Every token ever minted, never decremented and used to derive token ids
+    pub minted_total: Item<'a, u64>,
+    pub burned_total: Item<'a, u64>,
+    /// Tokens that ever arrived with `BridgeMint`, never decremented. They circulate next to
+    /// the minted ones but don't take ids of the mint sequence
+    pub bridged_in_total: Item<'a, u64>,
+    /// Tokens that ever left with `BridgeBurn`, which isn't counted as a burn
+    pub bridged_out_total: Item<'a, u64>,
+    pub supply_cap_basis: Item<'a, SupplyCapBasis>,
+    /// Stored as (minted_at seconds, token key) for every token with a known mint time
+    pub mint_times: Map<'a, (u64, &'a str), Empty>,
+    /// Every withdrawal of contract funds, keyed by an incrementing id
+    pub withdraw_history: Map<'a, u64, WithdrawRecord>,
+    /// Sum of all withdrawals per denom
+    pub total_withdrawn: Map<'a, &'a str, Uint128>,
+    pub record_token_history: Item<'a, bool>,
+    /// How many history entries are kept per token, older ones are pruned
+    pub max_token_history: Item<'a, u64>,
+    /// Stored as (token_id, sequence), kept after the token is burned
+    pub token_history: Map<'a, (&'a str, u64), HistoryEntry>,
+    /// How many of the newest events `activity` keeps, 0 disables the feed
+    pub activity_buffer_size: Item<'a, u64>,
+    /// Ring buffer of recent events, event `id` is stored in slot `id % activity_buffer_size`
+    pub activity: Map<'a, u64, ActivityEntry>,
+    /// Id of the next event, i.e. how many events were ever recorded
+    pub activity_head: Item<'a, u64>,
+    /// Every live token by position, burning moves the last token into the freed position
+    pub token_positions: Map<'a, u64, String>,
+    pub token_position_of: Map<'a, &'a str, u64>,
+    /// Stored as (owner, position) like `token_positions`, but for the tokens of each owner
+    pub owner_token_positions: Map<'a, (&'a Addr, u64), String>,
+    pub owner_token_position_of: Map<'a, &'a str, u64>,
+    /// If true, tokens are minted unrevealed and keep their placeholder token_uri until revealed
+    pub delayed_reveal: Item<'a, bool>,
+    /// Keys of the tokens still waiting for their reveal
+    pub unrevealed_tokens: Map<'a, &'a str, Empty>,
+    pub unrevealed_count: Item<'a, u64>,
+    /// Set once the sold out event was emitted, cleared if burning frees capacity again
+    pub sold_out_emitted: Item<'a, bool>,
+    /// How past holder balances are kept, fixed at instantiation
+    pub balance_snapshots: Item<'a, SnapshotStrategy>,
+    /// Mirrors `holdings` with a changelog, only written when snapshots are enabled.
+    /// Checkpoints are added per block or by the owner depending on `balance_snapshots`
+    pub holder_balance_snapshots: SnapshotMap<'a, &'a Addr, u64>,
+    pub holder_count_snapshots: SnapshotItem<'a, u64>,
+    /// Mirrors `token_count` with a changelog, written along with the balance snapshots
+    pub token_count_snapshots: SnapshotItem<'a, u64>,
+    /// What `FundRewards` pays holders in, can't change once rewards were funded
+    pub reward_asset: Item<'a, RewardAsset>,
+    /// Rewards funded per token held, accumulated over every funding
+    pub reward_per_token: Item<'a, Decimal256>,
+    /// `reward_per_token` as of the last time each holder's rewards were settled
+    pub reward_indices: Map<'a, &'a Addr, Decimal256>,
+    /// Rewards settled but not claimed yet, by holder
+    pub accrued_rewards: Map<'a, &'a Addr, Uint128>,
+    /// Shared registry of royalty compliant marketplaces, operators must be on it to transfer
+    pub royalty_registry: Item<'a, Addr>,
+    /// Last registry answer per operator as (height, compliant), only reused within that block
+    pub registry_answers: Map<'a, &'a Addr, (u64, bool)>,
+    /// secp256k1 public key of the creator signing mint vouchers
+    pub voucher_pubkey: Item<'a, Binary>,
+    /// Voucher nonces already redeemed
+    pub used_voucher_nonces: Map<'a, u64, Empty>,
+    /// Contracts allowed to mint on behalf of their users, e.g. launchpads
+    pub partner_minters: Map<'a, &'a Addr, PartnerConfig>,
+    /// Lock/release bridge allowed to hand out tokens locked with `BridgeLock`
+    pub bridge_operator: Item<'a, Addr>,
+    /// Original owner of every token locked with the contract for the bridge, by token key
+    pub bridge_locks: Map<'a, &'a str, Addr>,
+    /// Stage of the current or last raffle mint, see `StartRaffle`
+    pub raffle_phase: Item<'a, RafflePhase>,
+    /// Entries of the current raffle that haven't been claimed yet
+    pub raffle_entries: Map<'a, &'a Addr, RaffleEntry>,
+    /// Entries taken by the current raffle, claimed or not
+    pub raffle_entry_count: Item<'a, u64>,
+    /// Winners of the current raffle that claimed their token or gave it up
+    pub raffle_claimed: Item<'a, u64>,
+    /// If set, tokens are passes that stay active for a period and are renewed with `Renew`
+    pub subscription: Item<'a, SubscriptionConfig>,
+    /// Stored as (valid_until seconds, token key) for every pass
+    pub subscription_expiries: Map<'a, (u64, &'a str), Empty>,
+    /// Last entry of `subscription_expiries` looked at by `ReapExpired`, the next call goes on
+    /// from there
+    pub reap_cursor: Item<'a, (u64, String)>,
+    /// If set, tokens are only minted once every co-signer approved, see `ProposeMint`
+    pub co_signing: Item<'a, CoSignConfig>,
+    /// Mints waiting for approvals, by proposal id
+    pub mint_proposals: Map<'a, u64, MintProposal<T>>,
+    pub next_proposal_id: Item<'a, u64>,
+    /// If set, mint proceeds unlock for withdrawal over time. Only set at instantiation
+    pub proceeds_vesting: Item<'a, VestingConfig>,
+    /// Everything mints paid per denom while proceeds vest
+    pub vesting_proceeds: Map<'a, &'a str, Uint128>,
+    /// Start and end of an open edition, which mints without `max_supply` until it closes
+    pub open_edition: Item<'a, (Timestamp, Timestamp)>,
+    /// The last sale of each token, by its key
+    pub last_sale: Map<'a, &'a str, SaleRecord>,
+    pub sale_stats: Item<'a, SaleStats>,
+    /// Number of wallets in `wallets_minted_amount`
+    pub wallet_mint_count: Item<'a, u64>,
+    /// Stored as (key, value, token key) for every pair `index_extension` returns
+    pub extension_index: Map<'a, (&'a str, &'a str, &'a str), Empty>,
+    /// If true, burned tokens leave a record behind in `burned_tokens`
+    pub retain_burned_tokens: Item<'a, bool>,
+    pub burned_tokens: Map<'a, &'a str, BurnRecord>,
+    /// If true, tokens are indexed by their token_uri in `uri_tokens`
+    pub index_token_uris: Item<'a, bool>,
+    /// Stored as (token_uri, token key), several tokens may share a uri
+    pub uri_tokens: Map<'a, (&'a str, &'a str), Empty>,
+    pub pagination: Item<'a, PaginationConfig>,
+    /// While true the chain admin may write exported state with `MigrateMsg::ImportState`,
+    /// `FinalizeImport` closes it for good
+    pub import_open: Item<'a, bool>,
+    /// The `TokenInfo` shape every stored token is known to be in
+    pub storage_version: Item<'a, u32>,
+    /// Set while stored tokens are upgraded to a newer shape, see `MigrateBatch`
+    pub lazy_migration: Item<'a, LazyMigration>,
+    /// ICS-721 bridge allowed to mint returning tokens with `BridgeMint` and burn outbound
+    /// ones with `BridgeBurn`
+    pub bridge: Item<'a, Addr>,
+    /// Open sales, by token key. A listed token can't move until it is sold or delisted
+    pub listings: Map<'a, &'a str, Listing>,
+    /// Cut of every `Buy` paid to the collection
+    pub royalty: Item<'a, Royalty>,
+    /// Running auctions, by token key. The token is locked until the auction is settled
+    pub auctions: Map<'a, &'a str, Auction>,
+    /// Seconds before the end of an auction in which a bid pushes the end back
+    pub auction_extension: Item<'a, u64>,
+    /// Highest bids, open offers and unclaimed native rewards held by the contract, by denom.
+    /// Not withdrawable
+    pub escrowed_bids: Map<'a, &'a str, Uint128>,
+    /// Stored as (token_id, bidder)
+    pub offers: Map<'a, (&'a str, &'a Addr), Offer>,
+    /// Stored as (bidder, token_id), mirrors `offers`
+    pub bidder_offers: Map<'a, (&'a Addr, &'a str), Offer>,
+    /// Sales of several tokens at once, by id
+    pub bundles: Map<'a, u64, Bundle>,
+    /// Number of bundles ever listed, the id of the next one
+    pub bundle_count: Item<'a, u64>,
+    /// The bundle a token was last listed in, by token key. The token is locked while that
+    /// bundle is open
+    pub bundled_tokens: Map<'a, &'a str, u64>,
+    /// If true, the user of a token set with `SetUser` stays when the token changes hands
+    pub keep_user_on_transfer: Item<'a, bool>,
+    /// Stored as (owner, token key) -> staked since. Staked tokens can't change owner
+    pub staked_tokens: Map<'a, (&'a Addr, &'a str), Timestamp>,
+    /// Seconds a token stays staked before it can be unstaked
+    pub min_stake_period: Item<'a, u64>,
+    /// Legacy collection whose tokens can be wrapped by sending them here
+    pub wrapped_collection: Item<'a, Addr>,
+    /// The collection holding the original of each wrapper in escrow, by token key
+    pub wrapped_tokens: Map<'a, &'a str, Addr>,
+    /// Collection whose tokens can be traded in for new ones
+    pub burn_to_mint: Item<'a, BurnToMintConfig>,
+    /// Tokens each address has traded in towards its next mint
+    pub burn_to_mint_credits: Map<'a, &'a Addr, u64>,
+    /// Stored as (collection, token_id). Tokens already traded in, they can't count twice
+    pub consumed_tokens: Map<'a, (&'a Addr, &'a str), Empty>,
+    /// Stored as (delegate, token key) -> expiration, mirrors the delegates of `tokens`
+    pub delegated_tokens: Map<'a, (&'a Addr, &'a str), Expiration>,
+    /// Supplied by the embedding contract, see `with_extension_index`
+    pub index_extension: Option<ExtensionIndexFn<T>>,
+    /// Supplied by the embedding contract, see `with_accessory_merge`
+    pub merge_accessory: Option<AccessoryMergeFn<T>>,
 
     pub(crate) _custom_response: PhantomData<C>,
     pub(crate) _custom_query: PhantomData<Q>,
@@ -44,9 +286,116 @@ impl<T, C, E, Q> Default
             "tokens__owner",
             "withdraw_address",
             "max_nfts_per_wallet",
-            "wallets_minted_amount",
+            "wallet_mints",
             "max_supply",
-            "mint_price_per_nft"
+            "mint_price_per_nft",
+            "pending_sends",
+            "next_reply_id",
+            "token_children",
+            "operators_can_burn",
+            "bech32_prefix",
+            "redemptions",
+            "redemption_payout",
+            "spender_approvals",
+            "max_approvals_per_token",
+            "default_approval_ttl",
+            "allow_never_expiration",
+            "used_permit_nonces",
+            "operator_granters",
+            "token_approvals",
+            "operator_allowlist",
+            "enforce_operator_allowlist",
+            "holder_count",
+            "holdings",
+            "holdings__count",
+            "minted_total",
+            "burned_total",
+            "bridged_in_total",
+            "bridged_out_total",
+            "supply_cap_basis",
+            "mint_times",
+            "withdraw_history",
+            "total_withdrawn",
+            "record_token_history",
+            "max_token_history",
+            "token_history",
+            "activity_buffer_size",
+            "activity",
+            "activity_head",
+            "token_positions",
+            "token_position_of",
+            "owner_token_positions",
+            "owner_token_position_of",
+            "delayed_reveal",
+            "unrevealed_tokens",
+            "unrevealed_count",
+            "sold_out_emitted",
+            "balance_snapshots",
+            "holder_balance_snapshots",
+            "holder_balance_snapshots__checkpoints",
+            "holder_balance_snapshots__changelog",
+            "holder_count_snapshots",
+            "holder_count_snapshots__checkpoints",
+            "holder_count_snapshots__changelog",
+            "extension_index",
+            "retain_burned_tokens",
+            "burned_tokens",
+            "index_token_uris",
+            "uri_tokens",
+            "pagination",
+            "import_open",
+            "storage_version",
+            "lazy_migration",
+            "bridge",
+            "listings",
+            "royalty",
+            "auctions",
+            "auction_extension",
+            "escrowed_bids",
+            "offers",
+            "bidder_offers",
+            "bundles",
+            "bundle_count",
+            "bundled_tokens",
+            "keep_user_on_transfer",
+            "staked_tokens",
+            "min_stake_period",
+            "wrapped_collection",
+            "wrapped_tokens",
+            "burn_to_mint",
+            "burn_to_mint_credits",
+            "consumed_tokens",
+            "delegated_tokens",
+            "token_count_snapshots",
+            "token_count_snapshots__checkpoints",
+            "token_count_snapshots__changelog",
+            "reward_asset",
+            "reward_per_token",
+            "reward_indices",
+            "accrued_rewards",
+            "royalty_registry",
+            "registry_answers",
+            "voucher_pubkey",
+            "used_voucher_nonces",
+            "partner_minters",
+            "bridge_operator",
+            "bridge_locks",
+            "raffle_phase",
+            "raffle_entries",
+            "raffle_entry_count",
+            "raffle_claimed",
+            "subscription",
+            "subscription_expiries",
+            "reap_cursor",
+            "co_signing",
+            "mint_proposals",
+            "next_proposal_id",
+            "proceeds_vesting",
+            "vesting_proceeds",
+            "open_edition",
+            "last_sale",
+            "sale_stats",
+            "wallet_mint_count"
         )
     }
 }
@@ -54,6 +403,7 @@ impl<T, C, E, Q> Default
 impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
     where T: Serialize + DeserializeOwned + Clone, E: CustomMsg, Q: CustomMsg
 {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         contract_key: &'a str,
         token_count_key: &'a str,
@@ -64,11 +414,121 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
         max_nfts_per_wallet_key: &'a str,
         wallets_minted_amount_key: &'a str,
         max_supply_key: &'a str,
-        mint_price_per_nft_key: &'a str
+        mint_price_per_nft_key: &'a str,
+        pending_sends_key: &'a str,
+        next_reply_id_key: &'a str,
+        token_children_key: &'a str,
+        operators_can_burn_key: &'a str,
+        bech32_prefix_key: &'a str,
+        redemptions_key: &'a str,
+        redemption_payout_key: &'a str,
+        spender_approvals_key: &'a str,
+        max_approvals_per_token_key: &'a str,
+        default_approval_ttl_key: &'a str,
+        allow_never_expiration_key: &'a str,
+        used_permit_nonces_key: &'a str,
+        operator_granters_key: &'a str,
+        token_approvals_key: &'a str,
+        operator_allowlist_key: &'a str,
+        enforce_operator_allowlist_key: &'a str,
+        holder_count_key: &'a str,
+        holdings_key: &'a str,
+        holdings_count_key: &'a str,
+        minted_total_key: &'a str,
+        burned_total_key: &'a str,
+        bridged_in_total_key: &'a str,
+        bridged_out_total_key: &'a str,
+        supply_cap_basis_key: &'a str,
+        mint_times_key: &'a str,
+        withdraw_history_key: &'a str,
+        total_withdrawn_key: &'a str,
+        record_token_history_key: &'a str,
+        max_token_history_key: &'a str,
+        token_history_key: &'a str,
+        activity_buffer_size_key: &'a str,
+        activity_key: &'a str,
+        activity_head_key: &'a str,
+        token_positions_key: &'a str,
+        token_position_of_key: &'a str,
+        owner_token_positions_key: &'a str,
+        owner_token_position_of_key: &'a str,
+        delayed_reveal_key: &'a str,
+        unrevealed_tokens_key: &'a str,
+        unrevealed_count_key: &'a str,
+        sold_out_emitted_key: &'a str,
+        balance_snapshots_key: &'a str,
+        holder_balance_snapshots_key: &'a str,
+        holder_balance_checkpoints_key: &'a str,
+        holder_balance_changelog_key: &'a str,
+        holder_count_snapshots_key: &'a str,
+        holder_count_checkpoints_key: &'a str,
+        holder_count_changelog_key: &'a str,
+        extension_index_key: &'a str,
+        retain_burned_tokens_key: &'a str,
+        burned_tokens_key: &'a str,
+        index_token_uris_key: &'a str,
+        uri_tokens_key: &'a str,
+        pagination_key: &'a str,
+        import_open_key: &'a str,
+        storage_version_key: &'a str,
+        lazy_migration_key: &'a str,
+        bridge_key: &'a str,
+        listings_key: &'a str,
+        royalty_key: &'a str,
+        auctions_key: &'a str,
+        auction_extension_key: &'a str,
+        escrowed_bids_key: &'a str,
+        offers_key: &'a str,
+        bidder_offers_key: &'a str,
+        bundles_key: &'a str,
+        bundle_count_key: &'a str,
+        bundled_tokens_key: &'a str,
+        keep_user_on_transfer_key: &'a str,
+        staked_tokens_key: &'a str,
+        min_stake_period_key: &'a str,
+        wrapped_collection_key: &'a str,
+        wrapped_tokens_key: &'a str,
+        burn_to_mint_key: &'a str,
+        burn_to_mint_credits_key: &'a str,
+        consumed_tokens_key: &'a str,
+        delegated_tokens_key: &'a str,
+        token_count_snapshots_key: &'a str,
+        token_count_checkpoints_key: &'a str,
+        token_count_changelog_key: &'a str,
+        reward_asset_key: &'a str,
+        reward_per_token_key: &'a str,
+        reward_indices_key: &'a str,
+        accrued_rewards_key: &'a str,
+        royalty_registry_key: &'a str,
+        registry_answers_key: &'a str,
+        voucher_pubkey_key: &'a str,
+        used_voucher_nonces_key: &'a str,
+        partner_minters_key: &'a str,
+        bridge_operator_key: &'a str,
+        bridge_locks_key: &'a str,
+        raffle_phase_key: &'a str,
+        raffle_entries_key: &'a str,
+        raffle_entry_count_key: &'a str,
+        raffle_claimed_key: &'a str,
+        subscription_key: &'a str,
+        subscription_expiries_key: &'a str,
+        reap_cursor_key: &'a str,
+        co_signing_key: &'a str,
+        mint_proposals_key: &'a str,
+        next_proposal_id_key: &'a str,
+        proceeds_vesting_key: &'a str,
+        vesting_proceeds_key: &'a str,
+        open_edition_key: &'a str,
+        last_sale_key: &'a str,
+        sale_stats_key: &'a str,
+        wallet_mint_count_key: &'a str
     ) -> Self {
         let indexes = TokenIndexes {
             owner: MultiIndex::new(token_owner_idx, tokens_key, tokens_owner_key),
         };
+        let holding_indexes = HoldingIndexes {
+            count: MultiIndex::new(holding_count_idx, holdings_key, holdings_count_key),
+        };
         Self {
             contract_info: Item::new(contract_key),
             token_count: Item::new(token_count_key),
@@ -79,38 +539,991 @@ impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
             max_supply: Item::new(max_supply_key),
             mint_price_per_nft: Item::new(mint_price_per_nft_key),
             wallets_minted_amount: Map::new(wallets_minted_amount_key),
+            pending_sends: Map::new(pending_sends_key),
+            next_reply_id: Item::new(next_reply_id_key),
+            token_children: Map::new(token_children_key),
+            operators_can_burn: Item::new(operators_can_burn_key),
+            bech32_prefix: Item::new(bech32_prefix_key),
+            redemptions: Map::new(redemptions_key),
+            redemption_payout: Item::new(redemption_payout_key),
+            spender_approvals: Map::new(spender_approvals_key),
+            max_approvals_per_token: Item::new(max_approvals_per_token_key),
+            default_approval_ttl: Item::new(default_approval_ttl_key),
+            allow_never_expiration: Item::new(allow_never_expiration_key),
+            used_permit_nonces: Map::new(used_permit_nonces_key),
+            operator_granters: Map::new(operator_granters_key),
+            token_approvals: Map::new(token_approvals_key),
+            operator_allowlist: Map::new(operator_allowlist_key),
+            enforce_operator_allowlist: Item::new(enforce_operator_allowlist_key),
+            holder_count: Item::new(holder_count_key),
+            holdings: IndexedMap::new(holdings_key, holding_indexes),
+            minted_total: Item::new(minted_total_key),
+            burned_total: Item::new(burned_total_key),
+            bridged_in_total: Item::new(bridged_in_total_key),
+            bridged_out_total: Item::new(bridged_out_total_key),
+            supply_cap_basis: Item::new(supply_cap_basis_key),
+            mint_times: Map::new(mint_times_key),
+            withdraw_history: Map::new(withdraw_history_key),
+            total_withdrawn: Map::new(total_withdrawn_key),
+            record_token_history: Item::new(record_token_history_key),
+            max_token_history: Item::new(max_token_history_key),
+            token_history: Map::new(token_history_key),
+            activity_buffer_size: Item::new(activity_buffer_size_key),
+            activity: Map::new(activity_key),
+            activity_head: Item::new(activity_head_key),
+            token_positions: Map::new(token_positions_key),
+            token_position_of: Map::new(token_position_of_key),
+            owner_token_positions: Map::new(owner_token_positions_key),
+            owner_token_position_of: Map::new(owner_token_position_of_key),
+            delayed_reveal: Item::new(delayed_reveal_key),
+            unrevealed_tokens: Map::new(unrevealed_tokens_key),
+            unrevealed_count: Item::new(unrevealed_count_key),
+            sold_out_emitted: Item::new(sold_out_emitted_key),
+            balance_snapshots: Item::new(balance_snapshots_key),
+            // every block is emulated with a checkpoint on the first change of each block,
+            // so both strategies share the same storage
+            holder_balance_snapshots: SnapshotMap::new(
+                holder_balance_snapshots_key,
+                holder_balance_checkpoints_key,
+                holder_balance_changelog_key,
+                Strategy::Selected
+            ),
+            holder_count_snapshots: SnapshotItem::new(
+                holder_count_snapshots_key,
+                holder_count_checkpoints_key,
+                holder_count_changelog_key,
+                Strategy::Selected
+            ),
+            extension_index: Map::new(extension_index_key),
+            retain_burned_tokens: Item::new(retain_burned_tokens_key),
+            burned_tokens: Map::new(burned_tokens_key),
+            index_token_uris: Item::new(index_token_uris_key),
+            uri_tokens: Map::new(uri_tokens_key),
+            pagination: Item::new(pagination_key),
+            import_open: Item::new(import_open_key),
+            storage_version: Item::new(storage_version_key),
+            lazy_migration: Item::new(lazy_migration_key),
+            bridge: Item::new(bridge_key),
+            listings: Map::new(listings_key),
+            royalty: Item::new(royalty_key),
+            auctions: Map::new(auctions_key),
+            auction_extension: Item::new(auction_extension_key),
+            escrowed_bids: Map::new(escrowed_bids_key),
+            offers: Map::new(offers_key),
+            bidder_offers: Map::new(bidder_offers_key),
+            bundles: Map::new(bundles_key),
+            bundle_count: Item::new(bundle_count_key),
+            bundled_tokens: Map::new(bundled_tokens_key),
+            keep_user_on_transfer: Item::new(keep_user_on_transfer_key),
+            staked_tokens: Map::new(staked_tokens_key),
+            min_stake_period: Item::new(min_stake_period_key),
+            wrapped_collection: Item::new(wrapped_collection_key),
+            wrapped_tokens: Map::new(wrapped_tokens_key),
+            burn_to_mint: Item::new(burn_to_mint_key),
+            burn_to_mint_credits: Map::new(burn_to_mint_credits_key),
+            consumed_tokens: Map::new(consumed_tokens_key),
+            delegated_tokens: Map::new(delegated_tokens_key),
+            token_count_snapshots: SnapshotItem::new(
+                token_count_snapshots_key,
+                token_count_checkpoints_key,
+                token_count_changelog_key,
+                Strategy::Selected
+            ),
+            reward_asset: Item::new(reward_asset_key),
+            reward_per_token: Item::new(reward_per_token_key),
+            reward_indices: Map::new(reward_indices_key),
+            accrued_rewards: Map::new(accrued_rewards_key),
+            royalty_registry: Item::new(royalty_registry_key),
+            registry_answers: Map::new(registry_answers_key),
+            voucher_pubkey: Item::new(voucher_pubkey_key),
+            used_voucher_nonces: Map::new(used_voucher_nonces_key),
+            partner_minters: Map::new(partner_minters_key),
+            bridge_operator: Item::new(bridge_operator_key),
+            bridge_locks: Map::new(bridge_locks_key),
+            raffle_phase: Item::new(raffle_phase_key),
+            raffle_entries: Map::new(raffle_entries_key),
+            raffle_entry_count: Item::new(raffle_entry_count_key),
+            raffle_claimed: Item::new(raffle_claimed_key),
+            subscription: Item::new(subscription_key),
+            subscription_expiries: Map::new(subscription_expiries_key),
+            reap_cursor: Item::new(reap_cursor_key),
+            co_signing: Item::new(co_signing_key),
+            mint_proposals: Map::new(mint_proposals_key),
+            next_proposal_id: Item::new(next_proposal_id_key),
+            proceeds_vesting: Item::new(proceeds_vesting_key),
+            vesting_proceeds: Map::new(vesting_proceeds_key),
+            open_edition: Item::new(open_edition_key),
+            last_sale: Map::new(last_sale_key),
+            sale_stats: Item::new(sale_stats_key),
+            wallet_mint_count: Item::new(wallet_mint_count_key),
+            index_extension: None,
+            merge_accessory: None,
             _custom_response: PhantomData,
             _custom_execute: PhantomData,
             _custom_query: PhantomData,
         }
     }
 
+    /// Indexes tokens by the (key, value) pairs `index_extension` derives from their extension,
+    /// so they can be listed with `TokensByExtensionKey`. Matching is by equality, encode
+    /// numbers with a fixed width if their order matters
+    pub fn with_extension_index(mut self, index_extension: ExtensionIndexFn<T>) -> Self {
+        self.index_extension = Some(index_extension);
+        self
+    }
+
+    /// Makes `NftInfo` of a token show its extension with those of its attached accessories
+    /// folded in, in the order they were attached. Stored extensions stay as they are
+    pub fn with_accessory_merge(mut self, merge_accessory: AccessoryMergeFn<T>) -> Self {
+        self.merge_accessory = Some(merge_accessory);
+        self
+    }
+
+    /// The extension of the token as `NftInfo` shows it, see `with_accessory_merge`
+    pub fn merged_extension(&self, storage: &dyn Storage, token: &TokenInfo<T>) -> StdResult<T> {
+        let Some(merge_accessory) = self.merge_accessory else {
+            return Ok(token.extension.clone());
+        };
+        token.accessories.iter().try_fold(token.extension.clone(), |extension, accessory_id| {
+            let accessory = self.load_token(storage, accessory_id)?;
+            Ok(merge_accessory(extension, &accessory.extension))
+        })
+    }
+
     pub fn token_count(&self, storage: &dyn Storage) -> StdResult<u64> {
         Ok(self.token_count.may_load(storage)?.unwrap_or_default())
     }
 
-    pub fn increment_tokens(&self, storage: &mut dyn Storage, sender: &String) -> StdResult<u64> {
-        let val = self.token_count(storage)? + 1;
-        self.token_count.save(storage, &val)?;
+    pub fn minted_total(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.minted_total.may_load(storage)?.unwrap_or_default())
+    }
 
-        let user_minted = self.wallets_minted_amount.load(storage, sender.clone()).unwrap_or(0);
-        self.wallets_minted_amount.save(storage, sender.clone(), &(user_minted + 1))?;
+    pub fn burned_total(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.burned_total.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn bridged_in_total(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.bridged_in_total.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn bridged_out_total(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.bridged_out_total.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn supply_cap_basis(&self, storage: &dyn Storage) -> StdResult<SupplyCapBasis> {
+        Ok(self.supply_cap_basis.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// The counter `max_supply` is enforced against
+    pub fn capped_supply(&self, storage: &dyn Storage) -> StdResult<u64> {
+        match self.supply_cap_basis(storage)? {
+            SupplyCapBasis::Minted => self.minted_total(storage),
+            SupplyCapBasis::Circulating => self.token_count(storage),
+        }
+    }
 
+    /// Once an open edition is closed, fixes `max_supply` to the tokens it left in
+    /// circulation. Returns the new cap the first time only
+    pub fn freeze_open_edition(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo
+    ) -> StdResult<Option<u64>> {
+        let Some((_, end)) = self.open_edition.may_load(storage)? else {
+            return Ok(None);
+        };
+        if block.time < end || self.max_supply.exists(storage) {
+            return Ok(None);
+        }
+        let max_supply = self.token_count(storage)?;
+        self.max_supply.save(storage, &max_supply)?;
+        Ok(Some(max_supply))
+    }
+
+    /// True once `max_supply` is reached and minting fails
+    pub fn sold_out(&self, storage: &dyn Storage) -> StdResult<bool> {
+        let Some(max_supply) = self.max_supply.may_load(storage)? else {
+            return Ok(false);
+        };
+        Ok(self.capped_supply(storage)? >= max_supply)
+    }
+
+    /// Returns true only the first time the collection is found sold out
+    pub fn mark_sold_out(&self, storage: &mut dyn Storage) -> StdResult<bool> {
+        if !self.sold_out(storage)? || self.sold_out_emitted.may_load(storage)?.unwrap_or(false) {
+            return Ok(false);
+        }
+        self.sold_out_emitted.save(storage, &true)?;
+        Ok(true)
+    }
+
+    pub fn increment_tokens(&self, storage: &mut dyn Storage, block: &BlockInfo) -> StdResult<u64> {
+        let val = self.token_count(storage)? + 1;
+        self.save_token_count(storage, block.height, val)?;
+        let minted = self.minted_total(storage)? + 1;
+        self.minted_total.save(storage, &minted)?;
         Ok(val)
     }
 
-    pub fn decrement_tokens(&self, storage: &mut dyn Storage) -> StdResult<u64> {
+    /// Counts a mint towards the per-wallet limit of `wallet`
+    pub fn count_wallet_mint(&self, storage: &mut dyn Storage, wallet: &Addr) -> StdResult<()> {
+        let user_minted = self.wallets_minted_amount.may_load(storage, wallet)?.unwrap_or(0);
+        self.save_wallet_mints(storage, wallet, user_minted + 1)
+    }
+
+    /// Sets the mint count of `wallet`, counting wallets seen for the first time
+    pub fn save_wallet_mints(
+        &self,
+        storage: &mut dyn Storage,
+        wallet: &Addr,
+        minted: u64
+    ) -> StdResult<()> {
+        if !self.wallets_minted_amount.has(storage, wallet) {
+            let count = self.wallet_mint_count.may_load(storage)?.unwrap_or_default();
+            self.wallet_mint_count.save(storage, &(count + 1))?;
+        }
+        self.wallets_minted_amount.save(storage, wallet, &minted)
+    }
+
+    pub fn operators_can_burn(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.operators_can_burn.may_load(storage)?.unwrap_or(true))
+    }
+
+    pub fn max_approvals_per_token(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(
+            self.max_approvals_per_token
+                .may_load(storage)?
+                .unwrap_or(DEFAULT_MAX_APPROVALS_PER_TOKEN)
+        )
+    }
+
+    pub fn allow_never_expiration(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.allow_never_expiration.may_load(storage)?.unwrap_or(true))
+    }
+
+    pub fn enforce_operator_allowlist(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.enforce_operator_allowlist.may_load(storage)?.unwrap_or(false))
+    }
+
+    pub fn record_token_history(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.record_token_history.may_load(storage)?.unwrap_or(false))
+    }
+
+    pub fn max_token_history(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.max_token_history.may_load(storage)?.unwrap_or(DEFAULT_MAX_TOKEN_HISTORY))
+    }
+
+    /// Appends an entry to the history of a token if history is recorded,
+    /// pruning the oldest entries past `max_token_history`
+    pub fn push_history(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        action: HistoryAction,
+        from: Option<Addr>,
+        to: Option<Addr>
+    ) -> StdResult<()> {
+        if !self.record_token_history(storage)? {
+            return Ok(());
+        }
+        let max = self.max_token_history(storage)?;
+        if max == 0 {
+            return Ok(());
+        }
+        let history = self.token_history.prefix(token_id);
+        let seq = history
+            .keys(storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(1, |last| last + 1);
+        self.token_history.save(storage, (token_id, seq), &(HistoryEntry {
+            action,
+            from,
+            to,
+            at_height: block.height,
+            at_time: block.time,
+        }))?;
+
+        // sequences are contiguous, so everything at or below this one is too old
+        if let Some(prune_to) = seq.checked_sub(max) {
+            let stale = history
+                .keys(storage, None, Some(Bound::inclusive(prune_to)), Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for old in stale {
+                self.token_history.remove(storage, (token_id, old));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn activity_buffer_size(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.activity_buffer_size.may_load(storage)?.unwrap_or(DEFAULT_ACTIVITY_BUFFER_SIZE))
+    }
+
+    pub fn activity_head(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.activity_head.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Appends an event to the activity feed, overwriting the oldest one once the buffer is full
+    pub fn record_activity(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        action: ActivityAction,
+        token_id: &str,
+        from: Option<Addr>,
+        to: Option<Addr>
+    ) -> StdResult<()> {
+        let size = self.activity_buffer_size(storage)?;
+        if size == 0 {
+            return Ok(());
+        }
+        let id = self.activity_head(storage)?;
+        self.activity.save(storage, id % size, &(ActivityEntry {
+            id,
+            action,
+            token_id: token_id.to_string(),
+            from,
+            to,
+            at_height: block.height,
+            at_time: block.time,
+        }))?;
+        self.activity_head.save(storage, &(id + 1))
+    }
+
+    pub fn delayed_reveal(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.delayed_reveal.may_load(storage)?.unwrap_or(false))
+    }
+
+    pub fn unrevealed_count(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.unrevealed_count.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Tracks a token as waiting for its reveal
+    pub fn add_unrevealed(&self, storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+        self.unrevealed_tokens.save(storage, &token_key(token_id), &Empty {})?;
+        let count = self.unrevealed_count(storage)? + 1;
+        self.unrevealed_count.save(storage, &count)
+    }
+
+    /// Stops tracking a token as unrevealed, once revealed or burned
+    pub fn remove_unrevealed(&self, storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+        self.unrevealed_tokens.remove(storage, &token_key(token_id));
+        let count = self.unrevealed_count(storage)? - 1;
+        self.unrevealed_count.save(storage, &count)
+    }
+
+    /// Approvals granted on a token, ordered by spender
+    pub fn approvals_of(&self, storage: &dyn Storage, token_id: &str) -> StdResult<Vec<Approval>> {
+        self.token_approvals
+            .prefix(token_id)
+            .range(storage, None, None, Order::Ascending)
+            .map(|r| r.map(|(spender, expires)| Approval { spender, expires }))
+            .collect()
+    }
+
+    pub fn next_reply_id(&self, storage: &mut dyn Storage) -> StdResult<u64> {
+        let id = self.next_reply_id.may_load(storage)?.unwrap_or_default() + 1;
+        self.next_reply_id.save(storage, &id)?;
+        Ok(id)
+    }
+
+    /// Follows the parent chain up to the top-level token, whose owner owns the whole tree
+    pub fn root_token(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+        token: TokenInfo<T>
+    ) -> StdResult<(String, TokenInfo<T>)> {
+        let mut current_id = token_id.to_string();
+        let mut current = token;
+        for _ in 0..MAX_NEST_DEPTH {
+            match current.parent.clone() {
+                Some(parent) => {
+                    current = self.load_token(storage, &parent)?;
+                    current_id = parent;
+                }
+                None => {
+                    return Ok((current_id, current));
+                }
+            }
+        }
+        Err(StdError::generic_err("Nesting depth exceeded"))
+    }
+
+    /// Takes a token out of circulation, counting it as burned unless the bridge took it
+    pub fn decrement_tokens(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        bridged: bool
+    ) -> StdResult<u64> {
         let val = self.token_count(storage)? - 1;
-        self.token_count.save(storage, &val)?;
+        self.save_token_count(storage, block.height, val)?;
+        if bridged {
+            let bridged_out = self.bridged_out_total(storage)? + 1;
+            self.bridged_out_total.save(storage, &bridged_out)?;
+        } else {
+            let burned = self.burned_total(storage)? + 1;
+            self.burned_total.save(storage, &burned)?;
+        }
+        // with the circulating cap basis a burn reopens the mint
+        if !self.sold_out(storage)? {
+            self.sold_out_emitted.remove(storage);
+        }
         Ok(val)
     }
+
+    pub fn holder_count(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.holder_count.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Records one more token held by `owner`, counting a new holder on their first token
+    pub fn add_holding(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: &Addr,
+        token_id: &str
+    ) -> StdResult<()> {
+        self.settle_rewards(storage, owner)?;
+        let held = self.holdings.may_load(storage, owner)?.unwrap_or_default();
+        if held == 0 {
+            let holders = self.holder_count(storage)? + 1;
+            self.holder_count.save(storage, &holders)?;
+        }
+        self.owner_token_positions.save(storage, (owner, held), &token_id.to_string())?;
+        self.owner_token_position_of.save(storage, token_id, &held)?;
+        self.holdings.save(storage, owner, &(held + 1))?;
+        #[cfg(debug_assertions)]
+        self.debug_assert_holder_count(storage)?;
+        self.snapshot_holding(storage, block.height, owner, held + 1)
+    }
+
+    /// Records one token less held by `owner`, dropping the holder with their last token
+    pub fn remove_holding(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: &Addr,
+        token_id: &str
+    ) -> StdResult<()> {
+        self.settle_rewards(storage, owner)?;
+        let held = self.holdings.may_load(storage, owner)?.unwrap_or_default();
+        if held == 0 {
+            return Err(StdError::generic_err(format!("{} holds no tokens", owner)));
+        }
+        // swap the owner's last token into the freed position
+        let position = self.owner_token_position_of.load(storage, token_id)?;
+        let last = held - 1;
+        if position != last {
+            let moved = self.owner_token_positions.load(storage, (owner, last))?;
+            self.owner_token_positions.save(storage, (owner, position), &moved)?;
+            self.owner_token_position_of.save(storage, &moved, &position)?;
+        }
+        self.owner_token_positions.remove(storage, (owner, last));
+        self.owner_token_position_of.remove(storage, token_id);
+
+        if held == 1 {
+            self.holdings.remove(storage, owner)?;
+            // without tokens the index is meaningless, the next token settles from scratch
+            self.reward_indices.remove(storage, owner);
+            let holders = self.holder_count(storage)? - 1;
+            self.holder_count.save(storage, &holders)?;
+        } else {
+            self.holdings.save(storage, owner, &(held - 1))?;
+        }
+        #[cfg(debug_assertions)]
+        self.debug_assert_holder_count(storage)?;
+        self.snapshot_holding(storage, block.height, owner, held - 1)
+    }
+
+    /// `holder_count` must match the wallets in `holdings`. Scans them all, so debug only
+    #[cfg(debug_assertions)]
+    fn debug_assert_holder_count(&self, storage: &dyn Storage) -> StdResult<()> {
+        let holders = self.holdings.keys(storage, None, None, Order::Ascending).count();
+        debug_assert_eq!(self.holder_count(storage)?, holders as u64, "holder count drifted");
+        Ok(())
+    }
+
+    /// Adds the token under every pair its extension is indexed by
+    pub fn index_token_extension(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        extension: &T
+    ) -> StdResult<()> {
+        let Some(index_extension) = self.index_extension else {
+            return Ok(());
+        };
+        for (key, value) in index_extension(extension) {
+            self.extension_index.save(storage, (&key, &value, &token_key(token_id)), &Empty {})?;
+        }
+        Ok(())
+    }
+
+    /// Removes the token from every pair its extension is indexed by
+    pub fn unindex_token_extension(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        extension: &T
+    ) -> StdResult<()> {
+        let Some(index_extension) = self.index_extension else {
+            return Ok(());
+        };
+        for (key, value) in index_extension(extension) {
+            self.extension_index.remove(storage, (&key, &value, &token_key(token_id)));
+        }
+        Ok(())
+    }
+
+    /// Replaces the extension of a token and keeps the extension index in sync.
+    /// Embedding contracts that change extensions should go through this
+    pub fn update_extension(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        extension: T
+    ) -> StdResult<TokenInfo<T>> {
+        let mut token = self.load_token(storage, token_id)?;
+        self.unindex_token_extension(storage, token_id, &token.extension)?;
+        token.extension = extension;
+        self.index_token_extension(storage, token_id, &token.extension)?;
+        self.tokens.save(storage, &token_key(token_id), &token)?;
+        Ok(token)
+    }
+
+    pub fn retain_burned_tokens(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.retain_burned_tokens.may_load(storage)?.unwrap_or(false))
+    }
+
+    pub fn index_token_uris(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.index_token_uris.may_load(storage)?.unwrap_or(false))
+    }
+
+    /// Moves the token from the bucket of its old token_uri to the new one, if uris are indexed
+    pub fn update_uri_index(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        old: Option<&str>,
+        new: Option<&str>
+    ) -> StdResult<()> {
+        if !self.index_token_uris(storage)? {
+            return Ok(());
+        }
+        let key = token_key(token_id);
+        if let Some(old) = old {
+            self.uri_tokens.remove(storage, (old, &key));
+        }
+        if let Some(new) = new {
+            self.uri_tokens.save(storage, (new, &key), &Empty {})?;
+        }
+        Ok(())
+    }
+
+    pub fn pagination(&self, storage: &dyn Storage) -> StdResult<PaginationConfig> {
+        Ok(self.pagination.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Page size of a listing, clamped to the configured maximum and to `cap` if the listing
+    /// has a tighter one of its own
+    pub fn page_limit(
+        &self,
+        storage: &dyn Storage,
+        limit: Option<u32>,
+        cap: Option<u32>
+    ) -> StdResult<usize> {
+        let config = self.pagination(storage)?;
+        let max = cap.map_or(config.max_limit, |cap| cap.min(config.max_limit));
+        Ok(limit.unwrap_or(config.default_limit).min(max) as usize)
+    }
+
+    /// Loads a token, upgrading it in memory if it is stored in an older shape.
+    /// Saving it back writes the current shape
+    pub fn load_token(&self, storage: &dyn Storage, token_id: &str) -> StdResult<TokenInfo<T>> {
+        Ok(self.tokens.load(storage, &token_key(token_id))?.from_legacy())
+    }
+
+    pub fn may_load_token(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str
+    ) -> StdResult<Option<TokenInfo<T>>> {
+        Ok(self.tokens.may_load(storage, &token_key(token_id))?.map(TokenInfo::from_legacy))
+    }
+
+    pub fn storage_version(&self, storage: &dyn Storage) -> StdResult<u32> {
+        Ok(self.storage_version.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// The listing of a token, unless it has expired
+    pub fn active_listing(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        token_id: &str
+    ) -> StdResult<Option<Listing>> {
+        let listing = self.listings.may_load(storage, &token_key(token_id))?;
+        Ok(listing.filter(|listing| !listing.expires.is_expired(block)))
+    }
+
+    /// The id of the bundle a token is locked in, unless the bundle has been bought, delisted
+    /// or has expired
+    pub fn active_bundle(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        token_id: &str
+    ) -> StdResult<Option<u64>> {
+        let Some(bundle_id) = self.bundled_tokens.may_load(storage, &token_key(token_id))? else {
+            return Ok(None);
+        };
+        let bundle = self.bundles.may_load(storage, bundle_id)?;
+        Ok(bundle.filter(|bundle| !bundle.expires.is_expired(block)).map(|_| bundle_id))
+    }
+
+    pub fn auction_extension(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.auction_extension.may_load(storage)?.unwrap_or(DEFAULT_AUCTION_EXTENSION))
+    }
+
+    pub fn escrowed_bids(&self, storage: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+        Ok(self.escrowed_bids.may_load(storage, denom)?.unwrap_or_default())
+    }
+
+    /// Counts a sale and its price into the collection stats
+    pub fn record_sale_volume(&self, storage: &mut dyn Storage, price: &Coin) -> StdResult<()> {
+        let mut stats = self.sale_stats.may_load(storage)?.unwrap_or_default();
+        stats.sale_count += 1;
+        match stats.volume.iter_mut().find(|coin| coin.denom == price.denom) {
+            Some(volume) => {
+                volume.amount = volume.amount.checked_add(price.amount)?;
+            }
+            None => stats.volume.push(price.clone()),
+        }
+        self.sale_stats.save(storage, &stats)
+    }
+
+    /// Records the sale of a single token as its last sale and into the collection stats
+    pub fn record_sale(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        buyer: &Addr,
+        price: &Coin
+    ) -> StdResult<()> {
+        self.last_sale.save(storage, &token_key(token_id), &(SaleRecord {
+            price: price.amount,
+            denom: price.denom.clone(),
+            at_time: block.time,
+            buyer: buyer.clone(),
+        }))?;
+        self.record_sale_volume(storage, price)
+    }
+
+    /// Adds to the proceeds that vest, if they do
+    pub fn credit_proceeds(&self, storage: &mut dyn Storage, funds: &Coin) -> StdResult<()> {
+        if !self.proceeds_vesting.exists(storage) {
+            return Ok(());
+        }
+        self.vesting_proceeds.update(storage, &funds.denom, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default().checked_add(funds.amount)?)
+        })?;
+        Ok(())
+    }
+
+    pub fn escrow(&self, storage: &mut dyn Storage, funds: &Coin) -> StdResult<()> {
+        let escrowed = self.escrowed_bids(storage, &funds.denom)?.checked_add(funds.amount)?;
+        self.escrowed_bids.save(storage, &funds.denom, &escrowed)
+    }
+
+    pub fn release_escrow(&self, storage: &mut dyn Storage, funds: &Coin) -> StdResult<()> {
+        let escrowed = self.escrowed_bids(storage, &funds.denom)?.checked_sub(funds.amount)?;
+        if escrowed.is_zero() {
+            self.escrowed_bids.remove(storage, &funds.denom);
+            Ok(())
+        } else {
+            self.escrowed_bids.save(storage, &funds.denom, &escrowed)
+        }
+    }
+
+    pub fn reward_per_token(&self, storage: &dyn Storage) -> StdResult<Decimal256> {
+        Ok(self.reward_per_token.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Tokens earning rewards, which leaves out the ones `contract` holds itself, like
+    /// bridge-locked tokens. Nobody could ever claim their share
+    pub fn rewarded_supply(&self, storage: &dyn Storage, contract: &Addr) -> StdResult<u64> {
+        let held = self.holdings.may_load(storage, contract)?.unwrap_or_default();
+        Ok(self.token_count(storage)? - held)
+    }
+
+    /// Splits `amount` evenly over the rewarded supply
+    pub fn distribute_rewards(
+        &self,
+        storage: &mut dyn Storage,
+        contract: &Addr,
+        amount: Uint128
+    ) -> StdResult<()> {
+        let share = Decimal256::from_ratio(amount, self.rewarded_supply(storage, contract)?);
+        let reward_per_token = self.reward_per_token(storage)?.checked_add(share)?;
+        self.reward_per_token.save(storage, &reward_per_token)?;
+        // the contract's own tokens skip this share, so they never accrue anything
+        if self.holdings.has(storage, contract) {
+            self.reward_indices.save(storage, contract, &reward_per_token)?;
+        }
+        Ok(())
+    }
+
+    /// Rewards of `owner` not claimed yet, including what their tokens earned since they
+    /// were last settled
+    pub fn pending_rewards(&self, storage: &dyn Storage, owner: &Addr) -> StdResult<Uint128> {
+        let accrued = self.accrued_rewards.may_load(storage, owner)?.unwrap_or_default();
+        let held = self.holdings.may_load(storage, owner)?.unwrap_or_default();
+        if held == 0 {
+            return Ok(accrued);
+        }
+        let index = self.reward_indices.may_load(storage, owner)?.unwrap_or_default();
+        let earned = Uint256::from(held) * (self.reward_per_token(storage)? - index);
+        Ok(accrued.checked_add(Uint128::try_from(earned)?)?)
+    }
+
+    /// Moves what the tokens of `owner` earned so far into `accrued_rewards`, has to run
+    /// before every change of their balance so rewards go to whoever held at the time
+    pub fn settle_rewards(&self, storage: &mut dyn Storage, owner: &Addr) -> StdResult<Uint128> {
+        let reward_per_token = self.reward_per_token(storage)?;
+        if reward_per_token.is_zero() {
+            return Ok(Uint128::zero());
+        }
+        let pending = self.pending_rewards(storage, owner)?;
+        if !pending.is_zero() {
+            self.accrued_rewards.save(storage, owner, &pending)?;
+        }
+        self.reward_indices.save(storage, owner, &reward_per_token)?;
+        Ok(pending)
+    }
+
+    pub fn save_offer(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        bidder: &Addr,
+        offer: &Offer
+    ) -> StdResult<()> {
+        self.offers.save(storage, (token_id, bidder), offer)?;
+        self.bidder_offers.save(storage, (bidder, token_id), offer)
+    }
+
+    pub fn remove_offer(&self, storage: &mut dyn Storage, token_id: &str, bidder: &Addr) {
+        self.offers.remove(storage, (token_id, bidder));
+        self.bidder_offers.remove(storage, (bidder, token_id));
+    }
+
+    pub fn bridge(&self, storage: &dyn Storage) -> StdResult<Option<Addr>> {
+        self.bridge.may_load(storage)
+    }
+
+    pub fn keep_user_on_transfer(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.keep_user_on_transfer.may_load(storage)?.unwrap_or(false))
+    }
+
+    pub fn min_stake_period(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.min_stake_period.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn import_open(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.import_open.may_load(storage)?.unwrap_or(false))
+    }
+
+    pub fn balance_snapshots(&self, storage: &dyn Storage) -> StdResult<SnapshotStrategy> {
+        Ok(self.balance_snapshots.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Starts recording balance changes from `height` on, so they can be queried at `height`
+    pub fn add_balance_checkpoint(&self, storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+        self.holder_balance_snapshots.add_checkpoint(storage, height)?;
+        self.holder_count_snapshots.add_checkpoint(storage, height)?;
+        self.token_count_snapshots.add_checkpoint(storage, height)
+    }
+
+    /// Whether changes at `height` go into the snapshots, checkpointing the block first
+    /// if every block is snapshotted
+    fn snapshots_enabled(&self, storage: &mut dyn Storage, height: u64) -> StdResult<bool> {
+        match self.balance_snapshots(storage)? {
+            SnapshotStrategy::Never => Ok(false),
+            SnapshotStrategy::EveryBlock => {
+                if self.holder_balance_snapshots.assert_checkpointed(storage, height).is_err() {
+                    self.add_balance_checkpoint(storage, height)?;
+                }
+                Ok(true)
+            }
+            SnapshotStrategy::Selected => Ok(true),
+        }
+    }
+
+    /// Saves the number of tokens in circulation, mirroring it into the snapshots
+    pub fn save_token_count(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        count: u64
+    ) -> StdResult<()> {
+        self.token_count.save(storage, &count)?;
+        if self.snapshots_enabled(storage, height)? {
+            self.token_count_snapshots.save(storage, &count, height)?;
+        }
+        Ok(())
+    }
+
+    /// Mirrors a balance change and the current holder count into the snapshots
+    fn snapshot_holding(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        owner: &Addr,
+        held: u64
+    ) -> StdResult<()> {
+        if !self.snapshots_enabled(storage, height)? {
+            return Ok(());
+        }
+        if held == 0 {
+            self.holder_balance_snapshots.remove(storage, owner, height)?;
+        } else {
+            self.holder_balance_snapshots.save(storage, owner, &held, height)?;
+        }
+        let holders = self.holder_count(storage)?;
+        self.holder_count_snapshots.save(storage, &holders, height)
+    }
+
+    /// Fails unless balances can be looked up as of `height`
+    fn assert_balance_snapshot(&self, storage: &dyn Storage, height: u64) -> StdResult<()> {
+        match self.balance_snapshots(storage)? {
+            SnapshotStrategy::Never => Err(StdError::generic_err("Balance snapshots are disabled")),
+            SnapshotStrategy::EveryBlock => Ok(()),
+            SnapshotStrategy::Selected => {
+                self.holder_balance_snapshots.assert_checkpointed(storage, height)
+            }
+        }
+    }
+
+    /// Tokens held by `owner` at the start of block `height`
+    pub fn holder_balance_at(
+        &self,
+        storage: &dyn Storage,
+        owner: &Addr,
+        height: u64
+    ) -> StdResult<u64> {
+        self.assert_balance_snapshot(storage, height)?;
+        // the first change at or after `height` remembers the balance before it
+        let change = self.holder_balance_snapshots
+            .changelog()
+            .prefix(owner)
+            .range(storage, Some(Bound::inclusive(height)), None, Order::Ascending)
+            .next()
+            .transpose()?;
+        match change {
+            Some((_, change)) => Ok(change.old.unwrap_or_default()),
+            None => Ok(self.holdings.may_load(storage, owner)?.unwrap_or_default()),
+        }
+    }
+
+    /// Number of holders at the start of block `height`
+    pub fn holder_count_at(&self, storage: &dyn Storage, height: u64) -> StdResult<u64> {
+        self.assert_balance_snapshot(storage, height)?;
+        let change = self.holder_count_snapshots
+            .changelog()
+            .range(storage, Some(Bound::inclusive(height)), None, Order::Ascending)
+            .next()
+            .transpose()?;
+        match change {
+            Some((_, change)) => Ok(change.old.unwrap_or_default()),
+            None => self.holder_count(storage),
+        }
+    }
+
+    /// Number of tokens in circulation at the start of block `height`. The history starts
+    /// with the first change after upgrading to a version that keeps it
+    pub fn token_count_at(&self, storage: &dyn Storage, height: u64) -> StdResult<u64> {
+        self.assert_balance_snapshot(storage, height)?;
+        if self.balance_snapshots(storage)? == SnapshotStrategy::Selected {
+            self.token_count_snapshots.assert_checkpointed(storage, height)?;
+        }
+        let change = self.token_count_snapshots
+            .changelog()
+            .range(storage, Some(Bound::inclusive(height)), None, Order::Ascending)
+            .next()
+            .transpose()?;
+        match change {
+            Some((_, change)) => Ok(change.old.unwrap_or_default()),
+            None => self.token_count(storage),
+        }
+    }
+
+    /// Appends a newly minted token to the enumeration, before `token_count` is incremented
+    pub fn enumerate_token(&self, storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+        let position = self.token_count(storage)?;
+        self.token_positions.save(storage, position, &token_id.to_string())?;
+        self.token_position_of.save(storage, token_id, &position)
+    }
+
+    /// Drops a burned token from the enumeration by moving the last token into its position,
+    /// before `token_count` is decremented
+    pub fn unenumerate_token(&self, storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+        let position = self.token_position_of.load(storage, token_id)?;
+        let last = self.token_count(storage)? - 1;
+        if position != last {
+            let moved = self.token_positions.load(storage, last)?;
+            self.token_positions.save(storage, position, &moved)?;
+            self.token_position_of.save(storage, &moved, &position)?;
+        }
+        self.token_positions.remove(storage, last);
+        self.token_position_of.remove(storage, token_id);
+        Ok(())
+    }
+
+    /// Appends a withdrawal to the history and adds it to the total of its denom
+    pub fn record_withdrawal(
+        &self,
+        storage: &mut dyn Storage,
+        record: &WithdrawRecord
+    ) -> StdResult<u64> {
+        let id = self.withdraw_history
+            .keys(storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(1, |last| last + 1);
+        self.withdraw_history.save(storage, id, record)?;
+        self.total_withdrawn.update(storage, &record.denom, |total| {
+            total.unwrap_or_default().checked_add(record.amount).map_err(StdError::from)
+        })?;
+        Ok(id)
+    }
+
+    pub fn move_holding(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        from: &Addr,
+        to: &Addr,
+        token_id: &str
+    ) -> StdResult<()> {
+        if from == to {
+            return Ok(());
+        }
+        self.remove_holding(storage, block, from, token_id)?;
+        self.add_holding(storage, block, to, token_id)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TokenInfo<T> {
     /// The owner of the newly minted NFT
     pub owner: Addr,
-    /// Approvals are stored here, as we clear them all upon transfer and cannot accumulate much
+    /// Deprecated, approvals are stored in `token_approvals`. Only read by the migration
+    #[serde(default)]
     pub approvals: Vec<Approval>,
 
     /// Universal resource identifier for this NFT
@@ -120,6 +1533,73 @@ pub struct TokenInfo<T> {
 
     /// You can add any custom metadata here when you extend cw721-base
     pub extension: T,
+
+    /// The token this one is nested into, its owner is then the owner of the top-level parent
+    #[serde(default)]
+    pub parent: Option<String>,
+
+    /// Redeemed tokens stay owned but can no longer be transferred
+    #[serde(default)]
+    pub redeemed: bool,
+
+    /// How many times the token changed hands through transfer or send
+    #[serde(default)]
+    pub transfer_count: u64,
+    /// Block time of the most recent transfer or send, None if never moved
+    #[serde(default)]
+    pub last_transferred_at: Option<Timestamp>,
+    /// Block time of the mint, None for tokens minted before it was recorded
+    #[serde(default)]
+    pub minted_at: Option<Timestamp>,
+    /// False while the token_uri is still a placeholder under a delayed reveal
+    #[serde(default = "revealed_by_default")]
+    pub revealed: bool,
+    /// Account allowed to use the token without owning it, see `SetUser`
+    #[serde(default)]
+    pub user: Option<Addr>,
+    /// When `user` loses the role
+    #[serde(default)]
+    pub user_expires: Option<Expiration>,
+    /// Block time the owner staked the token, it can't move or burn until unstaked
+    #[serde(default)]
+    pub staked_since: Option<Timestamp>,
+    /// Account the owner lent the use rights to, see `Delegate`
+    #[serde(default)]
+    pub delegate: Option<Addr>,
+    /// When `delegate` loses the use rights
+    #[serde(default)]
+    pub delegate_expires: Option<Expiration>,
+    /// Tokens attached with `Attach`, they are nested into this one and locked with it
+    #[serde(default)]
+    pub accessories: Vec<String>,
+    /// End of the paid period of a pass, see `SubscriptionConfig`. None never expires
+    #[serde(default)]
+    pub valid_until: Option<Timestamp>,
+    /// The shape the token was written in, 0 for tokens from before shapes were tracked
+    #[serde(default)]
+    pub version: u32,
+}
+
+impl<T> TokenInfo<T> {
+    /// Brings a token written in an older shape up to `TOKEN_STORAGE_VERSION`. Fields added
+    /// so far load through serde defaults, so older shapes only need the new version
+    pub fn from_legacy(mut self) -> Self {
+        self.version = TOKEN_STORAGE_VERSION;
+        self
+    }
+}
+
+/// Progress of upgrading every stored token to the `target` shape
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LazyMigration {
+    pub target: u32,
+    /// Key of the last token `MigrateBatch` upgraded
+    pub cursor: Option<String>,
+}
+
+/// Tokens minted before reveals were tracked already carry their final metadata
+fn revealed_by_default() -> bool {
+    true
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -136,6 +1616,422 @@ impl Approval {
     }
 }
 
+/// What an owner granted an operator through `ApproveAll`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperatorGrant {
+    pub expires: Expiration,
+    /// How many more tokens the operator may transfer, send or burn, None if unlimited
+    #[serde(default)]
+    pub remaining_uses: Option<u64>,
+    #[serde(default)]
+    pub permissions: OperatorPermissions,
+}
+
+/// What an operator may do with the owner's tokens, everything by default
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+pub struct OperatorPermissions {
+    /// Transfer, send and nest tokens
+    pub can_transfer: bool,
+    /// Approve spenders other than the operator itself on single tokens. Those spenders may
+    /// transfer the token whatever the operator is allowed to do
+    pub can_approve: bool,
+    /// Burn tokens, if operators are allowed to burn at all
+    pub can_burn: bool,
+}
+
+impl Default for OperatorPermissions {
+    fn default() -> Self {
+        Self {
+            can_transfer: true,
+            can_approve: true,
+            can_burn: true,
+        }
+    }
+}
+
+impl OperatorGrant {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.expires.is_expired(block)
+    }
+}
+
+/// Which counter `max_supply` caps
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplyCapBasis {
+    /// Every token ever minted, burning does not free up supply
+    #[default]
+    Minted,
+    /// Tokens currently in circulation, burned tokens can be minted again
+    Circulating,
+}
+
+/// Page sizes of the listing queries
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PaginationConfig {
+    /// Used when a query doesn't set a limit
+    pub default_limit: u32,
+    /// Larger limits are clamped to this
+    pub max_limit: u32,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        PaginationConfig {
+            default_limit: DEFAULT_PAGE_LIMIT,
+            max_limit: MAX_PAGE_LIMIT,
+        }
+    }
+}
+
+/// How holder balances are checkpointed for `HolderBalanceAt` and `NumHoldersAt`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotStrategy {
+    /// No history is kept
+    #[default]
+    Never,
+    /// Balances can be looked up at any height, every block with a transfer costs extra gas
+    EveryBlock,
+    /// Balances can only be looked up at heights the owner checkpointed with
+    /// `CheckpointBalances`, changes between checkpoints cost little extra
+    Selected,
+}
+
+/// Lets holders of `source_collection` trade `ratio` of its tokens for a new one here, by
+/// sending them with `SendNft`. New tokens are free but count towards `max_supply`.
+/// They get `token_uri` and an empty extension, which must decode from `null`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BurnToMintConfig {
+    pub source_collection: Addr,
+    pub ratio: u64,
+    /// If true, traded in tokens are burned, otherwise the contract keeps them
+    pub burn: bool,
+    pub token_uri: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleRecord {
+    pub price: Uint128,
+    pub denom: String,
+    pub at_time: Timestamp,
+    pub buyer: Addr,
+}
+
+/// Totals over every sale of the collection, reported ones included
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct SaleStats {
+    pub sale_count: u64,
+    /// Sum of the sale prices per denom
+    pub volume: Vec<Coin>,
+}
+
+/// Unlocks mint proceeds linearly over `duration` seconds from `start`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingConfig {
+    pub start: Timestamp,
+    pub duration: u64,
+}
+
+impl VestingConfig {
+    pub fn end(&self) -> Timestamp {
+        self.start.plus_seconds(self.duration)
+    }
+
+    /// The part of `total` unlocked at `now`, rounded down
+    pub fn vested(&self, total: Uint128, now: Timestamp) -> Uint128 {
+        let elapsed = now.seconds().saturating_sub(self.start.seconds()).min(self.duration);
+        total.multiply_ratio(elapsed, self.duration)
+    }
+}
+
+/// Makes minting a two-step process, every co-signer must approve a proposed mint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CoSignConfig {
+    pub co_signers: Vec<Addr>,
+    /// Seconds a proposal stays open for approvals
+    pub proposal_ttl: u64,
+}
+
+/// A mint waiting for the approval of every co-signer, the proposer approves it right away
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintProposal<T> {
+    pub proposer: Addr,
+    pub owner: Addr,
+    pub token_uri: Option<String>,
+    pub extension: T,
+    pub approvals: Vec<Addr>,
+    pub expires: Timestamp,
+}
+
+/// Turns tokens into passes, active for `period` seconds after mint or renewal. Expired
+/// passes stay owned and transferable, but don't count for holder checks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SubscriptionConfig {
+    pub period: u64,
+    /// Paid with `Renew`, renewals are free without it
+    pub renewal_price: Option<Coin>,
+    /// Seconds after expiry the owner may burn a pass with `ReapExpired`, never without it
+    pub reap_after: Option<u64>,
+}
+
+impl<T> TokenInfo<T> {
+    /// Passes are active until `valid_until`, other tokens always are
+    pub fn is_active(&self, block: &BlockInfo) -> bool {
+        self.valid_until.map_or(true, |valid_until| block.time < valid_until)
+    }
+}
+
+/// Stages of a raffle mint. Winners are drawn from the revealed seed and the order of entries
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RafflePhase {
+    /// Taking entries until `ends_at`
+    Entry {
+        ends_at: Timestamp,
+        reveal_by: Timestamp,
+    },
+    /// Entries are closed and the owner committed to a seed by its sha256 hash
+    Committed {
+        hash: Binary,
+        winners: u64,
+        reveal_by: Timestamp,
+    },
+    /// The seed is known, winners claim their tokens and everyone else their refund
+    Revealed {
+        seed: Binary,
+        winners: u64,
+    },
+}
+
+impl RafflePhase {
+    /// Whether the seed missed `reveal_by`, which leaves the raffle without winners
+    pub fn is_abandoned(&self, block: &BlockInfo) -> bool {
+        match self {
+            RafflePhase::Entry { reveal_by, .. } | RafflePhase::Committed { reveal_by, .. } => {
+                block.time >= *reveal_by
+            }
+            RafflePhase::Revealed { .. } => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RaffleEntry {
+    /// Position in the order of entries
+    pub index: u64,
+    /// Escrowed until the entry is claimed
+    pub paid: Option<Coin>,
+}
+
+/// Terms for a contract minting on behalf of its users
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PartnerConfig {
+    /// Share of the mint price forwarded to the partner, in basis points
+    pub fee_bps: u16,
+    /// If true, mints count towards the per-wallet limit of the token owner, otherwise
+    /// the partner is trusted to enforce its own limits
+    pub counts_against_recipient: bool,
+}
+
+/// What holder rewards are paid in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardAsset {
+    /// Funded with `FundRewards`
+    Native {
+        denom: String,
+    },
+    /// Funded by sending the tokens with a `FundRewards` hook message
+    Cw20 {
+        address: Addr,
+    },
+}
+
+/// Royalty taken from sales, `share` is a fraction of the price between 0 and 1
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub payment_address: String,
+    pub share: Decimal,
+}
+
+impl Royalty {
+    /// The part of `price` owed to the payment address, rounded down
+    pub fn amount(&self, price: &Coin) -> Uint128 {
+        price.amount.mul_floor(self.share)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Listing {
+    /// The token owner at the time of listing, paid on `Buy`
+    pub seller: Addr,
+    pub price: Coin,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bundle {
+    /// The owner of every token at the time of listing, paid on `BuyBundle`
+    pub seller: Addr,
+    pub token_ids: Vec<String>,
+    pub price: Coin,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Auction {
+    /// The token owner at the time the auction was created, paid on settlement
+    pub seller: Addr,
+    /// The lowest first bid, its denom is the denom of every bid
+    pub min_bid: Coin,
+    pub end_time: Timestamp,
+    pub highest_bid: Option<Bid>,
+}
+
+impl Auction {
+    pub fn is_ended(&self, block: &BlockInfo) -> bool {
+        block.time >= self.end_time
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bid {
+    pub bidder: Addr,
+    pub amount: Uint128,
+}
+
+/// Funds a bidder holds in the contract for a token, whoever owns it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Offer {
+    pub amount: Coin,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BurnRecord {
+    /// The token owner at the time of the burn
+    pub owner: Addr,
+    pub burned_at_height: u64,
+    pub burned_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedemptionStatus {
+    Pending,
+    Fulfilled,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Redemption {
+    /// The token owner at the time of redemption
+    pub redeemer: Addr,
+    /// Free-form data from the redeemer, e.g. a shipping reference
+    pub redemption_data: Option<String>,
+    pub status: RedemptionStatus,
+    pub redeemed_at: Timestamp,
+    pub fulfilled_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    Mint,
+    Transfer,
+    Send,
+    /// The receiver of a send rejected the token and it went back to the sender
+    SendReverted,
+    Burn,
+    /// Bought from a listing
+    Sale,
+    /// Locked with the contract for a lock/release bridge
+    BridgeLock,
+    /// Handed out by the lock/release bridge
+    BridgeRelease,
+    /// Taken back to its origin chain by the ICS-721 bridge
+    BridgeBurn,
+}
+
+/// One change of custody in the history of a token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoryEntry {
+    pub action: HistoryAction,
+    /// None for mints
+    pub from: Option<Addr>,
+    /// None for burns
+    pub to: Option<Addr>,
+    pub at_height: u64,
+    pub at_time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityAction {
+    Mint,
+    Transfer,
+    Send,
+    /// The receiver of a send rejected the token and it went back to the sender
+    SendReverted,
+    Burn,
+    Approve,
+    Sale,
+    BridgeLock,
+    BridgeRelease,
+    BridgeBurn,
+}
+
+impl From<HistoryAction> for ActivityAction {
+    fn from(action: HistoryAction) -> Self {
+        match action {
+            HistoryAction::Mint => ActivityAction::Mint,
+            HistoryAction::Transfer => ActivityAction::Transfer,
+            HistoryAction::Send => ActivityAction::Send,
+            HistoryAction::SendReverted => ActivityAction::SendReverted,
+            HistoryAction::Burn => ActivityAction::Burn,
+            HistoryAction::Sale => ActivityAction::Sale,
+            HistoryAction::BridgeLock => ActivityAction::BridgeLock,
+            HistoryAction::BridgeRelease => ActivityAction::BridgeRelease,
+            HistoryAction::BridgeBurn => ActivityAction::BridgeBurn,
+        }
+    }
+}
+
+/// One event of the collection-wide activity feed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivityEntry {
+    pub id: u64,
+    pub action: ActivityAction,
+    pub token_id: String,
+    /// The owner the token moved away from, or the approving owner
+    pub from: Option<Addr>,
+    /// The new owner, or the approved spender
+    pub to: Option<Addr>,
+    pub at_height: u64,
+    pub at_time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawRecord {
+    pub amount: Uint128,
+    pub denom: String,
+    /// The withdraw address the funds were sent to
+    pub to: String,
+    pub at_height: u64,
+    pub at_time: Timestamp,
+}
+
+/// A token sent to a contract whose `ReceiveNft` has not completed yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingSend<T> {
+    pub token_id: String,
+    /// The token as it was before the send, restored if the receiver fails
+    pub previous: TokenInfo<T>,
+    /// The approvals the token had before the send
+    #[serde(default)]
+    pub approvals: Vec<Approval>,
+}
+
 pub struct TokenIndexes<'a, T> where T: Serialize + DeserializeOwned + Clone {
     pub owner: MultiIndex<'a, Addr, TokenInfo<T>, String>,
 }
@@ -150,6 +2046,53 @@ impl<'a, T> IndexList<TokenInfo<T>>
     }
 }
 
+/// Orders holders by how many tokens they hold
+pub struct HoldingIndexes<'a> {
+    pub count: MultiIndex<'a, u64, u64, Addr>,
+}
+
+impl<'a> IndexList<u64> for HoldingIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<u64>> + '_> {
+        let v: Vec<&dyn Index<u64>> = vec![&self.count];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn holding_count_idx(_pk: &[u8], count: &u64) -> u64 {
+    *count
+}
+
+/// Storage key of a token in `tokens`. Decimal ids are zero-padded so listings follow numeric
+/// order, any other id is prefixed with `~` so it can't collide with them and sorts after them.
+pub fn token_key(token_id: &str) -> String {
+    match token_id.parse::<u64>() {
+        Ok(n) if n.to_string() == token_id => format!("{:020}", n),
+        _ => format!("~{}", token_id),
+    }
+}
+
+/// Wrappers get this prefix, so their ids can't collide with the mint sequence, bridged or
+/// voucher tokens
+pub const WRAPPED_ID_PREFIX: &str = "wrapped-";
+
+/// The id of the wrapper for a legacy token
+pub fn wrapper_token_id(original: &str) -> String {
+    format!("{}{}", WRAPPED_ID_PREFIX, original)
+}
+
+/// The legacy token id a wrapper was created for
+pub fn wrapped_original_id(wrapper: &str) -> String {
+    wrapper.strip_prefix(WRAPPED_ID_PREFIX).unwrap_or(wrapper).to_string()
+}
+
+/// Turns a key of `tokens` back into the token id, the inverse of `token_key`
+pub fn token_id_from_key(key: String) -> String {
+    match key.strip_prefix('~') {
+        Some(token_id) => token_id.to_string(),
+        None => key.parse::<u64>().map_or(key, |n| n.to_string()),
+    }
+}
+
 pub fn token_owner_idx<T>(_pk: &[u8], d: &TokenInfo<T>) -> Addr {
     d.owner.clone()
 }
@@ -0,0 +1,90 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary,
+    Addr,
+    Binary,
+    Deps,
+    DepsMut,
+    Empty,
+    Env,
+    MessageInfo,
+    Response,
+    StdResult,
+    WasmMsg,
+};
+use cw721::Cw721ReceiveMsg;
+use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+
+use crate::Cw721CustomExecuteMsg;
+
+/// What an ICS-721 bridge does on this chain, without the IBC packets
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Escrow of a token sent out with `SendNft`
+    ReceiveNft(Cw721ReceiveMsg),
+    /// A token from another chain arrives
+    Mint {
+        cw721: String,
+        token_id: String,
+        owner: String,
+    },
+    /// A token from another chain goes back
+    Burn {
+        cw721: String,
+        token_id: String,
+    },
+    /// A token that left from here comes back
+    Release {
+        cw721: String,
+        token_id: String,
+        recipient: String,
+    },
+}
+
+fn instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty
+) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn execute(_deps: DepsMut, _env: Env, _info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    let (cw721, msg) = match msg {
+        ExecuteMsg::ReceiveNft(receive) => {
+            return Ok(Response::new().add_attribute("escrowed", receive.token_id));
+        }
+        ExecuteMsg::Mint { cw721, token_id, owner } =>
+            (cw721, Cw721CustomExecuteMsg::BridgeMint {
+                token_id,
+                owner,
+                token_uri: None,
+                extension: None,
+            }),
+        ExecuteMsg::Burn { cw721, token_id } =>
+            (cw721, Cw721CustomExecuteMsg::BridgeBurn { token_id }),
+        ExecuteMsg::Release { cw721, token_id, recipient } =>
+            (cw721, Cw721CustomExecuteMsg::TransferNft { recipient, token_id }),
+    };
+    let msg = WasmMsg::Execute {
+        contract_addr: cw721,
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    };
+    Ok(Response::new().add_message(msg))
+}
+
+fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Ok(Binary::default())
+}
+
+pub fn contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+pub fn instantiate_bridge(app: &mut App) -> Addr {
+    let code_id = app.store_code(contract());
+    app.instantiate_contract(code_id, Addr::unchecked("deployer"), &Empty {}, &[], "bridge", None)
+        .unwrap()
+}
@@ -0,0 +1,110 @@
+use std::marker::PhantomData;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary,
+    Addr,
+    Binary,
+    Deps,
+    DepsMut,
+    Empty,
+    Env,
+    MessageInfo,
+    Response,
+    StdError,
+    StdResult,
+};
+use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+use cw_storage_plus::Item;
+
+use crate::helpers::Cw721Contract;
+
+const COLLECTION: Item<Addr> = Item::new("collection");
+const MIN_COUNT: Item<u64> = Item::new("min_count");
+const ENTERED: Item<Vec<String>> = Item::new("entered");
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub cw721: String,
+    pub min_count: u64,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Lets in holders of at least `min_count` tokens
+    Enter {},
+    /// Lets in the owner of `token_id`
+    EnterWithToken {
+        token_id: String,
+    },
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Addresses let in so far, in the order they entered
+    Entered {},
+}
+
+fn collection(deps: Deps) -> StdResult<Cw721Contract<Empty, Empty>> {
+    Ok(Cw721Contract(COLLECTION.load(deps.storage)?, PhantomData, PhantomData))
+}
+
+fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg
+) -> StdResult<Response> {
+    COLLECTION.save(deps.storage, &deps.api.addr_validate(&msg.cw721)?)?;
+    MIN_COUNT.save(deps.storage, &msg.min_count)?;
+    ENTERED.save(deps.storage, &vec![])?;
+    Ok(Response::new())
+}
+
+fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    let collection = collection(deps.as_ref())?;
+    let allowed = match msg {
+        ExecuteMsg::Enter {} => {
+            let min_count = MIN_COUNT.load(deps.storage)?;
+            collection.is_holder(&deps.querier, &info.sender, Some(min_count))?
+        }
+        ExecuteMsg::EnterWithToken { token_id } => {
+            collection.holds_token(&deps.querier, info.sender.as_str(), &token_id)?
+        }
+    };
+    if !allowed {
+        return Err(StdError::generic_err("not a holder"));
+    }
+    let mut entered = ENTERED.load(deps.storage)?;
+    entered.push(info.sender.to_string());
+    ENTERED.save(deps.storage, &entered)?;
+    Ok(Response::new().add_attribute("entered", info.sender))
+}
+
+fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Entered {} => to_json_binary(&ENTERED.load(deps.storage)?),
+    }
+}
+
+/// A contract that only lets in holders of a collection, through the query helpers
+pub fn contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// Stores and instantiates a gatekeeper in one go
+pub fn instantiate_gatekeeper(app: &mut App, cw721: &Addr, min_count: u64) -> Addr {
+    let code_id = app.store_code(contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("deployer"),
+        &(InstantiateMsg { cw721: cw721.to_string(), min_count }),
+        &[],
+        "gatekeeper",
+        None
+    ).unwrap()
+}
+
+pub fn entered(app: &App, gatekeeper: &Addr) -> Vec<String> {
+    app.wrap().query_wasm_smart(gatekeeper, &(QueryMsg::Entered {})).unwrap()
+}
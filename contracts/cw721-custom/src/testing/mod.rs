@@ -0,0 +1,7 @@
+//! Support contracts shared by the cw-multi-test suites
+pub mod bridge;
+pub mod gatekeeper;
+pub mod partner;
+pub mod receiver;
+pub mod registry;
+pub mod staking;
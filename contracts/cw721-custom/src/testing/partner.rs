@@ -0,0 +1,85 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary,
+    Addr,
+    Binary,
+    Deps,
+    DepsMut,
+    Empty,
+    Env,
+    MessageInfo,
+    Response,
+    StdError,
+    StdResult,
+    WasmMsg,
+};
+use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+use cw_storage_plus::Item;
+
+use crate::Cw721CustomExecuteMsg;
+
+const COLLECTION: Item<Addr> = Item::new("collection");
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub collection: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Mints from the collection to `recipient`, paying with the funds sent along
+    MintFor {
+        recipient: String,
+    },
+}
+
+fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg
+) -> StdResult<Response> {
+    COLLECTION.save(deps.storage, &deps.api.addr_validate(&msg.collection)?)?;
+    Ok(Response::new())
+}
+
+fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::MintFor { recipient } => {
+            let mint = Cw721CustomExecuteMsg::Mint {
+                owner: recipient,
+                token_uri: None,
+                extension: None,
+            };
+            Ok(
+                Response::new().add_message(WasmMsg::Execute {
+                    contract_addr: COLLECTION.load(deps.storage)?.into_string(),
+                    msg: to_json_binary(&mint)?,
+                    funds: info.funds,
+                })
+            )
+        }
+    }
+}
+
+fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Err(StdError::generic_err("no queries"))
+}
+
+/// A launchpad minting from a collection on behalf of its users
+pub fn contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// Stores and instantiates a launchpad for `collection` in one go
+pub fn instantiate_partner(app: &mut App, collection: &Addr) -> Addr {
+    let code_id = app.store_code(contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("deployer"),
+        &(InstantiateMsg { collection: collection.to_string() }),
+        &[],
+        "mock-partner",
+        None
+    ).unwrap()
+}
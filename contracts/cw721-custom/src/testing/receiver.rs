@@ -0,0 +1,106 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary,
+    Addr,
+    Binary,
+    Deps,
+    DepsMut,
+    Empty,
+    Env,
+    MessageInfo,
+    Response,
+    StdError,
+    StdResult,
+};
+use cw721::Cw721ReceiveMsg;
+use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+use cw_storage_plus::Item;
+
+const REJECT: Item<bool> = Item::new("reject");
+const RECEIVED: Item<Vec<String>> = Item::new("received");
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Fail every `ReceiveNft` until changed with `SetReject`
+    pub reject: bool,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+    SetReject {
+        reject: bool,
+    },
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Token ids accepted so far, in the order they arrived
+    Received {},
+}
+
+fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg
+) -> StdResult<Response> {
+    REJECT.save(deps.storage, &msg.reject)?;
+    RECEIVED.save(deps.storage, &vec![])?;
+    Ok(Response::new())
+}
+
+fn execute(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: ExecuteMsg
+) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::ReceiveNft(receive) => {
+            if REJECT.load(deps.storage)? {
+                return Err(StdError::generic_err("receiver rejected token"));
+            }
+            let mut received = RECEIVED.load(deps.storage)?;
+            received.push(receive.token_id.clone());
+            RECEIVED.save(deps.storage, &received)?;
+            Ok(
+                Response::new()
+                    .add_attribute("received", receive.token_id)
+                    .add_attribute("sender", receive.sender)
+            )
+        }
+        ExecuteMsg::SetReject { reject } => {
+            REJECT.save(deps.storage, &reject)?;
+            Ok(Response::new())
+        }
+    }
+}
+
+fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Received {} => to_json_binary(&RECEIVED.load(deps.storage)?),
+    }
+}
+
+/// A contract that records the tokens sent to it and fails on request
+pub fn contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// Stores and instantiates a receiver in one go
+pub fn instantiate_receiver(app: &mut App, reject: bool) -> Addr {
+    let code_id = app.store_code(contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("deployer"),
+        &(InstantiateMsg { reject }),
+        &[],
+        "mock-receiver",
+        None
+    ).unwrap()
+}
+
+pub fn received(app: &App, receiver: &Addr) -> Vec<String> {
+    app.wrap().query_wasm_smart(receiver, &(QueryMsg::Received {})).unwrap()
+}
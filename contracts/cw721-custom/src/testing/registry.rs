@@ -0,0 +1,89 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary,
+    Addr,
+    Binary,
+    Deps,
+    DepsMut,
+    Empty,
+    Env,
+    MessageInfo,
+    Response,
+    StdResult,
+};
+use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+use cw_storage_plus::Map;
+
+use crate::msg::RoyaltyRegistryQueryMsg;
+
+const COMPLIANT: Map<&Addr, Empty> = Map::new("compliant");
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub compliant: Vec<String>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    SetCompliant {
+        operator: String,
+        compliant: bool,
+    },
+}
+
+fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg
+) -> StdResult<Response> {
+    for operator in msg.compliant {
+        COMPLIANT.save(deps.storage, &deps.api.addr_validate(&operator)?, &Empty {})?;
+    }
+    Ok(Response::new())
+}
+
+fn execute(deps: DepsMut, _env: Env, _info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::SetCompliant { operator, compliant } => {
+            let operator = deps.api.addr_validate(&operator)?;
+            if compliant {
+                COMPLIANT.save(deps.storage, &operator, &Empty {})?;
+            } else {
+                COMPLIANT.remove(deps.storage, &operator);
+            }
+            Ok(Response::new())
+        }
+    }
+}
+
+fn query(deps: Deps, _env: Env, msg: RoyaltyRegistryQueryMsg) -> StdResult<Binary> {
+    match msg {
+        RoyaltyRegistryQueryMsg::IsCompliant { operator } => {
+            let operator = deps.api.addr_validate(&operator)?;
+            to_json_binary(&COMPLIANT.has(deps.storage, &operator))
+        }
+    }
+}
+
+/// A royalty registry vouching for the operators it was told about
+pub fn contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// Stores and instantiates a registry in one go
+pub fn instantiate_registry(app: &mut App, compliant: &[&str]) -> Addr {
+    let code_id = app.store_code(contract());
+    let compliant = compliant
+        .iter()
+        .map(|operator| operator.to_string())
+        .collect();
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("deployer"),
+        &(InstantiateMsg { compliant }),
+        &[],
+        "mock-registry",
+        None
+    ).unwrap()
+}
@@ -0,0 +1,103 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    from_json,
+    to_json_binary,
+    Addr,
+    Binary,
+    Deps,
+    DepsMut,
+    Empty,
+    Env,
+    MessageInfo,
+    Response,
+    StdResult,
+};
+use cw721::Cw721ReceiveMsg;
+use cw_multi_test::{ App, Contract, ContractWrapper, Executor };
+use cw_storage_plus::Map;
+
+/// Stored as (collection, token_id) -> staker
+const STAKES: Map<(&Addr, &str), Addr> = Map::new("stakes");
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+/// Expected in the `msg` of a `ReceiveNft`
+#[cw_serde]
+pub enum ReceiveMsg {
+    Stake {},
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Who staked the token, if anyone
+    StakerOf {
+        collection: String,
+        token_id: String,
+    },
+}
+
+fn instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: InstantiateMsg
+) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::ReceiveNft(receive) => {
+            let ReceiveMsg::Stake {} = from_json(&receive.msg)?;
+            let staker = deps.api.addr_validate(&receive.sender)?;
+            STAKES.save(deps.storage, (&info.sender, &receive.token_id), &staker)?;
+            Ok(
+                Response::new()
+                    .add_attribute("staked", receive.token_id)
+                    .add_attribute("staker", staker)
+            )
+        }
+    }
+}
+
+fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::StakerOf { collection, token_id } => {
+            let collection = deps.api.addr_validate(&collection)?;
+            to_json_binary(&STAKES.may_load(deps.storage, (&collection, &token_id))?)
+        }
+    }
+}
+
+/// A staking contract that takes tokens sent with a `Stake` message, crediting the sender
+pub fn contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// Stores and instantiates a staking contract in one go
+pub fn instantiate_staking(app: &mut App) -> Addr {
+    let code_id = app.store_code(contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("deployer"),
+        &(InstantiateMsg {}),
+        &[],
+        "mock-staking",
+        None
+    ).unwrap()
+}
+
+pub fn staker_of(app: &App, staking: &Addr, collection: &Addr, token_id: &str) -> Option<String> {
+    app.wrap()
+        .query_wasm_smart(staking, &(QueryMsg::StakerOf {
+            collection: collection.to_string(),
+            token_id: token_id.to_string(),
+        }))
+        .unwrap()
+}
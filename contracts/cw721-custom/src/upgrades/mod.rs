@@ -1 +1,248 @@
 pub mod v0_17;
+pub mod v0_18;
+pub mod v0_19;
+
+use cosmwasm_std::{Coin, DepsMut, Empty, Order, Response, StdResult, Storage};
+use cw2::ContractVersion;
+use semver::Version;
+
+use crate::state::{LazyMigration, TOKEN_STORAGE_VERSION};
+use crate::{ContractError, Cw721CustomContract, Extension, CONTRACT_NAME, CONTRACT_VERSION};
+
+/// Checks the stored cw2 info belongs to this contract and is older than the code being
+/// migrated to, runs every state transition in between and bumps the stored version.
+pub fn upgrade(mut deps: DepsMut) -> Result<Response, ContractError> {
+    let stored = assert_contract_name(deps.storage)?;
+    let from: Version = stored.version.parse()?;
+    if from >= CONTRACT_VERSION.parse()? {
+        return Err(ContractError::MigrationNotNewer {
+            from: stored.version,
+            to: CONTRACT_VERSION.into(),
+        });
+    }
+
+    let res = migrate_from(deps.branch(), &stored, &from)?;
+
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(res)
+}
+
+/// Adopts a collection deployed with stock cw721-base. Its storage is converted as if it
+/// was on 0.17 of this contract whatever cw721-base version it runs, then every token is
+/// loaded to make sure it fits our `TokenInfo` before the mint settings are saved.
+pub fn from_cw721_base(
+    mut deps: DepsMut,
+    max_supply: Option<u64>,
+    max_nfts_per_wallet: Option<u64>,
+    price_per_nft: Option<Coin>,
+) -> Result<Response, ContractError> {
+    let stored = assert_contract_name(deps.storage)?;
+    let from: Version = stored.version.parse()?;
+    if from > CONTRACT_VERSION.parse()? {
+        return Err(ContractError::MigrationNotNewer {
+            from: stored.version,
+            to: CONTRACT_VERSION.into(),
+        });
+    }
+
+    let mut res = migrate_response(&stored);
+    if from < Version::new(0, 17, 0) {
+        res = migrate_minter(deps.branch(), res)?;
+    }
+    res = migrate_storage(deps.storage, res)?;
+
+    let contract = Cw721CustomContract::default();
+    let mut tokens = 0u64;
+    for item in contract
+        .tokens
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        item?;
+        tokens += 1;
+    }
+    res = res.add_attribute("validated_tokens", tokens.to_string());
+    let supply = v0_19::seed_token_count_snapshot(deps.storage)?;
+    res = res.add_attribute("seeded_token_count", supply.to_string());
+    res = start_token_migration(deps.storage, res)?;
+
+    // stock cw721-base never counted mints per wallet, `wallets_minted_amount` stays empty
+    if let Some(max_supply) = max_supply {
+        contract.max_supply.save(deps.storage, &max_supply)?;
+        res = res.add_attribute("max_supply", max_supply.to_string());
+    }
+    if let Some(max_nfts_per_wallet) = max_nfts_per_wallet {
+        contract
+            .max_nfts_per_wallet
+            .save(deps.storage, &max_nfts_per_wallet)?;
+        res = res.add_attribute("max_nfts_per_wallet", max_nfts_per_wallet.to_string());
+    }
+    if let Some(price_per_nft) = price_per_nft {
+        contract
+            .mint_price_per_nft
+            .save(deps.storage, &price_per_nft)?;
+        res = res.add_attribute("price_per_nft", price_per_nft.to_string());
+    }
+
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(res)
+}
+
+/// Sets mint rules on a deployment that may have been instantiated without them, with the
+/// chain's migrate permission standing in for the owner. Migrating to newer code at the
+/// same time runs its state transitions first.
+pub fn update_mint_settings(
+    mut deps: DepsMut,
+    max_supply: Option<u64>,
+    max_nfts_per_wallet: Option<u64>,
+    price_per_nft: Option<Coin>,
+    withdraw_address: Option<String>,
+) -> Result<Response, ContractError> {
+    let stored = assert_contract_name(deps.storage)?;
+    let from: Version = stored.version.parse()?;
+    if from > CONTRACT_VERSION.parse()? {
+        return Err(ContractError::MigrationNotNewer {
+            from: stored.version,
+            to: CONTRACT_VERSION.into(),
+        });
+    }
+    let mut res = migrate_from(deps.branch(), &stored, &from)?;
+
+    let contract = Cw721CustomContract::default();
+    if let Some(max_supply) = max_supply {
+        let token_count = contract.token_count(deps.storage)?;
+        if max_supply < token_count {
+            return Err(ContractError::MaxSupplyBelowTokenCount {
+                max_supply,
+                token_count,
+            });
+        }
+        if contract.max_supply.may_load(deps.storage)? != Some(max_supply) {
+            contract.max_supply.save(deps.storage, &max_supply)?;
+            res = res.add_attribute("max_supply", max_supply.to_string());
+        }
+        // raising the cap reopens minting, so the sold out event can fire again
+        if !contract.sold_out(deps.storage)? {
+            contract.sold_out_emitted.remove(deps.storage);
+        }
+    }
+    if let Some(max_nfts_per_wallet) = max_nfts_per_wallet {
+        if contract.max_nfts_per_wallet.may_load(deps.storage)? != Some(max_nfts_per_wallet) {
+            contract
+                .max_nfts_per_wallet
+                .save(deps.storage, &max_nfts_per_wallet)?;
+            res = res.add_attribute("max_nfts_per_wallet", max_nfts_per_wallet.to_string());
+        }
+    }
+    if let Some(price_per_nft) = price_per_nft {
+        if contract.mint_price_per_nft.may_load(deps.storage)?.as_ref() != Some(&price_per_nft) {
+            contract
+                .mint_price_per_nft
+                .save(deps.storage, &price_per_nft)?;
+            res = res.add_attribute("price_per_nft", price_per_nft.to_string());
+        }
+    }
+    if let Some(withdraw_address) = withdraw_address {
+        deps.api.addr_validate(&withdraw_address)?;
+        if contract.withdraw_address.may_load(deps.storage)?.as_ref() != Some(&withdraw_address) {
+            contract
+                .withdraw_address
+                .save(deps.storage, &withdraw_address)?;
+            res = res.add_attribute("withdraw_address", withdraw_address);
+        }
+    }
+
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(res)
+}
+
+fn assert_contract_name(storage: &dyn Storage) -> Result<ContractVersion, ContractError> {
+    let stored = cw2::get_contract_version(storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(cw2::VersionError::WrongContract {
+            expected: CONTRACT_NAME.into(),
+            found: stored.contract,
+        }
+        .into());
+    }
+    Ok(stored)
+}
+
+fn migrate_response(stored: &ContractVersion) -> Response {
+    Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", &stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+}
+
+/// Runs every state transition between the stored version and this code
+fn migrate_from(
+    mut deps: DepsMut,
+    stored: &ContractVersion,
+    from: &Version,
+) -> Result<Response, ContractError> {
+    let mut res = migrate_response(stored);
+    if *from < Version::new(0, 17, 0) {
+        res = migrate_minter(deps.branch(), res)?;
+    }
+    if *from < Version::new(0, 18, 0) {
+        res = migrate_storage(deps.storage, res)?;
+    }
+    if *from < Version::new(0, 19, 0) {
+        let wallets = v0_19::migrate_wallet_mints(deps.storage, deps.api)?;
+        let supply = v0_19::seed_token_count_snapshot(deps.storage)?;
+        res = res
+            .add_attribute("migrated_wallet_mints", wallets.to_string())
+            .add_attribute("seeded_token_count", supply.to_string());
+    }
+    Ok(start_token_migration(deps.storage, res)?)
+}
+
+/// Tokens stored in an older `TokenInfo` shape keep loading through `from_legacy`, so
+/// instead of rewriting them all here a migration is opened for `MigrateBatch` to work
+/// through. A collection without tokens is stamped right away.
+pub fn start_token_migration(storage: &mut dyn Storage, res: Response) -> StdResult<Response> {
+    let contract = Cw721CustomContract::default();
+    if contract.storage_version(storage)? >= TOKEN_STORAGE_VERSION {
+        return Ok(res);
+    }
+    if contract.tokens.is_empty(storage) {
+        contract
+            .storage_version
+            .save(storage, &TOKEN_STORAGE_VERSION)?;
+        return Ok(res);
+    }
+    let migration = LazyMigration {
+        target: TOKEN_STORAGE_VERSION,
+        cursor: None,
+    };
+    contract.lazy_migration.save(storage, &migration)?;
+    Ok(res.add_attribute("token_migration_target", TOKEN_STORAGE_VERSION.to_string()))
+}
+
+/// 0.17 replaced the minter item with cw-ownable
+fn migrate_minter(deps: DepsMut, res: Response) -> Result<Response, ContractError> {
+    let v17 = v0_17::migrate::<Extension, Empty, Empty, Empty>(deps)?;
+    Ok(res.add_attributes(v17.attributes))
+}
+
+/// 0.18 moved operators and approvals into their own maps, re-keyed the tokens and
+/// started tracking holdings and supply counters
+fn migrate_storage(storage: &mut dyn Storage, res: Response) -> StdResult<Response> {
+    let grants = v0_18::migrate_operator_grants(storage)?;
+    let approvals = v0_18::migrate_token_approvals::<Extension>(storage)?;
+    // approvals are keyed by the bare token id, so move them before re-keying the tokens
+    let token_keys = v0_18::migrate_token_keys::<Extension>(storage)?;
+    let holders = v0_18::migrate_holdings::<Extension>(storage)?;
+    let positions = v0_18::migrate_token_positions::<Extension>(storage)?;
+    let minted = v0_18::migrate_supply_counters::<Extension>(storage)?;
+    Ok(res
+        .add_attribute("migrated_operator_grants", grants.to_string())
+        .add_attribute("migrated_token_approvals", approvals.to_string())
+        .add_attribute("migrated_token_keys", token_keys.to_string())
+        .add_attribute("holder_count", holders.to_string())
+        .add_attribute("enumerated_tokens", positions.to_string())
+        .add_attribute("minted_total", minted.to_string()))
+}
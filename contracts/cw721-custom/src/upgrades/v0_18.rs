@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Empty, Order, StdResult, Storage};
+use cw721::Expiration;
+use cw_storage_plus::Map;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::state::{
+    token_id_from_key, token_key, Cw721Contract, OperatorGrant, OperatorPermissions, TokenInfo,
+};
+
+/// Operator grants used to be stored as a bare `Expiration`, wrap them into
+/// an unlimited `OperatorGrant` with every permission and index them by operator.
+/// Returns how many grants were converted.
+pub fn migrate_operator_grants(storage: &mut dyn Storage) -> StdResult<usize> {
+    let legacy: Map<(&Addr, &Addr), Expiration> = Map::new("operators");
+    let grants: Map<(&Addr, &Addr), OperatorGrant> = Map::new("operators");
+    let granters: Map<(&Addr, &Addr), Expiration> = Map::new("operator_granters");
+
+    let entries = legacy
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for ((owner, operator), expires) in &entries {
+        grants.save(
+            storage,
+            (owner, operator),
+            &OperatorGrant {
+                expires: *expires,
+                remaining_uses: None,
+                permissions: OperatorPermissions::default(),
+            },
+        )?;
+        granters.save(storage, (operator, owner), expires)?;
+    }
+
+    Ok(entries.len())
+}
+
+/// Approvals used to live in `TokenInfo.approvals`, move them into the
+/// `token_approvals` map and its spender index. Returns how many approvals were moved.
+pub fn migrate_token_approvals<T>(storage: &mut dyn Storage) -> StdResult<usize>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    // the owner index is untouched, so the plain map is enough here
+    let tokens: Map<&str, TokenInfo<T>> = Map::new("tokens");
+    let token_approvals: Map<(&str, &Addr), Expiration> = Map::new("token_approvals");
+    let spender_approvals: Map<(&Addr, &str), Expiration> = Map::new("spender_approvals");
+
+    let approved = tokens
+        .range(storage, None, None, Order::Ascending)
+        .filter(|r| {
+            r.as_ref()
+                .map_or(true, |(_, token)| !token.approvals.is_empty())
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut moved = 0;
+    for (token_id, mut token) in approved {
+        for approval in token.approvals.drain(..) {
+            token_approvals.save(storage, (&token_id, &approval.spender), &approval.expires)?;
+            spender_approvals.save(storage, (&approval.spender, &token_id), &approval.expires)?;
+            moved += 1;
+        }
+        tokens.save(storage, &token_id, &token)?;
+    }
+
+    Ok(moved)
+}
+
+/// Tokens used to be keyed by their bare decimal id, which lists "10" before "2".
+/// Re-key them with `token_key` so listings follow numeric order, moving the owner
+/// index along with them. Returns how many tokens were re-keyed.
+pub fn migrate_token_keys<T>(storage: &mut dyn Storage) -> StdResult<usize>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    let tokens = Cw721Contract::<T, Empty, Empty, Empty>::default().tokens;
+
+    // only runs before 0.18, when every key is still a bare id. Ids like "007" or "~x" can't
+    // be told apart from new keys, so nothing is skipped
+    let entries = tokens
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    // a new key can be the bare id of another token, "007" becomes "~007", so every token
+    // is removed before any is saved again
+    for (token_id, _) in &entries {
+        tokens.remove(storage, token_id)?;
+    }
+    for (token_id, token) in &entries {
+        tokens.save(storage, &token_key(token_id), token)?;
+    }
+
+    Ok(entries.len())
+}
+
+/// Holdings were not tracked before, tally them from the stored tokens and enumerate
+/// the tokens of each owner. Returns the number of holders.
+pub fn migrate_holdings<T>(storage: &mut dyn Storage) -> StdResult<u64>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    let contract = Cw721Contract::<T, Empty, Empty, Empty>::default();
+
+    let mut holdings: BTreeMap<Addr, Vec<String>> = BTreeMap::new();
+    for item in contract.tokens.range(storage, None, None, Order::Ascending) {
+        let (key, token) = item?;
+        holdings
+            .entry(token.owner)
+            .or_default()
+            .push(token_id_from_key(key));
+    }
+    for (owner, token_ids) in &holdings {
+        for (position, token_id) in token_ids.iter().enumerate() {
+            let position = position as u64;
+            contract
+                .owner_token_positions
+                .save(storage, (owner, position), token_id)?;
+            contract
+                .owner_token_position_of
+                .save(storage, token_id, &position)?;
+        }
+        let count = token_ids.len() as u64;
+        contract.holdings.save(storage, owner, &count)?;
+    }
+    let holders = holdings.len() as u64;
+    contract.holder_count.save(storage, &holders)?;
+
+    Ok(holders)
+}
+
+/// Enumerates every token in id order for `TokenByIndex`. Returns how many tokens were
+/// enumerated.
+pub fn migrate_token_positions<T>(storage: &mut dyn Storage) -> StdResult<u64>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    let contract = Cw721Contract::<T, Empty, Empty, Empty>::default();
+
+    let token_ids = contract
+        .tokens
+        .keys(storage, None, None, Order::Ascending)
+        .map(|key| key.map(token_id_from_key))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (position, token_id) in token_ids.iter().enumerate() {
+        let position = position as u64;
+        contract.token_positions.save(storage, position, token_id)?;
+        contract
+            .token_position_of
+            .save(storage, token_id, &position)?;
+    }
+
+    Ok(token_ids.len() as u64)
+}
+
+/// Token ids used to come from the circulating count, which reuses ids after a burn.
+/// Start `minted_total` after the highest id in use and count the gap as burned.
+/// Returns the new `minted_total`.
+pub fn migrate_supply_counters<T>(storage: &mut dyn Storage) -> StdResult<u64>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    let contract = Cw721Contract::<T, Empty, Empty, Empty>::default();
+    if let Some(minted) = contract.minted_total.may_load(storage)? {
+        return Ok(minted);
+    }
+
+    // numeric keys sort before any other id, so the last of them is the highest id
+    let mut highest = None;
+    for key in contract.tokens.keys(storage, None, None, Order::Descending) {
+        let key = key?;
+        if !key.starts_with('~') {
+            highest = key.parse::<u64>().ok();
+            break;
+        }
+    }
+    let circulating = contract.token_count(storage)?;
+    let minted = highest.map_or(0, |id| id + 1).max(circulating);
+
+    contract.minted_total.save(storage, &minted)?;
+    contract
+        .burned_total
+        .save(storage, &(minted - circulating))?;
+
+    Ok(minted)
+}
@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Api, Empty, Order, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+
+use crate::state::Cw721Contract;
+
+/// Mint counts used to be keyed by the owner string as sent to mint, so differently cased
+/// spellings of one address were counted apart. Key them by the normalized `Addr` instead,
+/// summing the counts of spellings of the same wallet. Returns how many wallets are left.
+pub fn migrate_wallet_mints(storage: &mut dyn Storage, api: &dyn Api) -> StdResult<usize> {
+    let legacy: Map<String, u64> = Map::new("wallets_minted_amount");
+    let contract = Cw721Contract::<Empty, Empty, Empty, Empty>::default();
+    let wallet_mints = contract.wallets_minted_amount;
+
+    let entries = legacy
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut merged: BTreeMap<Addr, u64> = BTreeMap::new();
+    for (wallet, minted) in entries {
+        legacy.remove(storage, wallet.clone());
+        // bech32 addresses are case insensitive, their normalized form is lowercase
+        let wallet = api.addr_validate(&wallet.to_lowercase())?;
+        *merged.entry(wallet).or_default() += minted;
+    }
+    for (wallet, minted) in &merged {
+        wallet_mints.update(storage, wallet, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default() + minted)
+        })?;
+    }
+    let count = wallet_mints
+        .keys(storage, None, None, Order::Ascending)
+        .count();
+    contract.wallet_mint_count.save(storage, &(count as u64))?;
+
+    Ok(merged.len())
+}
+
+/// The supply history only records the value before each change, which would read as 0
+/// for the first change after upgrading. Seed the current value underneath it, without a
+/// changelog entry, so heights before the first change report it. Returns the seeded value.
+pub fn seed_token_count_snapshot(storage: &mut dyn Storage) -> StdResult<u64> {
+    let contract = Cw721Contract::<Empty, Empty, Empty, Empty>::default();
+    // the snapshot's current value lives under its own namespace
+    let current: Item<u64> = Item::new("token_count_snapshots");
+    let count = contract.token_count(storage)?;
+    current.save(storage, &count)?;
+    Ok(count)
+}